@@ -0,0 +1,259 @@
+//! Generating bindings from a build script or the `rvm-bind` binary.
+//!
+//! ```no_run
+//! rvm_bind::build::Builder::new()
+//!     .classpath("bytecode")
+//!     .classpath("rt.zip")
+//!     .package("tests")
+//!     .output(std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("java_bindings.rs"))
+//!     .cargo_rerun_if_changed(true)
+//!     .generate()
+//!     .unwrap();
+//! ```
+use crate::JavaBinder;
+use eyre::{bail, Context, ContextCompat};
+use rvm_class::{ClassLoader, DirectoryClassSource, JarClassSource};
+use rvm_core::ObjectType;
+use std::env;
+use std::fs::{read, read_to_string, write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// The first line of generated bindings, it holds the hash of the inputs.
+const HEADER: &str = "// Generated by rvm-bind, input hash ";
+
+/// Changes whenever the generator writes different bindings for the same classes, so bindings of
+/// an older generator get replaced.
+const FORMAT_VERSION: u32 = 1;
+
+pub const USAGE: &str = "Usage: rvm-bind [OPTIONS] --output <FILE>
+
+Options:
+  -cp, --classpath <PATHS>  Jars and directories of classes, separated like PATH
+  -c, --class <NAME>        Binds a class, like java/lang/String
+  -p, --package <NAME>      Binds every class inside of a package, like java/util
+  --include <PREFIX>        Only binds classes starting with the prefix
+  --exclude <PREFIX>        Never binds classes starting with the prefix
+  -o, --output <FILE>       The rust file which the bindings get written to";
+
+#[derive(Default)]
+pub struct Builder {
+	classpath: Vec<PathBuf>,
+	classes: Vec<String>,
+	packages: Vec<String>,
+	includes: Vec<String>,
+	excludes: Vec<String>,
+	output: Option<PathBuf>,
+	rerun_if_changed: bool,
+}
+
+impl Builder {
+	pub fn new() -> Builder {
+		Builder::default()
+	}
+
+	/// Reads the options of the `rvm-bind` binary (see [`USAGE`]), this is `None` when the usage
+	/// got asked for.
+	pub fn from_args(args: impl IntoIterator<Item = String>) -> eyre::Result<Option<Builder>> {
+		let mut builder = Builder::new();
+		let mut args = args.into_iter();
+		while let Some(arg) = args.next() {
+			if arg == "-h" || arg == "--help" {
+				return Ok(None);
+			}
+
+			let value = args
+				.next()
+				.wrap_err_with(|| format!("{arg} needs a value\n\n{USAGE}"))?;
+			builder = match arg.as_str() {
+				"-cp" | "--classpath" => {
+					env::split_paths(&value).fold(builder, |builder, path| builder.classpath(path))
+				}
+				"-c" | "--class" => builder.class(value),
+				"-p" | "--package" => builder.package(value),
+				"--include" => builder.include(value),
+				"--exclude" => builder.exclude(value),
+				"-o" | "--output" => builder.output(value),
+				_ => bail!("Unknown option {arg}\n\n{USAGE}"),
+			};
+		}
+		Ok(Some(builder))
+	}
+
+	/// Adds a jar or a directory of class files to load the classes from.
+	pub fn classpath(mut self, path: impl Into<PathBuf>) -> Self {
+		self.classpath.push(path.into());
+		self
+	}
+
+	/// Binds a class (`java/lang/String`) and the classes it uses.
+	pub fn class(mut self, name: impl Into<String>) -> Self {
+		self.classes.push(name.into());
+		self
+	}
+
+	/// Binds every class of the classpath which is inside of the package (`java/util`), including
+	/// sub packages.
+	pub fn package(mut self, name: impl Into<String>) -> Self {
+		self.packages.push(name.into());
+		self
+	}
+
+	/// Only classes starting with one of the included prefixes get a binding, everything does if
+	/// nothing is included.
+	pub fn include(mut self, prefix: impl Into<String>) -> Self {
+		self.includes.push(prefix.into());
+		self
+	}
+
+	/// Classes starting with the prefix don't get a binding, neither do the methods and fields using
+	/// them.
+	pub fn exclude(mut self, prefix: impl Into<String>) -> Self {
+		self.excludes.push(prefix.into());
+		self
+	}
+
+	/// The rust file which the bindings get written to.
+	pub fn output(mut self, path: impl Into<PathBuf>) -> Self {
+		self.output = Some(path.into());
+		self
+	}
+
+	/// Prints `cargo:rerun-if-changed` for every classpath entry, for use in build scripts.
+	pub fn cargo_rerun_if_changed(mut self, enabled: bool) -> Self {
+		self.rerun_if_changed = enabled;
+		self
+	}
+
+	/// Writes the bindings to the output. When the classes and options did not change since the
+	/// bindings got generated, they are left alone and this returns `false`.
+	pub fn generate(self) -> eyre::Result<bool> {
+		let output = self.output.as_ref().wrap_err("No output path")?;
+		if self.classes.is_empty() && self.packages.is_empty() {
+			bail!("Nothing to bind, add a class or a package");
+		}
+
+		if self.rerun_if_changed {
+			for path in &self.classpath {
+				println!("cargo:rerun-if-changed={}", path.display());
+			}
+		}
+
+		// The version of the generator is part of the input, so the bindings get regenerated when it
+		// changes.
+		let mut hasher = InputHash::new();
+		hasher.add(env!("CARGO_PKG_VERSION").as_bytes());
+		hasher.add(&FORMAT_VERSION.to_le_bytes());
+		hasher.add_all(&self.classes);
+		hasher.add_all(&self.packages);
+		hasher.add_all(&self.includes);
+		hasher.add_all(&self.excludes);
+
+		let loader = ClassLoader::new();
+		let mut available = Vec::new();
+		for path in &self.classpath {
+			hasher.add(path.as_os_str().as_encoded_bytes());
+			if path.is_dir() {
+				let source = Arc::new(DirectoryClassSource::new(path.clone())?);
+				for class in source.classes()? {
+					hasher.add(class.as_bytes());
+					hasher.add(&read(path.join(&*class).with_extension("class"))?);
+					available.push(class);
+				}
+				loader.add_source(Box::new(source));
+			} else {
+				let data = read(path).wrap_err_with(|| format!("Reading {}", path.display()))?;
+				hasher.add(&data);
+				let source = JarClassSource::new(data)
+					.wrap_err_with(|| format!("Opening {}", path.display()))?;
+				available.extend(source.classes());
+				loader.add_source(Box::new(source));
+			}
+		}
+
+		let hash = format!("{HEADER}{:016x}", hasher.0);
+		if let Ok(existing) = read_to_string(output) {
+			if existing.lines().next() == Some(hash.as_str()) {
+				return Ok(false);
+			}
+		}
+
+		let mut roots: Vec<ObjectType> = self
+			.classes
+			.iter()
+			.map(|name| ObjectType::new(name.clone()))
+			.collect();
+		for class in available {
+			let in_package = self.packages.iter().any(|package| {
+				let package = package.trim_end_matches('/');
+				class
+					.strip_prefix(package)
+					.is_some_and(|rest| rest.starts_with('/'))
+			});
+			if in_package && self.is_included(&class) && !roots.contains(&class) {
+				roots.push(class);
+			}
+		}
+
+		let mut binder = JavaBinder {
+			loader,
+			to_bind: vec![],
+		};
+		for root in &roots {
+			binder
+				.bind(root)
+				.wrap_err_with(|| format!("Binding {root}"))?;
+		}
+
+		binder
+			.to_bind
+			.retain(|(id, _)| self.is_included(&binder.loader.get(*id).ty));
+
+		let bindings = binder.compile();
+		write(output, format!("{hash}\n{bindings}"))
+			.wrap_err_with(|| format!("Writing {}", output.display()))?;
+		Ok(true)
+	}
+
+	fn is_included(&self, class: &ObjectType) -> bool {
+		let included = self.includes.is_empty()
+			|| self
+				.includes
+				.iter()
+				.any(|prefix| class.starts_with(prefix.as_str()));
+		let excluded = self
+			.excludes
+			.iter()
+			.any(|prefix| class.starts_with(prefix.as_str()));
+		included && !excluded
+	}
+}
+
+/// FNV-1a, unlike `DefaultHasher` this is the same for the same input on every platform and rust
+/// version.
+struct InputHash(u64);
+
+impl InputHash {
+	fn new() -> InputHash {
+		InputHash(0xcbf29ce484222325)
+	}
+
+	/// Adds the bytes with their length in front, so neighbouring inputs can not run into each other.
+	fn add(&mut self, bytes: &[u8]) {
+		self.write(&(bytes.len() as u64).to_le_bytes());
+		self.write(bytes);
+	}
+
+	fn add_all(&mut self, strings: &[String]) {
+		self.write(&(strings.len() as u64).to_le_bytes());
+		for string in strings {
+			self.add(string.as_bytes());
+		}
+	}
+
+	fn write(&mut self, bytes: &[u8]) {
+		for &byte in bytes {
+			self.0 = (self.0 ^ byte as u64).wrapping_mul(0x100000001b3);
+		}
+	}
+}
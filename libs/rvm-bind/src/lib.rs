@@ -1,6 +1,7 @@
 #![feature(iterator_try_collect)]
 //! RVM bind creates rust bindings to the java classes
 
+pub mod build;
 mod class;
 mod package;
 
@@ -8,13 +9,17 @@ use crate::class::ClassFile;
 use crate::package::Package;
 use convert_case::{Case, Casing};
 use eyre::Context;
-use proc_macro2::{Ident, Span, TokenStream};
-use quote::{format_ident, quote, TokenStreamExt};
-use rvm_class::{Class, ClassLoader, Field, LoadResult, Method, MethodIdentifier};
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use quote::{format_ident, quote, ToTokens, TokenStreamExt};
+use rvm_class::{Class, ClassLoader, Field, FieldConstant, LoadResult, Method, MethodIdentifier};
 use rvm_core::{
-	Id, Kind, MethodAccessFlags, MethodDescriptor, ObjectType, PrimitiveType, Type, VecExt,
+	ClassAccessFlags, FieldAccessFlags, Id, Kind, MethodAccessFlags, MethodDescriptor, ObjectType,
+	PrimitiveType, Type, VecExt,
+};
+use rvm_reader::{
+	AttributeInfo, ClassTypeSignature, ConstantPool, Op, ReferenceTypeSignature, TypeArgument,
+	TypeParameter, TypeSignature,
 };
-use rvm_reader::{AttributeInfo, ConstantPool, Op};
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt::Write;
 use std::str::FromStr;
@@ -26,6 +31,15 @@ fn tokenize(str: &str) -> TokenStream {
 }
 
 const BASE_FIELD_NAME: &str = "_base";
+const THIS_FIELD_NAME: &str = "_this";
+const TYPES_FIELD_NAME: &str = "_types";
+
+/// The java type variables which are in scope, with the rust generic they became.
+pub type TypeVariables = Vec<(String, Ident)>;
+
+fn type_bounds() -> TokenStream {
+	quote!(rvm_runtime::ToJava + rvm_runtime::FromJava)
+}
 pub struct JavaBinder {
 	pub loader: ClassLoader,
 	pub to_bind: Vec<(Id<Class>, bool)>,
@@ -61,14 +75,17 @@ impl JavaBinder {
 
 	pub fn compile(&mut self) -> String {
 		let mut root = Package::default();
+		let mut bound = HashSet::new();
 		for (id, simple) in &self.to_bind {
+			let data = self.loader.get(*id);
+			bound.insert(data.ty.clone());
 			root.insert(ClassFile {
-				data: self.loader.get(*id),
+				data,
 				full_binding: *simple,
 			});
 		}
 
-		let stream = self.compile_module(&root);
+		let stream = self.compile_module(&root, &bound);
 		let mut text = stream.to_string();
 		match parse_file(&text) {
 			Ok(file) => prettyplease::unparse(&file),
@@ -81,15 +98,15 @@ impl JavaBinder {
 		}
 	}
 
-	fn compile_module(&mut self, package: &Package) -> TokenStream {
+	fn compile_module(&mut self, package: &Package, bound: &HashSet<ObjectType>) -> TokenStream {
 		let mut output = TokenStream::new();
 		for file in &package.files {
-			output.append_all(self.compile_class(file));
+			output.append_all(self.compile_class(file, bound));
 		}
 
 		for (inner, package) in &package.packages {
 			let module_name = Ident::new(inner, Span::call_site());
-			let module_content = self.compile_module(package);
+			let module_content = self.compile_module(package, bound);
 			output.append_all(quote! {
 				pub mod #module_name {
 					#module_content
@@ -100,10 +117,11 @@ impl JavaBinder {
 		output
 	}
 
-	fn compile_class(&mut self, class: &ClassFile) -> TokenStream {
+	fn compile_class(&mut self, class: &ClassFile, bound: &HashSet<ObjectType>) -> TokenStream {
 		let ctx = Ctx {
 			class,
 			classes: &self.loader,
+			bound,
 		};
 		let tokenized_class = TokenizedClass::new(&ctx);
 
@@ -112,21 +130,48 @@ impl JavaBinder {
 		let instance_binding_ts = Self::def_instance_binding(&tokenized_class);
 		let java_typed_ts = Self::def_java_typed(&tokenized_class);
 		let constants_ts = Self::def_constants(&tokenized_class);
-		let methods_ts = Self::def_methods(&tokenized_class);
+		let static_fields_ts = Self::def_static_fields(&tokenized_class);
+		let (static_methods_ts, methods_ts) = Self::def_methods(&tokenized_class);
+		let interface_ts = Self::def_interface(&tokenized_class);
+		let implements_ts = Self::def_implements(&tokenized_class);
 		let deref_ts = Self::def_deref(&tokenized_class);
 
-		let ident = tokenized_class.ident.clone();
-		quote! {
-			#struct_ts
+		// Static methods can't use the type variables, so they stay out of the generic impl.
+		let ident = &tokenized_class.ident;
+		let impl_ts = if tokenized_class.generics.is_empty() {
+			quote! {
+				impl #ident {
+					#constants_ts
+					#static_fields_ts
+					#static_methods_ts
+					#methods_ts
+				}
+			}
+		} else {
+			let impl_generics = tokenized_class.impl_generics();
+			let type_generics = tokenized_class.type_generics();
+			quote! {
+				impl #ident {
+					#constants_ts
+					#static_fields_ts
+					#static_methods_ts
+				}
 
-			impl #ident {
-				#constants_ts
-				#methods_ts
+				impl #impl_generics #ident #type_generics {
+					#methods_ts
+				}
 			}
+		};
+
+		quote! {
+			#struct_ts
+			#impl_ts
 
 			#instance_binding_ts
 			#java_typed_ts
 			#deref_ts
+			#interface_ts
+			#implements_ts
 		}
 	}
 
@@ -161,9 +206,27 @@ impl JavaBinder {
 			);
 		}
 
+		let this = rust_ident(THIS_FIELD_NAME, Span::call_site());
+		fields.insert(
+			0,
+			quote! {
+				#this: rvm_runtime::AnyInstance
+			},
+		);
+
+		let generics = &class.generics;
+		let mut struct_generics = TokenStream::new();
+		if !generics.is_empty() {
+			let types = rust_ident(TYPES_FIELD_NAME, Span::call_site());
+			fields.push(quote! {
+				#types: std::marker::PhantomData<fn() -> (#(#generics,)*)>
+			});
+			struct_generics = quote!(<#(#generics = rvm_runtime::Reference),*>);
+		}
+
 		quote! {
 			#[derive(Clone)]
-			pub struct #ident {
+			pub struct #ident #struct_generics {
 				#(#fields),*
 			}
 		}
@@ -194,15 +257,31 @@ impl JavaBinder {
 			field_bindings.insert(
 				0,
 				quote! {
-					#ident: #binding::bind(instance)
+					#ident: <#binding as rvm_runtime::InstanceBinding>::bind(instance)
 				},
 			);
 		}
 
+		let this = rust_ident(THIS_FIELD_NAME, Span::call_site());
+		field_bindings.insert(
+			0,
+			quote! {
+				#this: instance.clone()
+			},
+		);
+
+		if !class.generics.is_empty() {
+			let types = rust_ident(TYPES_FIELD_NAME, Span::call_site());
+			field_bindings.push(quote! {
+				#types: std::marker::PhantomData
+			});
+		}
+
+		let generics = class.type_generics();
 		quote! {
-			impl rvm_runtime::InstanceBinding for #ident {
+			impl #generics rvm_runtime::InstanceBinding for #ident #generics {
 				fn ty() -> rvm_core::ObjectType {
-					rvm_core::ObjectType::new(Self::TY)
+					rvm_core::ObjectType::new(#ident::TY)
 				}
 
 				fn bind(instance: &rvm_runtime::AnyInstance) -> Self {
@@ -211,90 +290,184 @@ impl JavaBinder {
 						#(#field_bindings),*
 					}
 				}
+
+				fn instance(&self) -> &rvm_runtime::AnyInstance {
+					&self.#this
+				}
 			}
 		}
 	}
 	fn def_constants(class: &TokenizedClass) -> TokenStream {
 		let name = class.full_name.to_string();
+		let constants = class
+			.constants
+			.iter()
+			.map(|TokenizedConstant { ident, ty, value }| {
+				quote! {
+					pub const #ident: #ty = #value;
+				}
+			});
 		quote! {
 			pub const TY: &'static str = #name;
+			#(#constants)*
 		}
 	}
 
-	fn def_methods(class: &TokenizedClass) -> TokenStream {
-		let mut methods = TokenStream::new();
-		for method in &class.methods {
-			let name = method.ident.clone();
-			let name_descriptor = format_ident!("{}_descriptor", method.ident);
-
-			if !method.flags.contains(MethodAccessFlags::STATIC) {
-				continue;
-			}
-
-			let returns = match method.returns.as_ref() {
-				Some(value) => value.clone(),
-				None => tokenize("()"),
-			};
+	/// Static fields get read and written through the class, so it gets initialized first.
+	fn def_static_fields(class: &TokenizedClass) -> TokenStream {
+		let ident = &class.ident;
+		let mut accessors = TokenStream::new();
+		for field in &class.static_fields {
+			let ty = &field.ty;
+			let name = &field.name;
+			let getter = rust_ident(&format!("get_{name}"), Span::call_site());
+			let setter = rust_ident(&format!("set_{name}"), Span::call_site());
+			accessors.append_all(quote! {
+				pub fn #getter(runtime: &mut rvm_runtime::Runtime) -> eyre::Result<#ty> {
+					runtime.class(#ident::TY)?.get_static(#name)
+				}
 
-			let arguments: Vec<TokenStream> = method
-				.arguments
-				.iter()
-				.map(|MethodArgument { name, ty, .. }| {
-					quote! {
-						#name: #ty
-					}
-				})
-				.collect();
+				pub fn #setter(runtime: &mut rvm_runtime::Runtime, value: #ty) -> eyre::Result<()> {
+					runtime.class(#ident::TY)?.set_static(#name, value)
+				}
+			});
+		}
 
-			let argument_call: Vec<TokenStream> = method
-				.arguments
-				.iter()
-				.map(|MethodArgument { name, .. }| {
-					quote! {
-						rvm_runtime::ToJava::to_java(#name, runtime)?
-					}
-				})
-				.collect();
+		accessors
+	}
 
-			let return_expr = match method.returns {
-				Some(_) => quote! {
-					let output = output.expect("expected return");
-					rvm_runtime::FromJava::from_java(output, runtime)
-				},
-				None => quote! {
-					if !output.is_none() {
-						panic!("Returned on void");
-					}
-					Ok(())
-				},
-			};
+	/// Returns the static methods and the ones which need an instance, which can use the type variables.
+	fn def_methods(class: &TokenizedClass) -> (TokenStream, TokenStream) {
+		let ident = &class.ident;
+		let mut static_methods = TokenStream::new();
+		let mut methods = TokenStream::new();
+		for method in &class.methods {
+			let name = &method.ident;
+			let name_descriptor = method.descriptor_ident();
+			let arguments = method.arguments();
+			let generics = method.generics();
 
 			let i_name = method.method_ident.name.to_string();
 			let i_descriptor = method.method_ident.descriptor.to_string();
-
-			methods.append_all(quote! {
+			static_methods.append_all(quote! {
 				pub fn #name_descriptor() -> rvm_runtime::MethodIdentifier {
 					rvm_runtime::MethodIdentifier {
 						name: std::sync::Arc::from(#i_name),
 						descriptor: std::sync::Arc::from(#i_descriptor),
 					}
 				}
+			});
+
+			if method.flags.contains(MethodAccessFlags::STATIC) {
+				let returns = method.returns();
+				let call = method.call(&class.ident, quote!(rvm_runtime::CallType::Static), None);
+				static_methods.append_all(quote! {
+					pub fn #name #generics(runtime: &mut rvm_runtime::Runtime, #(#arguments),*) -> eyre::Result<#returns> {
+						#call
+					}
+				});
+			} else if &*method.method_ident.name == "<init>" {
+				// Abstract classes and interfaces can't be created.
+				if class.is_abstract {
+					continue;
+				}
 
-				pub fn #name(runtime: &mut rvm_runtime::Runtime, #(#arguments),*) -> eyre::Result<#returns> {
-					let output = runtime.run(
-						rvm_runtime::CallType::Static,
-						&<Self as rvm_runtime::InstanceBinding>::ty(),
-						&Self::#name_descriptor(),
-						vec![
+				let argument_call = method.argument_call();
+				methods.append_all(quote! {
+					pub fn #name #generics(runtime: &mut rvm_runtime::Runtime, #(#arguments),*) -> eyre::Result<rvm_runtime::Instance<Self>> {
+						let parameters = vec![
 							#(#argument_call),*
-						],
-					)?;
-					#return_expr
+						];
+						let instance = runtime.construct(
+							&<Self as rvm_runtime::InstanceBinding>::ty(),
+							&#ident::#name_descriptor(),
+							parameters,
+						)?;
+						Ok(rvm_runtime::Instance::try_new(instance)?)
+					}
+				});
+			} else if !class.is_interface {
+				// Private methods don't get overridden, so they get called directly.
+				let call_type = if method.flags.contains(MethodAccessFlags::PRIVATE) {
+					quote!(rvm_runtime::CallType::Special)
+				} else {
+					quote!(rvm_runtime::CallType::Virtual)
+				};
+				let returns = method.returns();
+				let call = method.call(&class.ident, call_type, Some(quote!(self)));
+				methods.append_all(quote! {
+					pub fn #name #generics(&self, runtime: &mut rvm_runtime::Runtime, #(#arguments),*) -> eyre::Result<#returns> {
+						#call
+					}
+				});
+			}
+		}
+
+		(static_methods, methods)
+	}
+
+	/// Interfaces become a trait, which the structs of the classes implementing them implement.
+	fn def_interface(class: &TokenizedClass) -> TokenStream {
+		if !class.is_interface {
+			return TokenStream::new();
+		}
+
+		let ident = &class.ident;
+		let trait_ident = interface_ident(ident);
+		let mut methods = TokenStream::new();
+		for method in &class.methods {
+			if method.flags.contains(MethodAccessFlags::STATIC)
+				|| method.flags.contains(MethodAccessFlags::PRIVATE)
+			{
+				continue;
+			}
+
+			let name = &method.ident;
+			let arguments = method.arguments();
+			let generics = method.generics();
+			let returns = method.returns();
+			let call = method.call(
+				ident,
+				quote!(rvm_runtime::CallType::Interface),
+				Some(quote!(self)),
+			);
+			methods.append_all(quote! {
+				fn #name #generics(&self, runtime: &mut rvm_runtime::Runtime, #(#arguments),*) -> eyre::Result<#returns> {
+					#call
 				}
 			});
 		}
 
-		methods
+		let generics = &class.generics;
+		let mut trait_generics = TokenStream::new();
+		if !generics.is_empty() {
+			let bounds = type_bounds();
+			trait_generics = quote!(<#(#generics: #bounds = rvm_runtime::Reference),*>);
+		}
+
+		let impl_generics = class.impl_generics();
+		let type_generics = class.type_generics();
+		quote! {
+			pub trait #trait_ident #trait_generics: rvm_runtime::InstanceBinding {
+				#methods
+			}
+
+			impl #impl_generics #trait_ident #type_generics for #ident #type_generics {}
+		}
+	}
+
+	fn def_implements(class: &TokenizedClass) -> TokenStream {
+		let ident = &class.ident;
+		let impl_generics = class.impl_generics();
+		let type_generics = class.type_generics();
+		let mut implements = TokenStream::new();
+		for interface in &class.interfaces {
+			implements.append_all(quote! {
+				impl #impl_generics #interface for #ident #type_generics {}
+			});
+		}
+
+		implements
 	}
 
 	fn def_deref(class: &TokenizedClass) -> TokenStream {
@@ -309,8 +482,9 @@ impl JavaBinder {
 			return TokenStream::new();
 		};
 
+		let generics = class.type_generics();
 		quote! {
-			impl std::ops::Deref for #ident {
+			impl #generics std::ops::Deref for #ident #generics {
 				type Target = #base_ident;
 
 				fn deref(&self) -> &Self::Target {
@@ -318,7 +492,7 @@ impl JavaBinder {
 				}
 			}
 
-			impl std::ops::DerefMut for #ident {
+			impl #generics std::ops::DerefMut for #ident #generics {
 				fn deref_mut(&mut self) -> &mut Self::Target {
 					&mut self.#base_field
 				}
@@ -328,8 +502,9 @@ impl JavaBinder {
 
 	fn def_java_typed(class: &TokenizedClass) -> TokenStream {
 		let ident = &class.ident;
+		let generics = class.type_generics();
 		quote! {
-			impl rvm_runtime::JavaTyped for #ident {
+			impl #generics rvm_runtime::JavaTyped for #ident #generics {
 				fn java_type() -> rvm_core::Type {
 					<Self as rvm_runtime::InstanceBinding>::ty().into()
 				}
@@ -341,10 +516,17 @@ impl JavaBinder {
 pub struct TokenizedClass {
 	full_name: String,
 	ident: Ident,
+	is_interface: bool,
+	is_abstract: bool,
+	/// The type parameters of the class, which the struct is generic over.
+	generics: Vec<Ident>,
+	/// The traits of every interface this implements, including the ones of the super classes.
+	interfaces: Vec<TokenStream>,
 	// Fields
 	base_field: Option<BaseClass>,
 	fields: Vec<TokenizedField>,
 	static_fields: Vec<TokenizedField>,
+	constants: Vec<TokenizedConstant>,
 	//
 	methods: Vec<TokenizedMethod>,
 }
@@ -353,12 +535,33 @@ impl TokenizedClass {
 	pub fn new(ctx: &Ctx) -> TokenizedClass {
 		let class = ctx.class;
 		let ident = rust_ident(&class.ty.name(), Span::call_site());
+		let variables = ctx.type_variables();
 
 		let mut base_field = None;
 		let mut fields = Vec::new();
 		let mut static_fields = Vec::new();
+		let mut constants: Vec<TokenizedConstant> = Vec::new();
 		for field in class.fields.iter() {
-			let Some(tokenized) = TokenizedField::from_class(ctx, field) else {
+			// Constants don't need the class, the other static fields get accessors. A
+			// ConstantValue only makes static final fields constant.
+			if field
+				.flags
+				.contains(FieldAccessFlags::STATIC | FieldAccessFlags::FINAL)
+			{
+				if let Some(constant) = TokenizedConstant::from_class(field) {
+					if constants.iter().all(|v| v.ident != constant.ident) {
+						constants.push(constant);
+					}
+					continue;
+				}
+			}
+
+			// Static fields can't use the type variables of the class.
+			let field_variables = match field.is_static() {
+				true => TypeVariables::new(),
+				false => variables.clone(),
+			};
+			let Some(tokenized) = TokenizedField::from_class(ctx, field, &field_variables) else {
 				continue;
 			};
 			if !field.is_static() {
@@ -371,9 +574,12 @@ impl TokenizedClass {
 		base_field = class.superface.superclass.as_ref().and_then(|superclass| {
 			let field =
 				TokenizedField::new(ctx, BASE_FIELD_NAME, &Type::Object(superclass.ty.clone()))?;
-			let string = ctx.class_ident_to_rust(&superclass.ty)?;
+			let generic = class.signature.as_ref().and_then(|signature| {
+				ctx.class_signature_to_rust(&signature.superclass, &variables)
+			});
+			let path = ctx.class_ident_to_rust(&superclass.ty)?;
 			Some(BaseClass {
-				ident: string,
+				ident: generic.unwrap_or_else(|| quote!(#path)),
 				field,
 			})
 		});
@@ -396,7 +602,7 @@ impl TokenizedClass {
 			};
 
 			let Some(method) =
-				TokenizedMethod::from_class(ctx, ident.clone(), &ctx.class.cp, method)
+				TokenizedMethod::from_class(ctx, ident.clone(), &ctx.class.cp, method, &variables)
 			else {
 				continue;
 			};
@@ -404,15 +610,52 @@ impl TokenizedClass {
 			methods.push(method);
 		}
 
+		let mut interfaces = Vec::new();
+		for interface in ctx.all_interfaces() {
+			let Some(path) = ctx.interface_ident_to_rust(&interface.ty) else {
+				continue;
+			};
+
+			let arguments = ctx.type_arguments_to_rust(&interface, &variables);
+			interfaces.push(quote!(#path #arguments));
+		}
+
 		TokenizedClass {
 			full_name: (*class.ty).to_string(),
 			ident,
+			is_interface: class.is_interface(),
+			is_abstract: class.flags.contains(ClassAccessFlags::ABSTRACT),
+			generics: variables.into_iter().map(|(_, ident)| ident).collect(),
+			interfaces,
 			base_field,
 			fields,
 			static_fields,
+			constants,
 			methods,
 		}
 	}
+
+	/// `<E: Bounds>` for impl blocks which need to convert the type variables.
+	fn impl_generics(&self) -> TokenStream {
+		if self.generics.is_empty() {
+			return TokenStream::new();
+		}
+
+		let generics = &self.generics;
+		let bounds = type_bounds();
+		quote!(<#(#generics: #bounds),*>)
+	}
+
+	/// `<E>`, for naming the struct and for impl blocks which work with any type.
+	fn type_generics(&self) -> TokenStream {
+		if self.generics.is_empty() {
+			return TokenStream::new();
+		}
+
+		let generics = &self.generics;
+		quote!(<#(#generics),*>)
+	}
+
 	//pub fn all_fields(&self) -> impl Iterator<Item = &TokenizedField> {
 	//	self.fields
 	//		.iter()
@@ -421,12 +664,14 @@ impl TokenizedClass {
 }
 
 pub struct BaseClass {
-	ident: syn::Path,
+	ident: TokenStream,
 	field: TokenizedField,
 }
 
 pub struct TokenizedField {
 	ident: Ident,
+	/// The name of the field in java.
+	name: String,
 	ty: TokenStream,
 	bind_type: TokenStream,
 }
@@ -440,17 +685,102 @@ impl TokenizedField {
 
 		Some(Self {
 			ident,
+			name: name.to_string(),
 			bind_type,
 			ty: ty_ts,
 		})
 	}
-	pub fn from_class(ctx: &Ctx, field: &Field) -> Option<Self> {
-		Self::new(ctx, &field.name, &field.ty)
+	/// Fields with a generic signature get their type arguments, type variables stay erased.
+	pub fn from_class(ctx: &Ctx, field: &Field, variables: &TypeVariables) -> Option<Self> {
+		let generic = field.signature.as_ref().and_then(|signature| {
+			Some(Self {
+				ident: rust_ident(&field.name, Span::call_site()),
+				name: field.name.clone(),
+				ty: ctx.reference_signature_to_rust(signature, variables)?,
+				bind_type: ctx.reference_signature_to_rust_binding(signature, variables)?,
+			})
+		});
+
+		generic.or_else(|| Self::new(ctx, &field.name, &field.ty))
+	}
+}
+
+/// A static final field with a ConstantValue, which becomes a rust const.
+pub struct TokenizedConstant {
+	ident: Ident,
+	ty: TokenStream,
+	value: TokenStream,
+}
+
+impl TokenizedConstant {
+	pub fn from_class(field: &Field) -> Option<Self> {
+		let constant = field.constant.as_ref()?;
+		let name = field.name.to_case(Case::UpperSnake);
+		// TY is the name of the class.
+		if name == "TY" {
+			return None;
+		}
+
+		let (ty, value) = match (&field.ty, constant) {
+			(Type::Primitive(PrimitiveType::Boolean), FieldConstant::Int(v)) => (
+				quote!(bool),
+				if *v != 0 { quote!(true) } else { quote!(false) },
+			),
+			(Type::Primitive(PrimitiveType::Byte), FieldConstant::Int(v)) => (
+				quote!(i8),
+				Literal::i8_unsuffixed(*v as i8).into_token_stream(),
+			),
+			(Type::Primitive(PrimitiveType::Short), FieldConstant::Int(v)) => (
+				quote!(i16),
+				Literal::i16_unsuffixed(*v as i16).into_token_stream(),
+			),
+			(Type::Primitive(PrimitiveType::Char), FieldConstant::Int(v)) => (
+				quote!(u16),
+				Literal::u16_unsuffixed(*v as u16).into_token_stream(),
+			),
+			(Type::Primitive(PrimitiveType::Int), FieldConstant::Int(v)) => {
+				(quote!(i32), Literal::i32_unsuffixed(*v).into_token_stream())
+			}
+			(Type::Primitive(PrimitiveType::Long), FieldConstant::Long(v)) => {
+				(quote!(i64), Literal::i64_unsuffixed(*v).into_token_stream())
+			}
+			(Type::Primitive(PrimitiveType::Float), FieldConstant::Float(v)) => {
+				(quote!(f32), float_literal(*v as f64, quote!(f32)))
+			}
+			(Type::Primitive(PrimitiveType::Double), FieldConstant::Double(v)) => {
+				(quote!(f64), float_literal(*v, quote!(f64)))
+			}
+			(Type::Object(ty), FieldConstant::String(v)) if ty == &ObjectType::String() => {
+				(quote!(&'static str), Literal::string(v).into_token_stream())
+			}
+			_ => return None,
+		};
+
+		Some(Self {
+			ident: rust_ident(&name, Span::call_site()),
+			ty,
+			value,
+		})
+	}
+}
+
+/// Literals can't be NaN or infinite, those use the consts of the float type.
+fn float_literal(value: f64, ty: TokenStream) -> TokenStream {
+	if value.is_nan() {
+		quote!(#ty::NAN)
+	} else if value == f64::INFINITY {
+		quote!(#ty::INFINITY)
+	} else if value == f64::NEG_INFINITY {
+		quote!(#ty::NEG_INFINITY)
+	} else {
+		Literal::f64_unsuffixed(value).into_token_stream()
 	}
 }
 
 pub struct TokenizedMethod {
 	ident: Ident,
+	/// The type parameters of the method which its arguments or return type use.
+	generics: Vec<Ident>,
 	flags: MethodAccessFlags,
 	method_ident: MethodIdentifier,
 	arguments: Vec<MethodArgument>,
@@ -570,7 +900,101 @@ pub struct MethodArgument {
 }
 
 impl TokenizedMethod {
-	pub fn from_class(ctx: &Ctx, ident: Ident, cp: &ConstantPool, method: &Method) -> Option<Self> {
+	fn descriptor_ident(&self) -> Ident {
+		format_ident!("{}_descriptor", self.ident)
+	}
+
+	fn generics(&self) -> TokenStream {
+		if self.generics.is_empty() {
+			return TokenStream::new();
+		}
+
+		let generics = &self.generics;
+		let bounds = type_bounds();
+		quote!(<#(#generics: #bounds),*>)
+	}
+
+	fn arguments(&self) -> Vec<TokenStream> {
+		self.arguments
+			.iter()
+			.map(|MethodArgument { name, ty, .. }| {
+				quote! {
+					#name: #ty
+				}
+			})
+			.collect()
+	}
+
+	fn argument_call(&self) -> Vec<TokenStream> {
+		self.arguments
+			.iter()
+			.map(|MethodArgument { name, .. }| {
+				quote! {
+					rvm_runtime::ToJava::to_java(#name, runtime)?
+				}
+			})
+			.collect()
+	}
+
+	fn returns(&self) -> TokenStream {
+		match self.returns.as_ref() {
+			Some(value) => value.clone(),
+			None => tokenize("()"),
+		}
+	}
+
+	/// Runs the method of the class `owner`, instance methods get called on `receiver`.
+	fn call(
+		&self,
+		owner: &Ident,
+		call_type: TokenStream,
+		receiver: Option<TokenStream>,
+	) -> TokenStream {
+		let name_descriptor = self.descriptor_ident();
+		let argument_call = self.argument_call();
+		let receiver = receiver.map(|receiver| {
+			quote! {
+				let this = rvm_runtime::InstanceBinding::instance(#receiver).clone();
+				parameters.insert(0, rvm_runtime::ToJava::to_java(this, runtime)?);
+			}
+		});
+
+		let return_expr = match self.returns {
+			Some(_) => quote! {
+				let output = output.expect("expected return");
+				rvm_runtime::FromJava::from_java(output, runtime)
+			},
+			None => quote! {
+				if !output.is_none() {
+					panic!("Returned on void");
+				}
+				Ok(())
+			},
+		};
+
+		quote! {
+			#[allow(unused_mut)]
+			let mut parameters = vec![
+				#(#argument_call),*
+			];
+			#receiver
+			let output = runtime.run(
+				#call_type,
+				&<#owner as rvm_runtime::InstanceBinding>::ty(),
+				&#owner::#name_descriptor(),
+				parameters,
+			)?;
+			#return_expr
+		}
+	}
+
+	pub fn from_class(
+		ctx: &Ctx,
+		ident: Ident,
+		cp: &ConstantPool,
+		method: &Method,
+		class_variables: &TypeVariables,
+	) -> Option<Self> {
 		let range_start = if method.is_static() { 0 } else { 1 };
 		let range_end = range_start + method.desc.parameters.len();
 
@@ -607,25 +1031,82 @@ impl TokenizedMethod {
 			})
 			.collect();
 
+		// Static methods don't see the type variables of the class, and the ones of the method
+		// can shadow them. Shadowed variables are left out, so those types stay erased.
+		let mut variables = TypeVariables::new();
+		let mut method_variables = Vec::new();
+		let mut signature = method.signature.as_ref();
+		if let Some(value) = signature {
+			for parameter in &value.type_parameters {
+				if class_variables
+					.iter()
+					.all(|(name, _)| name != &parameter.name)
+				{
+					method_variables.push((
+						parameter.name.clone(),
+						rust_ident(&parameter.name, Span::call_site()),
+					));
+				}
+			}
+
+			// Synthetic parameters, like the outer instance of inner classes, are missing.
+			if value.parameters.len() != method.desc.parameters.len() {
+				signature = None;
+			}
+		}
+		if !method.is_static() {
+			for (name, ident) in class_variables {
+				let shadowed = signature.is_some_and(|signature| {
+					signature.type_parameters.iter().any(|v| &v.name == name)
+				});
+				if !shadowed {
+					variables.push((name.clone(), ident.clone()));
+				}
+			}
+		}
+		variables.extend(method_variables.iter().cloned());
+
+		let ty_to_rust = |ty: &Type, generic: Option<&TypeSignature>| {
+			generic
+				.and_then(|generic| ctx.signature_to_rust(generic, &variables))
+				.or_else(|| ctx.ty_to_rust(ty))
+		};
+
 		let arguments: Vec<MethodArgument> = method
 			.desc
 			.parameters
 			.iter()
+			.enumerate()
 			.zip(argument_names)
-			.map(|(ty, name)| {
+			.map(|((i, ty), name)| {
+				let generic = signature.map(|signature| &signature.parameters[i]);
 				Some(MethodArgument {
 					name: Ident::new(&name, Span::call_site()),
-					ty: ctx.ty_to_rust(ty)?,
+					ty: ty_to_rust(ty, generic)?,
 				})
 			})
 			.try_collect()?;
 
 		let returns = match method.desc.returns.as_ref() {
-			Some(ty) => Some(ctx.ty_to_rust(ty)?),
+			Some(ty) => {
+				let generic = signature.and_then(|signature| signature.returns.as_ref());
+				Some(ty_to_rust(ty, generic)?)
+			}
 			None => None,
 		};
+
+		// Type parameters which only got erased types can't be inferred, so they are left out.
+		let mut generics = Vec::new();
+		for (_, variable) in method_variables {
+			let used = arguments.iter().map(|v| &v.ty).chain(returns.as_ref());
+			if used.into_iter().any(|ty| mentions(ty, &variable)) {
+				generics.push(variable);
+			}
+		}
+
 		Some(Self {
 			ident,
+			generics,
 			method_ident: method.to_identifier(),
 			arguments,
 			returns,
@@ -637,6 +1118,8 @@ impl TokenizedMethod {
 pub struct Ctx<'a> {
 	class: &'a ClassFile,
 	classes: &'a ClassLoader,
+	/// The classes which get a binding, types of other classes can't be used.
+	bound: &'a HashSet<ObjectType>,
 }
 
 impl<'a> Ctx<'a> {
@@ -707,7 +1190,58 @@ impl<'a> Ctx<'a> {
 				}
 			}
 			Type::Array(array) => {
-				let component = self.ty_to_rust(array.component())?;
+				// Arrays hold the bindings of objects, not instances.
+				let component = self.ty_to_rust_binding(array.component())?;
+				quote! {
+					rvm_runtime::Array<#component>
+				}
+			}
+		})
+	}
+
+	/// The type parameters of the class, as the generics of its struct.
+	pub fn type_variables(&self) -> TypeVariables {
+		let Some(signature) = &self.class.signature else {
+			return TypeVariables::new();
+		};
+
+		signature
+			.type_parameters
+			.iter()
+			.map(|v| (v.name.clone(), rust_ident(&v.name, Span::call_site())))
+			.collect()
+	}
+
+	/// Like [`Self::ty_to_rust_binding`], but with type arguments. Returns None when it needs a
+	/// type variable which is not in scope, or which would be a binding, as that is a value type.
+	pub fn signature_to_rust_binding(
+		&self,
+		ty: &TypeSignature,
+		variables: &TypeVariables,
+	) -> Option<TokenStream> {
+		match ty {
+			TypeSignature::Primitive(primitive) => {
+				self.ty_to_rust_binding(&Type::Primitive(*primitive))
+			}
+			TypeSignature::Reference(ty) => self.reference_signature_to_rust_binding(ty, variables),
+		}
+	}
+
+	pub fn reference_signature_to_rust_binding(
+		&self,
+		ty: &ReferenceTypeSignature,
+		variables: &TypeVariables,
+	) -> Option<TokenStream> {
+		Some(match ty {
+			ReferenceTypeSignature::Class(class) if class.ty == ObjectType::Object() => {
+				tokenize("rvm_runtime::Reference")
+			}
+			ReferenceTypeSignature::Class(class) => {
+				self.class_signature_to_rust(class, variables)?
+			}
+			ReferenceTypeSignature::Variable(_) => return None,
+			ReferenceTypeSignature::Array(component) => {
+				let component = self.signature_to_rust_binding(component, variables)?;
 				quote! {
 					rvm_runtime::Array<#component>
 				}
@@ -715,12 +1249,96 @@ impl<'a> Ctx<'a> {
 		})
 	}
 
+	/// Like [`Self::ty_to_rust`], but with type arguments, `List<String>` becomes
+	/// `Instance<List<Instance<String>>>`. Type variables become the generics they are in scope as.
+	pub fn signature_to_rust(
+		&self,
+		ty: &TypeSignature,
+		variables: &TypeVariables,
+	) -> Option<TokenStream> {
+		match ty {
+			TypeSignature::Primitive(primitive) => self.ty_to_rust(&Type::Primitive(*primitive)),
+			TypeSignature::Reference(ty) => self.reference_signature_to_rust(ty, variables),
+		}
+	}
+
+	pub fn reference_signature_to_rust(
+		&self,
+		ty: &ReferenceTypeSignature,
+		variables: &TypeVariables,
+	) -> Option<TokenStream> {
+		Some(match ty {
+			ReferenceTypeSignature::Class(class) if class.ty == ObjectType::Object() => {
+				tokenize("rvm_runtime::Reference")
+			}
+			ReferenceTypeSignature::Class(class) => {
+				let binding = self.class_signature_to_rust(class, variables)?;
+				quote! {
+					rvm_runtime::Instance<#binding>
+				}
+			}
+			ReferenceTypeSignature::Variable(name) => {
+				let (_, ident) = variables.iter().find(|(variable, _)| variable == name)?;
+				quote!(#ident)
+			}
+			ReferenceTypeSignature::Array(component) => {
+				let component = self.signature_to_rust_binding(component, variables)?;
+				quote! {
+					rvm_runtime::Array<#component>
+				}
+			}
+		})
+	}
+
+	/// The binding struct of the class with its type arguments, `List<Instance<String>>`.
+	pub fn class_signature_to_rust(
+		&self,
+		ty: &ClassTypeSignature,
+		variables: &TypeVariables,
+	) -> Option<TokenStream> {
+		let path = self.class_ident_to_rust(&ty.ty)?;
+		let arguments = self.type_arguments_to_rust(ty, variables);
+		Some(quote!(#path #arguments))
+	}
+
+	/// `<Instance<String>>`, or nothing for raw types which get the defaults of the struct.
+	/// Wildcards are read as their upper bound, arguments which can't be expressed become
+	/// [`rvm_runtime::Reference`].
+	pub fn type_arguments_to_rust(
+		&self,
+		ty: &ClassTypeSignature,
+		variables: &TypeVariables,
+	) -> TokenStream {
+		let parameters = self
+			.classes
+			.get_named(&ty.ty)
+			.and_then(|id| self.classes.get(id).signature.clone())
+			.map(|signature| signature.type_parameters.len())
+			.unwrap_or(0);
+		if parameters == 0 || ty.arguments.len() != parameters {
+			return TokenStream::new();
+		}
+
+		let arguments = ty.arguments.iter().map(|argument| {
+			let ty = match argument {
+				TypeArgument::Exact(ty) | TypeArgument::Extends(ty) => {
+					self.reference_signature_to_rust(ty, variables)
+				}
+				TypeArgument::Any | TypeArgument::Super(_) => None,
+			};
+			ty.unwrap_or_else(|| tokenize("rvm_runtime::Reference"))
+		});
+		quote!(<#(#arguments),*>)
+	}
+
 	/// Converts a fully qualified java (java/lang/Object) name to rust (java::lang::Object)
 	pub fn class_ident_to_rust(&self, ident: &ObjectType) -> Option<syn::Path> {
 		let mut navigations = Vec::new();
 
-		// Ensure the class exists
-		_ = self.classes.get_named(ident)?;
+		// Ensure the class gets a binding
+		if !self.bound.contains(ident) {
+			return None;
+		}
 
 		let target_package = ident.package_path();
 		let current_package = self.class.ty.package_path();
@@ -743,6 +1361,79 @@ impl<'a> Ctx<'a> {
 		Some(rust_path(&navigations.join("::"), Span::call_site()))
 	}
 
+	/// The path to the trait of an interface, see [`interface_ident`].
+	pub fn interface_ident_to_rust(&self, ident: &ObjectType) -> Option<syn::Path> {
+		let mut path = self.class_ident_to_rust(ident)?;
+		let last = path.segments.last_mut()?;
+		last.ident = interface_ident(&last.ident);
+		Some(path)
+	}
+
+	/// Every interface the class implements, through its super classes and super interfaces too.
+	/// Their type arguments are in terms of the type variables of this class.
+	pub fn all_interfaces(&self) -> Vec<ClassTypeSignature> {
+		let mut interfaces: Vec<ClassTypeSignature> = Vec::new();
+		let mut classes = vec![(
+			self.class.data.clone(),
+			self.type_variables()
+				.into_iter()
+				.map(|(name, _)| TypeArgument::Exact(ReferenceTypeSignature::Variable(name)))
+				.collect::<Vec<_>>(),
+		)];
+		while let Some((class, arguments)) = classes.pop() {
+			let (superclass, class_interfaces): (_, Vec<_>) = match &class.signature {
+				Some(signature) => {
+					let parameters = &signature.type_parameters;
+					(
+						Some(substitute_class(
+							&signature.superclass,
+							parameters,
+							&arguments,
+						)),
+						signature
+							.interfaces
+							.iter()
+							.map(|v| substitute_class(v, parameters, &arguments))
+							.collect(),
+					)
+				}
+				None => (
+					class
+						.superface
+						.superclass
+						.as_ref()
+						.map(|v| raw_class(&v.ty)),
+					class
+						.superface
+						.interfaces
+						.iter()
+						.map(|v| raw_class(&v.ty))
+						.collect(),
+				),
+			};
+
+			for interface in class_interfaces {
+				if interface.ty == self.class.ty || interfaces.iter().any(|v| v.ty == interface.ty)
+				{
+					continue;
+				}
+
+				if let Some(id) = self.classes.get_named(&interface.ty) {
+					classes.push((self.classes.get(id), interface.arguments.clone()));
+				}
+				interfaces.push(interface);
+			}
+
+			if let Some(superclass) = superclass {
+				if let Some(id) = self.classes.get_named(&superclass.ty) {
+					classes.push((self.classes.get(id), superclass.arguments));
+				}
+			}
+		}
+
+		interfaces
+	}
+
 	pub fn qualified_name(&self) -> String {
 		(*self.class.ty).to_string()
 	}
@@ -781,6 +1472,87 @@ fn rust_ident(str: &str, span: Span) -> Ident {
 
 	Ident::new(&string, span)
 }
+/// The trait of an interface is named after its struct, `Runnable` becomes `RunnableInterface`.
+fn interface_ident(ident: &Ident) -> Ident {
+	format_ident!("{}Interface", ident.to_string().trim_start_matches("r#"))
+}
+
+fn raw_class(ty: &ObjectType) -> ClassTypeSignature {
+	ClassTypeSignature {
+		ty: ty.clone(),
+		arguments: vec![],
+	}
+}
+
+/// Replaces the type variables of a supertype with the arguments the subclass gave them.
+/// Raw types give no arguments, so their variables become wildcards.
+fn substitute_class(
+	ty: &ClassTypeSignature,
+	parameters: &[TypeParameter],
+	arguments: &[TypeArgument],
+) -> ClassTypeSignature {
+	let arguments = ty
+		.arguments
+		.iter()
+		.map(|argument| match argument {
+			TypeArgument::Any => TypeArgument::Any,
+			TypeArgument::Exact(ty) => substitute(ty, parameters, arguments),
+			TypeArgument::Extends(ty) => match substitute(ty, parameters, arguments) {
+				TypeArgument::Exact(ty) | TypeArgument::Extends(ty) => TypeArgument::Extends(ty),
+				_ => TypeArgument::Any,
+			},
+			TypeArgument::Super(ty) => match substitute(ty, parameters, arguments) {
+				TypeArgument::Exact(ty) | TypeArgument::Super(ty) => TypeArgument::Super(ty),
+				_ => TypeArgument::Any,
+			},
+		})
+		.collect();
+
+	ClassTypeSignature {
+		ty: ty.ty.clone(),
+		arguments,
+	}
+}
+
+fn substitute(
+	ty: &ReferenceTypeSignature,
+	parameters: &[TypeParameter],
+	arguments: &[TypeArgument],
+) -> TypeArgument {
+	match ty {
+		ReferenceTypeSignature::Class(class) => TypeArgument::Exact(ReferenceTypeSignature::Class(
+			substitute_class(class, parameters, arguments),
+		)),
+		ReferenceTypeSignature::Variable(name) => parameters
+			.iter()
+			.position(|v| &v.name == name)
+			.and_then(|i| arguments.get(i).cloned())
+			.unwrap_or(TypeArgument::Any),
+		ReferenceTypeSignature::Array(component) => match &**component {
+			TypeSignature::Primitive(_) => TypeArgument::Exact(ty.clone()),
+			TypeSignature::Reference(component) => {
+				match substitute(component, parameters, arguments) {
+					TypeArgument::Exact(component) => {
+						TypeArgument::Exact(ReferenceTypeSignature::Array(Box::new(
+							TypeSignature::Reference(component),
+						)))
+					}
+					_ => TypeArgument::Any,
+				}
+			}
+		},
+	}
+}
+
+/// If the generated tokens use the ident, like a generic in a type.
+fn mentions(tokens: &TokenStream, ident: &Ident) -> bool {
+	tokens.clone().into_iter().any(|token| match token {
+		proc_macro2::TokenTree::Ident(value) => &value == ident,
+		proc_macro2::TokenTree::Group(group) => mentions(&group.stream(), ident),
+		_ => false,
+	})
+}
+
 fn rust_path(str: &str, span: Span) -> syn::Path {
 	let mut punctuated = Punctuated::new();
 	let mut super_allowed = true;
@@ -0,0 +1,16 @@
+use rvm_bind::build::{Builder, USAGE};
+use std::env;
+
+fn main() -> eyre::Result<()> {
+	let Some(builder) = Builder::from_args(env::args().skip(1))? else {
+		println!("{USAGE}");
+		return Ok(());
+	};
+
+	if builder.generate()? {
+		println!("Generated bindings");
+	} else {
+		println!("Bindings are up to date");
+	}
+	Ok(())
+}
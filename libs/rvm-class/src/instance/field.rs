@@ -1,7 +1,7 @@
 use crate::ClassMethods;
 use eyre::ContextCompat;
-use rvm_core::{FieldAccessFlags, Storage, StorageValue, Type};
-use rvm_reader::{ConstantPool, FieldInfo};
+use rvm_core::{FieldAccessFlags, Storage, StorageValue, Type, VecExt};
+use rvm_reader::{AttributeInfo, ConstantInfo, ConstantPool, FieldInfo, FieldSignature};
 use std::ops::{Deref, DerefMut};
 
 pub struct ClassFields {
@@ -58,6 +58,9 @@ pub struct Field {
 	pub name: String,
 	pub ty: Type,
 	pub flags: FieldAccessFlags,
+	pub signature: Option<FieldSignature>,
+	/// The compile-time constant of static final fields.
+	pub constant: Option<FieldConstant>,
 }
 
 impl Field {
@@ -65,11 +68,27 @@ impl Field {
 		let name = cp[info.name_index].to_string();
 		let desc = cp[info.descriptor_index].as_str();
 		let field_type = Type::parse(desc)?;
+		let signature = info.attribute_info.first_where(|v| match v {
+			AttributeInfo::Signature { signature } => FieldSignature::parse(&cp[*signature]),
+			_ => None,
+		});
+
+		// A ConstantValue pointing outside of the pool or at anything but a constant is malformed.
+		let constant = match info.attribute_info.iter().find_map(|v| match v {
+			AttributeInfo::ConstantValue { constant_index } => Some(*constant_index),
+			_ => None,
+		}) {
+			Some(0) => return None,
+			Some(index) => Some(FieldConstant::parse(cp.raw_get(index)?, cp)?),
+			None => None,
+		};
 
 		Some(Field {
 			name,
 			ty: field_type,
 			flags: info.access_flags,
+			signature,
+			constant,
 		})
 	}
 
@@ -81,3 +100,56 @@ impl Field {
 impl StorageValue for Field {
 	type Idx = u16;
 }
+
+/// The value of a ConstantValue attribute, booleans, bytes, shorts and chars are ints too.
+#[derive(Clone, Debug, PartialEq)]
+pub enum FieldConstant {
+	Int(i32),
+	Long(i64),
+	Float(f32),
+	Double(f64),
+	String(String),
+}
+
+impl FieldConstant {
+	pub fn parse(info: &ConstantInfo, cp: &ConstantPool) -> Option<FieldConstant> {
+		Some(match info {
+			ConstantInfo::Integer(value) => FieldConstant::Int(value.bytes),
+			ConstantInfo::Long(value) => FieldConstant::Long(value.bytes),
+			ConstantInfo::Float(value) => FieldConstant::Float(value.bytes),
+			ConstantInfo::Double(value) => FieldConstant::Double(value.bytes),
+			ConstantInfo::String(value) => FieldConstant::String(cp.get(value.string)?.to_string()),
+			_ => return None,
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use rvm_reader::PoolBuilder;
+
+	fn parse(constant: impl FnOnce(&mut PoolBuilder) -> u16) -> Option<Field> {
+		let mut pool = PoolBuilder::new();
+		let info = FieldInfo {
+			access_flags: FieldAccessFlags::STATIC | FieldAccessFlags::FINAL,
+			name_index: pool.utf8("value"),
+			descriptor_index: pool.utf8("I"),
+			attribute_info: vec![AttributeInfo::ConstantValue {
+				constant_index: constant(&mut pool),
+			}],
+		};
+		Field::parse(&info, &pool.build().unwrap())
+	}
+
+	#[test]
+	fn constant_value() {
+		let field = parse(|pool| pool.integer(4).id()).unwrap();
+		assert_eq!(field.constant, Some(FieldConstant::Int(4)));
+
+		assert!(parse(|_| 0).is_none());
+		assert!(parse(|_| 1000).is_none());
+		assert!(parse(|pool| pool.class("java/lang/Object").id()).is_none());
+		assert!(parse(|pool| pool.utf8("text").id()).is_none());
+	}
+}
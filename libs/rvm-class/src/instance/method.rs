@@ -2,7 +2,9 @@ use eyre::Context;
 use rvm_core::StorageValue;
 use rvm_core::{MethodAccessFlags, MethodDescriptor};
 use rvm_core::{Storage, VecExt};
-use rvm_reader::{AttributeInfo, Code, ConstantPool, MethodInfo, NameAndTypeConst};
+use rvm_reader::{
+	AttributeInfo, Code, ConstantPool, MethodInfo, MethodSignature, NameAndTypeConst,
+};
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 
@@ -59,6 +61,7 @@ pub struct Method {
 	pub desc: MethodDescriptor,
 	pub flags: MethodAccessFlags,
 	pub code: Option<Code>,
+	pub signature: Option<MethodSignature>,
 	pub attributes: Vec<AttributeInfo>,
 }
 
@@ -89,11 +92,17 @@ impl Method {
 			}
 		}
 
+		let signature = info.attributes.first_where(|v| match v {
+			AttributeInfo::Signature { signature } => MethodSignature::parse(&consts[*signature]),
+			_ => None,
+		});
+
 		Ok(Method {
 			name: ident.name.to_string(),
 			desc,
 			flags: info.access_flags,
 			code,
+			signature,
 			attributes: info.attributes,
 		})
 	}
@@ -7,8 +7,8 @@ pub use crate::instance::method::*;
 pub use crate::instance::superface::*;
 use crate::ClassResolver;
 use eyre::Context;
-use rvm_core::{Id, ObjectType, StorageValue, Type};
-use rvm_reader::{ClassInfo, ConstantPool};
+use rvm_core::{ClassAccessFlags, Id, ObjectType, StorageValue, Type, VecExt};
+use rvm_reader::{AttributeInfo, ClassInfo, ClassSignature, ConstantPool};
 use std::sync::Arc;
 
 #[non_exhaustive]
@@ -26,6 +26,9 @@ impl ResolvedClassId {
 pub struct Class {
 	pub id: Id<Class>,
 	pub ty: ObjectType,
+	pub flags: ClassAccessFlags,
+	/// The generic signature, only classes with type parameters or generic supertypes have one.
+	pub signature: Option<ClassSignature>,
 	/// The constant pool
 	pub cp: Arc<ConstantPool>,
 
@@ -46,10 +49,16 @@ impl Class {
 		let superface = ClassSuperface::parse(&info).wrap_err("Superfaces")?;
 		let fields = ClassFields::parse(&info.fields, &info.cp).wrap_err("Fields")?;
 		let methods = ClassMethods::parse(info.methods, &info.cp).wrap_err("Methods")?;
+		let signature = info.attributes.first_where(|v| match v {
+			AttributeInfo::Signature { signature } => ClassSignature::parse(&info.cp[*signature]),
+			_ => None,
+		});
 
 		Ok(Class {
 			id,
 			ty: ObjectType::new(name.to_string()),
+			flags: info.access_flags,
+			signature,
 			cp: Arc::new(info.cp),
 			methods,
 			fields,
@@ -57,6 +66,10 @@ impl Class {
 		})
 	}
 
+	pub fn is_interface(&self) -> bool {
+		self.flags.contains(ClassAccessFlags::INTERFACE)
+	}
+
 	pub fn resolve(&mut self, func: &mut ClassResolver) -> eyre::Result<()> {
 		self.superface.resolve(func)?;
 
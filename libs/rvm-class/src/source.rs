@@ -1,9 +1,9 @@
 use parking_lot::Mutex;
 use rvm_core::ObjectType;
 use std::collections::HashMap;
-use std::fs::read;
+use std::fs::{read, read_dir};
 use std::io::{Cursor, Read};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use zip::ZipArchive;
 
@@ -24,6 +24,42 @@ impl DirectoryClassSource {
 	pub fn new(dir: PathBuf) -> eyre::Result<DirectoryClassSource> {
 		Ok(DirectoryClassSource { dir })
 	}
+
+	/// Every class inside of the directory, sorted by name.
+	pub fn classes(&self) -> eyre::Result<Vec<ObjectType>> {
+		let mut classes = Vec::new();
+		Self::walk(&self.dir, &mut classes)?;
+
+		let mut classes: Vec<ObjectType> = classes
+			.into_iter()
+			.filter_map(|path| {
+				let name = path.strip_prefix(&self.dir).ok()?.with_extension("");
+				let parts: Vec<&str> = name
+					.iter()
+					.map(|part| part.to_str())
+					.collect::<Option<_>>()?;
+				Some(ObjectType::new(parts.join("/")))
+			})
+			.collect();
+		classes.sort();
+		Ok(classes)
+	}
+
+	fn walk(dir: &Path, classes: &mut Vec<PathBuf>) -> eyre::Result<()> {
+		for entry in read_dir(dir)? {
+			let path = entry?.path();
+			if path.is_dir() {
+				Self::walk(&path, classes)?;
+			} else if path
+				.extension()
+				.is_some_and(|extension| extension == "class")
+			{
+				classes.push(path);
+			}
+		}
+
+		Ok(())
+	}
 }
 
 impl ClassSource for DirectoryClassSource {
@@ -71,6 +107,17 @@ impl JarClassSource {
 			archive: Mutex::new(archive),
 		})
 	}
+
+	/// Every class inside of the jar, sorted by name.
+	pub fn classes(&self) -> Vec<ObjectType> {
+		let mut classes: Vec<ObjectType> = self
+			.file_lookup
+			.keys()
+			.map(|name| ObjectType::new(name.clone()))
+			.collect();
+		classes.sort();
+		classes
+	}
 }
 
 impl ClassSource for JarClassSource {
@@ -1,6 +1,6 @@
 use std::fmt::{Debug, Display, Formatter};
 
-use crate::{PrimitiveType, Type};
+use crate::{ArrayType, ObjectType, PrimitiveType, Type};
 
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
 pub struct MethodDescriptor {
@@ -153,3 +153,15 @@ impl_typed_prim!(i32 => Int);
 impl_typed_prim!(i64 => Long);
 impl_typed_prim!(f32 => Float);
 impl_typed_prim!(f64 => Double);
+
+impl Typed for String {
+	fn ty() -> Type {
+		ObjectType::String().into()
+	}
+}
+
+impl<V: Typed> Typed for Vec<V> {
+	fn ty() -> Type {
+		ArrayType::from_component(V::ty()).into()
+	}
+}
@@ -57,4 +57,33 @@ bitflags! {
 		const ANNOTATION = 0x2000;
 		const ENUM = 0x4000;
 	}
+
+	#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+	pub struct ParameterAccessFlags: u16 {
+		const FINAL = 0x0010;
+		const SYNTHETIC = 0x1000;
+		const MANDATED = 0x8000;
+	}
+
+	#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+	pub struct ModuleFlags: u16 {
+		const OPEN = 0x0020;
+		const SYNTHETIC = 0x1000;
+		const MANDATED = 0x8000;
+	}
+
+	#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+	pub struct ModuleRequiresFlags: u16 {
+		const TRANSITIVE = 0x0020;
+		const STATIC_PHASE = 0x0040;
+		const SYNTHETIC = 0x1000;
+		const MANDATED = 0x8000;
+	}
+
+	/// Used by both the exports and the opens of a module.
+	#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+	pub struct ModuleExportsFlags: u16 {
+		const SYNTHETIC = 0x1000;
+		const MANDATED = 0x8000;
+	}
 }
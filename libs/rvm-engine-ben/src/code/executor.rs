@@ -1,4 +1,3 @@
-use either::Either;
 use eyre::{bail, Context, ContextCompat};
 use std::panic::UnwindSafe;
 use std::sync::Arc;
@@ -11,7 +10,7 @@ use crate::{BenEngine, BenMethod};
 use rvm_core::{Id, MethodAccessFlags, MethodDescriptor, ObjectType, Type};
 use rvm_runtime::engine::Thread;
 use rvm_runtime::gc::{GcMarker, GcRef, GcSweeper, JavaUser, RootProvider};
-use rvm_runtime::native::{JNIFunction, JNIFunctionSignature};
+use rvm_runtime::native::{JNIFunction, JNIFunctionSignature, NativeFrames};
 use rvm_runtime::{AnyValue, CallType, MethodIdentifier, Reference, Runtime, ThreadContext, Vm};
 
 /// The executor is where the java code actually executes.
@@ -24,6 +23,7 @@ pub struct Executor {
 	pub vm: Vm,
 	pub java_scopes: Vec<JavaScope>,
 	pub frozen_references: Vec<Reference>,
+	pub native_frames: NativeFrames,
 }
 
 impl Executor {
@@ -137,10 +137,11 @@ impl Executor {
 						//}
 					}
 					Task::Return(_return) => {
-						let output = method.returns.map(|kind| {
-							let value = frame.pop();
-							value.convert(kind).unwrap()
-						});
+						let output = method
+							.returns
+							.map(|kind| frame.pop().convert(kind))
+							.transpose()
+							.wrap_err("Return value")?;
 
 						returned = Some(output);
 
@@ -157,7 +158,7 @@ impl Executor {
 					Task::Combine(v) => v
 						.exec(&mut frame)
 						.wrap_err_with(|| format!("Combine {}", v))?,
-					Task::Local(v) => v.exec(&mut frame),
+					Task::Local(v) => v.exec(&mut frame)?,
 					Task::Jump(task) => {
 						task.exec(&mut frame);
 						continue;
@@ -167,6 +168,14 @@ impl Executor {
 						frame.cursor = frame.cursor.checked_add_signed(offset as isize).unwrap();
 						continue;
 					}
+					Task::Jsr(task) => {
+						task.exec(&mut frame);
+						continue;
+					}
+					Task::Ret(task) => {
+						task.exec(&mut frame)?;
+						continue;
+					}
 					Task::Stack(task) => task.exec(&mut frame),
 					Task::Field(task) => {
 						task.exec(self)?;
@@ -181,7 +190,10 @@ impl Executor {
 					}
 					Task::ArrayLength(v) => v.exec(&mut frame),
 					Task::ArrayLoad(v) => v.exec(&mut frame),
-					Task::ArrayStore(v) => v.exec(&mut frame)?,
+					Task::ArrayStore(v) => {
+						v.exec(self)?;
+						frame = self.current_frame();
+					}
 					Task::ArrayCreate(v) => {
 						v.exec(self)?;
 						frame = self.current_frame();
@@ -310,7 +322,7 @@ impl Executor {
 
 		let method = self
 			.engine
-			.compile_method(&self.vm, method_class, method_id);
+			.compile_method(&self.vm, method_class, method_id)?;
 
 		Ok(match &*method {
 			BenMethod::Java(java) => {
@@ -333,8 +345,9 @@ impl Executor {
 					.push(
 						java.max_stack,
 						java.max_locals,
+						// Inherited methods run in the class which declares them.
 						FrameHeader {
-							class_id,
+							class_id: method_class,
 							method_id,
 							cursor: 0,
 						},
@@ -360,40 +373,58 @@ impl Executor {
 				self.java_scopes.push(scope);
 				ScopeResult::ContinueJava
 			}
-			BenMethod::Binding(binding) => ScopeResult::Return(
-				binding
-					.call(&self.vm, inputs.parameters)
-					.wrap_err("Failed externally")?,
-			),
+			BenMethod::Binding(binding) => {
+				// The values have left the frame, so the references are kept alive like the ones
+				// passed to a JNI native.
+				self.native_frames.push_frame(inputs.parameters.len() + 1);
+				let references = inputs.parameters.iter().filter_map(|value| match value {
+					AnyValue::Reference(reference) => Some(*reference),
+					_ => None,
+				});
+				for reference in inputs.instance.into_iter().chain(references) {
+					self.native_frames.new_local(reference);
+				}
+				let returned =
+					binding.call(&mut self.runtime(), inputs.instance, inputs.parameters);
+				self.native_frames.pop_frame();
+				ScopeResult::Return(returned.wrap_err("Failed externally")?)
+			}
 			BenMethod::Native(native, desc) => {
-				let mut linker = self.vm.linker.lock();
-				let option = linker
-					.get(native, |function| unsafe {
-						trace!("Calling native function");
-
-						let desc = desc.clone();
-
-						let Either::Left(value) = function else {
-							panic!("Cringe");
-						};
-						let jni_function = JNIFunction::new(
-							value,
-							JNIFunctionSignature {
-								parameters: desc.parameters.iter().map(|v| v.kind()).collect(),
-								returns: desc.returns.map(|v| v.kind()),
-							},
-						);
-
-						jni_function.call(
-							&self.vm,
-							&inputs.parameters,
-							method_descriptor.returns.as_ref().map(|v| v.kind()),
-						)
-					})
+				// The linker stays unlocked during the call, native code can call back into java.
+				let function = self
+					.vm
+					.linker
+					.lock()
+					.get(native, |function| function.left())
 					.wrap_err_with(|| {
-						format!("Could not find native function link for {native}{desc:?}")
-					})?;
-				ScopeResult::Return(option)
+						format!(
+							"Could not find native function link for {}{desc}",
+							native.short
+						)
+					})?
+					.wrap_err_with(|| format!("{} is linked to a rust binding", native.short))?;
+
+				trace!("Calling native function");
+				let jni_function = unsafe {
+					JNIFunction::new(
+						function,
+						JNIFunctionSignature {
+							parameters: desc.parameters.iter().map(|v| v.kind()).collect(),
+							returns: desc.returns.as_ref().map(|v| v.kind()),
+						},
+					)
+				};
+
+				// Static methods get their class instead of an instance.
+				let this = match inputs.instance {
+					Some(instance) => instance,
+					None => {
+						let class = self.vm.classes.get(method_class);
+						*class.to_instance().companion().class
+					}
+				};
+				let value = jni_function.call(&mut self.runtime(), this, &inputs.parameters)?;
+				ScopeResult::Return(value)
 			}
 		})
 	}
@@ -408,6 +439,29 @@ impl ThreadContext for Executor {
 		GcSweeper::wait_until_gc(self);
 	}
 
+	fn gc_sweeper(&mut self) -> &mut GcSweeper {
+		&mut self.sweeper
+	}
+
+	fn native_frames(&mut self) -> &mut NativeFrames {
+		&mut self.native_frames
+	}
+
+	unsafe fn enter_native(&mut self) {
+		unsafe { GcSweeper::enter_native(self) };
+	}
+
+	fn leave_native(&mut self) -> bool {
+		self.sweeper.leave_native()
+	}
+
+	fn detach(mut self: Box<Self>) {
+		self.sweeper.leave_native();
+		// Removing the sweeper waits for a running collection, which must not wait for us meanwhile.
+		unsafe { GcSweeper::enter_native(&mut *self) };
+		self.vm.gc.remove_sweeper(self.sweeper);
+	}
+
 	fn run(
 		&mut self,
 		call_type: CallType,
@@ -502,6 +556,8 @@ impl RootProvider<JavaUser> for Executor {
 		for reference in &self.frozen_references {
 			visitor.mark(**reference);
 		}
+		self.native_frames
+			.visit_refs(|reference| visitor.mark(*reference));
 		for frame in self.call_stack.iter() {
 			for value in frame.stack_slice() {
 				if let StackValue::Reference(reference) = value {
@@ -521,6 +577,8 @@ impl RootProvider<JavaUser> for Executor {
 		for reference in &mut self.frozen_references {
 			*reference = Reference::new(mapper(**reference));
 		}
+		self.native_frames
+			.map_refs(|reference| Reference::new(mapper(*reference)));
 		for mut frame in self.call_stack.iter_mut() {
 			for value in frame.stack_slice_mut() {
 				if let StackValue::Reference(reference) = value {
@@ -114,7 +114,8 @@ pub struct ArrayStoreTask(pub Kind);
 
 impl ArrayStoreTask {
 	#[inline(always)]
-	pub fn exec(&self, frame: &mut BenFrameMut) -> eyre::Result<()> {
+	pub fn exec(&self, executor: &mut Executor) -> eyre::Result<()> {
+		let mut frame = executor.current_frame();
 		let value = frame.pop();
 		let value = value.convert(self.0)?;
 
@@ -126,6 +127,12 @@ impl ArrayStoreTask {
 			panic!("Array type does not match");
 		}
 
+		if self.0 == Kind::Reference {
+			executor
+				.vm
+				.gc
+				.write_barrier(|| array.get(index).expect("Out of bounds"));
+		}
 		array.set(index, value);
 		Ok(())
 	}
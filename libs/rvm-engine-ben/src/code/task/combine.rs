@@ -1,8 +1,8 @@
 use crate::thread::{BenFrameMut, ThreadFrame};
-use crate::value::StackValue;
+use crate::value::{StackCastError, StackValue};
 use eyre::{bail, Context, ContextCompat};
 use num_traits::{Bounded, PrimInt, Signed, WrappingAdd, WrappingMul, WrappingSub, Zero};
-use rvm_core::{CastKindError, PrimitiveType};
+use rvm_core::PrimitiveType;
 use rvm_reader::MathInst;
 use rvm_runtime::Value;
 use std::fmt::{Display, Formatter};
@@ -186,8 +186,8 @@ fn java_ushr<V: PrimInt + Signed + WrappingAdd>(v0: V, v1: i32) -> V {
 
 fn cast_values<V0, V1>(v0: StackValue, v1: StackValue) -> eyre::Result<(V0, V1)>
 where
-	StackValue: TryInto<V0, Error = CastKindError>,
-	StackValue: TryInto<V1, Error = CastKindError>,
+	StackValue: TryInto<V0, Error = StackCastError>,
+	StackValue: TryInto<V1, Error = StackCastError>,
 {
 	let v0 = v0.try_into().wrap_err("Failed to convert value1")?;
 	let v1 = v1.try_into().wrap_err("Failed to convert value2")?;
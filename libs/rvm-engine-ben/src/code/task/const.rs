@@ -1,14 +1,10 @@
 use crate::code::Executor;
 use crate::thread::{BenFrameMut, ThreadFrame};
 use crate::value::StackValue;
-use rvm_core::{ObjectType, PrimitiveType, Type};
+use rvm_core::{ObjectType, Type};
 use rvm_reader::{ConstInst, ConstantInfo};
-use rvm_runtime::{
-	AnyValue, CallType, InstanceClass, MethodIdentifier, Reference, ReferenceKind, ThreadContext,
-};
+use rvm_runtime::{AnyValue, InstanceClass, Reference, ThreadContext, ToJava};
 use std::fmt::{Display, Formatter};
-use std::sync::Arc;
-use tracing::info;
 
 #[derive(Debug)]
 pub enum ConstTask {
@@ -81,43 +77,14 @@ impl ConstTask {
 			ConstTask::Float(v) => frame.push(StackValue::Float(*v)),
 			ConstTask::Double(v) => frame.push(StackValue::Double(*v)),
 			ConstTask::String(v) => {
-				info!("CREATING STYRING");
-
 				// TODO not create string instances every time ldc gets hit
-				let mut runtime = executor.runtime();
-
-				let array =
-					runtime.alloc_array(&PrimitiveType::Char.into(), v.chars().count() as u32)?;
-
-				executor.frozen_references.push(*array);
-				let i = executor.frozen_references.len();
-
-				let mut runtime = executor.runtime();
-
-				assert_eq!((*array).reference_kind(), Some(ReferenceKind::Array));
-				let id = runtime.resolve_class(&ObjectType::String().into())?;
-				let class = runtime.classes.get(id);
-				let class = class.to_instance();
-
-				let string = runtime.alloc_object(class)?;
-
-				assert_eq!(i, executor.frozen_references.len());
-				executor.frozen_references.pop();
-				let mut runtime = executor.runtime();
-
-				assert_eq!((*array).reference_kind(), Some(ReferenceKind::Array));
-				let _ = runtime.run(
-					CallType::Special,
-					&ObjectType::String(),
-					&MethodIdentifier {
-						name: Arc::from("<init>"),
-						descriptor: Arc::from("([C)V"),
-					},
-					vec![AnyValue::Reference(**string), AnyValue::Reference(*array)],
-				)?;
+				let AnyValue::Reference(string) = v.as_str().to_java(&mut executor.runtime())?
+				else {
+					unreachable!("Strings convert to references");
+				};
 
 				let mut frame = executor.current_frame();
-				frame.push(StackValue::Reference(**string));
+				frame.push(StackValue::Reference(string));
 			}
 			ConstTask::Class(ty) => {
 				let mut runtime = executor.runtime();
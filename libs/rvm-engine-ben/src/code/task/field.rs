@@ -75,7 +75,9 @@ impl FieldTask {
 							let class = reference.to_instance()?;
 							let instance = AnyInstance::try_new(runtime.clone(), class).unwrap();
 							let fields = instance.fields();
-							fields.by_id(id).set(value.to_any());
+							let field = fields.by_id(id);
+							runtime.gc.write_barrier(|| field.get());
+							field.set(value.to_any()?);
 						}
 					}
 				} else {
@@ -90,6 +92,7 @@ impl FieldTask {
 						FieldInstKind::Put => {
 							let value = frame.pop();
 							let value = value.convert(field.kind())?;
+							runtime.gc.write_barrier(|| field.get());
 							field.set(value);
 						}
 					}
@@ -1,9 +1,11 @@
 use std::fmt::{Debug, Display, Formatter};
 
+use eyre::bail;
 use rvm_core::StackKind;
 use rvm_reader::LocalInst;
 
 use crate::thread::{BenFrameMut, ThreadFrame};
+use crate::value::StackValue;
 
 #[derive(Debug)]
 pub struct LocalTask {
@@ -49,33 +51,31 @@ impl LocalTask {
 	}
 
 	#[inline(always)]
-	pub fn exec(&self, frame: &mut BenFrameMut) {
+	pub fn exec(&self, frame: &mut BenFrameMut) -> eyre::Result<()> {
 		let idx = self.idx;
 
 		match self.kind {
 			LocalTaskKind::Load => {
 				let stack_value = frame.load(idx);
-				if stack_value.kind() != self.ty {
-					panic!(
-						"Expected stack value {:?} but got {:?}",
-						self.ty,
-						stack_value.kind()
-					)
+				if stack_value.kind() != Some(self.ty) {
+					bail!("Expected local {:?} but got {stack_value}", self.ty);
 				}
 				frame.push(stack_value);
 			}
 			LocalTaskKind::Store => {
 				let value = frame.pop();
-				if value.kind() != self.ty {
-					panic!(
-						"Expected stack value {:?} but got {:?}",
-						self.ty,
-						value.kind()
-					)
+				// astore also stores the return addresses of subroutines.
+				let stored = match value {
+					StackValue::ReturnAddress(_) => self.ty == StackKind::Reference,
+					_ => value.kind() == Some(self.ty),
+				};
+				if !stored {
+					bail!("Expected stack value {:?} but got {value}", self.ty);
 				}
 
 				frame.store(idx, value);
 			}
 		}
+		Ok(())
 	}
 }
@@ -16,6 +16,7 @@ use crate::code::task::increment::IncrementTask;
 use crate::code::task::jump::JumpTask;
 use crate::code::task::object::NewTask;
 use crate::code::task::stack::StackTask;
+use crate::code::task::subroutine::{JsrTask, RetTask};
 use crate::code::task::switch::SwitchTableTask;
 
 mod array;
@@ -29,6 +30,7 @@ mod local;
 mod object;
 mod r#return;
 mod stack;
+mod subroutine;
 mod switch;
 
 #[derive(Debug)]
@@ -51,6 +53,8 @@ pub enum Task {
 	ArrayLoad(ArrayLoadTask),
 	ArrayStore(ArrayStoreTask),
 	SwitchTable(SwitchTableTask),
+	Jsr(JsrTask),
+	Ret(RetTask),
 	Unsupported(Inst),
 }
 
@@ -74,6 +78,8 @@ impl Display for Task {
 			Task::ArrayCreate(v) => v.fmt(f),
 			Task::ArrayCreateRef(v) => v.fmt(f),
 			Task::SwitchTable(v) => v.fmt(f),
+			Task::Jsr(v) => v.fmt(f),
+			Task::Ret(v) => v.fmt(f),
 			Task::Unsupported(v) => write!(f, "Unsupported {v:?}"),
 		}
 	}
@@ -118,6 +124,11 @@ impl Task {
 				Task::ArrayCreateRef(ArrayCreateRefTask::new(ptr, class))
 			}
 			Inst::TableSwitch(inst) => Task::SwitchTable(SwitchTableTask::new(inst)),
+			Inst::JSR(offset) => Task::Jsr(JsrTask {
+				offset: offset.0 as i32,
+			}),
+			Inst::JSR_W(offset) => Task::Jsr(JsrTask { offset: offset.0 }),
+			Inst::RET(local) => Task::Ret(RetTask { local: *local }),
 			i => Task::Unsupported(i.clone()),
 		}
 	}
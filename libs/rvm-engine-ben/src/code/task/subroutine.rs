@@ -0,0 +1,50 @@
+use eyre::bail;
+use std::fmt::{Display, Formatter};
+
+use crate::thread::BenFrameMut;
+use crate::value::StackValue;
+
+/// Jumps to a subroutine, with the index of the next task on the stack to return to.
+#[derive(Debug)]
+pub struct JsrTask {
+	pub offset: i32,
+}
+
+impl JsrTask {
+	pub fn exec(&self, frame: &mut BenFrameMut) {
+		let next = frame.cursor + 1;
+		frame.push(StackValue::ReturnAddress(next));
+		frame.cursor = frame
+			.cursor
+			.checked_add_signed(self.offset as isize)
+			.unwrap();
+	}
+}
+
+impl Display for JsrTask {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "JSR -> {}", self.offset)
+	}
+}
+
+/// Returns from a subroutine to the address in a local.
+#[derive(Debug)]
+pub struct RetTask {
+	pub local: u16,
+}
+
+impl RetTask {
+	pub fn exec(&self, frame: &mut BenFrameMut) -> eyre::Result<()> {
+		match frame.load(self.local) {
+			StackValue::ReturnAddress(cursor) => frame.cursor = cursor,
+			value => bail!("Returning to {value}, which is not a return address"),
+		}
+		Ok(())
+	}
+}
+
+impl Display for RetTask {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "RET {}", self.local)
+	}
+}
@@ -8,12 +8,12 @@ use std::sync::{Arc, RwLock};
 use tracing::{debug, info};
 
 use crate::method::JavaMethod;
-use crate::thread::spawn;
+use crate::thread::{attach, spawn};
 use rvm_core::{Id, MethodAccessFlags, MethodDescriptor, Storage, StorageValue};
 use rvm_reader::ConstantPool;
 use rvm_runtime::engine::{Engine, ThreadConfig, ThreadHandle};
-use rvm_runtime::native::JNIFunction;
-use rvm_runtime::{Class, Method, MethodBinding, MethodCode, MethodIdentifier, Vm};
+use rvm_runtime::native::{JNIFunction, NativeName};
+use rvm_runtime::{Class, Method, MethodBinding, MethodCode, MethodIdentifier, ThreadContext, Vm};
 
 mod code;
 mod method;
@@ -59,10 +59,10 @@ impl BenEngine {
 		runtime: &Vm,
 		id: Id<Class>,
 		method_id: Id<Method>,
-	) -> Arc<BenMethod> {
+	) -> eyre::Result<Arc<BenMethod>> {
 		let methods = self.methods.read().unwrap();
 		if let Some(method) = methods.get_keyed(&(id, method_id)) {
-			return method.clone();
+			return Ok(method.clone());
 		}
 		drop(methods);
 
@@ -88,12 +88,11 @@ impl BenEngine {
 					if let Some(binding) =
 						runtime
 							.bindings
-							.get_binding(&instance.ty, &method.name, &method.desc)
+							.get_binding(&instance.ty, &method.name, &method.desc)?
 					{
 						BenMethod::Binding(binding)
 					} else {
-						let name =
-							format!("Java_{}_{}", instance.ty.replace('/', "_"), method.name);
+						let name = NativeName::new(&instance.ty, &method.name, &method.desc);
 						BenMethod::Native(name, method.desc.clone())
 					}
 				} else {
@@ -104,13 +103,13 @@ impl BenEngine {
 
 		let mut guard = self.methods.write().unwrap();
 		guard.insert((id, method_id), ben_method.clone());
-		ben_method
+		Ok(ben_method)
 	}
 }
 
 pub enum BenMethod {
 	Java(JavaMethod),
-	Native(String, MethodDescriptor),
+	Native(NativeName, MethodDescriptor),
 	Binding(Arc<MethodBinding>),
 }
 
@@ -141,11 +140,17 @@ impl BenBinding {
 	}
 }
 
+rvm_runtime::export_jni_create_java_vm!(BenBinding::new());
+
 impl Engine for BenBinding {
 	fn create_thread(&self, runtime: Vm, config: ThreadConfig) -> ThreadHandle {
 		spawn(runtime, config, 1024 * 1024, self.engine.clone())
 	}
 
+	fn attach_thread(&self, runtime: Vm, config: ThreadConfig) -> Box<dyn ThreadContext> {
+		Box::new(attach(runtime, config, 1024 * 1024, self.engine.clone()))
+	}
+
 	fn compile_method(
 		&self,
 		_runtime: &Pin<&Vm>,
@@ -1,6 +1,8 @@
+use crossbeam::channel::unbounded;
 use eyre::Context;
 use rvm_core::Id;
-use rvm_runtime::engine::{ThreadCommand, ThreadConfig, ThreadHandle};
+use rvm_runtime::engine::{Thread, ThreadCommand, ThreadConfig, ThreadHandle};
+use rvm_runtime::gc::GcSweeper;
 use rvm_runtime::{CallType, Class, Method, ThreadContext, Vm};
 use rvm_stack::StackUser;
 pub use stack::{ThreadFrame, ThreadStack};
@@ -37,6 +39,24 @@ pub struct FrameHeader {
 	pub cursor: usize,
 }
 
+/// Makes the current thread a java thread, nothing sends commands to an attached thread.
+pub fn attach(runtime: Vm, config: ThreadConfig, size: usize, engine: Arc<BenEngine>) -> Executor {
+	let (_, receiver) = unbounded();
+	Executor {
+		thread: Thread {
+			config: Arc::new(config),
+			receiver,
+		},
+		call_stack: BenCallStack::new_on_heap(size),
+		engine,
+		sweeper: runtime.gc.new_sweeper(),
+		vm: runtime,
+		java_scopes: vec![],
+		frozen_references: vec![],
+		native_frames: Default::default(),
+	}
+}
+
 pub fn spawn(
 	runtime: Vm,
 	config: ThreadConfig,
@@ -69,6 +89,7 @@ pub fn spawn(
 							java_scopes: vec![],
 							sweeper,
 							frozen_references: vec![],
+							native_frames: Default::default(),
 						};
 
 						let output = panic::catch_unwind(AssertUnwindSafe(|| {
@@ -77,6 +98,8 @@ pub fn spawn(
 								.wrap_err_with(|| format!("Running in thread \"{}\"", config.name))
 						}));
 
+						executor.sweeper.leave_native();
+						unsafe { GcSweeper::enter_native(&mut executor) };
 						executor.vm.gc.remove_sweeper(executor.sweeper);
 
 						match output {
@@ -1,7 +1,9 @@
 use bytemuck::Zeroable;
 use derive_more::From;
-use rvm_core::{CastKindError, Kind, StackKind};
+use eyre::bail;
+use rvm_core::{Kind, StackKind};
 use rvm_runtime::{AnyValue, Reference, ReferenceKind};
+use std::error::Error;
 use std::fmt::{Display, Formatter};
 
 #[repr(C)]
@@ -13,6 +15,9 @@ pub enum StackValue {
 	Long(i64),
 	Double(f64),
 	Reference(Reference),
+	/// The index of the task after a `jsr`, which its subroutine returns to.
+	#[from(ignore)]
+	ReturnAddress(usize),
 }
 
 unsafe impl Zeroable for StackValue {}
@@ -28,6 +33,33 @@ impl Display for StackValue {
 			StackValue::Reference(v) => {
 				write!(f, "{v:?}")
 			}
+			StackValue::ReturnAddress(v) => write!(f, "->{v}"),
+		}
+	}
+}
+
+/// A value on the stack which is not of the kind an instruction expects.
+#[derive(Debug, Copy, Clone)]
+pub struct StackCastError {
+	pub expected: Kind,
+	pub found: StackValue,
+}
+
+impl Error for StackCastError {}
+
+impl Display for StackCastError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		match self.found.kind() {
+			Some(found) => write!(
+				f,
+				"Cast error! Expected {} but found {found}",
+				self.expected
+			),
+			None => write!(
+				f,
+				"Cast error! Expected {} but found a return address",
+				self.expected
+			),
 		}
 	}
 }
@@ -36,12 +68,12 @@ macro_rules! to_impl {
 	($($METHOD_NAME:ident $KIND:ident $TY:ty),*) => {
 		impl StackValue {
 			$(
-				pub fn $METHOD_NAME(self) -> Result<$TY, CastKindError> {
+				pub fn $METHOD_NAME(self) -> Result<$TY, StackCastError> {
 					match self {
 						StackValue::$KIND(value) => Ok(value),
-						_ => Err(CastKindError {
+						_ => Err(StackCastError {
 							expected: Kind::$KIND,
-							found: self.kind().kind(),
+							found: self,
 						}),
 					}
 				}
@@ -50,7 +82,7 @@ macro_rules! to_impl {
 
 		$(
 			impl TryInto<$TY> for StackValue {
-				type Error = CastKindError;
+				type Error = StackCastError;
 
 				fn try_into(self) -> Result<$TY, Self::Error> {
 					self.$METHOD_NAME()
@@ -68,14 +100,16 @@ to_impl!(
 	to_ref Reference Reference
 );
 impl StackValue {
-	pub fn kind(&self) -> StackKind {
+	/// The kind of the value, return addresses only exist inside of a method and have none.
+	pub fn kind(&self) -> Option<StackKind> {
 		match self {
-			StackValue::Char(_) => StackKind::Char,
-			StackValue::Int(_) => StackKind::Int,
-			StackValue::Float(_) => StackKind::Float,
-			StackValue::Long(_) => StackKind::Long,
-			StackValue::Double(_) => StackKind::Double,
-			StackValue::Reference(_) => StackKind::Reference,
+			StackValue::Char(_) => Some(StackKind::Char),
+			StackValue::Int(_) => Some(StackKind::Int),
+			StackValue::Float(_) => Some(StackKind::Float),
+			StackValue::Long(_) => Some(StackKind::Long),
+			StackValue::Double(_) => Some(StackKind::Double),
+			StackValue::Reference(_) => Some(StackKind::Reference),
+			StackValue::ReturnAddress(_) => None,
 		}
 	}
 
@@ -85,6 +119,7 @@ impl StackValue {
 			StackValue::Int(_) => 1,
 			StackValue::Float(_) => 1,
 			StackValue::Reference(_) => 1,
+			StackValue::ReturnAddress(_) => 1,
 			StackValue::Long(_) => 2,
 			StackValue::Double(_) => 2,
 		}
@@ -112,7 +147,7 @@ impl StackValue {
 		}
 	}
 
-	pub fn convert(self, kind: Kind) -> Result<AnyValue, CastKindError> {
+	pub fn convert(self, kind: Kind) -> Result<AnyValue, StackCastError> {
 		match kind {
 			Kind::Boolean => {
 				if let StackValue::Int(value) = self {
@@ -160,21 +195,22 @@ impl StackValue {
 			}
 		}
 
-		Err(CastKindError {
+		Err(StackCastError {
 			expected: kind,
-			found: self.kind().kind(),
+			found: self,
 		})
 	}
 
-	pub fn to_any(self) -> AnyValue {
-		match self {
+	pub fn to_any(self) -> eyre::Result<AnyValue> {
+		Ok(match self {
 			StackValue::Char(value) => AnyValue::Char(value),
 			StackValue::Int(value) => AnyValue::Int(value),
 			StackValue::Float(value) => AnyValue::Float(value),
 			StackValue::Long(value) => AnyValue::Long(value),
 			StackValue::Double(value) => AnyValue::Double(value),
 			StackValue::Reference(value) => AnyValue::Reference(value),
-		}
+			StackValue::ReturnAddress(_) => bail!("Return addresses can not leave their method"),
+		})
 	}
 }
 //#[derive(Copy, Clone)]
@@ -102,7 +102,7 @@ impl Task {
 				todo!("field")
 			}
 			// alpha reading challange any%
-			Inst::LOOKUPSWITCH => todo!("read"),
+			Inst::LookupSwitch(_) => todo!("read"),
 			Inst::TableSwitch => todo!("read"),
 			Inst::MONITORENTER => todo!("read"),
 			Inst::MONITOREXIT => todo!("read"),
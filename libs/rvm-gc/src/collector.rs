@@ -1,20 +1,53 @@
+use crate::handles::HandleTable;
+use crate::tlab::Tlab;
 use crate::{
-	new_sweeper, GcHeader, GcMarker, GcRef, GcSweeper, GcSweeperHandle, GcUser, ObjectFlags,
-	ObjectSize, ALIGNMENT, ALIGNMENT_BITS,
+	new_sweeper, GCStatistics, GcHeader, GcMarker, GcPauses, GcRef, GcRequest, GcSweeper,
+	GcSweeperHandle, GcTotals, GcUser, GlobalHandle, ObjectFlags, ObjectSize, SatbBarrier,
+	WeakHandle, ALIGNMENT, ALIGNMENT_BITS, TLAB_MAX_OBJECT_SIZE, TLAB_SIZE,
 };
 use ahash::{HashMap, HashMapExt};
-use parking_lot::Mutex;
+use parking_lot::{Mutex, MutexGuard};
 use rvm_core::{Kind, PrimitiveType};
 use std::alloc::{alloc_zeroed, dealloc, Layout};
 use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{available_parallelism, scope, yield_now};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use tracing::{debug, trace};
 use uuid::Uuid;
 
 pub const OBJECT_ALIGNMENT: usize = 8;
+/// The size of the heap regions which get forwarded and moved in parallel.
+const REGION_SIZE: usize = 256 * 1024;
+
+/// Gets called with the statistics of every finished collection.
+pub type GcSubscriber = dyn Fn(&GCStatistics) + Send + Sync;
+
+/// How the garbage collector marks the heap.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum GcMode {
+	/// Every thread is stopped for the whole collection.
+	#[default]
+	StopTheWorld,
+	/// Threads are only stopped to mark their roots and for compaction, marking runs next to them.
+	/// Reference stores have to go through [`GarbageCollector::write_barrier`] in this mode.
+	Concurrent,
+}
 
 pub struct GarbageCollector<U: GcUser> {
 	inner: Mutex<InnerGarbageCollector<U>>,
+	size: usize,
+	mode: GcMode,
+	barrier: Arc<SatbBarrier>,
+	// Kept outside of the heap lock, so threads with a sweeper can use them while a collection waits for them.
+	roots: Arc<Mutex<HandleTable<U>>>,
+	// Held for the whole collection, as concurrent collections release the heap lock while marking.
+	collecting: Mutex<()>,
+	// These are kept outside of the heap lock, so they can be read while a collection is running.
+	totals: Mutex<GcTotals>,
+	subscribers: Mutex<Vec<Arc<GcSubscriber>>>,
 }
 
 unsafe impl<U: GcUser> Sync for GarbageCollector<U> {}
@@ -23,22 +56,37 @@ unsafe impl<U: GcUser> Send for GarbageCollector<U> {}
 
 impl<U: GcUser> GarbageCollector<U> {
 	pub fn new(size: usize) -> Self {
+		Self::with_mode(size, GcMode::default())
+	}
+
+	pub fn with_mode(size: usize, mode: GcMode) -> Self {
 		let layout = Layout::from_size_align(size, ALIGNMENT_BITS).unwrap();
 		let data = unsafe { alloc_zeroed(layout) };
 
 		assert!(data.is_aligned_to(ALIGNMENT));
 
+		let barrier = Arc::new(SatbBarrier::default());
+		let roots = Arc::new(Mutex::new(HandleTable::new()));
 		Self {
 			inner: Mutex::new(InnerGarbageCollector {
 				handles: HashMap::new(),
+				barrier: barrier.clone(),
+				roots: roots.clone(),
 				frozen: HashSet::new(),
 				mark: false,
+				workers: available_parallelism().map(|v| v.get()).unwrap_or(1),
 				size,
-				objects: 0,
 				layout,
 				free: data,
 				data,
 			}),
+			size,
+			mode,
+			barrier,
+			roots,
+			collecting: Mutex::new(()),
+			totals: Mutex::new(GcTotals::default()),
+			subscribers: Mutex::new(Vec::new()),
 		}
 	}
 
@@ -58,8 +106,134 @@ impl<U: GcUser> GarbageCollector<U> {
 		self.inner.lock().remove_frozen(reference)
 	}
 
+	/// Keeps `reference` alive and at the same address until it gets unpinned, an object which
+	/// has been pinned multiple times has to be unpinned as often.
+	pub fn pin(&self, reference: GcRef<U>) {
+		self.roots.lock().pin(reference)
+	}
+
+	pub fn unpin(&self, reference: GcRef<U>) {
+		self.roots.lock().unpin(reference)
+	}
+
+	/// Keeps `reference` alive until the handle gets removed, the same object may have many handles.
+	pub fn new_global(&self, reference: GcRef<U>) -> GlobalHandle {
+		self.roots.lock().new_global(reference)
+	}
+
+	/// The current location of the object, this changes with every collection.
+	pub fn global(&self, handle: GlobalHandle) -> GcRef<U> {
+		self.roots.lock().global(handle)
+	}
+
+	pub fn remove_global(&self, handle: GlobalHandle) {
+		self.roots.lock().remove_global(handle)
+	}
+
+	pub fn new_weak(&self, reference: GcRef<U>) -> WeakHandle {
+		self.roots.lock().new_weak(reference)
+	}
+
+	/// The object of a weak handle, or null once it has been collected.
+	pub fn weak(&self, handle: WeakHandle) -> GcRef<U> {
+		let reference = self.roots.lock().weak(handle);
+		// The object might not be reachable in the snapshot of a running mark, it is now.
+		if self.is_marking() {
+			self.write_barrier(reference);
+		}
+		reference
+	}
+
+	pub fn remove_weak(&self, handle: WeakHandle) {
+		self.roots.lock().remove_weak(handle)
+	}
+
 	pub fn gc(&self) -> GCStatistics {
-		self.inner.lock().gc()
+		let Some(_collecting) = self.collecting.try_lock() else {
+			// Someone else is already collecting, that collection is just as good as ours.
+			drop(self.collecting.lock());
+			return self.totals.lock().last.clone().unwrap_or_default();
+		};
+
+		let mut statistics = match self.mode {
+			GcMode::StopTheWorld => self.inner.lock().gc(),
+			GcMode::Concurrent => self.concurrent_gc(),
+		};
+		self.totals.lock().record(&mut statistics);
+		debug!(
+			"GC #{} complete: removed {} objects ({} bytes), {} remaining, paused for {:?}",
+			statistics.collection,
+			statistics.objects_cleared,
+			statistics.bytes_reclaimed(),
+			statistics.objects_remaining,
+			statistics.pauses.total()
+		);
+
+		// Subscribers may subscribe themselves, so they are not called under the lock.
+		let subscribers = self.subscribers.lock().clone();
+		for subscriber in subscribers {
+			subscriber(&statistics);
+		}
+		statistics
+	}
+
+	fn concurrent_gc(&self) -> GCStatistics {
+		let mut inner = self.inner.lock();
+		let workers = inner.workers;
+		let mut pauses = GcPauses::default();
+		let marker = inner.start_concurrent_mark(&mut pauses);
+		drop(inner);
+
+		// The threads keep running and allocating while we trace, anything they overwrite gets marked by the barrier.
+		let started = Instant::now();
+		marker.trace::<U>(workers);
+		let concurrent_mark = started.elapsed();
+
+		let mut statistics = self.inner.lock().collect(marker, false, pauses);
+		statistics.concurrent_mark = concurrent_mark;
+		statistics
+	}
+
+	pub fn mode(&self) -> GcMode {
+		self.mode
+	}
+
+	/// If the write barrier needs to be called, this is only the case while a concurrent mark is running.
+	#[inline(always)]
+	pub fn is_marking(&self) -> bool {
+		self.barrier.is_active()
+	}
+
+	/// Has to be called with the old value of a reference slot before it gets overwritten, while [`Self::is_marking`].
+	pub fn write_barrier(&self, old: GcRef<U>) {
+		if !old.is_null() {
+			self.barrier.record(old);
+		}
+	}
+
+	/// Calls `subscriber` after every collection, on the thread which ran the collection.
+	pub fn subscribe(&self, subscriber: impl Fn(&GCStatistics) + Send + Sync + 'static) {
+		self.subscribers.lock().push(Arc::new(subscriber));
+	}
+
+	/// The cumulative statistics of every collection so far.
+	pub fn totals(&self) -> GcTotals {
+		self.totals.lock().clone()
+	}
+
+	pub fn size(&self) -> usize {
+		self.size
+	}
+
+	/// Stops every thread and gives a view of the heap and all of its roots, nothing gets collected or moved.
+	/// This may not be called from a thread which owns a sweeper, as that thread would never stop.
+	pub fn inspect<O>(&self, func: impl FnOnce(&HeapView<U>) -> O) -> O {
+		let _collecting = self.collecting.lock();
+		self.inner.lock().inspect(func)
+	}
+
+	pub fn set_workers(&self, workers: usize) {
+		self.inner.lock().workers = workers.max(1);
 	}
 
 	pub fn used(&self) -> usize {
@@ -73,14 +247,61 @@ impl<U: GcUser> GarbageCollector<U> {
 	) -> Result<GcRef<U>, AllocationError> {
 		self.inner.lock().allocate(data_size, header)
 	}
+
+	/// Allocates in the thread-local allocation buffer of the sweeper, this only takes the heap lock when the buffer needs a refill.
+	pub fn alloc_tlab(
+		&self,
+		sweeper: &mut GcSweeper,
+		data_size: usize,
+		header: U::Header,
+	) -> Result<GcRef<U>, AllocationError> {
+		if data_size > ObjectSize::MAX as usize {
+			return Err(AllocationError::ObjectTooBig);
+		}
+
+		let total_size = GcRef::<U>::calc_total_size(data_size);
+		if total_size > TLAB_MAX_OBJECT_SIZE {
+			return self.lock_for(sweeper)?.allocate(data_size, header);
+		}
+
+		let header = match sweeper.tlab.allocate(total_size, header) {
+			Ok(gc_ref) => return Ok(gc_ref),
+			Err(header) => header,
+		};
+
+		self.lock_for(sweeper)?.refill_tlab(sweeper, total_size)?;
+		sweeper
+			.tlab
+			.allocate(total_size, header)
+			.map_err(|_| AllocationError::OutOfHeap)
+	}
+
+	/// Locks the heap from a thread which owns a sweeper. The collector holds the lock while it waits
+	/// for the thread to stop, so instead of blocking the thread has to yield to the collector first.
+	fn lock_for(
+		&self,
+		sweeper: &GcSweeper,
+	) -> Result<MutexGuard<'_, InnerGarbageCollector<U>>, AllocationError> {
+		loop {
+			if let Some(inner) = self.inner.try_lock_for(Duration::from_millis(1)) {
+				return Ok(inner);
+			}
+			if sweeper.should_yield.load(Ordering::Relaxed) {
+				return Err(AllocationError::Collecting);
+			}
+		}
+	}
 }
 
 pub struct InnerGarbageCollector<U: GcUser> {
 	handles: HashMap<Uuid, GcSweeperHandle>,
+	barrier: Arc<SatbBarrier>,
+	roots: Arc<Mutex<HandleTable<U>>>,
 	frozen: HashSet<GcRef<U>>,
 	mark: bool,
+	/// The amount of threads used for marking and compacting.
+	workers: usize,
 	size: usize,
-	objects: usize,
 	layout: Layout,
 	/// This is the pointer to the end of the used data
 	free: *mut u8,
@@ -97,9 +318,39 @@ impl<U: GcUser> InnerGarbageCollector<U> {
 
 	pub fn remove_sweeper(&mut self, mut gc_sweeper: GcSweeper) {
 		assert!(self.handles.remove(&gc_sweeper.uuid).is_some());
+		gc_sweeper.tlab.retire::<U>();
 		gc_sweeper.finished = true;
 	}
 
+	/// Retires the current buffer of the sweeper and carves a new one from the heap which fits at least `min_size`.
+	pub fn refill_tlab(
+		&mut self,
+		sweeper: &mut GcSweeper,
+		min_size: usize,
+	) -> Result<(), AllocationError> {
+		sweeper.tlab.retire::<U>();
+
+		// The heap may never become completely full, same as in allocate.
+		let available = (self.size - self.used()).saturating_sub(1);
+		let mut size = TLAB_SIZE.min(available) / ALIGNMENT * ALIGNMENT;
+		if size < min_size {
+			return Err(AllocationError::OutOfHeap);
+		}
+
+		// The tail has to be able to fit a filler.
+		if size - min_size < GcHeader::<U>::SIZE {
+			size = min_size;
+		}
+
+		unsafe {
+			let start = self.free;
+			self.free = self.free.add(size);
+			trace!("Refilled TLAB {:?}-{:?}", start, self.free);
+			sweeper.tlab = Tlab::new(start, self.free, self.mark);
+		}
+		Ok(())
+	}
+
 	pub fn add_frozen(&mut self, reference: GcRef<U>) {
 		if !self.frozen.insert(reference) {
 			panic!("Double insertion!");
@@ -131,7 +382,6 @@ impl<U: GcUser> InnerGarbageCollector<U> {
 			return Err(AllocationError::OutOfHeap);
 		}
 
-		self.objects += 1;
 		trace!(
 			"Allocating {}/{} {}+{} at {} {:?}.",
 			after_bits,
@@ -172,70 +422,165 @@ impl<U: GcUser> InnerGarbageCollector<U> {
 
 	pub(super) fn gc(&mut self) -> GCStatistics {
 		debug!("Starting garbage collection");
+		let marker = GcMarker::new(!self.mark);
+		self.collect(marker, true, GcPauses::default())
+	}
+
+	/// The first pause of a concurrent collection, this marks the roots and enables the write barrier.
+	/// The returned marker still has to trace everything reachable from the roots.
+	pub(super) fn start_concurrent_mark(&mut self, pauses: &mut GcPauses) -> GcMarker {
+		debug!("Starting concurrent garbage collection");
+		let phase = Instant::now();
+		let marker = GcMarker::new(!self.mark);
+		for handle in self.handles.values() {
+			handle.start(GcRequest::MarkRoots(marker.clone()));
+		}
+
+		// Everything allocated from here on is marked, as the buffers of the threads have been retired.
+		self.mark = !self.mark;
+		pauses.stop += phase.elapsed();
+		let phase = Instant::now();
+
+		for handle in self.handles.values() {
+			handle.start_marking();
+		}
+		for reference in &self.frozen {
+			marker.mark(*reference);
+		}
+		self.roots.lock().mark(&marker);
+		for handle in self.handles.values() {
+			handle.wait_complete();
+		}
+
+		self.barrier.enable(marker.clone());
+		for handle in self.handles.values() {
+			handle.continue_execution();
+		}
+		pauses.mark += phase.elapsed();
+		marker
+	}
+
+	/// Stops every thread, finishes marking and compacts the heap.
+	/// The mark is only flipped when marking has not been started by [`Self::start_concurrent_mark`].
+	pub(super) fn collect(
+		&mut self,
+		marker: GcMarker,
+		flip: bool,
+		mut pauses: GcPauses,
+	) -> GCStatistics {
+		let mut phase = Instant::now();
 
 		// Stops all threads
 		debug!("Stopping threads");
 		for handle in self.handles.values() {
-			let new_mark = !self.mark;
-			handle.start(new_mark);
+			handle.start(GcRequest::Collect(marker.clone()));
 		}
 
-		self.mark = !self.mark;
+		if flip {
+			self.mark = !self.mark;
+		}
+		// Nothing can get overwritten anymore, the rest of marking happens in this pause.
+		self.barrier.disable();
+		pauses.stop += phase.elapsed();
+		phase = Instant::now();
+		// The threads keep allocating during a concurrent mark, so this is only known once they stopped.
+		let heap_before = self.used();
 
-		// Makes all threads start marking
+		// Makes all threads mark their roots at the same time
 		debug!("Marking threads");
 		for handle in self.handles.values() {
 			handle.start_marking();
 		}
 		for reference in &self.frozen {
-			GcMarker { mark: self.mark }.mark(*reference)
-		}
-
-		//use std::fmt::Write;
-		//let mut build = String::new();
-		//let mut gc_mark = self.mark;
-		//self.walk(|mark, reference| {
-		//	if mark == gc_mark {
-		//		writeln!(&mut build, "x{} [color=green]", reference.0 as usize,).unwrap();
-		//	} else {
-		//		writeln!(&mut build, "x{} [color=gray]", reference.0 as usize,).unwrap();
-		//	}
-		//	reference.visit_refs(|r| {
-		//		if r.is_null() {
-		//			return;
-		//		}
-		//		writeln!(&mut build, "x{} -> x{}", reference.0 as usize, r.0 as usize).unwrap();
-		//	});
-		//});
-
-		//// Visit all of the objects, and mark them as visitable.
-		//roots.mark_roots(GcMarker { mark: self.mark });
+			marker.mark(*reference);
+		}
+		self.roots.lock().mark(&marker);
+		for handle in self.handles.values() {
+			handle.wait_complete();
+		}
+
+		// Trace everything reachable from the roots
+		marker.trace::<U>(self.workers);
+		self.roots.lock().clear_unmarked(self.mark);
+		pauses.mark += phase.elapsed();
+		phase = Instant::now();
 
 		debug!("Calculating targets");
+		// Pinned objects can not move, the heap around them gets compacted into the gaps between them.
+		let mark = self.mark;
+		let mut pinned: Vec<GcRef<U>> = self.roots.lock().pinned().collect();
+		pinned.sort_unstable_by_key(|reference| reference.head_ptr() as usize);
+		for reference in &mut pinned {
+			unsafe {
+				reference.set_forward(reference.head_ptr());
+			}
+		}
+
+		// Split the heap into regions, every region gets compacted into a destination right after the previous region.
+		let mut regions = self.regions(&pinned);
+		let live = parallel_map(self.workers, regions.len(), |i| {
+			let region = &regions[i];
+			let mut live_size = 0;
+			let mut live_objects = 0;
+			let mut dead_objects = 0;
+			walk_range::<U>(region.start, region.end, |object_mark, reference| {
+				if object_mark == mark {
+					live_size += reference.total_size();
+					live_objects += 1;
+				} else {
+					dead_objects += 1;
+				}
+			});
+			(live_size, live_objects, dead_objects)
+		});
+
+		// A region which does not fit in front of the next pin goes behind it, the rest of the gap in
+		// front of the pin becomes a filler. A region always fits in front of the pins after it.
+		let mut new_free_ptr = self.data as usize;
+		let mut gaps = Vec::new();
+		let mut pins = pinned.iter().peekable();
+		let mut alive_objects = pinned.len();
+		let mut cleared_objects = 0;
+		for (region, (live_size, live_objects, dead_objects)) in regions.iter_mut().zip(live) {
+			while let Some(pin) = pins.peek() {
+				let head = pin.head_ptr() as usize;
+				let end = new_free_ptr + live_size;
+				if end == head || end + GcHeader::<U>::SIZE <= head {
+					break;
+				}
+				gaps.push((new_free_ptr, head));
+				new_free_ptr = head + pin.total_size();
+				pins.next();
+			}
+			region.destination = new_free_ptr;
+			region.live_size = live_size;
+			new_free_ptr += live_size;
+			alive_objects += live_objects;
+			cleared_objects += dead_objects;
+		}
+		for pin in pins {
+			gaps.push((new_free_ptr, pin.head_ptr() as usize));
+			new_free_ptr = pin.head_ptr() as usize + pin.total_size();
+		}
+
 		// Go through all objects, and find the location where the object will soon be moved to,
 		// we store this in the forward field in the object so we can move references in step 3.
-		let mut new_free_ptr = self.data;
-		let mut alive_objects = 0;
-		self.walk_alive(|mut pointer| unsafe {
-			//writeln!(&mut build, "root -> x{}", pointer.0 as usize).unwrap();
-			alive_objects += 1;
-
-			// Set the forward field to the soon to be the new object location.
-			pointer.set_forward(new_free_ptr);
-
-			trace!(
-				"Object {:?} next is {:?} ",
-				pointer.data_ptr(),
-				new_free_ptr
+		parallel_map(self.workers, regions.len(), |i| {
+			let region = &regions[i];
+			let mut new_ptr = region.destination as *mut u8;
+			walk_range::<U>(
+				region.start,
+				region.end,
+				|object_mark, mut pointer| unsafe {
+					if object_mark == mark {
+						pointer.set_forward(new_ptr);
+						trace!("Object {:?} next is {:?} ", pointer.data_ptr(), new_ptr);
+						new_ptr = new_ptr.add(pointer.total_size());
+					}
+				},
 			);
-
-			// Increment the free pointer by the size of the object
-			let object_size = pointer.total_size();
-			new_free_ptr = new_free_ptr.add(object_size);
 		});
 
-		//write(format!("./out{}.txt", self.objects), build).unwrap();
-
 		debug!("Moving references");
 		// Move frozen slots to the new locations
 		let mut new_frozen = HashSet::new();
@@ -245,6 +590,9 @@ impl<U: GcUser> InnerGarbageCollector<U> {
 			}
 		}
 		self.frozen = new_frozen;
+		self.roots
+			.lock()
+			.remap(|reference| unsafe { reference.forward() });
 
 		// This sets the roots to the new references
 		for handle in self.handles.values() {
@@ -252,11 +600,25 @@ impl<U: GcUser> InnerGarbageCollector<U> {
 		}
 
 		// This goes to the ref contents and makes sure that their children are pointing to the new references
-		self.walk_alive(|pointer| {
-			trace!("Updating {pointer:?}");
+		for pointer in &pinned {
 			pointer.map_refs(|r| unsafe { r.forward() });
+		}
+		parallel_map(self.workers, regions.len(), |i| {
+			let region = &regions[i];
+			walk_range::<U>(region.start, region.end, |object_mark, pointer| {
+				if object_mark == mark {
+					trace!("Updating {pointer:?}");
+					pointer.map_refs(|r| unsafe { r.forward() });
+				}
+			});
 		});
 
+		for handle in self.handles.values() {
+			handle.wait_complete();
+		}
+		pauses.forward = phase.elapsed();
+		phase = Instant::now();
+
 		debug!("Dropping data");
 		self.walk_marked_for_deletion(|pointer| unsafe {
 			U::drop_ref(pointer);
@@ -264,19 +626,52 @@ impl<U: GcUser> InnerGarbageCollector<U> {
 
 		debug!("Moving data");
 		// This goes through all of the live objects, and moves them to their new location, (which is always behind).
-		// Go through the live objects, and move them to their new locations.
-		self.walk_alive(|mut pointer| unsafe {
-			pointer.move_forward();
+		// A region may only be moved once every region which overlaps with its destination has been moved out of the way.
+		let moved: Vec<AtomicBool> = regions.iter().map(|_| AtomicBool::new(false)).collect();
+		parallel_map(self.workers, regions.len(), |i| {
+			let region = &regions[i];
+			let destination_end = region.destination + region.live_size;
+			for (j, other) in regions[..i].iter().enumerate() {
+				if other.start < destination_end && other.end > region.destination {
+					while !moved[j].load(Ordering::Acquire) {
+						yield_now();
+					}
+				}
+			}
+
+			walk_range::<U>(
+				region.start,
+				region.end,
+				|object_mark, mut pointer| unsafe {
+					if object_mark == mark {
+						pointer.move_forward();
+					}
+				},
+			);
+			moved[i].store(true, Ordering::Release);
 		});
+		for (start, end) in gaps {
+			if start < end {
+				unsafe {
+					GcHeader::<U>::write_filler(start as *mut u8, end - start);
+				}
+			}
+		}
 
 		debug!("Finalizing");
 		// Set the free pointer to the new limit.
-		self.free = new_free_ptr;
+		self.free = new_free_ptr as *mut u8;
+		pauses.moving = phase.elapsed();
 		let statistics = GCStatistics {
-			objects_cleared: self.objects - alive_objects,
+			collection: 0,
+			objects_cleared: cleared_objects,
 			objects_remaining: alive_objects,
+			heap_before,
+			heap_after: self.used(),
+			heap_size: self.size,
+			pauses,
+			concurrent_mark: Duration::ZERO,
 		};
-		self.objects = alive_objects;
 
 		// Release all threads
 		for handle in self.handles.values() {
@@ -286,22 +681,76 @@ impl<U: GcUser> InnerGarbageCollector<U> {
 		statistics
 	}
 
-	pub fn used(&self) -> usize {
-		(self.free as usize) - (self.data as usize)
+	pub(super) fn inspect<O>(&mut self, func: impl FnOnce(&HeapView<U>) -> O) -> O {
+		debug!("Stopping threads for inspection");
+		let markers: Vec<GcMarker> = self
+			.handles
+			.values()
+			.map(|_| GcMarker::recording())
+			.collect();
+		for (handle, marker) in self.handles.values().zip(&markers) {
+			handle.start(GcRequest::MarkRoots(marker.clone()));
+		}
+
+		for handle in self.handles.values() {
+			handle.start_marking();
+		}
+		for handle in self.handles.values() {
+			handle.wait_complete();
+		}
+
+		let roots = self.roots.lock();
+		let view = HeapView {
+			thread_roots: markers
+				.iter()
+				.map(|marker| marker.take_recorded())
+				.collect(),
+			frozen: self.frozen.iter().copied().collect(),
+			globals: roots.globals().collect(),
+			pinned: roots.pinned().collect(),
+			start: self.data as usize,
+			end: self.free as usize,
+		};
+		drop(roots);
+		let output = func(&view);
+
+		for handle in self.handles.values() {
+			handle.continue_execution();
+		}
+
+		output
 	}
-	pub fn walk(&self, mut visitor: impl FnMut(bool, GcRef<U>)) {
-		unsafe {
-			let mut current = self.data;
-			while (current as usize) < (self.free as usize) {
-				let gc_ref = GcRef::<U>::from_ptr(current).unwrap();
-				let object_mark = gc_ref.header().flags.contains(ObjectFlags::MARK);
 
-				visitor(object_mark, gc_ref);
-				// Increment by this objects size
-				current = current.add(gc_ref.total_size());
-				debug_assert!(current.is_aligned_to(ALIGNMENT));
+	/// Splits the used heap into regions of roughly REGION_SIZE, which always start at an object and
+	/// never contain one of the sorted `pinned` objects.
+	fn regions(&self, pinned: &[GcRef<U>]) -> Vec<HeapRegion> {
+		let mut regions = Vec::new();
+		let mut pinned = pinned.iter().peekable();
+		let mut start = self.data as usize;
+		walk_range::<U>(start, self.free as usize, |_, reference| {
+			let head = reference.head_ptr() as usize;
+			if pinned.next_if_eq(&&reference).is_some() {
+				if head > start {
+					regions.push(HeapRegion::new(start, head));
+				}
+				start = head + reference.total_size();
+			} else if head - start >= REGION_SIZE {
+				regions.push(HeapRegion::new(start, head));
+				start = head;
 			}
+		});
+
+		if start < self.free as usize {
+			regions.push(HeapRegion::new(start, self.free as usize));
 		}
+		regions
+	}
+
+	pub fn used(&self) -> usize {
+		(self.free as usize) - (self.data as usize)
+	}
+	pub fn walk(&self, visitor: impl FnMut(bool, GcRef<U>)) {
+		walk_range(self.data as usize, self.free as usize, visitor);
 	}
 	pub fn walk_marked_for_deletion(&self, mut visitor: impl FnMut(GcRef<U>)) {
 		let mark = self.mark;
@@ -321,6 +770,96 @@ impl<U: GcUser> InnerGarbageCollector<U> {
 	}
 }
 
+fn walk_range<U: GcUser>(start: usize, end: usize, mut visitor: impl FnMut(bool, GcRef<U>)) {
+	unsafe {
+		let mut current = start as *mut u8;
+		while (current as usize) < end {
+			let gc_ref = GcRef::<U>::from_ptr(current).unwrap();
+			let flags = &gc_ref.header().flags;
+			let object_mark = flags.contains(ObjectFlags::MARK);
+			let filler = flags.contains(ObjectFlags::FILLER);
+			// Read the size before visiting, as the visitor may move the object over its own header.
+			let total_size = gc_ref.total_size();
+
+			// Fillers are the unused tails of allocation buffers, those are not objects.
+			if !filler {
+				visitor(object_mark, gc_ref);
+			}
+			// Increment by this objects size
+			current = current.add(total_size);
+			debug_assert!(current.is_aligned_to(ALIGNMENT));
+		}
+	}
+}
+
+/// Runs `func` for every index in `0..count` over `workers` threads, indices are handed out in increasing order.
+fn parallel_map<T: Send>(workers: usize, count: usize, func: impl Fn(usize) -> T + Sync) -> Vec<T> {
+	let next = AtomicUsize::new(0);
+	let mut results: Vec<(usize, T)> = scope(|scope| {
+		let threads: Vec<_> = (0..workers.clamp(1, count.max(1)))
+			.map(|_| {
+				scope.spawn(|| {
+					let mut results = Vec::new();
+					loop {
+						let i = next.fetch_add(1, Ordering::Relaxed);
+						if i >= count {
+							return results;
+						}
+						results.push((i, func(i)));
+					}
+				})
+			})
+			.collect();
+
+		threads
+			.into_iter()
+			.flat_map(|thread| thread.join().unwrap())
+			.collect()
+	});
+
+	results.sort_unstable_by_key(|(i, _)| *i);
+	results.into_iter().map(|(_, value)| value).collect()
+}
+
+/// A stopped view of the heap, given by [`GarbageCollector::inspect`].
+pub struct HeapView<U: GcUser> {
+	/// The roots of every thread.
+	pub thread_roots: Vec<Vec<GcRef<U>>>,
+	pub frozen: Vec<GcRef<U>>,
+	/// The objects of the global handles, weak handles are not roots.
+	pub globals: Vec<GcRef<U>>,
+	pub pinned: Vec<GcRef<U>>,
+	start: usize,
+	end: usize,
+}
+
+impl<U: GcUser> HeapView<U> {
+	/// Visits every object on the heap, including the ones which are not reachable anymore.
+	pub fn walk(&self, mut visitor: impl FnMut(GcRef<U>)) {
+		walk_range::<U>(self.start, self.end, |_, reference| visitor(reference));
+	}
+}
+
+/// A part of the heap which gets forwarded and moved by a single worker.
+struct HeapRegion {
+	start: usize,
+	end: usize,
+	/// Where the live objects of this region get compacted to.
+	destination: usize,
+	live_size: usize,
+}
+
+impl HeapRegion {
+	fn new(start: usize, end: usize) -> HeapRegion {
+		HeapRegion {
+			start,
+			end,
+			destination: 0,
+			live_size: 0,
+		}
+	}
+}
+
 impl<U: GcUser> Drop for InnerGarbageCollector<U> {
 	fn drop(&mut self) {
 		self.walk_alive(|value| unsafe {
@@ -332,17 +871,14 @@ impl<U: GcUser> Drop for InnerGarbageCollector<U> {
 	}
 }
 
-pub struct GCStatistics {
-	pub objects_cleared: usize,
-	pub objects_remaining: usize,
-}
-
 #[derive(Error, Debug, Clone)]
 pub enum AllocationError {
 	#[error("Out of heap space")]
 	OutOfHeap,
 	#[error("Object is too big to be allocated")]
 	ObjectTooBig,
+	#[error("The garbage collector is waiting for this thread to yield")]
+	Collecting,
 }
 
 #[cfg(test)]
@@ -0,0 +1,174 @@
+use crate::{GcMarker, GcRef, GcUser};
+use ahash::HashMap;
+
+/// A strong root which lives until it gets removed, see [`crate::GarbageCollector::new_global`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct GlobalHandle(u32);
+
+/// A root which does not keep its object alive, it turns null once the object has been collected.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct WeakHandle(u32);
+
+macro_rules! impl_handle {
+	($TY:ty) => {
+		impl $TY {
+			pub fn from_index(index: u32) -> Self {
+				Self(index)
+			}
+
+			pub fn index(&self) -> u32 {
+				self.0
+			}
+		}
+	};
+}
+
+impl_handle!(GlobalHandle);
+impl_handle!(WeakHandle);
+
+/// The roots which belong to the collector instead of a thread, native code uses these to keep
+/// objects across calls. The collector marks and remaps them like the roots of a thread.
+pub(crate) struct HandleTable<U: GcUser> {
+	strong: Slots<U>,
+	weak: Slots<U>,
+	/// Objects which may not move, with the amount of times they have been pinned.
+	pinned: HashMap<GcRef<U>, usize>,
+}
+
+impl<U: GcUser> HandleTable<U> {
+	pub fn new() -> HandleTable<U> {
+		HandleTable {
+			strong: Slots::new(),
+			weak: Slots::new(),
+			pinned: HashMap::default(),
+		}
+	}
+
+	pub fn new_global(&mut self, reference: GcRef<U>) -> GlobalHandle {
+		GlobalHandle(self.strong.insert(reference))
+	}
+
+	pub fn global(&self, handle: GlobalHandle) -> GcRef<U> {
+		self.strong.get(handle.0)
+	}
+
+	pub fn remove_global(&mut self, handle: GlobalHandle) {
+		self.strong.remove(handle.0);
+	}
+
+	pub fn new_weak(&mut self, reference: GcRef<U>) -> WeakHandle {
+		WeakHandle(self.weak.insert(reference))
+	}
+
+	pub fn weak(&self, handle: WeakHandle) -> GcRef<U> {
+		self.weak.get(handle.0)
+	}
+
+	pub fn remove_weak(&mut self, handle: WeakHandle) {
+		self.weak.remove(handle.0);
+	}
+
+	pub fn pin(&mut self, reference: GcRef<U>) {
+		*self.pinned.entry(reference).or_insert(0) += 1;
+	}
+
+	pub fn unpin(&mut self, reference: GcRef<U>) {
+		let Some(count) = self.pinned.get_mut(&reference) else {
+			panic!("Unpinned a reference which was not pinned. (did you double free?)");
+		};
+
+		*count -= 1;
+		if *count == 0 {
+			self.pinned.remove(&reference);
+		}
+	}
+
+	pub fn pinned(&self) -> impl Iterator<Item = GcRef<U>> + '_ {
+		self.pinned.keys().copied()
+	}
+
+	pub fn globals(&self) -> impl Iterator<Item = GcRef<U>> + '_ {
+		self.strong.iter()
+	}
+
+	pub fn mark(&self, marker: &GcMarker) {
+		for reference in self.strong.iter().chain(self.pinned()) {
+			marker.mark(reference);
+		}
+	}
+
+	/// Clears the weak handles of every object which did not get `mark`, this has to happen after
+	/// tracing and before the heap gets forwarded.
+	pub fn clear_unmarked(&mut self, mark: bool) {
+		for reference in self.weak.slots.iter_mut().flatten() {
+			if !reference.is_null() && !reference.is_marked(mark) {
+				*reference = GcRef::NULL;
+			}
+		}
+	}
+
+	/// Pinned objects do not move, so only the handles get remapped.
+	pub fn remap(&mut self, mut mapper: impl FnMut(GcRef<U>) -> GcRef<U>) {
+		for slots in [&mut self.strong, &mut self.weak] {
+			for reference in slots.slots.iter_mut().flatten() {
+				if !reference.is_null() {
+					*reference = mapper(*reference);
+				}
+			}
+		}
+	}
+}
+
+/// Removed slots are reused, so handles stay small.
+struct Slots<U: GcUser> {
+	slots: Vec<Option<GcRef<U>>>,
+	free: Vec<u32>,
+}
+
+impl<U: GcUser> Slots<U> {
+	fn new() -> Slots<U> {
+		Slots {
+			slots: vec![],
+			free: vec![],
+		}
+	}
+
+	fn insert(&mut self, reference: GcRef<U>) -> u32 {
+		match self.free.pop() {
+			Some(index) => {
+				self.slots[index as usize] = Some(reference);
+				index
+			}
+			None => {
+				self.slots.push(Some(reference));
+				(self.slots.len() - 1) as u32
+			}
+		}
+	}
+
+	fn get(&self, index: u32) -> GcRef<U> {
+		self.slots
+			.get(index as usize)
+			.copied()
+			.flatten()
+			.expect("Handle has been removed")
+	}
+
+	fn remove(&mut self, index: u32) {
+		match self.slots.get_mut(index as usize) {
+			Some(slot @ Some(_)) => {
+				*slot = None;
+				self.free.push(index);
+			}
+			_ => panic!("Removed a handle which does not exist. (did you double free?)"),
+		}
+	}
+
+	fn iter(&self) -> impl Iterator<Item = GcRef<U>> + '_ {
+		self.slots
+			.iter()
+			.flatten()
+			.copied()
+			.filter(|reference| !reference.is_null())
+	}
+}
@@ -3,7 +3,7 @@ use bitflags::bitflags;
 use rvm_core::align_size;
 use std::mem::size_of;
 use std::ops::{Deref, DerefMut};
-use std::ptr::null_mut;
+use std::ptr::{addr_of_mut, null_mut};
 
 pub type ObjectSize = u16;
 
@@ -11,6 +11,8 @@ bitflags! {
 	#[repr(C)]
 	pub struct ObjectFlags: u8 {
 		const MARK = 1;
+		/// The unused tail of a thread-local allocation buffer, this is not a real object.
+		const FILLER = 2;
 	}
 }
 
@@ -42,6 +44,18 @@ impl<U: GcUser> GcHeader<U> {
 		})
 	}
 
+	/// Writes the header of a filler object which spans `total_size`, the user header is left uninitialized.
+	pub(crate) unsafe fn write_filler(head: *mut u8, total_size: usize) {
+		debug_assert!(total_size >= Self::SIZE, "Filler does not fit a header");
+		let raw_size = (total_size - Self::SIZE) / ALIGNMENT;
+		assert!(raw_size <= ObjectSize::MAX as usize);
+
+		let header = head.cast::<GcHeader<U>>();
+		addr_of_mut!((*header).flags).write(ObjectFlags::FILLER);
+		addr_of_mut!((*header).forward).write(null_mut());
+		addr_of_mut!((*header).raw_size).write(raw_size as ObjectSize);
+	}
+
 	pub fn user(&self) -> &U::Header {
 		&self.user
 	}
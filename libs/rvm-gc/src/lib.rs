@@ -3,15 +3,23 @@
 #![feature(slice_ptr_get)]
 
 mod collector;
+mod handles;
 mod header;
+mod marker;
 mod reference;
+mod statistics;
 mod sweeper;
+mod tlab;
 
 pub use collector::*;
+pub use handles::{GlobalHandle, WeakHandle};
 pub use header::*;
+pub use marker::*;
 pub use reference::*;
+pub use statistics::*;
 use std::marker::PhantomData;
 pub use sweeper::*;
+pub use tlab::{TLAB_MAX_OBJECT_SIZE, TLAB_SIZE};
 
 pub trait GcUser: Sized {
 	type Header: Sized;
@@ -95,6 +103,7 @@ mod tests {
 	use std::ptr;
 	use std::ptr::slice_from_raw_parts_mut;
 	use std::slice::{from_raw_parts, from_raw_parts_mut};
+	use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 	use std::sync::Arc;
 	use std::thread::spawn;
 	use std::thread::{sleep, JoinHandle};
@@ -114,14 +123,28 @@ mod tests {
 			gc: &GarbageCollector<SimpleUser>,
 			fields: &[Field],
 		) -> Result<Reference, AllocationError> {
-			let mut result = Reference(gc.alloc_raw(
-				size_of_val(fields),
-				SimpleHeader {
-					is_good: 0xff00ff00,
-					fields: fields.len() as u32,
-				},
-			)?);
+			let result = gc.alloc_raw(size_of_val(fields), Self::header(fields))?;
+			Ok(Self::init(result, fields))
+		}
+
+		pub fn new_local(
+			gc: &GarbageCollector<SimpleUser>,
+			sweeper: &mut GcSweeper,
+			fields: &[Field],
+		) -> Result<Reference, AllocationError> {
+			let result = gc.alloc_tlab(sweeper, size_of_val(fields), Self::header(fields))?;
+			Ok(Self::init(result, fields))
+		}
+
+		fn header(fields: &[Field]) -> SimpleHeader {
+			SimpleHeader {
+				is_good: 0xff00ff00,
+				fields: fields.len() as u32,
+			}
+		}
 
+		fn init(reference: GcRef<SimpleUser>, fields: &[Field]) -> Reference {
+			let mut result = Reference(reference);
 			unsafe {
 				let dst = result.fields_mut_ptr();
 				let src_len = fields.len();
@@ -133,7 +156,7 @@ mod tests {
 				}
 			}
 
-			Ok(result)
+			result
 		}
 
 		pub fn fields(&self) -> &[Field] {
@@ -195,10 +218,24 @@ mod tests {
 
 	impl Gc {
 		pub fn new(size: usize) -> Gc {
+			Self::with_mode(size, GcMode::default())
+		}
+
+		pub fn with_mode(size: usize, mode: GcMode) -> Gc {
 			rvm_core::init();
 			Gc {
-				inner: Arc::new(GarbageCollector::<SimpleUser>::new(size)),
+				inner: Arc::new(GarbageCollector::<SimpleUser>::with_mode(size, mode)),
+			}
+		}
+
+		/// Overwrites a field, going through the write barrier like a mutator has to.
+		pub fn store(&self, mut object: Reference, index: usize, value: Field) {
+			if self.inner.is_marking() {
+				if let Field::Ref(old) = &object.fields()[index] {
+					self.inner.write_barrier(old.0);
+				}
 			}
+			object.fields_mut()[index] = value;
 		}
 
 		pub fn alloc(&self, fields: &[Field]) -> Reference {
@@ -288,6 +325,41 @@ mod tests {
 		assert_eq!(stats.objects_remaining, 1);
 	}
 
+	#[test]
+	fn gc_statistics() {
+		let gc = Gc::new(4096);
+		let collected = Arc::new(AtomicUsize::new(0));
+		let subscriber = collected.clone();
+		gc.inner.subscribe(move |stats| {
+			subscriber.fetch_add(stats.objects_cleared, Ordering::Relaxed);
+		});
+
+		let kept = gc.alloc(&fields(2));
+		gc.inner.add_frozen(kept.0);
+		let dropped = gc.alloc(&fields(3));
+		let kept_size = kept.0.total_size();
+		let dropped_size = dropped.0.total_size();
+
+		let stats = gc.gc();
+		assert_eq!(stats.collection, 1);
+		assert_eq!(stats.heap_before, kept_size + dropped_size);
+		assert_eq!(stats.heap_after, kept_size);
+		assert_eq!(stats.bytes_reclaimed(), dropped_size);
+		assert_eq!(stats.heap_size, 4096);
+		assert!(stats.pauses.total() >= stats.pauses.mark);
+
+		let stats = gc.gc();
+		assert_eq!(stats.collection, 2);
+		assert_eq!(stats.bytes_reclaimed(), 0);
+
+		let totals = gc.inner.totals();
+		assert_eq!(totals.collections, 2);
+		assert_eq!(totals.objects_cleared, 1);
+		assert_eq!(totals.bytes_reclaimed, dropped_size as u64);
+		assert_eq!(totals.pauses.count(), 2);
+		assert_eq!(collected.load(Ordering::Relaxed), 1);
+	}
+
 	pub struct RootedTester {
 		gc: Gc,
 		users: Vec<(Parker, JoinHandle<()>)>,
@@ -315,6 +387,27 @@ mod tests {
 		pub fn unkeep(&mut self, id: usize) {
 			self.roots.remove(id);
 		}
+
+		pub fn alloc_local(&mut self, fields: &[Field]) -> Reference {
+			loop {
+				match Reference::new_local(&self.gc.inner, &mut self.roots.gc_sweeper, fields) {
+					Err(AllocationError::Collecting) => self.yield_gc(),
+					result => return result.unwrap(),
+				}
+			}
+		}
+
+		pub fn yield_gc(&mut self) {
+			GcSweeper::yield_gc(&mut self.roots);
+		}
+
+		/// Runs `func` like native code, collections go on without waiting for the user.
+		pub fn native(&mut self, func: impl FnOnce()) {
+			unsafe { GcSweeper::enter_native(&mut self.roots) };
+			self.unparker.unpark();
+			func();
+			assert!(self.roots.gc_sweeper.leave_native());
+		}
 	}
 	impl Deref for RootedUser {
 		type Target = Gc;
@@ -326,32 +419,40 @@ mod tests {
 
 	impl RootedTester {
 		pub fn new(size: usize) -> RootedTester {
+			Self::with_mode(size, GcMode::default())
+		}
+
+		pub fn with_mode(size: usize, mode: GcMode) -> RootedTester {
 			RootedTester {
-				gc: Gc::new(size),
+				gc: Gc::with_mode(size, mode),
 				users: vec![],
 			}
 		}
-		pub fn spawn_user(&mut self, func: impl FnOnce(RootedUser) + Send + 'static) {
+		pub fn spawn_user(&mut self, func: impl FnOnce(&mut RootedUser) + Send + 'static) {
 			let sweeper = self.gc.inner.new_sweeper();
 			let parker = Parker::new();
 			let unparker = parker.unparker().clone();
+			// Separate parker for the init, otherwise the init unpark can merge with the first gc unpark.
+			let init = Parker::new();
+			let init_unparker = init.unparker().clone();
 
 			let user_gc = self.gc.clone();
 			let handle = spawn(move || {
 				info!("Spawning user");
-				let user = RootedUser {
+				let mut user = RootedUser {
 					roots: VecRootProvider::<SimpleUser>::new(sweeper),
 					unparker,
 					gc: user_gc,
 				};
 				info!("Finishing spawn user");
-				user.unparker.unpark();
-				func(user);
+				init_unparker.unpark();
+				func(&mut user);
+				user.gc.inner.remove_sweeper(user.roots.gc_sweeper);
 				info!("User func finished");
 			});
 
 			info!("Waiting for init");
-			parker.park();
+			init.park();
 			self.users.push((parker, handle));
 		}
 
@@ -416,6 +517,28 @@ mod tests {
 		assert_eq!(stats.objects_remaining, 0);
 	}
 
+	#[test]
+	fn native_roots() {
+		let mut tester = RootedTester::new(1024);
+
+		let _ = tester.alloc(&fields(1));
+		let (sender, receiver) = crossbeam::channel::bounded(0);
+		tester.spawn_user(move |tester| {
+			let original_fields = fields(2);
+			let reference = tester.alloc(&original_fields);
+			let i = tester.keep(reference);
+			// The collection moves the roots while the user is blocked in native code.
+			tester.native(|| receiver.recv().unwrap());
+			assert!(tester.get(i) != reference);
+			assert_eq!(tester.get(i).fields(), &original_fields);
+		});
+
+		let stats = tester.gc();
+		sender.send(()).unwrap();
+		assert_eq!(stats.objects_cleared, 1);
+		assert_eq!(stats.objects_remaining, 1);
+	}
+
 	#[test]
 	fn cyclic_gc() {
 		let mut tester = RootedTester::new(1024);
@@ -505,4 +628,343 @@ mod tests {
 		assert_eq!(stats.objects_cleared, 3);
 		assert_eq!(stats.objects_remaining, 0);
 	}
+
+	#[test]
+	fn parallel_gc() {
+		let gc = Gc::new(1024 * 1024 * 8);
+		gc.inner.set_workers(4);
+
+		// Build chains which cross region boundaries, and only keep every other one alive.
+		let mut roots = Vec::new();
+		let mut alive = 0;
+		let mut dead = 0;
+		for i in 0..512 {
+			let head = gc.alloc(&fields(4));
+			let mut current = head;
+			for _ in 0..16 {
+				let _ = gc.alloc(&fields(8));
+				dead += 1;
+
+				let child = gc.alloc(&fields(3));
+				current.fields_mut()[0] = Field::Ref(child);
+				current = child;
+			}
+
+			if i % 2 == 0 {
+				let root = gc.alloc(&[Field::Ref(head), Field::Name(i.to_string())]);
+				gc.inner.add_frozen(root.0);
+				roots.push(i);
+				alive += 18;
+			} else {
+				dead += 17;
+			}
+		}
+
+		let stats = gc.gc();
+		assert_eq!(stats.objects_cleared, dead);
+		assert_eq!(stats.objects_remaining, alive);
+
+		let stats = gc.gc();
+		assert_eq!(stats.objects_cleared, 0);
+		assert_eq!(stats.objects_remaining, alive);
+	}
+
+	#[test]
+	fn tlab_gc() {
+		let mut tester = RootedTester::new(1024 * 1024);
+
+		tester.spawn_user(|tester| {
+			let original_fields = fields(2);
+			let mut kept = Vec::new();
+			for i in 0..256 {
+				let reference = tester.alloc_local(&original_fields);
+				if i % 4 == 0 {
+					kept.push(tester.keep(reference));
+				}
+			}
+			// Objects on the shared heap sit in between the buffers.
+			let _ = tester.alloc(&fields(1));
+
+			tester.wait_for_gc(); // GC 1
+			for i in &kept {
+				assert_eq!(tester.get(*i).fields(), &original_fields);
+			}
+
+			// The buffer has been retired, so this refills a new one after the compacted objects.
+			let reference = tester.alloc_local(&original_fields);
+			tester.keep(reference);
+			tester.wait_for_gc(); // GC 2
+		});
+
+		let stats = tester.gc(); // GC 1
+		assert_eq!(stats.objects_cleared, 193);
+		assert_eq!(stats.objects_remaining, 64);
+
+		let stats = tester.gc(); // GC 2
+		assert_eq!(stats.objects_cleared, 0);
+		assert_eq!(stats.objects_remaining, 65);
+	}
+
+	#[test]
+	fn concurrent_gc() {
+		const CHAIN: usize = 2000;
+		const GARBAGE: usize = 4000;
+		let mut tester = RootedTester::with_mode(1024 * 1024 * 8, GcMode::Concurrent);
+		tester.inner.set_workers(2);
+
+		let done = Arc::new(AtomicBool::new(false));
+		let user_done = done.clone();
+		tester.spawn_user(move |tester| {
+			// Every node is [name, next]
+			let mut next = Field::Name("end".to_string());
+			for i in (0..CHAIN).rev() {
+				let node = tester.alloc_local(&[Field::Name(i.to_string()), next]);
+				next = Field::Ref(node);
+				tester.alloc_local(&fields(1));
+			}
+			for _ in 0..GARBAGE - CHAIN {
+				tester.alloc_local(&fields(1));
+			}
+			let head_root = tester.keep(next.reference());
+
+			tester.wait_for_gc(); // Marks the roots, marking continues while we shuffle the list.
+			let mut added = 0;
+			let mut position = 0;
+			while !user_done.load(Ordering::Acquire) {
+				// Unlink the node after the cursor and put it right after the head, the node is only
+				// reachable from this stack frame in between, which the marker never sees.
+				let head = tester.get(head_root);
+				let mut cursor = head;
+				for _ in 0..position {
+					cursor = cursor.fields()[1].reference();
+				}
+				match &cursor.fields()[1] {
+					Field::Ref(node) if node != &head => {
+						let node = *node;
+						tester.store(cursor, 1, node.fields()[1].clone());
+						tester.store(node, 1, head.fields()[1].clone());
+						tester.store(head, 1, Field::Ref(node));
+						position += 1;
+					}
+					_ => position = 0,
+				}
+
+				// These are allocated while marking, so they may not be collected either.
+				if position % 16 == 0 {
+					let name = Field::Name((CHAIN + added).to_string());
+					let node = tester.alloc_local(&[name, Field::Name("end".to_string())]);
+					// Allocating may have yielded to the collector, which moves the head.
+					let head = tester.get(head_root);
+					tester.store(node, 1, head.fields()[1].clone());
+					tester.store(head, 1, Field::Ref(node));
+					added += 1;
+				}
+				tester.yield_gc();
+			}
+
+			let mut names = Vec::new();
+			let mut field = tester.get(head_root).fields()[1].clone();
+			while let Field::Ref(node) = field {
+				let Field::Name(name) = &node.fields()[0] else {
+					panic!("Node name got corrupted");
+				};
+				names.push(name.parse::<usize>().unwrap());
+				field = node.fields()[1].clone();
+			}
+			names.sort();
+			assert_eq!(names, (1..CHAIN + added).collect::<Vec<_>>());
+		});
+
+		let stats = tester.gc();
+		done.store(true, Ordering::Release);
+		assert_eq!(stats.objects_cleared, GARBAGE);
+		assert!(stats.concurrent_mark > Duration::ZERO);
+	}
+
+	#[test]
+	fn inspect_heap() {
+		let mut tester = RootedTester::new(1024 * 16);
+		let frozen = tester.alloc(&fields(1));
+		tester.inner.add_frozen(frozen.0);
+
+		tester.spawn_user(|tester| {
+			let original_fields = fields(2);
+			let reference = tester.alloc(&original_fields);
+			let i = tester.keep(reference);
+			tester.wait_for_gc(); // Inspection
+			assert_eq!(tester.get(i), reference);
+			assert_eq!(reference.fields(), &original_fields);
+		});
+
+		for (parker, _) in &tester.users {
+			parker.park();
+		}
+		let (thread_roots, frozen_roots, objects) = tester.inner.inspect(|heap| {
+			let mut objects = 0;
+			heap.walk(|_| objects += 1);
+			(heap.thread_roots.clone(), heap.frozen.clone(), objects)
+		});
+
+		assert_eq!(thread_roots.len(), 1);
+		assert_eq!(thread_roots[0].len(), 1);
+		assert_eq!(frozen_roots, vec![frozen.0]);
+		assert_eq!(objects, 2);
+	}
+
+	#[test]
+	fn global_handles() {
+		let gc = Gc::new(4096);
+		let garbage = gc.alloc(&fields(4));
+		let original_fields = fields(2);
+		let kept = gc.alloc(&original_fields);
+		let handle = gc.inner.new_global(kept.0);
+		// Handles are not a set, the same object may be held twice.
+		let second = gc.inner.new_global(kept.0);
+		gc.inner.remove_global(second);
+
+		let stats = gc.gc();
+		assert_eq!(stats.objects_cleared, 1);
+		assert_eq!(stats.objects_remaining, 1);
+
+		// The object moved to where the garbage was.
+		let moved = Reference(gc.inner.global(handle));
+		assert_eq!(moved.0, garbage.0);
+		assert_eq!(moved.fields(), &original_fields);
+
+		gc.inner.remove_global(handle);
+		let stats = gc.gc();
+		assert_eq!(stats.objects_cleared, 1);
+		assert_eq!(stats.objects_remaining, 0);
+	}
+
+	#[test]
+	#[should_panic]
+	fn global_handle_double_free() {
+		let gc = Gc::new(1024);
+		let handle = gc.inner.new_global(gc.alloc(&fields(1)).0);
+		gc.inner.remove_global(handle);
+		gc.inner.remove_global(handle);
+	}
+
+	#[test]
+	fn weak_handles() {
+		let gc = Gc::new(4096);
+		let garbage = gc.alloc(&fields(3));
+		let dropped = gc.inner.new_weak(garbage.0);
+		let original_fields = fields(2);
+		let kept = gc.alloc(&original_fields);
+		let global = gc.inner.new_global(kept.0);
+		let weak = gc.inner.new_weak(kept.0);
+
+		let stats = gc.gc();
+		assert_eq!(stats.objects_cleared, 1);
+		assert!(gc.inner.weak(dropped).is_null());
+		assert_eq!(gc.inner.weak(weak), gc.inner.global(global));
+		assert_eq!(Reference(gc.inner.weak(weak)).fields(), &original_fields);
+
+		gc.inner.remove_global(global);
+		gc.gc();
+		assert!(gc.inner.weak(weak).is_null());
+		gc.inner.remove_weak(weak);
+		gc.inner.remove_weak(dropped);
+	}
+
+	#[test]
+	fn pinned_objects() {
+		let gc = Gc::new(4096);
+		let first = gc.alloc(&fields(4));
+		let before = gc.alloc(&[Field::Name("before".to_string())]);
+		let pinned_fields = fields(2);
+		let pinned = gc.alloc(&pinned_fields);
+		let garbage = gc.alloc(&fields(3));
+		let after_fields = fields(2);
+		let after = gc.alloc(&after_fields);
+		gc.store(before, 0, Field::Ref(after));
+
+		let global = gc.inner.new_global(before.0);
+		gc.inner.pin(pinned.0);
+		gc.inner.pin(pinned.0);
+
+		let stats = gc.gc();
+		assert_eq!(stats.objects_cleared, 2);
+		assert_eq!(stats.objects_remaining, 3);
+
+		// The pinned object stays where it is, the objects behind it get compacted into the gap in
+		// front of it.
+		let before = Reference(gc.inner.global(global));
+		assert_eq!(before.0, first.0);
+		assert_eq!(pinned.fields(), &pinned_fields);
+		let moved = before.fields()[0].reference();
+		assert_eq!(moved.0.head_ptr(), unsafe {
+			before.0.head_ptr().add(before.0.total_size())
+		});
+		assert_eq!(moved.fields(), &after_fields);
+		assert_eq!(
+			stats.heap_after,
+			garbage.0.head_ptr() as usize - first.0.head_ptr() as usize
+		);
+
+		// Pins are counted.
+		gc.inner.unpin(pinned.0);
+		let stats = gc.gc();
+		assert_eq!(stats.objects_remaining, 3);
+		assert_eq!(pinned.fields(), &pinned_fields);
+
+		gc.inner.unpin(pinned.0);
+		gc.inner.remove_global(global);
+		let stats = gc.gc();
+		assert_eq!(stats.objects_cleared, 3);
+		assert_eq!(stats.objects_remaining, 0);
+		assert_eq!(stats.heap_after, 0);
+	}
+
+	#[test]
+	fn compact_between_pins() {
+		let gc = Gc::new(4096);
+		let garbage = gc.alloc(&fields(4));
+		let first_pin = gc.alloc(&fields(1));
+		let small = gc.alloc(&fields(1));
+		let _ = gc.alloc(&fields(1));
+		let second_pin = gc.alloc(&fields(1));
+		let large_fields = fields(4);
+		let large = gc.alloc(&large_fields);
+		let used = gc.inner.used();
+
+		gc.inner.pin(first_pin.0);
+		gc.inner.pin(second_pin.0);
+		let small = gc.inner.new_global(small.0);
+		let large = gc.inner.new_global(large.0);
+
+		// The small object fits in front of the first pin, the large one does not fit in any gap.
+		let stats = gc.gc();
+		assert_eq!(stats.objects_cleared, 2);
+		assert_eq!(stats.objects_remaining, 4);
+		assert_eq!(stats.heap_after, used);
+		assert_eq!(gc.inner.global(small), garbage.0);
+		assert_eq!(Reference(gc.inner.global(large)).fields(), &large_fields);
+
+		// The gaps got filled, so the heap can still be walked.
+		let stats = gc.gc();
+		assert_eq!(stats.objects_cleared, 0);
+		assert_eq!(stats.objects_remaining, 4);
+		assert_eq!(stats.heap_after, used);
+
+		gc.inner.unpin(first_pin.0);
+		gc.inner.unpin(second_pin.0);
+		gc.inner.remove_global(small);
+		gc.inner.remove_global(large);
+		let stats = gc.gc();
+		assert_eq!(stats.objects_cleared, 4);
+		assert_eq!(stats.heap_after, 0);
+	}
+
+	#[test]
+	#[should_panic]
+	fn unpin_double_free() {
+		let gc = Gc::new(1024);
+		let reference = gc.alloc(&fields(1));
+		gc.inner.pin(reference.0);
+		gc.inner.unpin(reference.0);
+		gc.inner.unpin(reference.0);
+	}
 }
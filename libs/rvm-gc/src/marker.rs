@@ -0,0 +1,156 @@
+use crate::reference::GcRef;
+use crate::GcUser;
+use crossbeam::deque::{Injector, Steal, Stealer, Worker};
+use parking_lot::RwLock;
+use std::iter;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::{scope, yield_now};
+use tracing::trace;
+
+/// Marks objects as alive. Marking a reference only claims it and queues it up,
+/// the children are traced afterwards by the parallel mark workers.
+#[derive(Clone)]
+pub struct GcMarker {
+	pub(super) mark: bool,
+	/// When recording, references are only collected and never marked.
+	record: bool,
+	queue: Arc<MarkQueue>,
+}
+
+#[derive(Default)]
+struct MarkQueue {
+	// Head pointers of objects which still need their children traced.
+	injector: Injector<usize>,
+	// Objects which have been queued but have not been fully traced yet.
+	pending: AtomicUsize,
+}
+
+impl GcMarker {
+	pub(super) fn new(mark: bool) -> GcMarker {
+		GcMarker {
+			mark,
+			record: false,
+			queue: Arc::new(MarkQueue::default()),
+		}
+	}
+
+	/// A marker which leaves the heap alone, and only collects the roots it gets given.
+	pub(super) fn recording() -> GcMarker {
+		GcMarker {
+			mark: false,
+			record: true,
+			queue: Arc::new(MarkQueue::default()),
+		}
+	}
+
+	pub(super) fn take_recorded<U: GcUser>(&self) -> Vec<GcRef<U>> {
+		let mut output = Vec::new();
+		loop {
+			match self.queue.injector.steal() {
+				Steal::Success(head) => {
+					output.push(unsafe { GcRef::from_ptr(head as *mut u8).unwrap() });
+				}
+				Steal::Empty => return output,
+				Steal::Retry => {}
+			}
+		}
+	}
+
+	pub fn mark<U: GcUser>(&self, reference: GcRef<U>) {
+		if self.record && !reference.is_null() {
+			self.queue.injector.push(reference.head_ptr() as usize);
+			return;
+		}
+
+		if reference.is_null() || !reference.try_mark(self.mark) {
+			// We have already visited this object so we return here.
+			return;
+		}
+
+		trace!("Visiting {:?}", reference);
+		self.queue.pending.fetch_add(1, Ordering::AcqRel);
+		self.queue.injector.push(reference.head_ptr() as usize);
+	}
+
+	/// Traces everything reachable from the marked roots using `workers` threads which steal work from each other.
+	pub(super) fn trace<U: GcUser>(&self, workers: usize) {
+		let locals: Vec<Worker<usize>> = (0..workers.max(1)).map(|_| Worker::new_lifo()).collect();
+		let stealers: Vec<Stealer<usize>> = locals.iter().map(|local| local.stealer()).collect();
+
+		scope(|scope| {
+			for local in locals {
+				let stealers = &stealers;
+				scope.spawn(move || self.run_worker::<U>(local, stealers));
+			}
+		});
+	}
+
+	fn run_worker<U: GcUser>(&self, local: Worker<usize>, stealers: &[Stealer<usize>]) {
+		loop {
+			let Some(head) = self.find_task(&local, stealers) else {
+				if self.queue.pending.load(Ordering::Acquire) == 0 {
+					return;
+				}
+
+				// Someone is still tracing, and may produce more work.
+				yield_now();
+				continue;
+			};
+
+			let reference = unsafe { GcRef::<U>::from_ptr(head as *mut u8).unwrap() };
+			reference.visit_refs(|child| {
+				if !child.is_null() && child.try_mark(self.mark) {
+					self.queue.pending.fetch_add(1, Ordering::AcqRel);
+					local.push(child.head_ptr() as usize);
+				}
+			});
+			self.queue.pending.fetch_sub(1, Ordering::AcqRel);
+		}
+	}
+
+	fn find_task(&self, local: &Worker<usize>, stealers: &[Stealer<usize>]) -> Option<usize> {
+		local.pop().or_else(|| {
+			iter::repeat_with(|| {
+				self.queue
+					.injector
+					.steal_batch_and_pop(local)
+					.or_else(|| stealers.iter().map(|stealer| stealer.steal()).collect())
+			})
+			.find(|steal| !steal.is_retry())
+			.and_then(|steal| steal.success())
+		})
+	}
+}
+
+/// The snapshot-at-the-beginning write barrier used while marking runs next to the mutators.
+/// Every reference which gets overwritten during marking is marked, so everything which was
+/// reachable when marking started stays alive.
+#[derive(Default)]
+pub(super) struct SatbBarrier {
+	active: AtomicBool,
+	marker: RwLock<Option<GcMarker>>,
+}
+
+impl SatbBarrier {
+	pub(super) fn enable(&self, marker: GcMarker) {
+		*self.marker.write() = Some(marker);
+		self.active.store(true, Ordering::Release);
+	}
+
+	pub(super) fn disable(&self) {
+		self.active.store(false, Ordering::Release);
+		*self.marker.write() = None;
+	}
+
+	#[inline(always)]
+	pub(super) fn is_active(&self) -> bool {
+		self.active.load(Ordering::Acquire)
+	}
+
+	pub(super) fn record<U: GcUser>(&self, old: GcRef<U>) {
+		if let Some(marker) = &*self.marker.read() {
+			marker.mark(old);
+		}
+	}
+}
@@ -1,11 +1,12 @@
-use crate::{GcHeader, GcUser};
+use crate::{GcHeader, GcUser, ObjectFlags};
 use rvm_core::align_size;
 use std::fmt::{Debug, Formatter};
 use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 use std::mem;
-use std::ptr::{copy, null_mut, slice_from_raw_parts};
+use std::ptr::{addr_of, copy, null_mut, slice_from_raw_parts};
 use std::slice::from_raw_parts;
+use std::sync::atomic::{AtomicU8, Ordering};
 // This pointer points to the start of the data (NOT THE START OF THE REFERENCE OBJECT WHICH CONTAINS THE HEADER!)
 
 // HEADER
@@ -93,6 +94,30 @@ impl<U: GcUser> GcRef<U> {
 		total_size
 	}
 
+	/// If the object has `mark`, after tracing this means that it is alive.
+	pub(crate) fn is_marked(&self, mark: bool) -> bool {
+		self.header().flags.contains(ObjectFlags::MARK) == mark
+	}
+
+	/// Atomically sets the mark flag of this object, returns false if the object already had this mark.
+	pub(crate) fn try_mark(&self, mark: bool) -> bool {
+		let flags = unsafe {
+			let header = self.head_ptr().cast::<GcHeader<U>>();
+			&*addr_of!((*header).flags).cast::<AtomicU8>()
+		};
+
+		let bit = ObjectFlags::MARK.bits();
+		flags
+			.fetch_update(Ordering::AcqRel, Ordering::Acquire, |flags| {
+				if (flags & bit != 0) == mark {
+					None
+				} else {
+					Some(flags ^ bit)
+				}
+			})
+			.is_ok()
+	}
+
 	// Next location is the start of the reference (NOT THE DATA)
 	pub(crate) unsafe fn set_forward(&mut self, next_location: *mut u8) {
 		let header = self.header_mut();
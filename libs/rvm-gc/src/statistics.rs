@@ -0,0 +1,138 @@
+use std::time::Duration;
+
+/// The result of a single garbage collection.
+#[derive(Clone, Debug, Default)]
+pub struct GCStatistics {
+	/// The amount of collections which have run, including this one.
+	pub collection: u64,
+	pub objects_cleared: usize,
+	pub objects_remaining: usize,
+	/// Bytes used on the heap before and after the collection.
+	pub heap_before: usize,
+	pub heap_after: usize,
+	pub heap_size: usize,
+	pub pauses: GcPauses,
+	/// How long marking ran next to the threads, this is not part of the pauses.
+	pub concurrent_mark: Duration,
+}
+
+impl GCStatistics {
+	pub fn bytes_reclaimed(&self) -> usize {
+		self.heap_before.saturating_sub(self.heap_after)
+	}
+}
+
+/// How long every phase of a collection took, the threads are stopped for all of them.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct GcPauses {
+	/// Waiting for every thread to reach a safepoint.
+	pub stop: Duration,
+	/// Marking the roots and tracing everything reachable from them.
+	pub mark: Duration,
+	/// Finding the new locations and updating every reference to them.
+	pub forward: Duration,
+	/// Dropping the dead objects and moving the live ones.
+	pub moving: Duration,
+}
+
+impl GcPauses {
+	pub fn total(&self) -> Duration {
+		self.stop + self.mark + self.forward + self.moving
+	}
+}
+
+/// Counts pauses in power of two buckets of microseconds, bucket `i` holds pauses shorter than `2^i` µs.
+#[derive(Clone, Debug, Default)]
+pub struct PauseHistogram {
+	buckets: [u64; PauseHistogram::BUCKETS],
+}
+
+impl PauseHistogram {
+	pub const BUCKETS: usize = 32;
+
+	pub fn record(&mut self, pause: Duration) {
+		let micros = pause.as_micros().min(u64::MAX as u128) as u64;
+		let bucket = (u64::BITS - micros.leading_zeros()) as usize;
+		self.buckets[bucket.min(Self::BUCKETS - 1)] += 1;
+	}
+
+	pub fn count(&self) -> u64 {
+		self.buckets.iter().sum()
+	}
+
+	/// The upper bound of every bucket together with the amount of pauses in it.
+	pub fn buckets(&self) -> impl Iterator<Item = (Duration, u64)> + '_ {
+		self.buckets
+			.iter()
+			.enumerate()
+			.map(|(i, count)| (Duration::from_micros(1 << i), *count))
+	}
+
+	/// The upper bound of the bucket which contains the given percentile (0.0..=1.0) of pauses.
+	pub fn percentile(&self, percentile: f64) -> Duration {
+		let target = (self.count() as f64 * percentile.clamp(0.0, 1.0)).ceil() as u64;
+		let mut seen = 0;
+		for (bound, count) in self.buckets() {
+			seen += count;
+			if seen >= target.max(1) {
+				return bound;
+			}
+		}
+
+		Duration::ZERO
+	}
+}
+
+/// The statistics of every collection since the garbage collector was created.
+#[derive(Clone, Debug, Default)]
+pub struct GcTotals {
+	pub collections: u64,
+	pub objects_cleared: u64,
+	pub bytes_reclaimed: u64,
+	pub pause_time: Duration,
+	pub pauses: PauseHistogram,
+	pub last: Option<GCStatistics>,
+}
+
+impl GcTotals {
+	pub(super) fn record(&mut self, statistics: &mut GCStatistics) {
+		self.collections += 1;
+		self.objects_cleared += statistics.objects_cleared as u64;
+		self.bytes_reclaimed += statistics.bytes_reclaimed() as u64;
+		self.pause_time += statistics.pauses.total();
+		self.pauses.record(statistics.pauses.total());
+		statistics.collection = self.collections;
+		self.last = Some(statistics.clone());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn histogram() {
+		let mut histogram = PauseHistogram::default();
+		histogram.record(Duration::ZERO);
+		histogram.record(Duration::from_micros(3));
+		histogram.record(Duration::from_micros(700));
+		histogram.record(Duration::from_millis(5));
+
+		assert_eq!(histogram.count(), 4);
+		let buckets: Vec<_> = histogram
+			.buckets()
+			.filter(|(_, count)| *count > 0)
+			.collect();
+		assert_eq!(
+			buckets,
+			vec![
+				(Duration::from_micros(1), 1),
+				(Duration::from_micros(4), 1),
+				(Duration::from_micros(1024), 1),
+				(Duration::from_micros(8192), 1),
+			]
+		);
+		assert_eq!(histogram.percentile(0.5), Duration::from_micros(4));
+		assert_eq!(histogram.percentile(1.0), Duration::from_micros(8192));
+	}
+}
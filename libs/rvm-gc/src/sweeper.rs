@@ -2,12 +2,12 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::header::ObjectFlags;
-use crate::reference::GcRef;
-use crate::{GcUser, RootProvider};
+use crate::tlab::Tlab;
+use crate::{GcMarker, GcUser, RootProvider};
 use crossbeam::channel::{unbounded, Receiver, Sender};
 use crossbeam::sync::{Parker, Unparker};
-use tracing::{debug, trace};
+use parking_lot::{Condvar, Mutex};
+use tracing::debug;
 use uuid::Uuid;
 
 pub(super) fn new_sweeper() -> (GcSweeperHandle, GcSweeper) {
@@ -17,6 +17,10 @@ pub(super) fn new_sweeper() -> (GcSweeperHandle, GcSweeper) {
 	let unparker = parker.unparker().clone();
 
 	let should_yield: Arc<AtomicBool> = Arc::new(Default::default());
+	let state = Arc::new(SharedState {
+		state: Mutex::new(ThreadState::Vm),
+		changed: Condvar::new(),
+	});
 
 	let (sender, receiver) = unbounded();
 	let uuid = Uuid::new_v4();
@@ -27,58 +31,149 @@ pub(super) fn new_sweeper() -> (GcSweeperHandle, GcSweeper) {
 			complete_parker,
 			sender,
 			should_yield: should_yield.clone(),
+			state: state.clone(),
 		},
 		GcSweeper {
 			uuid,
 			finished: false,
+			tlab: Tlab::empty(),
 			receiver,
 			should_yield: should_yield.clone(),
 			parker,
 			complete: complete_unparker,
+			state,
 		},
 	)
 }
 
+/// What a thread has to do when it stops for the garbage collector.
+pub(super) enum GcRequest {
+	/// Mark the roots, and remap them after the heap has been compacted.
+	Collect(GcMarker),
+	/// Only mark the roots, the thread continues right after.
+	MarkRoots(GcMarker),
+}
+
+/// The roots of a thread which runs native code, with the functions which go through them.
+#[derive(Copy, Clone)]
+struct NativeRoots {
+	roots: *mut (),
+	retire: unsafe fn(*mut ()),
+	mark: unsafe fn(*mut (), GcMarker),
+	remap: unsafe fn(*mut ()),
+}
+
+// The roots are only used by the collector while the thread stays out of them.
+unsafe impl Send for NativeRoots {}
+
+impl NativeRoots {
+	fn new<U: GcUser, R: RootProvider<U>>(roots: &mut R) -> NativeRoots {
+		unsafe fn retire<U: GcUser, R: RootProvider<U>>(roots: *mut ()) {
+			(*(roots as *mut R)).sweeper().tlab.retire::<U>();
+		}
+		unsafe fn mark<U: GcUser, R: RootProvider<U>>(roots: *mut (), marker: GcMarker) {
+			(*(roots as *mut R)).mark_roots(marker);
+		}
+		unsafe fn remap<U: GcUser, R: RootProvider<U>>(roots: *mut ()) {
+			(*(roots as *mut R)).remap_roots(|r| r.forward());
+		}
+
+		NativeRoots {
+			roots: roots as *mut R as *mut (),
+			retire: retire::<U, R>,
+			mark: mark::<U, R>,
+			remap: remap::<U, R>,
+		}
+	}
+}
+
+enum ThreadState {
+	/// The thread stops for a collection by itself once it yields.
+	Vm,
+	/// The thread runs native code, collections go through its roots without waiting for it.
+	Native(NativeRoots),
+	/// A collection is going through the roots, the thread can't come back until it is done.
+	Collecting(NativeRoots, GcMarker),
+}
+
+struct SharedState {
+	state: Mutex<ThreadState>,
+	changed: Condvar,
+}
+
 pub struct GcSweeperHandle {
 	pub(super) uuid: Uuid,
 	pub(super) unparker: Unparker,
 	pub(super) complete_parker: Parker,
-	pub(super) sender: Sender<bool>,
+	pub(super) sender: Sender<GcRequest>,
 	pub(super) should_yield: Arc<AtomicBool>,
+	state: Arc<SharedState>,
 }
 
 impl GcSweeperHandle {
-	pub(super) fn start(&self, mark: bool) {
+	pub(super) fn start(&self, request: GcRequest) {
+		let mut state = self.state.state.lock();
+		if let ThreadState::Native(roots) = *state {
+			let (GcRequest::Collect(marker) | GcRequest::MarkRoots(marker)) = request;
+			unsafe { (roots.retire)(roots.roots) };
+			*state = ThreadState::Collecting(roots, marker);
+			return;
+		}
+
+		// The request gets sent while the thread can't leave the vm, so it never misses it.
 		self.should_yield.store(true, Ordering::Relaxed);
-		self.sender.send(mark).unwrap();
+		self.sender.send(request).unwrap();
+		drop(state);
 		debug!("Waiting for {}", self.uuid);
 		self.complete_parker.park();
 		self.should_yield.store(false, Ordering::Relaxed);
 	}
 
+	// Lets the thread continue to the next step, use wait_complete to wait for it to finish that step.
 	pub(super) fn start_marking(&self) {
-		self.unparker.unpark();
-		self.complete_parker.park();
+		match &*self.state.state.lock() {
+			ThreadState::Collecting(roots, marker) => unsafe {
+				(roots.mark)(roots.roots, marker.clone())
+			},
+			_ => self.unparker.unpark(),
+		}
 	}
 
 	pub(super) fn move_roots(&self) {
-		self.unparker.unpark();
-		self.complete_parker.park();
+		match &*self.state.state.lock() {
+			ThreadState::Collecting(roots, _) => unsafe { (roots.remap)(roots.roots) },
+			_ => self.unparker.unpark(),
+		}
+	}
+
+	pub(super) fn wait_complete(&self) {
+		if !matches!(*self.state.state.lock(), ThreadState::Collecting(..)) {
+			self.complete_parker.park();
+		}
 	}
 
 	pub(super) fn continue_execution(&self) {
-		self.unparker.unpark();
+		let mut state = self.state.state.lock();
+		match *state {
+			ThreadState::Collecting(roots, _) => {
+				*state = ThreadState::Native(roots);
+				self.state.changed.notify_all();
+			}
+			_ => self.unparker.unpark(),
+		}
 	}
 }
 
 pub struct GcSweeper {
 	pub(super) uuid: Uuid,
 	pub(super) finished: bool,
-	// Gives which mark
-	pub(super) receiver: Receiver<bool>,
+	pub(super) tlab: Tlab,
+	// Gives the request for the current collection
+	pub(super) receiver: Receiver<GcRequest>,
 	pub(super) should_yield: Arc<AtomicBool>,
 	pub(super) parker: Parker,
 	pub(super) complete: Unparker,
+	state: Arc<SharedState>,
 }
 
 impl Drop for GcSweeper {
@@ -93,29 +188,85 @@ impl GcSweeper {
 	pub fn yield_gc<U: GcUser>(roots: &mut impl RootProvider<U>) {
 		let sweeper = roots.sweeper();
 		if sweeper.should_yield.load(Ordering::Relaxed) {
-			if let Ok(mark) = sweeper.receiver.try_recv() {
-				Self::gc(mark, roots);
+			if let Ok(request) = sweeper.receiver.try_recv() {
+				Self::gc(request, roots);
+			}
+		}
+	}
+
+	/// Lets collections go on without waiting for the thread, they go through its roots by
+	/// themselves until it comes back with [GcSweeper::leave_native].
+	///
+	/// # Safety
+	/// The roots must stay where they are and must not be used until the thread comes back.
+	pub unsafe fn enter_native<U: GcUser, R: RootProvider<U>>(roots: &mut R) {
+		loop {
+			let state = roots.sweeper().state.clone();
+			let mut guard = state.state.lock();
+			assert!(
+				matches!(*guard, ThreadState::Vm),
+				"Thread is already in native code"
+			);
+			// A collection which already waits for the thread has to get its answer first.
+			match roots.sweeper().receiver.try_recv() {
+				Ok(request) => {
+					drop(guard);
+					Self::gc(request, roots);
+				}
+				Err(_) => {
+					*guard = ThreadState::Native(NativeRoots::new(roots));
+					return;
+				}
+			}
+		}
+	}
+
+	/// Comes back from native code, after the collection which goes through the roots is done.
+	/// Returns false if the thread was not in native code.
+	pub fn leave_native(&mut self) -> bool {
+		let mut state = self.state.state.lock();
+		loop {
+			match *state {
+				ThreadState::Vm => return false,
+				ThreadState::Native(_) => {
+					*state = ThreadState::Vm;
+					return true;
+				}
+				ThreadState::Collecting(..) => self.state.changed.wait(&mut state),
 			}
 		}
 	}
 
 	pub fn wait_until_gc<U: GcUser>(roots: &mut impl RootProvider<U>) {
-		let mark = roots
+		let request = roots
 			.sweeper()
 			.receiver
 			.recv_timeout(Duration::from_secs_f32(5.0))
 			.expect("GC timeout");
-		Self::gc(mark, roots);
+		Self::gc(request, roots);
 	}
 
-	fn gc<U: GcUser>(mark: bool, roots: &mut impl RootProvider<U>) {
-		// Wait until gc is ready to start marking
+	fn gc<U: GcUser>(request: GcRequest, roots: &mut impl RootProvider<U>) {
+		// Wait until gc is ready to start marking, the heap gets compacted so we give back our buffer.
 		let sweeper = roots.sweeper();
+		sweeper.tlab.retire::<U>();
 		sweeper.complete.unpark();
 		sweeper.parker.park();
 
+		let marker = match request {
+			GcRequest::Collect(marker) => marker,
+			GcRequest::MarkRoots(marker) => {
+				// Marking continues while we run, so we only wait until every thread has marked.
+				roots.mark_roots(marker);
+				let sweeper = roots.sweeper();
+				sweeper.complete.unpark();
+				sweeper.parker.park();
+				return;
+			}
+		};
+
 		// mark all of the objects
-		roots.mark_roots(GcMarker { mark });
+		roots.mark_roots(marker);
 
 		// Wait until all marking has been complete, and the objects have found their new location
 		let sweeper = roots.sweeper();
@@ -129,32 +280,3 @@ impl GcSweeper {
 		sweeper.parker.park();
 	}
 }
-
-pub struct GcMarker {
-	pub(super) mark: bool,
-}
-
-impl GcMarker {
-	pub fn mark<U: GcUser>(&self, mut reference: GcRef<U>) {
-		if reference.is_null() {
-			return;
-		}
-
-		let header = reference.header_mut();
-		let object_mark = header.flags.contains(ObjectFlags::MARK);
-		if object_mark == self.mark {
-			// We have already visited this object so we return here.
-			return;
-		}
-
-		trace!("Visiting {:?}", reference);
-
-		// we toggle the mark to say that we have visited/visiting this object.
-		let header = reference.header_mut();
-		header.flags.set(ObjectFlags::MARK, self.mark);
-
-		reference.visit_refs(|value| {
-			self.mark(value);
-		});
-	}
-}
@@ -0,0 +1,83 @@
+use crate::{GcHeader, GcRef, GcUser, ObjectFlags};
+use std::ptr::null_mut;
+
+/// The size of a freshly carved thread-local allocation buffer.
+pub const TLAB_SIZE: usize = 32 * 1024;
+
+/// Objects bigger than this skip the TLAB and get allocated on the shared heap directly.
+pub const TLAB_MAX_OBJECT_SIZE: usize = TLAB_SIZE / 4;
+
+/// A thread-local allocation buffer, this is a part of the heap which only a single sweeper allocates in,
+/// which means that allocations can be done with a pointer bump instead of taking the heap lock.
+pub(crate) struct Tlab {
+	top: *mut u8,
+	end: *mut u8,
+	/// The mark new objects should have, this only changes on a gc which always retires the buffer.
+	mark: bool,
+}
+
+unsafe impl Send for Tlab {}
+
+impl Tlab {
+	pub(crate) fn empty() -> Tlab {
+		Tlab {
+			top: null_mut(),
+			end: null_mut(),
+			mark: false,
+		}
+	}
+
+	pub(crate) fn new(start: *mut u8, end: *mut u8, mark: bool) -> Tlab {
+		Tlab {
+			top: start,
+			end,
+			mark,
+		}
+	}
+
+	pub(crate) fn remaining(&self) -> usize {
+		self.end as usize - self.top as usize
+	}
+
+	/// Bumps the pointer, gives the header back if the object does not fit.
+	pub(crate) fn allocate<U: GcUser>(
+		&mut self,
+		total_size: usize,
+		header: U::Header,
+	) -> Result<GcRef<U>, U::Header> {
+		let remaining = self.remaining();
+		// The tail always needs to fit a filler header, or be empty.
+		if total_size > remaining
+			|| (remaining != total_size && remaining - total_size < GcHeader::<U>::SIZE)
+		{
+			return Err(header);
+		}
+
+		let flags = if self.mark {
+			ObjectFlags::MARK
+		} else {
+			ObjectFlags::empty()
+		};
+		let Some(gc_header) = GcHeader::<U>::new(flags, total_size, header) else {
+			unreachable!("TLAB objects are always smaller than the max object size");
+		};
+
+		unsafe {
+			let gc_ref = GcRef::create_at(self.top, gc_header);
+			self.top = self.top.add(total_size);
+			Ok(gc_ref)
+		}
+	}
+
+	/// Fills the unused tail with a filler object so the heap walkers can skip over it.
+	pub(crate) fn retire<U: GcUser>(&mut self) {
+		let remaining = self.remaining();
+		if remaining > 0 {
+			unsafe {
+				GcHeader::<U>::write_filler(self.top, remaining);
+			}
+		}
+
+		*self = Tlab::empty();
+	}
+}
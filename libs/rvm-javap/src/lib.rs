@@ -0,0 +1,4 @@
+mod listing;
+mod text;
+
+pub use listing::*;
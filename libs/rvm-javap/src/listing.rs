@@ -0,0 +1,449 @@
+use eyre::{bail, Context};
+use num_traits::FromPrimitive;
+use serde::Serialize;
+
+use rvm_core::Type;
+use rvm_reader::{
+	write_instructions, ArrayInst, AttributeInfo, ClassInfo, Code, ConstInst, ConstPtr,
+	ConstantInfo, ConstantPool, Inst, InvokeInstKind, LocalInst, Op, UTF8Const,
+};
+
+/// Everything which gets printed about a class, with the constants it refers to resolved.
+#[derive(Serialize)]
+pub struct ClassListing {
+	pub name: String,
+	pub super_class: Option<String>,
+	pub interfaces: Vec<String>,
+	pub minor_version: u16,
+	pub major_version: u16,
+	pub flags: Vec<String>,
+	pub constant_pool: Vec<ConstantListing>,
+	pub fields: Vec<FieldListing>,
+	pub methods: Vec<MethodListing>,
+}
+
+#[derive(Serialize)]
+pub struct ConstantListing {
+	pub index: u16,
+	pub kind: &'static str,
+	/// The value, or the indices of the constants it is made of.
+	pub value: String,
+	pub resolved: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct FieldListing {
+	pub name: String,
+	pub descriptor: String,
+	pub flags: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct MethodListing {
+	pub name: String,
+	pub descriptor: String,
+	pub flags: Vec<String>,
+	pub code: Option<CodeListing>,
+}
+
+#[derive(Serialize)]
+pub struct CodeListing {
+	pub max_stack: u16,
+	pub max_locals: u16,
+	pub instructions: Vec<InstListing>,
+	pub exception_table: Vec<ExceptionListing>,
+	pub line_numbers: Vec<LineListing>,
+	pub local_variables: Vec<LocalListing>,
+}
+
+/// Jumps and switches list their targets as byte offsets, like every other offset.
+#[derive(Serialize)]
+pub struct InstListing {
+	pub index: usize,
+	pub offset: usize,
+	pub opcode: String,
+	pub operands: Vec<String>,
+	pub reference: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ExceptionListing {
+	pub start: u16,
+	pub end: u16,
+	pub handler: u16,
+	/// Handlers without a type catch everything.
+	pub catch_type: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct LineListing {
+	pub offset: u16,
+	pub line: u16,
+}
+
+#[derive(Serialize)]
+pub struct LocalListing {
+	pub start: u16,
+	pub length: u16,
+	pub index: u16,
+	pub name: String,
+	pub descriptor: String,
+}
+
+impl ClassListing {
+	pub fn new(class: &ClassInfo) -> eyre::Result<ClassListing> {
+		let cp = &class.cp;
+		let constant_pool = cp
+			.entries()
+			.iter()
+			.enumerate()
+			.filter_map(|(index, info)| constant(cp, index as u16 + 1, info))
+			.collect();
+
+		let fields = class
+			.fields
+			.iter()
+			.map(|field| FieldListing {
+				name: utf8(cp, field.name_index),
+				descriptor: utf8(cp, field.descriptor_index),
+				flags: flags(field.access_flags.iter_names()),
+			})
+			.collect();
+
+		let mut methods = Vec::with_capacity(class.methods.len());
+		for method in &class.methods {
+			let name = utf8(cp, method.name_index);
+			let code = method
+				.attributes
+				.iter()
+				.find_map(|attribute| match attribute {
+					AttributeInfo::CodeAttribute { code } => Some(code),
+					_ => None,
+				});
+			let code = match code {
+				Some(code) => {
+					Some(CodeListing::new(cp, code).wrap_err_with(|| format!("Method {name}"))?)
+				}
+				None => None,
+			};
+
+			methods.push(MethodListing {
+				name,
+				descriptor: utf8(cp, method.descriptor_index),
+				flags: flags(method.access_flags.iter_names()),
+				code,
+			});
+		}
+
+		Ok(ClassListing {
+			name: resolve(cp, class.this_class.id()),
+			// Only java/lang/Object has no super class.
+			super_class: (class.super_class.id() != 0).then(|| resolve(cp, class.super_class.id())),
+			interfaces: class
+				.interfaces
+				.iter()
+				.map(|interface| resolve(cp, interface.id()))
+				.collect(),
+			minor_version: class.minor_version,
+			major_version: class.major_version,
+			flags: flags(class.access_flags.iter_names()),
+			constant_pool,
+			fields,
+			methods,
+		})
+	}
+}
+
+impl CodeListing {
+	fn new(cp: &ConstantPool, code: &Code) -> eyre::Result<CodeListing> {
+		// The opcodes come from the shortest encoding, which is the one javac writes.
+		let (bytes, encoded) = write_instructions(&code.instructions)?;
+		let offsets = code.pc_map.positions();
+		if offsets.len() != code.instructions.len() + 1 {
+			bail!("The byte offsets of the instructions are unknown");
+		}
+
+		let mut instructions = Vec::with_capacity(code.instructions.len());
+		for (index, inst) in code.instructions.iter().enumerate() {
+			let offset = offsets[index];
+			let position = encoded[index];
+			let Some(op) = Op::from_u8(bytes[position]) else {
+				bail!("Unknown opcode {} at {offset}", bytes[position]);
+			};
+			let opcode = match op {
+				Op::WIDE => match Op::from_u8(bytes[position + 1]) {
+					Some(op) => format!("wide {}", name(op)),
+					None => bail!("Unknown opcode {} at {}", bytes[position + 1], offset + 1),
+				},
+				op => name(op),
+			};
+
+			let short = encoded[index + 1] - position == 1;
+			let target = |offset: i32| offsets[(index as i64 + offset as i64) as usize].to_string();
+			let (operands, reference) = operands(cp, inst, short, target);
+			instructions.push(InstListing {
+				index,
+				offset,
+				opcode,
+				operands,
+				reference,
+			});
+		}
+
+		let exception_table = code
+			.exception_table
+			.iter()
+			.map(|exception| ExceptionListing {
+				start: exception.start_pc,
+				end: exception.end_pc,
+				handler: exception.handler_pc,
+				catch_type: (exception.catch_type != 0).then(|| resolve(cp, exception.catch_type)),
+			})
+			.collect();
+
+		let mut line_numbers = vec![];
+		let mut local_variables = vec![];
+		for attribute in &code.attribute_info {
+			match attribute {
+				AttributeInfo::LineNumberTable {
+					line_numbers: lines,
+				} => line_numbers.extend(lines.iter().map(|line| LineListing {
+					offset: line.start_pc,
+					line: line.line_number,
+				})),
+				AttributeInfo::LocalVariableTable { variables } => {
+					local_variables.extend(variables.iter().map(|variable| LocalListing {
+						start: variable.start_pc,
+						length: variable.length,
+						index: variable.index,
+						name: utf8(cp, variable.name_index),
+						descriptor: utf8(cp, variable.descriptor_index),
+					}))
+				}
+				_ => {}
+			}
+		}
+
+		Ok(CodeListing {
+			max_stack: code.max_stack,
+			max_locals: code.max_locals,
+			instructions,
+			exception_table,
+			line_numbers,
+			local_variables,
+		})
+	}
+}
+
+fn name(op: Op) -> String {
+	format!("{op:?}").to_lowercase()
+}
+
+/// The operands of an instruction, and the constant it refers to.
+fn operands(
+	cp: &ConstantPool,
+	inst: &Inst,
+	short: bool,
+	target: impl Fn(i32) -> String,
+) -> (Vec<String>, Option<String>) {
+	let reference = |id: u16| (vec![format!("#{id}")], Some(resolve(cp, id)));
+	match inst {
+		// These have their operand in the opcode, like iconst_1 or iload_0.
+		Inst::Const(ConstInst::Null) => (vec![], None),
+		Inst::Const(ConstInst::Ldc { id, .. }) => reference(*id),
+		Inst::Local(LocalInst::Load(..) | LocalInst::Store(..)) | Inst::Const(_) if short => {
+			(vec![], None)
+		}
+		Inst::Const(ConstInst::Int(value)) => (vec![value.to_string()], None),
+		Inst::Const(ConstInst::Long(value)) => (vec![value.to_string()], None),
+		Inst::Const(ConstInst::Float(value)) => (vec![value.to_string()], None),
+		Inst::Const(ConstInst::Double(value)) => (vec![value.to_string()], None),
+		Inst::Local(LocalInst::Load(_, index) | LocalInst::Store(_, index)) | Inst::RET(index) => {
+			(vec![index.to_string()], None)
+		}
+		Inst::Local(LocalInst::Increment(amount, index)) => {
+			(vec![index.to_string(), amount.to_string()], None)
+		}
+		Inst::Array(ArrayInst::NewPrim(ty)) => {
+			(vec![Type::Primitive(*ty).kind().to_string()], None)
+		}
+		Inst::Array(ArrayInst::NewRef(class)) => reference(class.id()),
+		Inst::Array(ArrayInst::NewMultiRef { class, dimensions }) => {
+			let (mut operands, reference) = reference(class.id());
+			operands.push(dimensions.to_string());
+			(operands, reference)
+		}
+		Inst::Jump(jump) => (vec![target(jump.offset)], None),
+		Inst::JSR(jump) => (vec![target(jump.0 as i32)], None),
+		Inst::JSR_W(jump) => (vec![target(jump.0)], None),
+		Inst::New(inst) => reference(inst.class.id()),
+		Inst::CheckCast(inst) => reference(inst.value.id()),
+		Inst::InstanceOf(inst) => reference(inst.value.id()),
+		Inst::Field(inst) => reference(inst.value.id()),
+		Inst::Invoke(inst) => {
+			let (mut operands, reference) = reference(inst.value.id());
+			if let InvokeInstKind::Interface(count) = inst.kind {
+				operands.push(count.to_string());
+			}
+			(operands, reference)
+		}
+		Inst::TableSwitch(switch) => {
+			let mut operands: Vec<String> = switch
+				.offsets
+				.iter()
+				.enumerate()
+				.map(|(key, offset)| format!("{}: {}", switch.low + key as i32, target(*offset)))
+				.collect();
+			operands.push(format!("default: {}", target(switch.default_offset)));
+			(operands, None)
+		}
+		Inst::LookupSwitch(switch) => {
+			let mut operands: Vec<String> = switch
+				.pairs
+				.iter()
+				.map(|(key, offset)| format!("{key}: {}", target(*offset)))
+				.collect();
+			operands.push(format!("default: {}", target(switch.default_offset)));
+			(operands, None)
+		}
+		_ => (vec![], None),
+	}
+}
+
+fn flags<'a>(names: impl Iterator<Item = (&'a str, impl Sized)>) -> Vec<String> {
+	names.map(|(name, _)| name.to_string()).collect()
+}
+
+fn utf8(cp: &ConstantPool, ptr: ConstPtr<UTF8Const>) -> String {
+	match cp.get(ptr) {
+		Some(text) => text.to_string(),
+		None => format!("#{}", ptr.id()),
+	}
+}
+
+/// A constant in the words of javap, the second slot of longs and doubles is skipped.
+fn constant(cp: &ConstantPool, index: u16, info: &ConstantInfo) -> Option<ConstantListing> {
+	let (kind, value, resolved) = match info {
+		ConstantInfo::UTF8(text) => ("Utf8", text.to_string(), None),
+		ConstantInfo::Integer(value) => ("Integer", value.bytes.to_string(), None),
+		ConstantInfo::Float(value) => ("Float", value.bytes.to_string(), None),
+		ConstantInfo::Long(value) => ("Long", value.bytes.to_string(), None),
+		ConstantInfo::Double(value) => ("Double", value.bytes.to_string(), None),
+		ConstantInfo::Class(value) => ("Class", format!("#{}", value.name.id()), None),
+		ConstantInfo::String(value) => ("String", format!("#{}", value.string.id()), None),
+		ConstantInfo::Field(value) => (
+			"Fieldref",
+			format!("#{}.#{}", value.class.id(), value.name_and_type.id()),
+			None,
+		),
+		ConstantInfo::Method(value) => (
+			"Methodref",
+			format!("#{}.#{}", value.class.id(), value.name_and_type.id()),
+			None,
+		),
+		ConstantInfo::Interface(value) => (
+			"InterfaceMethodref",
+			format!("#{}.#{}", value.class.id(), value.name_and_type.id()),
+			None,
+		),
+		ConstantInfo::NameAndType(value) => (
+			"NameAndType",
+			format!("#{}:#{}", value.name.id(), value.descriptor.id()),
+			None,
+		),
+		ConstantInfo::MethodHandle(value) => (
+			"MethodHandle",
+			format!("{}:#{}", value.reference_kind, value.reference_index),
+			None,
+		),
+		ConstantInfo::MethodType(value) => {
+			("MethodType", format!("#{}", value.descriptor.id()), None)
+		}
+		ConstantInfo::Dynamic(value) => (
+			"Dynamic",
+			format!(
+				"#{}:#{}",
+				value.bootstrap_method_attr_index,
+				value.name_and_type.id()
+			),
+			None,
+		),
+		ConstantInfo::InvokeDynamic(value) => (
+			"InvokeDynamic",
+			format!(
+				"#{}:#{}",
+				value.bootstrap_method_attr_index,
+				value.name_and_type.id()
+			),
+			None,
+		),
+		ConstantInfo::Module(value) => ("Module", format!("#{}", value.name.id()), None),
+		ConstantInfo::Package(value) => ("Package", format!("#{}", value.name.id()), None),
+		ConstantInfo::Unusable => ("Unusable", String::new(), None),
+		ConstantInfo::Unknown => return None,
+	};
+
+	let resolved = resolved.or_else(|| match info {
+		ConstantInfo::UTF8(_)
+		| ConstantInfo::Integer(_)
+		| ConstantInfo::Float(_)
+		| ConstantInfo::Long(_)
+		| ConstantInfo::Double(_)
+		| ConstantInfo::Unusable => None,
+		_ => Some(resolve(cp, index)),
+	});
+	Some(ConstantListing {
+		index,
+		kind,
+		value,
+		resolved,
+	})
+}
+
+/// The constant at the index as text, like `java/lang/Object."<init>":()V` for a method.
+fn resolve(cp: &ConstantPool, index: u16) -> String {
+	let Some(info) = (index != 0).then(|| cp.raw_get(index)).flatten() else {
+		return format!("#{index}");
+	};
+
+	let member = |class: u16, name_and_type: u16| {
+		format!("{}.{}", resolve(cp, class), resolve(cp, name_and_type))
+	};
+	match info {
+		ConstantInfo::UTF8(text) => text.to_string(),
+		ConstantInfo::Integer(value) => value.bytes.to_string(),
+		ConstantInfo::Float(value) => value.bytes.to_string(),
+		ConstantInfo::Long(value) => value.bytes.to_string(),
+		ConstantInfo::Double(value) => value.bytes.to_string(),
+		ConstantInfo::Class(value) => resolve(cp, value.name.id()),
+		ConstantInfo::String(value) => format!("{:?}", resolve(cp, value.string.id())),
+		ConstantInfo::Field(value) => member(value.class.id(), value.name_and_type.id()),
+		ConstantInfo::Method(value) => member(value.class.id(), value.name_and_type.id()),
+		ConstantInfo::Interface(value) => member(value.class.id(), value.name_and_type.id()),
+		ConstantInfo::NameAndType(value) => {
+			let name = resolve(cp, value.name.id());
+			// Like javap, so constructors stand out.
+			let name = if name.starts_with('<') {
+				format!("{name:?}")
+			} else {
+				name
+			};
+			format!("{name}:{}", resolve(cp, value.descriptor.id()))
+		}
+		ConstantInfo::MethodHandle(value) => resolve(cp, value.reference_index),
+		ConstantInfo::MethodType(value) => resolve(cp, value.descriptor.id()),
+		ConstantInfo::Dynamic(value) => format!(
+			"#{}:{}",
+			value.bootstrap_method_attr_index,
+			resolve(cp, value.name_and_type.id())
+		),
+		ConstantInfo::InvokeDynamic(value) => format!(
+			"#{}:{}",
+			value.bootstrap_method_attr_index,
+			resolve(cp, value.name_and_type.id())
+		),
+		ConstantInfo::Module(value) => resolve(cp, value.name.id()),
+		ConstantInfo::Package(value) => resolve(cp, value.name.id()),
+		ConstantInfo::Unusable | ConstantInfo::Unknown => format!("#{index}"),
+	}
+}
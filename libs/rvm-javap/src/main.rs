@@ -0,0 +1,86 @@
+use eyre::{bail, Context, ContextCompat};
+use rvm_class::{ClassSource, DirectoryClassSource, JarClassSource};
+use rvm_core::ObjectType;
+use rvm_javap::ClassListing;
+use rvm_reader::ClassInfo;
+use std::env;
+use std::fs::read;
+use std::path::PathBuf;
+
+const USAGE: &str = "Usage: rvm-javap [OPTIONS] <CLASSES>...
+
+Classes are .class files, or names like java/lang/String which get looked up on the classpath.
+
+Options:
+  -cp, --classpath <PATHS>  Jars and directories of classes, separated like PATH
+  --json                    Prints a JSON array with a listing for every class";
+
+fn main() -> eyre::Result<()> {
+	let mut sources: Vec<Box<dyn ClassSource>> = vec![];
+	let mut classes = vec![];
+	let mut json = false;
+	let mut args = env::args().skip(1);
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"-h" | "--help" => {
+				println!("{USAGE}");
+				return Ok(());
+			}
+			"--json" => json = true,
+			"-cp" | "--classpath" => {
+				let value = args
+					.next()
+					.wrap_err_with(|| format!("{arg} needs a value\n\n{USAGE}"))?;
+				for path in env::split_paths(&value) {
+					sources.push(source(path)?);
+				}
+			}
+			_ if arg.starts_with('-') => bail!("Unknown option {arg}\n\n{USAGE}"),
+			_ => classes.push(arg),
+		}
+	}
+	if classes.is_empty() {
+		bail!("No classes given\n\n{USAGE}");
+	}
+
+	let mut listings = Vec::with_capacity(classes.len());
+	for class in &classes {
+		let data = load(&sources, class)?;
+		let info = ClassInfo::parse_complete(&data).wrap_err_with(|| format!("Parsing {class}"))?;
+		listings.push(ClassListing::new(&info).wrap_err_with(|| format!("Listing {class}"))?);
+	}
+
+	if json {
+		println!("{}", serde_json::to_string_pretty(&listings)?);
+	} else {
+		for listing in &listings {
+			print!("{listing}");
+		}
+	}
+	Ok(())
+}
+
+fn source(path: PathBuf) -> eyre::Result<Box<dyn ClassSource>> {
+	if path.is_dir() {
+		return Ok(Box::new(DirectoryClassSource::new(path)?));
+	}
+
+	let data = read(&path).wrap_err_with(|| format!("Reading {}", path.display()))?;
+	let source =
+		JarClassSource::new(data).wrap_err_with(|| format!("Opening {}", path.display()))?;
+	Ok(Box::new(source))
+}
+
+fn load(sources: &[Box<dyn ClassSource>], class: &str) -> eyre::Result<Vec<u8>> {
+	if class.ends_with(".class") {
+		return read(class).wrap_err_with(|| format!("Reading {class}"));
+	}
+
+	let ty = ObjectType::new(class.replace('.', "/"));
+	for source in sources {
+		if let Some(data) = source.try_load(&ty)? {
+			return Ok(data);
+		}
+	}
+	bail!("Class {class} is not on the classpath")
+}
@@ -0,0 +1,102 @@
+use std::fmt::{Display, Formatter};
+
+use crate::listing::{ClassListing, CodeListing};
+
+/// Roughly the layout of `javap -v -p`.
+impl Display for ClassListing {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "class {}", self.name)?;
+		if let Some(super_class) = &self.super_class {
+			writeln!(f, "  extends {super_class}")?;
+		}
+		if !self.interfaces.is_empty() {
+			writeln!(f, "  implements {}", self.interfaces.join(", "))?;
+		}
+		writeln!(f, "  minor version: {}", self.minor_version)?;
+		writeln!(f, "  major version: {}", self.major_version)?;
+		writeln!(f, "  flags: {}", self.flags.join(", "))?;
+
+		writeln!(f, "Constant pool:")?;
+		for constant in &self.constant_pool {
+			let index = format!("#{}", constant.index);
+			write!(f, "{index:>6} = {:<18} {}", constant.kind, constant.value)?;
+			match &constant.resolved {
+				Some(resolved) => writeln!(f, " // {resolved}")?,
+				None => writeln!(f)?,
+			}
+		}
+
+		writeln!(f, "{{")?;
+		for field in &self.fields {
+			writeln!(f, "  {} {};", field.descriptor, field.name)?;
+			writeln!(f, "    flags: {}", field.flags.join(", "))?;
+			writeln!(f)?;
+		}
+		for method in &self.methods {
+			writeln!(f, "  {}{}", method.name, method.descriptor)?;
+			writeln!(f, "    flags: {}", method.flags.join(", "))?;
+			if let Some(code) = &method.code {
+				write!(f, "{code}")?;
+			}
+			writeln!(f)?;
+		}
+		writeln!(f, "}}")
+	}
+}
+
+impl Display for CodeListing {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		writeln!(f, "    Code:")?;
+		writeln!(
+			f,
+			"      stack={}, locals={}",
+			self.max_stack, self.max_locals
+		)?;
+		for inst in &self.instructions {
+			write!(f, "{:>10}: {}", inst.offset, inst.opcode)?;
+			if !inst.operands.is_empty() {
+				let padding = 16usize.saturating_sub(inst.opcode.len());
+				write!(f, "{:padding$}{}", "", inst.operands.join(", "))?;
+			}
+			match &inst.reference {
+				Some(reference) => writeln!(f, " // {reference}")?,
+				None => writeln!(f)?,
+			}
+		}
+
+		if !self.exception_table.is_empty() {
+			writeln!(f, "      Exception table:")?;
+			writeln!(f, "         from    to  target type")?;
+			for exception in &self.exception_table {
+				writeln!(
+					f,
+					"         {:>4}  {:>4}  {:>4}   {}",
+					exception.start,
+					exception.end,
+					exception.handler,
+					exception.catch_type.as_deref().unwrap_or("any")
+				)?;
+			}
+		}
+
+		if !self.line_numbers.is_empty() {
+			writeln!(f, "      LineNumberTable:")?;
+			for line in &self.line_numbers {
+				writeln!(f, "        line {}: {}", line.line, line.offset)?;
+			}
+		}
+
+		if !self.local_variables.is_empty() {
+			writeln!(f, "      LocalVariableTable:")?;
+			writeln!(f, "        Start  Length  Slot  Name   Signature")?;
+			for local in &self.local_variables {
+				writeln!(
+					f,
+					"        {:>5}  {:>6}  {:>4}  {:<6} {}",
+					local.start, local.length, local.index, local.name, local.descriptor
+				)?;
+			}
+		}
+		Ok(())
+	}
+}
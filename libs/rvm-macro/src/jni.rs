@@ -6,30 +6,38 @@ use std::str::FromStr;
 use syn::__private::ToTokens;
 use syn::punctuated::Iter;
 use syn::spanned::Spanned;
-use syn::{parse, FnArg, ImplItem, ImplItemConst, ImplItemFn, ItemImpl, ReturnType};
+use syn::{
+	parse, FnArg, GenericArgument, ImplItem, ImplItemFn, ItemImpl, Pat, PathArguments, ReturnType,
+	Type,
+};
+
+/// The most parameters a binding can have, this is the largest tuple which `FromJavaMulti` is
+/// implemented for.
+const MAX_PARAMETERS: usize = 4;
 
 pub fn jni_binding(attr: TokenStream, item: TokenStream) -> TokenStream {
-	let package_name = attr.to_string();
+	let class_name = attr.to_string().replace(' ', "");
 	let mut item_impl: ItemImpl = parse(item).unwrap();
 
 	let mut out = String::new();
-	generate_link(&mut out, &package_name, &item_impl).unwrap();
+	generate_register(&mut out, &class_name, &item_impl).unwrap();
 
 	let token_stream = TokenStream::from_str(&out).expect(&out);
-	let link_item_impl: ImplItemFn = parse(token_stream).unwrap();
+	let register_item_impl: ImplItemFn = parse(token_stream).unwrap();
 
-	item_impl.items.push(ImplItem::Fn(link_item_impl));
+	item_impl.items.push(ImplItem::Fn(register_item_impl));
 	let stream = item_impl.to_token_stream();
 
 	stream.into()
 }
-pub fn jni_method(attr: TokenStream, item: TokenStream) -> TokenStream {
+
+pub fn jni_method(_: TokenStream, item: TokenStream) -> TokenStream {
+	// The binding gets generated by the jni_binding of the impl block.
 	item
 }
 
-fn generate_link(out: &mut String, package_name: &str, item: &ItemImpl) -> std::fmt::Result {
-	let item_name = item.self_ty.to_token_stream().to_string();
-	writeln!(out, "fn link(runtime: &Runtime) {{ unsafe {{")?;
+fn generate_register(out: &mut String, class_name: &str, item: &ItemImpl) -> std::fmt::Result {
+	writeln!(out, "pub fn register(vm: &rvm_runtime::Vm) {{")?;
 	for item in &item.items {
 		let ImplItem::Fn(func) = item else {
 			continue;
@@ -38,7 +46,7 @@ fn generate_link(out: &mut String, package_name: &str, item: &ItemImpl) -> std::
 		let Some(method_attr) = func
 			.attrs
 			.iter()
-			.find(|attribute| attribute.meta.path().is_ident(&"jni_method"))
+			.find(|attribute| attribute.meta.path().is_ident("jni_method"))
 		else {
 			continue;
 		};
@@ -52,91 +60,112 @@ fn generate_link(out: &mut String, package_name: &str, item: &ItemImpl) -> std::
 			.unwrap_or(func_name.clone().to_case(Case::Camel));
 
 		let mut inputs = func.sig.inputs.iter();
-
 		ensure_first_input_runtime(func.sig.span(), &mut inputs);
 
-		let mut output_type = None;
-		if let ReturnType::Type(_, ty, ..) = &func.sig.output {
-			output_type = Some(ty.to_token_stream().to_string());
+		// A first parameter named `this` gets the instance of non-static methods.
+		let mut inputs = inputs.peekable();
+		let mut receiver = None;
+		if let Some(FnArg::Typed(arg)) = inputs.peek() {
+			if matches!(&*arg.pat, Pat::Ident(ident) if ident.ident == "this") {
+				receiver = Some(arg.ty.to_token_stream().to_string().replace(' ', ""));
+				inputs.next();
+			}
 		}
 
 		let mut input_types = Vec::new();
 		for arg in inputs {
-			let FnArg::Typed(arg) = arg else { panic!() };
+			let FnArg::Typed(arg) = arg else {
+				abort!(arg.span(), "Bindings cannot take self");
+			};
 
 			input_types.push(arg.ty.to_token_stream().to_string());
 		}
-
-		{
-			writeln!(
-				out,
-				"unsafe extern \"C\" fn {func_name}(runtime: *const Runtime,"
-			)?;
-			for (i, ty) in input_types.iter().enumerate() {
-				writeln!(out, "v{i}: {ty},")?;
-			}
-			writeln!(out, ")")?;
-			if let Some(ty) = output_type.as_ref() {
-				writeln!(out, " -> {ty}")?;
-			}
-			writeln!(out, "{{")?;
-			writeln!(out, "let runtime = Arc::from_raw(runtime);")?;
-			write!(out, "let returns = {item_name}::{func_name}(&runtime, ")?;
-			for (i, ty) in input_types.iter().enumerate() {
-				write!(out, "v{i},")?;
-			}
-			writeln!(out, ");")?;
-			writeln!(out, "let _ = Arc::into_raw(runtime);")?;
-			writeln!(out, "returns")?;
-			writeln!(out, "}}")?;
+		if input_types.len() > MAX_PARAMETERS {
+			abort!(
+				func.sig.span(),
+				"Bindings can have at most {} parameters",
+				MAX_PARAMETERS
+			);
 		}
-		// 			runtime.linker.lock().link(
-		// 				"Java_testing_rni_RniTests_testNative".to_string(),
-		// 				MethodBinding::new(
-		// 					transmute::<*const (), extern "C" fn()>(test as *const ()),
-		// 					MethodDescriptor {
-		// 						parameters: vec![i32::ty(), i64::ty(), i32::ty()],
-		// 						returns: Some(i64::ty()),
-		// 					},
-		// 				),
-		// 			);
-		{
-			writeln!(out, "let desc = ")?;
-			writeln!(out, "rvm_core::MethodDescriptor {{ parameters: vec![")?;
-			for ty in &input_types {
-				write!(out, "<{ty} as rvm_core::Typed>::ty(),")?;
+
+		let (returns, descriptor_returns) = match &func.sig.output {
+			ReturnType::Type(_, ty) => (
+				format!(" -> {}", ty.to_token_stream()),
+				descriptor_type(ty)
+					.map(|ty| format!(" -> {}", ty.to_token_stream()))
+					.unwrap_or_default(),
+			),
+			ReturnType::Default => (String::new(), String::new()),
+		};
+
+		// The parameters get passed as a single value or a tuple, see FromJavaMulti.
+		let names: Vec<String> = (0..input_types.len()).map(|i| format!("v{i}")).collect();
+		let (pattern, pattern_ty) = match input_types.len() {
+			1 => (names[0].clone(), input_types[0].clone()),
+			_ => (
+				format!("({})", names.join(", ")),
+				format!("({})", input_types.join(", ")),
+			),
+		};
+
+		let this = match receiver.as_deref() {
+			None => String::new(),
+			Some(ty) if ty.starts_with("Option<") => "this, ".to_string(),
+			Some(_) => format!("this.expect(\"{class_name}.{jni_func_name} is not static\"), "),
+		};
+
+		writeln!(out, "{{")?;
+		// java_desc! only compiles if every type has a descriptor, the binder checks it against the
+		// descriptor of the java method when the method gets linked.
+		write!(out, "let descriptor = rvm_macro::java_desc!(fn(")?;
+		write!(out, "{}", input_types.join(", "))?;
+		writeln!(out, "){descriptor_returns});")?;
+		writeln!(
+			out,
+			"let binding = rvm_runtime::MethodBinding::new(|runtime: &mut rvm_runtime::Runtime, this: Option<rvm_runtime::Reference>, {pattern}: {pattern_ty}|{returns} {{"
+		)?;
+		writeln!(
+			out,
+			"Self::{func_name}(runtime, {this}{})",
+			names.join(", ")
+		)?;
+		writeln!(out, "}});")?;
+		writeln!(
+			out,
+			"assert_eq!(binding.signature().to_string(), descriptor, \"Binding of {class_name}.{jni_func_name} does not match its descriptor\");"
+		)?;
+		writeln!(
+			out,
+			"vm.bindings.bind(\"{class_name}\", \"{jni_func_name}\", binding);"
+		)?;
+		writeln!(out, "}}")?;
+	}
+
+	writeln!(out, "}}")?;
+	Ok(())
+}
+
+/// The type which a return type has in the descriptor, a `Result` returns its value and throws
+/// its error. `None` is void.
+fn descriptor_type(ty: &Type) -> Option<&Type> {
+	match ty {
+		Type::Tuple(tuple) if tuple.elems.is_empty() => None,
+		Type::Path(path) => {
+			let segment = path.path.segments.last()?;
+			if segment.ident != "Result" {
+				return Some(ty);
 			}
-			write!(out, "], returns: ")?;
-			match output_type {
-				Some(ty) => {
-					writeln!(out, "Some(<{ty} as rvm_core::Typed>::ty())")?;
-				}
-				None => {
-					writeln!(out, "None")?;
-				}
+
+			let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+				return Some(ty);
+			};
+			match arguments.args.first() {
+				Some(GenericArgument::Type(value)) => descriptor_type(value),
+				_ => Some(ty),
 			}
-			writeln!(out, "}};")?;
-
-			writeln!(out, "runtime.bindings.write().insert(")?;
-
-			writeln!(out, "rvm_runtime::MethodIdentifier {{")?;
-			//let string = package_name.replace("/", "_").replace(" ", "");
-			//writeln!(
-			//	out,
-			//	"name: \"Java_{}_{jni_func_name}\".to_string(),",
-			//	string
-			//)?;
-			writeln!(out, "name: \"{jni_func_name}\".to_string(),")?;
-			writeln!(out, "descriptor: desc.to_string(),")?;
-			writeln!(out, "}},")?;
-
-			writeln!(out, "MethodBinding::new(std::mem::transmute::<*const (), extern \"C\" fn()>({func_name} as *const ()), desc)")?;
-			writeln!(out, ");")?;
 		}
+		_ => Some(ty),
 	}
-
-	writeln!(out, "}} }}")?;
-	Ok(())
 }
 
 fn ensure_first_input_runtime(span: proc_macro2::Span, inputs: &mut Iter<FnArg>) {
@@ -144,13 +173,13 @@ fn ensure_first_input_runtime(span: proc_macro2::Span, inputs: &mut Iter<FnArg>)
 		.next()
 		.map(|v| v.to_token_stream().to_string())
 		.unwrap_or_default()
-		.replace(" ", "");
-	if !first.contains("Arc<Runtime>") {
+		.replace(' ', "");
+	if !first.ends_with("&mutRuntime") {
 		if first.is_empty() {
 			abort!(span, "Function needs to contain atleast a single argument.");
 		} else {
 			let message =
-				format!("Function first argument needs to be Arc<Runtime> and not {first}");
+				format!("Function first argument needs to be &mut Runtime and not {first}");
 			abort!(span, message);
 		}
 	}
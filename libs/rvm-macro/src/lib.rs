@@ -120,11 +120,9 @@ pub fn jni_method_name(item: TokenStream) -> TokenStream {
 #[proc_macro_attribute]
 #[proc_macro_error]
 pub fn jni_binding(attr: TokenStream, item: TokenStream) -> TokenStream {
-	item
-	//jni::jni_binding(attr, item)
+	jni::jni_binding(attr, item)
 }
 #[proc_macro_attribute]
 pub fn jni_method(attr: TokenStream, item: TokenStream) -> TokenStream {
-	item
-	//jni::jni_method(attr, item)
+	jni::jni_method(attr, item)
 }
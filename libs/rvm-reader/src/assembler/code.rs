@@ -0,0 +1,400 @@
+use eyre::{bail, Context};
+
+use rvm_core::{MethodDescriptor, StackKind};
+
+use crate::assembler::stack::max_stack;
+use crate::assembler::PoolBuilder;
+use crate::writer::write_instructions;
+use crate::{
+	AttributeException, BranchOffset, CheckCastInst, Code, ConstInst, ConstPtr, FieldInst,
+	FieldInstKind, Inst, InstanceOfInst, InvokeInst, InvokeInstKind, JumpInst, JumpKind, LocalInst,
+	LookupSwitchInst, NewInst, PcMap, ReturnInst, TableSwitchInst, ThrowInst, WideBranchOffset,
+};
+
+/// A position in the code, which can be jumped to before the instruction there exists.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Label(usize);
+
+struct Handler {
+	start: Label,
+	end: Label,
+	handler: Label,
+	catch_type: u16,
+}
+
+/// Appends instructions to the code of a method, the offsets of jumps and the exception table
+/// are filled in from the labels once the code is done.
+pub struct CodeBuilder<'a> {
+	pool: &'a mut PoolBuilder,
+	instructions: Vec<Inst>,
+	/// The instruction every label is placed at.
+	labels: Vec<Option<usize>>,
+	/// Instructions whose offsets still hold labels.
+	jumps: Vec<usize>,
+	handlers: Vec<Handler>,
+	parameter_slots: u16,
+}
+
+impl<'a> CodeBuilder<'a> {
+	pub(super) fn new(pool: &'a mut PoolBuilder, parameter_slots: u16) -> CodeBuilder<'a> {
+		CodeBuilder {
+			pool,
+			instructions: vec![],
+			labels: vec![],
+			jumps: vec![],
+			handlers: vec![],
+			parameter_slots,
+		}
+	}
+
+	pub fn pool(&mut self) -> &mut PoolBuilder {
+		self.pool
+	}
+
+	pub fn label(&mut self) -> Label {
+		self.labels.push(None);
+		Label(self.labels.len() - 1)
+	}
+
+	/// Points the label at the next instruction.
+	pub fn place(&mut self, label: Label) -> &mut Self {
+		assert!(
+			self.labels[label.0].is_none(),
+			"{label:?} is already placed"
+		);
+		self.labels[label.0] = Some(self.instructions.len());
+		self
+	}
+
+	pub fn inst(&mut self, inst: Inst) -> &mut Self {
+		self.instructions.push(inst);
+		self
+	}
+
+	pub fn null(&mut self) -> &mut Self {
+		self.inst(Inst::Const(ConstInst::Null))
+	}
+
+	/// Ints which do not fit into a short get loaded from the pool.
+	pub fn int(&mut self, value: i32) -> &mut Self {
+		if i16::try_from(value).is_ok() {
+			return self.inst(Inst::Const(ConstInst::Int(value)));
+		}
+		let id = self.pool.integer(value).id();
+		self.ldc(id, false)
+	}
+
+	pub fn long(&mut self, value: i64) -> &mut Self {
+		if let 0..=1 = value {
+			return self.inst(Inst::Const(ConstInst::Long(value)));
+		}
+		let id = self.pool.long(value).id();
+		self.ldc(id, true)
+	}
+
+	pub fn float(&mut self, value: f32) -> &mut Self {
+		if [0.0f32, 1.0, 2.0]
+			.iter()
+			.any(|constant| constant.to_bits() == value.to_bits())
+		{
+			return self.inst(Inst::Const(ConstInst::Float(value)));
+		}
+		let id = self.pool.float(value).id();
+		self.ldc(id, false)
+	}
+
+	pub fn double(&mut self, value: f64) -> &mut Self {
+		if [0.0f64, 1.0]
+			.iter()
+			.any(|constant| constant.to_bits() == value.to_bits())
+		{
+			return self.inst(Inst::Const(ConstInst::Double(value)));
+		}
+		let id = self.pool.double(value).id();
+		self.ldc(id, true)
+	}
+
+	pub fn string(&mut self, value: &str) -> &mut Self {
+		let id = self.pool.string(value).id();
+		self.ldc(id, false)
+	}
+
+	fn ldc(&mut self, id: u16, cat2: bool) -> &mut Self {
+		self.inst(Inst::Const(ConstInst::Ldc { id, cat2 }))
+	}
+
+	pub fn load(&mut self, kind: StackKind, index: u16) -> &mut Self {
+		self.inst(Inst::Local(LocalInst::Load(kind, index)))
+	}
+
+	pub fn store(&mut self, kind: StackKind, index: u16) -> &mut Self {
+		self.inst(Inst::Local(LocalInst::Store(kind, index)))
+	}
+
+	pub fn increment(&mut self, index: u16, amount: i16) -> &mut Self {
+		self.inst(Inst::Local(LocalInst::Increment(amount, index)))
+	}
+
+	pub fn jump(&mut self, kind: JumpKind, target: Label) -> &mut Self {
+		self.jumps.push(self.instructions.len());
+		self.inst(Inst::Jump(JumpInst {
+			offset: target.0 as i32,
+			kind,
+		}))
+	}
+
+	/// Jumps to a subroutine, with the address of the next instruction on the stack.
+	pub fn jsr(&mut self, target: Label) -> &mut Self {
+		self.jumps.push(self.instructions.len());
+		self.inst(Inst::JSR_W(WideBranchOffset(target.0 as i32)))
+	}
+
+	/// Returns from a subroutine to the address in the local.
+	pub fn ret(&mut self, index: u16) -> &mut Self {
+		self.inst(Inst::RET(index))
+	}
+
+	/// Jumps to `targets[key - low]`, or to the default if the key is outside of them.
+	pub fn table_switch(&mut self, low: i32, default: Label, targets: &[Label]) -> &mut Self {
+		self.jumps.push(self.instructions.len());
+		self.inst(Inst::TableSwitch(TableSwitchInst {
+			low,
+			high: low + targets.len() as i32 - 1,
+			default_offset: default.0 as i32,
+			offsets: targets.iter().map(|target| target.0 as i32).collect(),
+		}))
+	}
+
+	pub fn lookup_switch(&mut self, default: Label, targets: &[(i32, Label)]) -> &mut Self {
+		let mut pairs: Vec<(i32, i32)> = targets
+			.iter()
+			.map(|(key, target)| (*key, target.0 as i32))
+			.collect();
+		pairs.sort_by_key(|(key, _)| *key);
+
+		self.jumps.push(self.instructions.len());
+		self.inst(Inst::LookupSwitch(LookupSwitchInst {
+			default_offset: default.0 as i32,
+			pairs,
+		}))
+	}
+
+	pub fn get_static(&mut self, class: &str, name: &str, descriptor: &str) -> &mut Self {
+		self.field(class, name, descriptor, false, FieldInstKind::Get)
+	}
+
+	pub fn put_static(&mut self, class: &str, name: &str, descriptor: &str) -> &mut Self {
+		self.field(class, name, descriptor, false, FieldInstKind::Put)
+	}
+
+	pub fn get_field(&mut self, class: &str, name: &str, descriptor: &str) -> &mut Self {
+		self.field(class, name, descriptor, true, FieldInstKind::Get)
+	}
+
+	pub fn put_field(&mut self, class: &str, name: &str, descriptor: &str) -> &mut Self {
+		self.field(class, name, descriptor, true, FieldInstKind::Put)
+	}
+
+	fn field(
+		&mut self,
+		class: &str,
+		name: &str,
+		descriptor: &str,
+		instance: bool,
+		kind: FieldInstKind,
+	) -> &mut Self {
+		let value = self.pool.field(class, name, descriptor);
+		self.inst(Inst::Field(FieldInst {
+			value,
+			instance,
+			kind,
+		}))
+	}
+
+	pub fn invoke_static(&mut self, class: &str, name: &str, descriptor: &str) -> &mut Self {
+		self.invoke(class, name, descriptor, InvokeInstKind::Static)
+	}
+
+	pub fn invoke_virtual(&mut self, class: &str, name: &str, descriptor: &str) -> &mut Self {
+		self.invoke(class, name, descriptor, InvokeInstKind::Virtual)
+	}
+
+	pub fn invoke_special(&mut self, class: &str, name: &str, descriptor: &str) -> &mut Self {
+		self.invoke(class, name, descriptor, InvokeInstKind::Special)
+	}
+
+	fn invoke(
+		&mut self,
+		class: &str,
+		name: &str,
+		descriptor: &str,
+		kind: InvokeInstKind,
+	) -> &mut Self {
+		let value = self.pool.method(class, name, descriptor);
+		self.inst(Inst::Invoke(InvokeInst { value, kind }))
+	}
+
+	/// An invalid descriptor gets caught once the code is done.
+	pub fn invoke_interface(&mut self, class: &str, name: &str, descriptor: &str) -> &mut Self {
+		let count = MethodDescriptor::parse(descriptor)
+			.map_or(0, |descriptor| parameter_slots(&descriptor));
+		let value = self.pool.interface_method(class, name, descriptor);
+		self.inst(Inst::Invoke(InvokeInst {
+			value: ConstPtr::new(value.id()),
+			kind: InvokeInstKind::Interface((count + 1) as u8),
+		}))
+	}
+
+	pub fn new_object(&mut self, class: &str) -> &mut Self {
+		let class = self.pool.class(class);
+		self.inst(Inst::New(NewInst { class }))
+	}
+
+	pub fn check_cast(&mut self, class: &str) -> &mut Self {
+		let value = self.pool.class(class);
+		self.inst(Inst::CheckCast(CheckCastInst { value }))
+	}
+
+	pub fn instance_of(&mut self, class: &str) -> &mut Self {
+		let value = self.pool.class(class);
+		self.inst(Inst::InstanceOf(InstanceOfInst { value }))
+	}
+
+	pub fn throw(&mut self) -> &mut Self {
+		self.inst(Inst::Throw(ThrowInst {}))
+	}
+
+	pub fn return_void(&mut self) -> &mut Self {
+		self.inst(Inst::Return(ReturnInst { value: None }))
+	}
+
+	pub fn return_value(&mut self, kind: StackKind) -> &mut Self {
+		self.inst(Inst::Return(ReturnInst { value: Some(kind) }))
+	}
+
+	/// Jumps to the handler when an exception of the class is thrown between start and end, the
+	/// handler catches everything without a class.
+	pub fn try_catch(
+		&mut self,
+		start: Label,
+		end: Label,
+		handler: Label,
+		catch_type: Option<&str>,
+	) -> &mut Self {
+		let catch_type = catch_type.map_or(0, |class| self.pool.class(class).id());
+		self.handlers.push(Handler {
+			start,
+			end,
+			handler,
+			catch_type,
+		});
+		self
+	}
+
+	pub(super) fn build(self) -> eyre::Result<Code> {
+		let labels = self
+			.labels
+			.iter()
+			.enumerate()
+			.map(|(label, index)| match index {
+				Some(index) => Ok(*index),
+				None => bail!("Label({label}) is never placed"),
+			})
+			.collect::<eyre::Result<Vec<usize>>>()?;
+
+		let mut instructions = self.instructions;
+		for index in self.jumps {
+			let map = |offset: &mut i32| *offset = labels[*offset as usize] as i32 - index as i32;
+			match &mut instructions[index] {
+				Inst::Jump(JumpInst { offset, .. }) => map(offset),
+				Inst::JSR_W(WideBranchOffset(offset)) => {
+					map(offset);
+					// The writer widens it again if the offset in bytes does not fit.
+					if let Ok(offset) = i16::try_from(*offset) {
+						instructions[index] = Inst::JSR(BranchOffset(offset));
+					}
+				}
+				Inst::TableSwitch(TableSwitchInst {
+					default_offset,
+					offsets,
+					..
+				}) => {
+					map(default_offset);
+					offsets.iter_mut().for_each(map);
+				}
+				Inst::LookupSwitch(LookupSwitchInst {
+					default_offset,
+					pairs,
+				}) => {
+					map(default_offset);
+					pairs.iter_mut().for_each(|(_, offset)| map(offset));
+				}
+				_ => unreachable!(),
+			}
+		}
+
+		let handlers: Vec<(usize, usize, usize)> = self
+			.handlers
+			.iter()
+			.map(|handler| {
+				(
+					labels[handler.start.0],
+					labels[handler.end.0],
+					labels[handler.handler.0],
+				)
+			})
+			.collect();
+		for &(start, end, handler) in &handlers {
+			if start >= end || handler >= instructions.len() {
+				bail!("Invalid exception handler {start}..{end} -> {handler}");
+			}
+		}
+
+		let max_stack = max_stack(&instructions, &handlers, self.pool).wrap_err("Max stack")?;
+		let max_locals = max_locals(&instructions).max(self.parameter_slots as usize);
+
+		// The exception table is in bytes, which the writer will lay out the same way.
+		let (_, positions) = write_instructions(&instructions)?;
+		let mut exception_table = Vec::with_capacity(handlers.len());
+		for (&(start, end, handler), info) in handlers.iter().zip(&self.handlers) {
+			exception_table.push(AttributeException {
+				start_pc: u16::try_from(positions[start])?,
+				end_pc: u16::try_from(positions[end])?,
+				handler_pc: u16::try_from(positions[handler])?,
+				catch_type: info.catch_type,
+			});
+		}
+
+		Ok(Code {
+			max_stack: u16::try_from(max_stack).wrap_err("Max stack")?,
+			max_locals: u16::try_from(max_locals).wrap_err("Max locals")?,
+			instructions,
+			exception_table,
+			attribute_info: vec![],
+			pc_map: PcMap::new(positions),
+		})
+	}
+}
+
+fn max_locals(instructions: &[Inst]) -> usize {
+	instructions
+		.iter()
+		.map(|inst| match inst {
+			Inst::Local(LocalInst::Load(kind, index) | LocalInst::Store(kind, index)) => {
+				*index as usize + kind.kind().local_size() as usize
+			}
+			Inst::Local(LocalInst::Increment(_, index)) | Inst::RET(index) => *index as usize + 1,
+			_ => 0,
+		})
+		.max()
+		.unwrap_or(0)
+}
+
+/// The slots the parameters take up in the locals and on the stack.
+pub(super) fn parameter_slots(descriptor: &MethodDescriptor) -> usize {
+	descriptor
+		.parameters
+		.iter()
+		.map(|ty| ty.kind().local_size() as usize)
+		.sum()
+}
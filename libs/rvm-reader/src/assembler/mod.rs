@@ -0,0 +1,211 @@
+//! Building classes from rust, for code which javac would not generate.
+//!
+//! ```
+//! use rvm_core::{MethodAccessFlags, StackKind};
+//! use rvm_reader::{ClassBuilder, JumpKind};
+//!
+//! let mut class = ClassBuilder::new("tests/Count");
+//! class
+//!     .method(
+//!         MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+//!         "count",
+//!         "(I)I",
+//!         |code| {
+//!             let start = code.label();
+//!             let end = code.label();
+//!             code.int(0).store(StackKind::Int, 1);
+//!             code.place(start)
+//!                 .load(StackKind::Int, 0)
+//!                 .jump(JumpKind::IFLE, end)
+//!                 .increment(1, 1)
+//!                 .increment(0, -1)
+//!                 .jump(JumpKind::GOTO, start);
+//!             code.place(end).load(StackKind::Int, 1).return_value(StackKind::Int);
+//!             Ok(())
+//!         },
+//!     )
+//!     .unwrap();
+//! let bytes = class.write().unwrap();
+//! ```
+use eyre::{Context, ContextCompat};
+
+use rvm_core::{
+	ClassAccessFlags, FieldAccessFlags, MethodAccessFlags, MethodDescriptor, StackKind,
+};
+
+pub use code::*;
+pub use pool::*;
+
+use crate::{AttributeInfo, ClassConst, ClassInfo, ClassWriter, ConstPtr, FieldInfo, MethodInfo};
+
+mod code;
+mod pool;
+mod stack;
+
+/// Builds a [ClassInfo], with the constant pool, the offsets and the sizes of the code filled in.
+///
+/// Classes are version 49 unless changed, which is the last one that loads without a
+/// `StackMapTable`.
+pub struct ClassBuilder {
+	pool: PoolBuilder,
+	minor_version: u16,
+	major_version: u16,
+	access_flags: ClassAccessFlags,
+	this_class: ConstPtr<ClassConst>,
+	super_class: ConstPtr<ClassConst>,
+	/// `None` for `java/lang/Object`.
+	super_name: Option<String>,
+	interfaces: Vec<ConstPtr<ClassConst>>,
+	fields: Vec<FieldInfo>,
+	methods: Vec<MethodInfo>,
+}
+
+impl ClassBuilder {
+	/// A public class extending `java/lang/Object`, which itself has no super class.
+	pub fn new(name: &str) -> ClassBuilder {
+		let mut pool = PoolBuilder::new();
+		let this_class = pool.class(name);
+		let (super_class, super_name) = match name {
+			"java/lang/Object" => (ConstPtr::new(0), None),
+			_ => (
+				pool.class("java/lang/Object"),
+				Some("java/lang/Object".to_string()),
+			),
+		};
+		ClassBuilder {
+			pool,
+			minor_version: 0,
+			major_version: 49,
+			access_flags: ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER,
+			this_class,
+			super_class,
+			super_name,
+			interfaces: vec![],
+			fields: vec![],
+			methods: vec![],
+		}
+	}
+
+	pub fn pool(&mut self) -> &mut PoolBuilder {
+		&mut self.pool
+	}
+
+	pub fn version(&mut self, major: u16, minor: u16) -> &mut Self {
+		self.major_version = major;
+		self.minor_version = minor;
+		self
+	}
+
+	pub fn access_flags(&mut self, flags: ClassAccessFlags) -> &mut Self {
+		self.access_flags = flags;
+		self
+	}
+
+	pub fn super_class(&mut self, name: &str) -> &mut Self {
+		self.super_class = self.pool.class(name);
+		self.super_name = Some(name.to_string());
+		self
+	}
+
+	pub fn interface(&mut self, name: &str) -> &mut Self {
+		let interface = self.pool.class(name);
+		self.interfaces.push(interface);
+		self
+	}
+
+	pub fn field(&mut self, flags: FieldAccessFlags, name: &str, descriptor: &str) -> &mut Self {
+		let name_index = self.pool.utf8(name);
+		let descriptor_index = self.pool.utf8(descriptor);
+		self.fields.push(FieldInfo {
+			access_flags: flags,
+			name_index,
+			descriptor_index,
+			attribute_info: vec![],
+		});
+		self
+	}
+
+	/// A method with code, which gets its `max_stack` and `max_locals` computed.
+	pub fn method(
+		&mut self,
+		flags: MethodAccessFlags,
+		name: &str,
+		descriptor: &str,
+		build: impl FnOnce(&mut CodeBuilder) -> eyre::Result<()>,
+	) -> eyre::Result<&mut Self> {
+		let parsed = MethodDescriptor::parse(descriptor)
+			.wrap_err_with(|| format!("Invalid descriptor {descriptor} of {name}"))?;
+		let mut slots = parameter_slots(&parsed);
+		if !flags.contains(MethodAccessFlags::STATIC) {
+			slots += 1;
+		}
+
+		let name_index = self.pool.utf8(name);
+		let descriptor_index = self.pool.utf8(descriptor);
+		self.pool.utf8("Code");
+
+		let mut code = CodeBuilder::new(&mut self.pool, slots as u16);
+		build(&mut code).wrap_err_with(|| format!("Method {name}"))?;
+		let code = code.build().wrap_err_with(|| format!("Method {name}"))?;
+
+		self.methods.push(MethodInfo {
+			access_flags: flags,
+			name_index,
+			descriptor_index,
+			attributes: vec![AttributeInfo::CodeAttribute { code }],
+		});
+		Ok(self)
+	}
+
+	/// A method without code, which has to be abstract or native.
+	pub fn declare_method(
+		&mut self,
+		flags: MethodAccessFlags,
+		name: &str,
+		descriptor: &str,
+	) -> &mut Self {
+		let name_index = self.pool.utf8(name);
+		let descriptor_index = self.pool.utf8(descriptor);
+		self.methods.push(MethodInfo {
+			access_flags: flags,
+			name_index,
+			descriptor_index,
+			attributes: vec![],
+		});
+		self
+	}
+
+	/// A constructor which only calls the one of the super class without arguments.
+	pub fn default_constructor(&mut self) -> eyre::Result<&mut Self> {
+		let super_name = self.super_name.clone();
+		self.method(MethodAccessFlags::PUBLIC, "<init>", "()V", |code| {
+			if let Some(super_name) = &super_name {
+				code.load(StackKind::Reference, 0)
+					.invoke_special(super_name, "<init>", "()V");
+			}
+			code.return_void();
+			Ok(())
+		})
+	}
+
+	pub fn build(self) -> eyre::Result<ClassInfo> {
+		Ok(ClassInfo {
+			minor_version: self.minor_version,
+			major_version: self.major_version,
+			cp: self.pool.build().wrap_err("Constant Pool")?,
+			access_flags: self.access_flags,
+			this_class: self.this_class,
+			super_class: self.super_class,
+			interfaces: self.interfaces,
+			fields: self.fields,
+			methods: self.methods,
+			attributes: vec![],
+		})
+	}
+
+	/// Builds the class and writes it into the bytes of a `.class` file.
+	pub fn write(self) -> eyre::Result<Vec<u8>> {
+		let class = self.build()?;
+		ClassWriter::new(&class).write()
+	}
+}
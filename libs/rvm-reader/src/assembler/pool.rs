@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+
+use eyre::bail;
+
+use crate::{
+	ClassConst, ConstPtr, Constant, ConstantInfo, ConstantPool, DoubleConst, FieldConst,
+	FloatConst, IntegerConst, InterfaceConst, LongConst, MethodConst, NameAndTypeConst,
+	StringConst, UTF8Const,
+};
+
+/// Builds a constant pool, every constant only gets added once.
+#[derive(Default)]
+pub struct PoolBuilder {
+	entries: Vec<ConstantInfo>,
+	indices: HashMap<PoolKey, u16>,
+}
+
+/// Floats are keyed by their bits, so `NaN` and `-0.0` get their own entries.
+#[derive(Clone, Eq, PartialEq, Hash)]
+enum PoolKey {
+	UTF8(String),
+	Integer(i32),
+	Float(u32),
+	Long(i64),
+	Double(u64),
+	Class(u16),
+	String(u16),
+	NameAndType(u16, u16),
+	Field(u16, u16),
+	Method(u16, u16),
+	Interface(u16, u16),
+}
+
+impl PoolBuilder {
+	pub fn new() -> PoolBuilder {
+		PoolBuilder::default()
+	}
+
+	pub fn utf8(&mut self, text: &str) -> ConstPtr<UTF8Const> {
+		self.add(PoolKey::UTF8(text.to_string()), || {
+			ConstantInfo::UTF8(UTF8Const(text.to_string()))
+		})
+	}
+
+	pub fn integer(&mut self, bytes: i32) -> ConstPtr<IntegerConst> {
+		self.add(PoolKey::Integer(bytes), || {
+			ConstantInfo::Integer(IntegerConst { bytes })
+		})
+	}
+
+	pub fn float(&mut self, bytes: f32) -> ConstPtr<FloatConst> {
+		self.add(PoolKey::Float(bytes.to_bits()), || {
+			ConstantInfo::Float(FloatConst { bytes })
+		})
+	}
+
+	pub fn long(&mut self, bytes: i64) -> ConstPtr<LongConst> {
+		self.add(PoolKey::Long(bytes), || {
+			ConstantInfo::Long(LongConst { bytes })
+		})
+	}
+
+	pub fn double(&mut self, bytes: f64) -> ConstPtr<DoubleConst> {
+		self.add(PoolKey::Double(bytes.to_bits()), || {
+			ConstantInfo::Double(DoubleConst { bytes })
+		})
+	}
+
+	/// A class by its internal name (`java/lang/Object`), or the descriptor of an array.
+	pub fn class(&mut self, name: &str) -> ConstPtr<ClassConst> {
+		let name = self.utf8(name);
+		self.add(PoolKey::Class(name.id()), || {
+			ConstantInfo::Class(ClassConst { name })
+		})
+	}
+
+	pub fn string(&mut self, text: &str) -> ConstPtr<StringConst> {
+		let string = self.utf8(text);
+		self.add(PoolKey::String(string.id()), || {
+			ConstantInfo::String(StringConst { string })
+		})
+	}
+
+	pub fn name_and_type(&mut self, name: &str, descriptor: &str) -> ConstPtr<NameAndTypeConst> {
+		let name = self.utf8(name);
+		let descriptor = self.utf8(descriptor);
+		self.add(PoolKey::NameAndType(name.id(), descriptor.id()), || {
+			ConstantInfo::NameAndType(NameAndTypeConst { name, descriptor })
+		})
+	}
+
+	pub fn field(&mut self, class: &str, name: &str, descriptor: &str) -> ConstPtr<FieldConst> {
+		let class = self.class(class);
+		let name_and_type = self.name_and_type(name, descriptor);
+		self.add(PoolKey::Field(class.id(), name_and_type.id()), || {
+			ConstantInfo::Field(FieldConst {
+				class,
+				name_and_type,
+			})
+		})
+	}
+
+	pub fn method(&mut self, class: &str, name: &str, descriptor: &str) -> ConstPtr<MethodConst> {
+		let class = self.class(class);
+		let name_and_type = self.name_and_type(name, descriptor);
+		self.add(PoolKey::Method(class.id(), name_and_type.id()), || {
+			ConstantInfo::Method(MethodConst {
+				class,
+				name_and_type,
+			})
+		})
+	}
+
+	pub fn interface_method(
+		&mut self,
+		class: &str,
+		name: &str,
+		descriptor: &str,
+	) -> ConstPtr<InterfaceConst> {
+		let class = self.class(class);
+		let name_and_type = self.name_and_type(name, descriptor);
+		self.add(PoolKey::Interface(class.id(), name_and_type.id()), || {
+			ConstantInfo::Interface(InterfaceConst {
+				class,
+				name_and_type,
+			})
+		})
+	}
+
+	pub fn build(self) -> eyre::Result<ConstantPool> {
+		// The count which gets written is one bigger than the amount of entries.
+		if self.entries.len() >= u16::MAX as usize {
+			bail!("The constant pool has {} entries", self.entries.len());
+		}
+		Ok(ConstantPool::new(self.entries))
+	}
+
+	pub(super) fn get(&self, index: u16) -> Option<&ConstantInfo> {
+		self.entries.get((index as usize).checked_sub(1)?)
+	}
+
+	/// Indices past the limit are 0, [PoolBuilder::build] fails on them.
+	fn add<V: Constant>(
+		&mut self,
+		key: PoolKey,
+		info: impl FnOnce() -> ConstantInfo,
+	) -> ConstPtr<V> {
+		if let Some(index) = self.indices.get(&key) {
+			return ConstPtr::new(*index);
+		}
+
+		let info = info();
+		let wide = matches!(info, ConstantInfo::Long(_) | ConstantInfo::Double(_));
+		let index = u16::try_from(self.entries.len() + 1).unwrap_or(0);
+		self.entries.push(info);
+		if wide {
+			self.entries.push(ConstantInfo::Unknown);
+		}
+
+		self.indices.insert(key, index);
+		ConstPtr::new(index)
+	}
+}
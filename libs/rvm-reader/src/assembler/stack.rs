@@ -0,0 +1,188 @@
+use eyre::{bail, ContextCompat};
+
+use rvm_core::{Kind, MethodDescriptor, StackKind, Type};
+
+use crate::assembler::code::parameter_slots;
+use crate::assembler::PoolBuilder;
+use crate::{
+	ArrayInst, ComparisonInst, ConstInst, ConstantInfo, FieldInstKind, Inst, InvokeInstKind,
+	LocalInst, MathInst, StackInst,
+};
+
+/// Follows every path through the code and returns the highest stack, in slots.
+///
+/// `handlers` are the instruction ranges with the instruction which handles exceptions thrown
+/// inside of them, the handler starts with the exception on the stack.
+pub(super) fn max_stack(
+	instructions: &[Inst],
+	handlers: &[(usize, usize, usize)],
+	pool: &PoolBuilder,
+) -> eyre::Result<usize> {
+	let mut heights: Vec<Option<usize>> = vec![None; instructions.len()];
+	let mut pending = vec![(0, 0)];
+	let mut max = 0;
+	while let Some((index, height)) = pending.pop() {
+		if index >= instructions.len() {
+			bail!("The code runs past its end");
+		}
+		match heights[index] {
+			Some(existing) if existing == height => continue,
+			Some(existing) => {
+				bail!("Instruction {index} is reached with {existing} and {height} slots")
+			}
+			None => heights[index] = Some(height),
+		}
+
+		let inst = &instructions[index];
+		let (pops, pushes) = effect(inst, pool)?;
+		let Some(remaining) = height.checked_sub(pops) else {
+			bail!("Instruction {index} {inst:?} pops {pops} slots from {height}");
+		};
+		let height = remaining + pushes;
+		max = max.max(height);
+
+		for &(start, end, handler) in handlers {
+			if (start..end).contains(&index) {
+				pending.push((handler, 1));
+				max = max.max(1);
+			}
+		}
+
+		let target = |offset: i32| (index as i64 + offset as i64) as usize;
+		match inst {
+			Inst::Jump(jump) => {
+				pending.push((target(jump.offset), height));
+				if jump.kind.is_conditional() {
+					pending.push((index + 1, height));
+				}
+			}
+			Inst::TableSwitch(switch) => {
+				pending.push((target(switch.default_offset), height));
+				for offset in &switch.offsets {
+					pending.push((target(*offset), height));
+				}
+			}
+			Inst::LookupSwitch(switch) => {
+				pending.push((target(switch.default_offset), height));
+				for (_, offset) in &switch.pairs {
+					pending.push((target(*offset), height));
+				}
+			}
+			// The subroutine returns with the stack it got called with, without its address.
+			Inst::JSR(offset) => {
+				pending.push((target(offset.0 as i32), height));
+				pending.push((index + 1, height - 1));
+			}
+			Inst::JSR_W(offset) => {
+				pending.push((target(offset.0), height));
+				pending.push((index + 1, height - 1));
+			}
+			Inst::Return(_) | Inst::Throw(_) | Inst::RET(_) => {}
+			_ => pending.push((index + 1, height)),
+		}
+	}
+	Ok(max)
+}
+
+/// The slots an instruction pops, and the ones it pushes afterwards.
+fn effect(inst: &Inst, pool: &PoolBuilder) -> eyre::Result<(usize, usize)> {
+	Ok(match inst {
+		Inst::Nop => (0, 0),
+		Inst::Const(ConstInst::Long(_) | ConstInst::Double(_)) => (0, 2),
+		Inst::Const(ConstInst::Ldc { cat2, .. }) => (0, if *cat2 { 2 } else { 1 }),
+		Inst::Const(_) => (0, 1),
+		Inst::Stack(inst) => match inst {
+			StackInst::Dup => (1, 2),
+			StackInst::DupX1 => (2, 3),
+			StackInst::DupX2 => (3, 4),
+			StackInst::Dup2 => (2, 4),
+			StackInst::Dup2X1 => (3, 5),
+			StackInst::Dup2X2 => (4, 6),
+			StackInst::Pop => (1, 0),
+			StackInst::Pop2 => (2, 0),
+			StackInst::Swap => (2, 2),
+		},
+		Inst::Array(inst) => match inst {
+			ArrayInst::Length => (1, 1),
+			ArrayInst::Load(kind) => (2, slots(*kind)),
+			ArrayInst::Store(kind) => (2 + slots(*kind), 0),
+			ArrayInst::NewPrim(_) | ArrayInst::NewRef(_) => (1, 1),
+			ArrayInst::NewMultiRef { dimensions, .. } => (*dimensions as usize, 1),
+		},
+		Inst::Math(inst) => {
+			let size = slots(inst.ty().kind());
+			match inst {
+				MathInst::Neg(_) => (size, size),
+				// The shift distance is always an int.
+				MathInst::Shl(_) | MathInst::Shr(_) | MathInst::Ushr(_) => (size + 1, size),
+				_ => (size * 2, size),
+			}
+		}
+		Inst::Conversion(inst) => {
+			let (from, to) = inst.types();
+			(slots(from.kind()), slots(to.kind()))
+		}
+		Inst::Comparison(inst) => match inst {
+			ComparisonInst::DCMPG | ComparisonInst::DCMPL | ComparisonInst::LCMP => (4, 1),
+			ComparisonInst::FCMPG | ComparisonInst::FCMPL => (2, 1),
+		},
+		Inst::Jump(inst) => (inst.kind.args() as usize, 0),
+		Inst::Local(LocalInst::Load(kind, _)) => (0, stack_slots(*kind)),
+		Inst::Local(LocalInst::Store(kind, _)) => (stack_slots(*kind), 0),
+		Inst::Local(LocalInst::Increment(_, _)) => (0, 0),
+		Inst::Return(inst) => (inst.value.map_or(0, stack_slots), 0),
+		Inst::New(_) => (0, 1),
+		Inst::Throw(_) => (1, 0),
+		Inst::CheckCast(_) | Inst::InstanceOf(_) => (1, 1),
+		Inst::Field(inst) => {
+			let ty = Type::parse(descriptor(pool, inst.value.id())?)
+				.wrap_err("Invalid field descriptor")?;
+			let size = slots(ty.kind());
+			let object = inst.instance as usize;
+			match inst.kind {
+				FieldInstKind::Get => (object, size),
+				FieldInstKind::Put => (object + size, 0),
+			}
+		}
+		Inst::Invoke(inst) => {
+			let descriptor = MethodDescriptor::parse(descriptor(pool, inst.value.id())?)
+				.wrap_err("Invalid method descriptor")?;
+			let object = match inst.kind {
+				InvokeInstKind::Static | InvokeInstKind::Dynamic => 0,
+				_ => 1,
+			};
+			let returns = descriptor.returns.as_ref().map_or(0, |ty| slots(ty.kind()));
+			(object + parameter_slots(&descriptor), returns)
+		}
+		Inst::JSR(_) | Inst::JSR_W(_) => (0, 1),
+		Inst::RET(_) => (0, 0),
+		Inst::LookupSwitch(_) | Inst::TableSwitch(_) => (1, 0),
+		Inst::MONITORENTER | Inst::MONITOREXIT => (1, 0),
+	})
+}
+
+fn slots(kind: Kind) -> usize {
+	kind.local_size() as usize
+}
+
+fn stack_slots(kind: StackKind) -> usize {
+	slots(kind.kind())
+}
+
+/// The descriptor of the field, method or call site at the index.
+fn descriptor(pool: &PoolBuilder, index: u16) -> eyre::Result<&str> {
+	let name_and_type = match pool.get(index) {
+		Some(ConstantInfo::Field(value)) => value.name_and_type,
+		Some(ConstantInfo::Method(value)) => value.name_and_type,
+		Some(ConstantInfo::Interface(value)) => value.name_and_type,
+		Some(ConstantInfo::InvokeDynamic(value)) => value.name_and_type,
+		info => bail!("Constant {index} is not a member but {info:?}"),
+	};
+	let Some(ConstantInfo::NameAndType(name_and_type)) = pool.get(name_and_type.id()) else {
+		bail!("Constant {index} has no name and type");
+	};
+	let Some(ConstantInfo::UTF8(descriptor)) = pool.get(name_and_type.descriptor.id()) else {
+		bail!("Constant {index} has no descriptor");
+	};
+	Ok(descriptor.as_str())
+}
@@ -1,20 +1,28 @@
-use nom::bytes::complete::take;
-use nom::combinator::{map, map_opt};
+use nom::combinator::{all_consuming, fail, map, map_opt, rest};
 use nom::error::context;
-use nom::multi::length_count;
-use nom::number::complete::{be_u16, be_u32};
-use nom::sequence::tuple;
+use nom::multi::{count, length_count, length_data};
+use nom::number::complete::{be_u16, be_u32, be_u8};
+use nom::sequence::{pair, tuple};
+
+use rvm_core::{
+	InnerClassAccessFlags, ModuleExportsFlags, ModuleFlags, ModuleRequiresFlags,
+	ParameterAccessFlags,
+};
 
 use crate::code::Code;
-use crate::consts::{ConstantInfo, ConstantPool};
-use crate::{be_cp, ConstPtr, IResult, UTF8Const};
+use crate::consts::ConstantPool;
+use crate::{
+	be_cp, ClassConst, ConstPtr, DoubleConst, FloatConst, IResult, IntegerConst, LongConst,
+	MethodHandleConst, ModuleConst, NameAndTypeConst, PackageConst, UTF8Const,
+};
 
 #[derive(Clone)]
 pub struct AttributeException {
-	start_pc: u16,
-	end_pc: u16,
-	handler_pc: u16,
-	catch_type: u16,
+	pub start_pc: u16,
+	pub end_pc: u16,
+	pub handler_pc: u16,
+	/// 0 if the handler catches everything.
+	pub catch_type: u16,
 }
 
 impl AttributeException {
@@ -31,16 +39,50 @@ impl AttributeException {
 	}
 }
 
+#[derive(Clone, Debug)]
 pub struct AttributeClass {
-	inner_class_info_index: u16,
-	outer_class_info_index: u16,
-	inner_name_index: u16,
-	inner_class_access_flags: u16,
+	pub inner_class: ConstPtr<ClassConst>,
+	/// 0 if the class is not a member, like local and anonymous classes.
+	pub outer_class: ConstPtr<ClassConst>,
+	/// 0 if the class is anonymous.
+	pub inner_name: ConstPtr<UTF8Const>,
+	pub access_flags: InnerClassAccessFlags,
+}
+
+impl AttributeClass {
+	pub fn parse(input: &[u8]) -> IResult<Self> {
+		map(
+			tuple((
+				be_cp,
+				be_cp,
+				be_cp,
+				map_opt(be_u16, InnerClassAccessFlags::from_bits),
+			)),
+			|(inner_class, outer_class, inner_name, access_flags)| AttributeClass {
+				inner_class,
+				outer_class,
+				inner_name,
+				access_flags,
+			},
+		)(input)
+	}
 }
 
+#[derive(Clone, Debug)]
 pub struct AttributeLineNumber {
-	start_pc: u16,
-	line_number: u16,
+	pub start_pc: u16,
+	pub line_number: u16,
+}
+
+impl AttributeLineNumber {
+	pub fn parse(input: &[u8]) -> IResult<Self> {
+		map(pair(be_u16, be_u16), |(start_pc, line_number)| {
+			AttributeLineNumber {
+				start_pc,
+				line_number,
+			}
+		})(input)
+	}
 }
 
 pub struct AttributeLocalVariable {
@@ -73,16 +115,550 @@ impl AttributeLocalVariable {
 }
 
 pub struct AttributeLocalVariableType {
-	start_pc: u16,
-	length: u16,
-	name_index: u16,
-	signature_index: u16,
-	index: u16,
+	pub start_pc: u16,
+	pub length: u16,
+	pub name_index: ConstPtr<UTF8Const>,
+	pub signature_index: ConstPtr<UTF8Const>,
+	pub index: u16,
 }
 
+impl AttributeLocalVariableType {
+	pub fn parse(input: &[u8]) -> IResult<Self> {
+		let (input, start_pc) = be_u16(input)?;
+		let (input, length) = be_u16(input)?;
+		let (input, name_index) = be_cp(input)?;
+		let (input, signature_index) = be_cp(input)?;
+		let (input, index) = be_u16(input)?;
+
+		Ok((
+			input,
+			AttributeLocalVariableType {
+				start_pc,
+				length,
+				name_index,
+				signature_index,
+				index,
+			},
+		))
+	}
+}
+
+#[derive(Clone, Debug)]
 pub struct AttributeBootstrapMethod {
-	bootstrap_method_ref: u16,
-	bootstrap_arguments: Vec<u16>,
+	pub bootstrap_method_ref: ConstPtr<MethodHandleConst>,
+	/// Indexes of loadable constants, these can be of any constant kind.
+	pub bootstrap_arguments: Vec<u16>,
+}
+
+impl AttributeBootstrapMethod {
+	pub fn parse(input: &[u8]) -> IResult<Self> {
+		map(
+			pair(be_cp, length_count(be_u16, be_u16)),
+			|(bootstrap_method_ref, bootstrap_arguments)| AttributeBootstrapMethod {
+				bootstrap_method_ref,
+				bootstrap_arguments,
+			},
+		)(input)
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct AttributeMethodParameter {
+	/// 0 if the parameter has no name.
+	pub name: ConstPtr<UTF8Const>,
+	pub access_flags: ParameterAccessFlags,
+}
+
+impl AttributeMethodParameter {
+	pub fn parse(input: &[u8]) -> IResult<Self> {
+		map(
+			pair(be_cp, map_opt(be_u16, ParameterAccessFlags::from_bits)),
+			|(name, access_flags)| AttributeMethodParameter { name, access_flags },
+		)(input)
+	}
+}
+
+pub struct AttributeRecordComponent {
+	pub name: ConstPtr<UTF8Const>,
+	pub descriptor: ConstPtr<UTF8Const>,
+	pub attributes: Vec<AttributeInfo>,
+}
+
+impl AttributeRecordComponent {
+	pub fn parse<'a>(input: &'a [u8], constant_pool: &ConstantPool) -> IResult<'a, Self> {
+		let (input, name) = be_cp(input)?;
+		let (input, descriptor) = be_cp(input)?;
+		let (input, attributes) = AttributeInfo::parse_list(input, constant_pool)?;
+		Ok((
+			input,
+			AttributeRecordComponent {
+				name,
+				descriptor,
+				attributes,
+			},
+		))
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct AttributeModule {
+	pub name: ConstPtr<ModuleConst>,
+	pub flags: ModuleFlags,
+	/// 0 if the module has no version.
+	pub version: ConstPtr<UTF8Const>,
+	pub requires: Vec<AttributeModuleRequires>,
+	pub exports: Vec<AttributeModuleExports>,
+	pub opens: Vec<AttributeModuleExports>,
+	pub uses: Vec<ConstPtr<ClassConst>>,
+	pub provides: Vec<AttributeModuleProvides>,
+}
+
+impl AttributeModule {
+	pub fn parse(input: &[u8]) -> IResult<Self> {
+		let (input, name) = be_cp(input)?;
+		let (input, flags) = map_opt(be_u16, ModuleFlags::from_bits)(input)?;
+		let (input, version) = be_cp(input)?;
+		let (input, requires) = length_count(be_u16, AttributeModuleRequires::parse)(input)?;
+		let (input, exports) = length_count(be_u16, AttributeModuleExports::parse)(input)?;
+		let (input, opens) = length_count(be_u16, AttributeModuleExports::parse)(input)?;
+		let (input, uses) = length_count(be_u16, be_cp)(input)?;
+		let (input, provides) = length_count(be_u16, AttributeModuleProvides::parse)(input)?;
+
+		Ok((
+			input,
+			AttributeModule {
+				name,
+				flags,
+				version,
+				requires,
+				exports,
+				opens,
+				uses,
+				provides,
+			},
+		))
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct AttributeModuleRequires {
+	pub module: ConstPtr<ModuleConst>,
+	pub flags: ModuleRequiresFlags,
+	/// 0 if the version is unknown.
+	pub version: ConstPtr<UTF8Const>,
+}
+
+impl AttributeModuleRequires {
+	pub fn parse(input: &[u8]) -> IResult<Self> {
+		map(
+			tuple((
+				be_cp,
+				map_opt(be_u16, ModuleRequiresFlags::from_bits),
+				be_cp,
+			)),
+			|(module, flags, version)| AttributeModuleRequires {
+				module,
+				flags,
+				version,
+			},
+		)(input)
+	}
+}
+
+/// An exported or opened package, which is open to every module if `to` is empty.
+#[derive(Clone, Debug)]
+pub struct AttributeModuleExports {
+	pub package: ConstPtr<PackageConst>,
+	pub flags: ModuleExportsFlags,
+	pub to: Vec<ConstPtr<ModuleConst>>,
+}
+
+impl AttributeModuleExports {
+	pub fn parse(input: &[u8]) -> IResult<Self> {
+		map(
+			tuple((
+				be_cp,
+				map_opt(be_u16, ModuleExportsFlags::from_bits),
+				length_count(be_u16, be_cp),
+			)),
+			|(package, flags, to)| AttributeModuleExports { package, flags, to },
+		)(input)
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct AttributeModuleProvides {
+	pub service: ConstPtr<ClassConst>,
+	pub with: Vec<ConstPtr<ClassConst>>,
+}
+
+impl AttributeModuleProvides {
+	pub fn parse(input: &[u8]) -> IResult<Self> {
+		map(
+			pair(be_cp, length_count(be_u16, be_cp)),
+			|(service, with)| AttributeModuleProvides { service, with },
+		)(input)
+	}
+}
+
+/// The offsets are in bytes and relative to the previous frame, see JVMS 4.7.4.
+#[derive(Clone, Debug)]
+pub enum StackMapFrame {
+	Same {
+		offset_delta: u16,
+	},
+	SameLocals1StackItem {
+		offset_delta: u16,
+		stack: VerificationType,
+	},
+	Chop {
+		offset_delta: u16,
+		/// How many of the last locals are gone, from 1 to 3.
+		chopped: u8,
+	},
+	Append {
+		offset_delta: u16,
+		locals: Vec<VerificationType>,
+	},
+	Full {
+		offset_delta: u16,
+		locals: Vec<VerificationType>,
+		stack: Vec<VerificationType>,
+	},
+}
+
+impl StackMapFrame {
+	pub fn parse(input: &[u8]) -> IResult<Self> {
+		let (input, frame_type) = be_u8(input)?;
+		match frame_type {
+			0..=63 => Ok((
+				input,
+				StackMapFrame::Same {
+					offset_delta: frame_type as u16,
+				},
+			)),
+			64..=127 => map(VerificationType::parse, |stack| {
+				StackMapFrame::SameLocals1StackItem {
+					offset_delta: frame_type as u16 - 64,
+					stack,
+				}
+			})(input),
+			247 => map(
+				pair(be_u16, VerificationType::parse),
+				|(offset_delta, stack)| StackMapFrame::SameLocals1StackItem {
+					offset_delta,
+					stack,
+				},
+			)(input),
+			248..=250 => map(be_u16, |offset_delta| StackMapFrame::Chop {
+				offset_delta,
+				chopped: 251 - frame_type,
+			})(input),
+			251 => map(be_u16, |offset_delta| StackMapFrame::Same { offset_delta })(input),
+			252..=254 => map(
+				pair(
+					be_u16,
+					count(VerificationType::parse, (frame_type - 251) as usize),
+				),
+				|(offset_delta, locals)| StackMapFrame::Append {
+					offset_delta,
+					locals,
+				},
+			)(input),
+			255 => map(
+				tuple((
+					be_u16,
+					length_count(be_u16, VerificationType::parse),
+					length_count(be_u16, VerificationType::parse),
+				)),
+				|(offset_delta, locals, stack)| StackMapFrame::Full {
+					offset_delta,
+					locals,
+					stack,
+				},
+			)(input),
+			// 128 to 246 are reserved
+			_ => fail(input),
+		}
+	}
+
+	pub fn offset_delta(&self) -> u16 {
+		match self {
+			StackMapFrame::Same { offset_delta }
+			| StackMapFrame::SameLocals1StackItem { offset_delta, .. }
+			| StackMapFrame::Chop { offset_delta, .. }
+			| StackMapFrame::Append { offset_delta, .. }
+			| StackMapFrame::Full { offset_delta, .. } => *offset_delta,
+		}
+	}
+
+	pub fn set_offset_delta(&mut self, delta: u16) {
+		match self {
+			StackMapFrame::Same { offset_delta }
+			| StackMapFrame::SameLocals1StackItem { offset_delta, .. }
+			| StackMapFrame::Chop { offset_delta, .. }
+			| StackMapFrame::Append { offset_delta, .. }
+			| StackMapFrame::Full { offset_delta, .. } => *offset_delta = delta,
+		}
+	}
+
+	/// The types of the locals and the stack which the frame lists.
+	pub fn types_mut(&mut self) -> impl Iterator<Item = &mut VerificationType> {
+		let (locals, stack): (&mut [VerificationType], &mut [VerificationType]) = match self {
+			StackMapFrame::Same { .. } | StackMapFrame::Chop { .. } => (&mut [], &mut []),
+			StackMapFrame::SameLocals1StackItem { stack, .. } => {
+				(&mut [], std::slice::from_mut(stack))
+			}
+			StackMapFrame::Append { locals, .. } => (locals, &mut []),
+			StackMapFrame::Full { locals, stack, .. } => (locals, stack),
+		};
+		locals.iter_mut().chain(stack.iter_mut())
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VerificationType {
+	Top,
+	Integer,
+	Float,
+	Double,
+	Long,
+	Null,
+	UninitializedThis,
+	Object(ConstPtr<ClassConst>),
+	/// An object which is created by the `new` at this byte offset.
+	Uninitialized {
+		offset: u16,
+	},
+}
+
+impl VerificationType {
+	pub fn parse(input: &[u8]) -> IResult<Self> {
+		let (input, tag) = be_u8(input)?;
+		match tag {
+			0 => Ok((input, VerificationType::Top)),
+			1 => Ok((input, VerificationType::Integer)),
+			2 => Ok((input, VerificationType::Float)),
+			3 => Ok((input, VerificationType::Double)),
+			4 => Ok((input, VerificationType::Long)),
+			5 => Ok((input, VerificationType::Null)),
+			6 => Ok((input, VerificationType::UninitializedThis)),
+			7 => map(be_cp, VerificationType::Object)(input),
+			8 => map(be_u16, |offset| VerificationType::Uninitialized { offset })(input),
+			_ => fail(input),
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct Annotation {
+	/// The descriptor of the annotation interface.
+	pub ty: ConstPtr<UTF8Const>,
+	pub elements: Vec<AnnotationElement>,
+}
+
+impl Annotation {
+	pub fn parse(input: &[u8]) -> IResult<Self> {
+		map(
+			pair(be_cp, length_count(be_u16, AnnotationElement::parse)),
+			|(ty, elements)| Annotation { ty, elements },
+		)(input)
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct AnnotationElement {
+	pub name: ConstPtr<UTF8Const>,
+	pub value: ElementValue,
+}
+
+impl AnnotationElement {
+	pub fn parse(input: &[u8]) -> IResult<Self> {
+		map(pair(be_cp, ElementValue::parse), |(name, value)| {
+			AnnotationElement { name, value }
+		})(input)
+	}
+}
+
+#[derive(Clone, Debug)]
+pub enum ElementValue {
+	Byte(ConstPtr<IntegerConst>),
+	Char(ConstPtr<IntegerConst>),
+	Double(ConstPtr<DoubleConst>),
+	Float(ConstPtr<FloatConst>),
+	Int(ConstPtr<IntegerConst>),
+	Long(ConstPtr<LongConst>),
+	Short(ConstPtr<IntegerConst>),
+	Boolean(ConstPtr<IntegerConst>),
+	String(ConstPtr<UTF8Const>),
+	Enum {
+		/// The descriptor of the enum.
+		ty: ConstPtr<UTF8Const>,
+		name: ConstPtr<UTF8Const>,
+	},
+	/// The return descriptor of the class, like `Ljava/lang/Object;` or `V`.
+	Class(ConstPtr<UTF8Const>),
+	Annotation(Annotation),
+	Array(Vec<ElementValue>),
+}
+
+impl ElementValue {
+	pub fn parse(input: &[u8]) -> IResult<Self> {
+		let (input, tag) = be_u8(input)?;
+		match tag {
+			b'B' => map(be_cp, ElementValue::Byte)(input),
+			b'C' => map(be_cp, ElementValue::Char)(input),
+			b'D' => map(be_cp, ElementValue::Double)(input),
+			b'F' => map(be_cp, ElementValue::Float)(input),
+			b'I' => map(be_cp, ElementValue::Int)(input),
+			b'J' => map(be_cp, ElementValue::Long)(input),
+			b'S' => map(be_cp, ElementValue::Short)(input),
+			b'Z' => map(be_cp, ElementValue::Boolean)(input),
+			b's' => map(be_cp, ElementValue::String)(input),
+			b'e' => map(pair(be_cp, be_cp), |(ty, name)| ElementValue::Enum {
+				ty,
+				name,
+			})(input),
+			b'c' => map(be_cp, ElementValue::Class)(input),
+			b'@' => map(Annotation::parse, ElementValue::Annotation)(input),
+			b'[' => map(
+				length_count(be_u16, ElementValue::parse),
+				ElementValue::Array,
+			)(input),
+			_ => fail(input),
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct TypeAnnotation {
+	/// Tells what the target refers to, as some kinds of targets share a layout.
+	pub target_type: u8,
+	pub target: TypeAnnotationTarget,
+	pub path: Vec<TypePathEntry>,
+	pub annotation: Annotation,
+}
+
+impl TypeAnnotation {
+	pub fn parse(input: &[u8]) -> IResult<Self> {
+		let (input, target_type) = be_u8(input)?;
+		let (input, target) = TypeAnnotationTarget::parse(input, target_type)?;
+		let (input, path) = length_count(be_u8, TypePathEntry::parse)(input)?;
+		let (input, annotation) = Annotation::parse(input)?;
+
+		Ok((
+			input,
+			TypeAnnotation {
+				target_type,
+				target,
+				path,
+				annotation,
+			},
+		))
+	}
+}
+
+#[derive(Clone, Debug)]
+pub enum TypeAnnotationTarget {
+	TypeParameter {
+		index: u8,
+	},
+	/// 65535 for the superclass, otherwise the index of the interface.
+	Supertype {
+		index: u16,
+	},
+	TypeParameterBound {
+		parameter: u8,
+		bound: u8,
+	},
+	/// Field types, return types and receiver types.
+	Empty,
+	FormalParameter {
+		index: u8,
+	},
+	Throws {
+		index: u16,
+	},
+	LocalVariable {
+		table: Vec<LocalVariableTarget>,
+	},
+	Catch {
+		exception_table_index: u16,
+	},
+	/// instanceof, new and method references.
+	Offset {
+		offset: u16,
+	},
+	/// Casts and type arguments of method calls.
+	TypeArgument {
+		offset: u16,
+		index: u8,
+	},
+}
+
+impl TypeAnnotationTarget {
+	pub fn parse(input: &[u8], target_type: u8) -> IResult<Self> {
+		match target_type {
+			0x00 | 0x01 => map(be_u8, |index| TypeAnnotationTarget::TypeParameter { index })(input),
+			0x10 => map(be_u16, |index| TypeAnnotationTarget::Supertype { index })(input),
+			0x11 | 0x12 => map(pair(be_u8, be_u8), |(parameter, bound)| {
+				TypeAnnotationTarget::TypeParameterBound { parameter, bound }
+			})(input),
+			0x13..=0x15 => Ok((input, TypeAnnotationTarget::Empty)),
+			0x16 => map(be_u8, |index| TypeAnnotationTarget::FormalParameter {
+				index,
+			})(input),
+			0x17 => map(be_u16, |index| TypeAnnotationTarget::Throws { index })(input),
+			0x40 | 0x41 => map(length_count(be_u16, LocalVariableTarget::parse), |table| {
+				TypeAnnotationTarget::LocalVariable { table }
+			})(input),
+			0x42 => map(be_u16, |exception_table_index| {
+				TypeAnnotationTarget::Catch {
+					exception_table_index,
+				}
+			})(input),
+			0x43..=0x46 => map(be_u16, |offset| TypeAnnotationTarget::Offset { offset })(input),
+			0x47..=0x4B => map(pair(be_u16, be_u8), |(offset, index)| {
+				TypeAnnotationTarget::TypeArgument { offset, index }
+			})(input),
+			_ => fail(input),
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct LocalVariableTarget {
+	pub start_pc: u16,
+	pub length: u16,
+	pub index: u16,
+}
+
+impl LocalVariableTarget {
+	pub fn parse(input: &[u8]) -> IResult<Self> {
+		map(
+			tuple((be_u16, be_u16, be_u16)),
+			|(start_pc, length, index)| LocalVariableTarget {
+				start_pc,
+				length,
+				index,
+			},
+		)(input)
+	}
+}
+
+#[derive(Clone, Debug)]
+pub struct TypePathEntry {
+	pub kind: u8,
+	pub argument_index: u8,
+}
+
+impl TypePathEntry {
+	pub fn parse(input: &[u8]) -> IResult<Self> {
+		map(pair(be_u8, be_u8), |(kind, argument_index)| TypePathEntry {
+			kind,
+			argument_index,
+		})(input)
+	}
 }
 
 pub enum AttributeInfo {
@@ -92,51 +668,93 @@ pub enum AttributeInfo {
 	CodeAttribute {
 		code: Code,
 	},
-	// TODO do this
-	StackMapTable,
+	StackMapTable {
+		frames: Vec<StackMapFrame>,
+	},
 	Exceptions {
-		exception_index_table: Vec<u16>,
+		exceptions: Vec<ConstPtr<ClassConst>>,
 	},
 	InnerClasses {
 		classes: Vec<AttributeClass>,
 	},
 	EnclosingMethod {
-		class_index: u16,
-		method_index: u16,
+		class: ConstPtr<ClassConst>,
+		/// 0 if the class is not enclosed by a method.
+		method: ConstPtr<NameAndTypeConst>,
 	},
 	Synthetic,
 	Signature {
-		signature_index: u16,
+		signature: ConstPtr<UTF8Const>,
 	},
 	SourceFile {
-		source_file_index: u16,
+		source_file: ConstPtr<UTF8Const>,
 	},
 	SourceDebugExtension {
 		debug_extension: Vec<u8>,
 	},
 	LineNumberTable {
-		line_number_table: Vec<AttributeLineNumber>,
+		line_numbers: Vec<AttributeLineNumber>,
 	},
 	LocalVariableTable {
 		variables: Vec<AttributeLocalVariable>,
 	},
 	LocalVariableTypeTable {
-		local_variable_type_table: Vec<AttributeLocalVariableType>,
+		variables: Vec<AttributeLocalVariableType>,
 	},
 	Deprecated,
-	// TODO do this
-	RuntimeInvisibleAnnotations,
-	// TODO do this
-	RuntimeVisibleParameterAnnotations,
-	// TODO do this
-	RuntimeInvisibleParameterAnnotations,
-	// TODO do this
-	AnnotationDefault,
-	// TODO do this
-	RuntimeVisibleAnnotations,
+	RuntimeVisibleAnnotations {
+		annotations: Vec<Annotation>,
+	},
+	RuntimeInvisibleAnnotations {
+		annotations: Vec<Annotation>,
+	},
+	RuntimeVisibleParameterAnnotations {
+		parameters: Vec<Vec<Annotation>>,
+	},
+	RuntimeInvisibleParameterAnnotations {
+		parameters: Vec<Vec<Annotation>>,
+	},
+	RuntimeVisibleTypeAnnotations {
+		annotations: Vec<TypeAnnotation>,
+	},
+	RuntimeInvisibleTypeAnnotations {
+		annotations: Vec<TypeAnnotation>,
+	},
+	AnnotationDefault {
+		value: ElementValue,
+	},
 	BootstrapMethods {
 		bootstrap_methods: Vec<AttributeBootstrapMethod>,
 	},
+	MethodParameters {
+		parameters: Vec<AttributeMethodParameter>,
+	},
+	Module {
+		module: AttributeModule,
+	},
+	ModulePackages {
+		packages: Vec<ConstPtr<PackageConst>>,
+	},
+	ModuleMainClass {
+		main_class: ConstPtr<ClassConst>,
+	},
+	NestHost {
+		host_class: ConstPtr<ClassConst>,
+	},
+	NestMembers {
+		classes: Vec<ConstPtr<ClassConst>>,
+	},
+	Record {
+		components: Vec<AttributeRecordComponent>,
+	},
+	PermittedSubclasses {
+		classes: Vec<ConstPtr<ClassConst>>,
+	},
+	/// Attributes which are not defined by the JVMS, these get kept as they are.
+	Unknown {
+		name: String,
+		bytes: Vec<u8>,
+	},
 }
 
 impl AttributeInfo {
@@ -146,33 +764,214 @@ impl AttributeInfo {
 			length_count(be_u16, |input| AttributeInfo::parse(input, constant_pool)),
 		)(input)
 	}
+
+	pub fn name(&self) -> &str {
+		match self {
+			AttributeInfo::ConstantValue { .. } => "ConstantValue",
+			AttributeInfo::CodeAttribute { .. } => "Code",
+			AttributeInfo::StackMapTable { .. } => "StackMapTable",
+			AttributeInfo::Exceptions { .. } => "Exceptions",
+			AttributeInfo::InnerClasses { .. } => "InnerClasses",
+			AttributeInfo::EnclosingMethod { .. } => "EnclosingMethod",
+			AttributeInfo::Synthetic => "Synthetic",
+			AttributeInfo::Signature { .. } => "Signature",
+			AttributeInfo::SourceFile { .. } => "SourceFile",
+			AttributeInfo::SourceDebugExtension { .. } => "SourceDebugExtension",
+			AttributeInfo::LineNumberTable { .. } => "LineNumberTable",
+			AttributeInfo::LocalVariableTable { .. } => "LocalVariableTable",
+			AttributeInfo::LocalVariableTypeTable { .. } => "LocalVariableTypeTable",
+			AttributeInfo::Deprecated => "Deprecated",
+			AttributeInfo::RuntimeVisibleAnnotations { .. } => "RuntimeVisibleAnnotations",
+			AttributeInfo::RuntimeInvisibleAnnotations { .. } => "RuntimeInvisibleAnnotations",
+			AttributeInfo::RuntimeVisibleParameterAnnotations { .. } => {
+				"RuntimeVisibleParameterAnnotations"
+			}
+			AttributeInfo::RuntimeInvisibleParameterAnnotations { .. } => {
+				"RuntimeInvisibleParameterAnnotations"
+			}
+			AttributeInfo::RuntimeVisibleTypeAnnotations { .. } => "RuntimeVisibleTypeAnnotations",
+			AttributeInfo::RuntimeInvisibleTypeAnnotations { .. } => {
+				"RuntimeInvisibleTypeAnnotations"
+			}
+			AttributeInfo::AnnotationDefault { .. } => "AnnotationDefault",
+			AttributeInfo::BootstrapMethods { .. } => "BootstrapMethods",
+			AttributeInfo::MethodParameters { .. } => "MethodParameters",
+			AttributeInfo::Module { .. } => "Module",
+			AttributeInfo::ModulePackages { .. } => "ModulePackages",
+			AttributeInfo::ModuleMainClass { .. } => "ModuleMainClass",
+			AttributeInfo::NestHost { .. } => "NestHost",
+			AttributeInfo::NestMembers { .. } => "NestMembers",
+			AttributeInfo::Record { .. } => "Record",
+			AttributeInfo::PermittedSubclasses { .. } => "PermittedSubclasses",
+			AttributeInfo::Unknown { name, .. } => name,
+		}
+	}
+
 	pub fn parse<'a>(input: &'a [u8], constant_pool: &ConstantPool) -> IResult<'a, Self> {
-		let (input, info) = map_opt(be_u16, |index| constant_pool.raw_get(index))(input)?;
-		let (input, length) = be_u32(input)?;
-
-		match info {
-			ConstantInfo::UTF8(text) => match text.as_str() {
-				"ConstantValue" => map(be_u16, |constant_index| AttributeInfo::ConstantValue {
-					constant_index,
-				})(input),
-				"Code" => context(
-					"Code",
-					map(
-						|input| Code::parse(input, constant_pool),
-						|code| AttributeInfo::CodeAttribute { code },
-					),
-				)(input),
-				"LocalVariableTable" => context(
-					"LocalVariableTable",
-					map(
-						length_count(be_u16, |input| AttributeLocalVariable::parse(input)),
-						|value| AttributeInfo::LocalVariableTable { variables: value },
-					),
-				)(input),
-				_ => map(take(length), |_| AttributeInfo::AnnotationDefault)(input),
-			},
-			//discard the remaining bytes
-			_ => map(take(length), |_| AttributeInfo::AnnotationDefault)(input),
+		let (input, name) = map_opt(be_cp::<UTF8Const>, |name| constant_pool.get(name))(input)?;
+		let (input, data) = length_data(be_u32)(input)?;
+
+		// Every attribute has to take up exactly the length it declares.
+		let (_, info) = all_consuming(|data| Self::parse_data(name, data, constant_pool))(data)?;
+		Ok((input, info))
+	}
+
+	fn parse_data<'a>(
+		name: &str,
+		input: &'a [u8],
+		constant_pool: &ConstantPool,
+	) -> IResult<'a, Self> {
+		match name {
+			"ConstantValue" => map(be_u16, |constant_index| AttributeInfo::ConstantValue {
+				constant_index,
+			})(input),
+			"Code" => context(
+				"Code",
+				map(
+					|input| Code::parse(input, constant_pool),
+					|code| AttributeInfo::CodeAttribute { code },
+				),
+			)(input),
+			"StackMapTable" => context(
+				"StackMapTable",
+				map(length_count(be_u16, StackMapFrame::parse), |frames| {
+					AttributeInfo::StackMapTable { frames }
+				}),
+			)(input),
+			"Exceptions" => map(length_count(be_u16, be_cp), |exceptions| {
+				AttributeInfo::Exceptions { exceptions }
+			})(input),
+			"InnerClasses" => context(
+				"InnerClasses",
+				map(length_count(be_u16, AttributeClass::parse), |classes| {
+					AttributeInfo::InnerClasses { classes }
+				}),
+			)(input),
+			"EnclosingMethod" => map(pair(be_cp, be_cp), |(class, method)| {
+				AttributeInfo::EnclosingMethod { class, method }
+			})(input),
+			"Synthetic" => Ok((input, AttributeInfo::Synthetic)),
+			"Signature" => map(be_cp, |signature| AttributeInfo::Signature { signature })(input),
+			"SourceFile" => map(be_cp, |source_file| AttributeInfo::SourceFile {
+				source_file,
+			})(input),
+			"SourceDebugExtension" => map(rest, |debug_extension: &[u8]| {
+				AttributeInfo::SourceDebugExtension {
+					debug_extension: debug_extension.to_vec(),
+				}
+			})(input),
+			"LineNumberTable" => context(
+				"LineNumberTable",
+				map(
+					length_count(be_u16, AttributeLineNumber::parse),
+					|line_numbers| AttributeInfo::LineNumberTable { line_numbers },
+				),
+			)(input),
+			"LocalVariableTable" => context(
+				"LocalVariableTable",
+				map(
+					length_count(be_u16, |input| AttributeLocalVariable::parse(input)),
+					|value| AttributeInfo::LocalVariableTable { variables: value },
+				),
+			)(input),
+			"LocalVariableTypeTable" => context(
+				"LocalVariableTypeTable",
+				map(
+					length_count(be_u16, AttributeLocalVariableType::parse),
+					|variables| AttributeInfo::LocalVariableTypeTable { variables },
+				),
+			)(input),
+			"Deprecated" => Ok((input, AttributeInfo::Deprecated)),
+			"RuntimeVisibleAnnotations" => context(
+				"RuntimeVisibleAnnotations",
+				map(length_count(be_u16, Annotation::parse), |annotations| {
+					AttributeInfo::RuntimeVisibleAnnotations { annotations }
+				}),
+			)(input),
+			"RuntimeInvisibleAnnotations" => context(
+				"RuntimeInvisibleAnnotations",
+				map(length_count(be_u16, Annotation::parse), |annotations| {
+					AttributeInfo::RuntimeInvisibleAnnotations { annotations }
+				}),
+			)(input),
+			"RuntimeVisibleParameterAnnotations" => context(
+				"RuntimeVisibleParameterAnnotations",
+				map(
+					length_count(be_u8, length_count(be_u16, Annotation::parse)),
+					|parameters| AttributeInfo::RuntimeVisibleParameterAnnotations { parameters },
+				),
+			)(input),
+			"RuntimeInvisibleParameterAnnotations" => context(
+				"RuntimeInvisibleParameterAnnotations",
+				map(
+					length_count(be_u8, length_count(be_u16, Annotation::parse)),
+					|parameters| AttributeInfo::RuntimeInvisibleParameterAnnotations { parameters },
+				),
+			)(input),
+			"RuntimeVisibleTypeAnnotations" => context(
+				"RuntimeVisibleTypeAnnotations",
+				map(length_count(be_u16, TypeAnnotation::parse), |annotations| {
+					AttributeInfo::RuntimeVisibleTypeAnnotations { annotations }
+				}),
+			)(input),
+			"RuntimeInvisibleTypeAnnotations" => context(
+				"RuntimeInvisibleTypeAnnotations",
+				map(length_count(be_u16, TypeAnnotation::parse), |annotations| {
+					AttributeInfo::RuntimeInvisibleTypeAnnotations { annotations }
+				}),
+			)(input),
+			"AnnotationDefault" => context(
+				"AnnotationDefault",
+				map(ElementValue::parse, |value| {
+					AttributeInfo::AnnotationDefault { value }
+				}),
+			)(input),
+			"BootstrapMethods" => context(
+				"BootstrapMethods",
+				map(
+					length_count(be_u16, AttributeBootstrapMethod::parse),
+					|bootstrap_methods| AttributeInfo::BootstrapMethods { bootstrap_methods },
+				),
+			)(input),
+			"MethodParameters" => context(
+				"MethodParameters",
+				map(
+					length_count(be_u8, AttributeMethodParameter::parse),
+					|parameters| AttributeInfo::MethodParameters { parameters },
+				),
+			)(input),
+			"Module" => context(
+				"Module",
+				map(AttributeModule::parse, |module| AttributeInfo::Module {
+					module,
+				}),
+			)(input),
+			"ModulePackages" => map(length_count(be_u16, be_cp), |packages| {
+				AttributeInfo::ModulePackages { packages }
+			})(input),
+			"ModuleMainClass" => map(be_cp, |main_class| AttributeInfo::ModuleMainClass {
+				main_class,
+			})(input),
+			"NestHost" => map(be_cp, |host_class| AttributeInfo::NestHost { host_class })(input),
+			"NestMembers" => map(length_count(be_u16, be_cp), |classes| {
+				AttributeInfo::NestMembers { classes }
+			})(input),
+			"Record" => context(
+				"Record",
+				map(
+					length_count(be_u16, |input| {
+						AttributeRecordComponent::parse(input, constant_pool)
+					}),
+					|components| AttributeInfo::Record { components },
+				),
+			)(input),
+			"PermittedSubclasses" => map(length_count(be_u16, be_cp), |classes| {
+				AttributeInfo::PermittedSubclasses { classes }
+			})(input),
+			_ => map(rest, |bytes: &[u8]| AttributeInfo::Unknown {
+				name: name.to_string(),
+				bytes: bytes.to_vec(),
+			})(input),
 		}
 	}
 }
@@ -1,8 +1,9 @@
 use std::fmt::{Display, Formatter};
 
-use nom::combinator::map;
+use nom::combinator::{fail, map};
+use nom::error::context;
 use nom::multi::length_count;
-use nom::number::complete::{be_i16, be_i32, be_i8, be_u16, be_u8};
+use nom::number::complete::{be_i16, be_i32, be_i8, be_u16, be_u32, be_u8};
 use nom::sequence::tuple;
 
 use rvm_core::{Kind, PrimitiveType, StackKind};
@@ -100,6 +101,29 @@ pub enum ConversionInst {
 	L2I,
 }
 
+impl ConversionInst {
+	/// The type which gets converted, and the one it gets converted to.
+	pub fn types(&self) -> (PrimitiveType, PrimitiveType) {
+		match self {
+			ConversionInst::D2F => (PrimitiveType::Double, PrimitiveType::Float),
+			ConversionInst::D2I => (PrimitiveType::Double, PrimitiveType::Int),
+			ConversionInst::D2L => (PrimitiveType::Double, PrimitiveType::Long),
+			ConversionInst::F2D => (PrimitiveType::Float, PrimitiveType::Double),
+			ConversionInst::F2I => (PrimitiveType::Float, PrimitiveType::Int),
+			ConversionInst::F2L => (PrimitiveType::Float, PrimitiveType::Long),
+			ConversionInst::I2B => (PrimitiveType::Int, PrimitiveType::Byte),
+			ConversionInst::I2C => (PrimitiveType::Int, PrimitiveType::Char),
+			ConversionInst::I2D => (PrimitiveType::Int, PrimitiveType::Double),
+			ConversionInst::I2F => (PrimitiveType::Int, PrimitiveType::Float),
+			ConversionInst::I2L => (PrimitiveType::Int, PrimitiveType::Long),
+			ConversionInst::I2S => (PrimitiveType::Int, PrimitiveType::Short),
+			ConversionInst::L2D => (PrimitiveType::Long, PrimitiveType::Double),
+			ConversionInst::L2F => (PrimitiveType::Long, PrimitiveType::Float),
+			ConversionInst::L2I => (PrimitiveType::Long, PrimitiveType::Int),
+		}
+	}
+}
+
 #[derive(Copy, Clone, Debug)]
 pub enum ComparisonInst {
 	DCMPG,
@@ -176,9 +200,12 @@ impl JumpKind {
 				| JumpKind::IF_ICMPGE
 				| JumpKind::IF_ICMPGT
 				| JumpKind::IF_ICMPLE
-				| JumpKind::IFEQ | JumpKind::IFNE
-				| JumpKind::IFLT | JumpKind::IFGE
-				| JumpKind::IFGT | JumpKind::IFLE
+				| JumpKind::IFEQ
+				| JumpKind::IFNE
+				| JumpKind::IFLT
+				| JumpKind::IFGE
+				| JumpKind::IFGT
+				| JumpKind::IFLE
 				| JumpKind::IFNONNULL
 				| JumpKind::IFNULL
 		)
@@ -253,6 +280,13 @@ pub struct TableSwitchInst {
 	pub offsets: Vec<i32>,
 }
 
+#[derive(Clone, Debug)]
+pub struct LookupSwitchInst {
+	pub default_offset: i32,
+	/// The keys with their offsets, sorted by key.
+	pub pairs: Vec<(i32, i32)>,
+}
+
 #[allow(non_camel_case_types)]
 #[derive(Clone, Debug)]
 pub enum Inst {
@@ -272,12 +306,12 @@ pub enum Inst {
 	InstanceOf(InstanceOfInst),
 	Field(FieldInst),
 	Invoke(InvokeInst),
-	// Grandpa shit
+	// Subroutines, which the compilers before Java 6 used for finally. The offsets are in
+	// instructions like the ones of jumps.
 	JSR(BranchOffset),
 	JSR_W(WideBranchOffset),
 	RET(u16),
-	// TODO read
-	LOOKUPSWITCH,
+	LookupSwitch(LookupSwitchInst),
 	TableSwitch(TableSwitchInst),
 	MONITORENTER,
 	MONITOREXIT,
@@ -553,6 +587,9 @@ impl Inst {
 					kind: JumpKind::GOTO,
 				})
 			})(input)?,
+			Op::JSR => map(be_i16, |v| Inst::JSR(BranchOffset(v)))(input)?,
+			Op::JSR_W => map(be_i32, |v| Inst::JSR_W(WideBranchOffset(v)))(input)?,
+			Op::RET => map(be_u8, |v| Inst::RET(v as u16))(input)?,
 			// Locals
 			Op::ALOAD => map(be_u8, |v| {
 				Inst::Local(LocalInst::Load(StackKind::Reference, v as u16))
@@ -870,15 +907,15 @@ impl Inst {
 					input = new_input;
 				}
 				let (input, default) = be_i32(input)?;
-				let (mut input, n_pairs) = be_i32(input)?;
+				let (input, pairs) = length_count(be_u32, tuple((be_i32, be_i32)))(input)?;
 
-				for _ in 0..n_pairs {
-					let (input2, match_v) = be_i32(input)?;
-					let (input2, offset_v) = be_i32(input2)?;
-					input = input2;
-				}
-
-				(input, Inst::LOOKUPSWITCH)
+				(
+					input,
+					Inst::LookupSwitch(LookupSwitchInst {
+						default_offset: default,
+						pairs,
+					}),
+				)
 			}
 			Op::WIDE => {
 				let (input, opcode) = Op::parse_op(input)?;
@@ -903,7 +940,12 @@ impl Inst {
 							input = input2;
 							LocalInst::Increment(amount, index)
 						}
-						_ => unreachable!(),
+						_ => {
+							return context(
+								"wide can only modify loads, stores, iinc and ret",
+								fail,
+							)(input)
+						}
 					})
 				};
 
@@ -911,7 +953,6 @@ impl Inst {
 			}
 			Op::MONITORENTER => (input, Inst::MONITORENTER),
 			Op::MONITOREXIT => (input, Inst::MONITOREXIT),
-			v => panic!("instruction {v:?} kinda dodo"),
 		})
 	}
 }
@@ -4,18 +4,25 @@ use nom::number::streaming::{be_u16, be_u32};
 use crate::attribute::{AttributeException, AttributeInfo};
 pub use crate::code::inst::*;
 pub use crate::code::op::*;
+pub use crate::code::pc::*;
 use crate::consts::ConstantPool;
 use crate::IResult;
 
 mod inst;
 mod op;
+mod pc;
 
 pub struct Code {
 	pub max_stack: u16,
 	pub max_locals: u16,
 	pub instructions: Vec<Inst>,
+	/// The exception table and the attributes keep the byte offsets of the class file.
 	pub exception_table: Vec<AttributeException>,
 	pub attribute_info: Vec<AttributeInfo>,
+	/// Where the instructions were in the class file, which the writer moves the offsets of the
+	/// exception table and the attributes with. Changing `instructions` does not update it,
+	/// instructions inserted into them have to be added with [PcMap::insert] or writing fails.
+	pub pc_map: PcMap,
 }
 
 impl Code {
@@ -27,6 +34,7 @@ impl Code {
 
 		let mut op_byte_to_op: Vec<u32> = Vec::with_capacity(code_length as usize);
 		let mut op_byte_ops: Vec<(u32, Inst)> = Vec::new();
+		let mut positions: Vec<usize> = Vec::new();
 
 		let mut op_byte_pos: usize = 0;
 		let mut op_pos: u32 = 0;
@@ -38,6 +46,7 @@ impl Code {
 			let new = input2.len();
 
 			let op_byte_length = old - new;
+			positions.push(op_byte_pos);
 			op_byte_ops.push((op_byte_pos as u32, op));
 			for _i in 0..op_byte_length {
 				op_byte_to_op.push(op_pos);
@@ -46,6 +55,7 @@ impl Code {
 			op_byte_pos += op_byte_length;
 			op_pos += 1;
 		}
+		positions.push(op_byte_pos);
 
 		// Apply all jumps, as jumps are relative to byte location not op location,
 		// This also adds stuff to the split vec which is all of the spots which it should split the code on.
@@ -56,12 +66,21 @@ impl Code {
 				let i = ((op_byte as i64) + (*offset as i64)) as usize;
 				let jump_pos = op_byte_to_op[i] as i64;
 
-				*offset = (jump_pos - op_pos as i64) as i16 as i32;
+				*offset = (jump_pos - op_pos as i64) as i32;
 			};
 			match &mut op {
 				Inst::Jump(JumpInst { offset, .. }) => {
 					map(offset);
 				}
+				Inst::JSR(BranchOffset(offset)) => {
+					// An offset in instructions is never larger than the one in bytes.
+					let mut wide = *offset as i32;
+					map(&mut wide);
+					*offset = wide as i16;
+				}
+				Inst::JSR_W(WideBranchOffset(offset)) => {
+					map(offset);
+				}
 				Inst::TableSwitch(TableSwitchInst {
 					offsets,
 					default_offset,
@@ -72,6 +91,15 @@ impl Code {
 						map(offset);
 					}
 				}
+				Inst::LookupSwitch(LookupSwitchInst {
+					pairs,
+					default_offset,
+				}) => {
+					map(default_offset);
+					for (_, offset) in pairs {
+						map(offset);
+					}
+				}
 				_ => {}
 			};
 
@@ -91,7 +119,59 @@ impl Code {
 				instructions: code,
 				exception_table,
 				attribute_info,
+				pc_map: PcMap::new(positions),
 			},
 		))
 	}
+
+	/// The exception table with instruction indices instead of byte offsets.
+	pub fn exception_handlers(&self) -> eyre::Result<Vec<ExceptionHandler>> {
+		self.exception_table
+			.iter()
+			.map(|exception| {
+				Ok(ExceptionHandler {
+					start: self.pc_map.instruction(exception.start_pc as usize)?,
+					end: self.pc_map.end(exception.end_pc as usize)?,
+					handler: self.pc_map.instruction(exception.handler_pc as usize)?,
+					catch_type: exception.catch_type,
+				})
+			})
+			.collect()
+	}
+
+	/// The `LineNumberTable` with instruction indices instead of byte offsets.
+	pub fn line_numbers(&self) -> eyre::Result<Vec<LineNumber>> {
+		let mut lines = vec![];
+		for attribute in &self.attribute_info {
+			if let AttributeInfo::LineNumberTable { line_numbers } = attribute {
+				for line in line_numbers {
+					lines.push(LineNumber {
+						start: self.pc_map.instruction(line.start_pc as usize)?,
+						line_number: line.line_number,
+					});
+				}
+			}
+		}
+		Ok(lines)
+	}
+
+	/// The `LocalVariableTable` with instruction indices instead of byte offsets.
+	pub fn local_variables(&self) -> eyre::Result<Vec<LocalVariable>> {
+		let mut locals = vec![];
+		for attribute in &self.attribute_info {
+			if let AttributeInfo::LocalVariableTable { variables } = attribute {
+				for variable in variables {
+					let start = variable.start_pc as usize;
+					locals.push(LocalVariable {
+						start: self.pc_map.end(start)?,
+						end: self.pc_map.end(start + variable.length as usize)?,
+						name_index: variable.name_index,
+						descriptor_index: variable.descriptor_index,
+						index: variable.index,
+					});
+				}
+			}
+		}
+		Ok(locals)
+	}
 }
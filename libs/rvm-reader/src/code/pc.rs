@@ -0,0 +1,95 @@
+use eyre::bail;
+
+use crate::{ConstPtr, UTF8Const};
+
+/// Maps the byte offsets of the class file to the instruction indices which jumps use.
+#[derive(Clone, Debug, Default)]
+pub struct PcMap {
+	/// The byte offset of every instruction, followed by the length of the code.
+	positions: Vec<usize>,
+}
+
+impl PcMap {
+	pub fn new(positions: Vec<usize>) -> PcMap {
+		PcMap { positions }
+	}
+
+	/// The byte offset of an instruction, the instruction count gives the length of the code.
+	pub fn pc(&self, index: usize) -> Option<usize> {
+		self.positions.get(index).copied()
+	}
+
+	/// The instruction starting at a byte offset, the length of the code gives the instruction
+	/// count.
+	///
+	/// Instructions inserted with [PcMap::insert] share the offset of the one after them, which is
+	/// the one this returns.
+	pub fn index(&self, pc: usize) -> Option<usize> {
+		let index = self.positions.partition_point(|&position| position <= pc);
+		(index > 0 && self.positions[index - 1] == pc).then_some(index - 1)
+	}
+
+	/// Makes room for instructions inserted in front of the one at `index`, which were not in the
+	/// class file. The instruction count appends them at the end.
+	pub fn insert(&mut self, index: usize, count: usize) -> eyre::Result<()> {
+		let Some(&pc) = self.positions.get(index) else {
+			bail!(
+				"Can not insert at {index}, there are only {} instructions",
+				self.positions.len().saturating_sub(1)
+			);
+		};
+		self.positions
+			.splice(index..index, std::iter::repeat_n(pc, count));
+		Ok(())
+	}
+
+	pub fn positions(&self) -> &[usize] {
+		&self.positions
+	}
+
+	/// Like [PcMap::index], but the offset has to be at an instruction.
+	pub(crate) fn instruction(&self, pc: usize) -> eyre::Result<usize> {
+		match self.index(pc) {
+			Some(index) if index + 1 < self.positions.len() => Ok(index),
+			_ => bail!("Byte offset {pc} is not at an instruction"),
+		}
+	}
+
+	/// Like [PcMap::index], but for the end of a range which may be the end of the code.
+	pub(crate) fn end(&self, pc: usize) -> eyre::Result<usize> {
+		match self.index(pc) {
+			Some(index) => Ok(index),
+			None => bail!("Byte offset {pc} is not at an instruction or the end of the code"),
+		}
+	}
+}
+
+/// An entry of the exception table, with the instruction indices of its range and handler.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ExceptionHandler {
+	pub start: usize,
+	/// Exclusive.
+	pub end: usize,
+	pub handler: usize,
+	/// 0 if the handler catches everything.
+	pub catch_type: u16,
+}
+
+/// The line which the instructions from `start` on belong to.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LineNumber {
+	pub start: usize,
+	pub line_number: u16,
+}
+
+/// A local variable of the `LocalVariableTable`, which is in scope for the instructions from
+/// `start` to `end`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LocalVariable {
+	pub start: usize,
+	/// Exclusive.
+	pub end: usize,
+	pub name_index: ConstPtr<UTF8Const>,
+	pub descriptor_index: ConstPtr<UTF8Const>,
+	pub index: u16,
+}
@@ -3,6 +3,7 @@ use crate::consts::dynamic::{DynamicConst, InvokeDynamicConst};
 pub use crate::consts::field::FieldConst;
 pub use crate::consts::interface::InterfaceConst;
 pub use crate::consts::method::{MethodConst, MethodHandleConst, MethodTypeConst};
+pub use crate::consts::module::{ModuleConst, PackageConst};
 pub use crate::consts::name_and_type::NameAndTypeConst;
 pub use crate::consts::number::{DoubleConst, FloatConst, IntegerConst, LongConst};
 pub use crate::consts::string::StringConst;
@@ -23,6 +24,7 @@ mod dynamic;
 mod field;
 mod interface;
 mod method;
+mod module;
 mod name_and_type;
 mod number;
 mod string;
@@ -88,6 +90,14 @@ impl<V: Constant> Debug for ConstPtr<V> {
 
 impl<V: Constant> Copy for ConstPtr<V> {}
 
+impl<V: Constant> PartialEq for ConstPtr<V> {
+	fn eq(&self, other: &Self) -> bool {
+		self.0 == other.0
+	}
+}
+
+impl<V: Constant> Eq for ConstPtr<V> {}
+
 impl ConstPtr<ClassConst> {
 	pub fn ty(&self, cp: &ConstantPool) -> Option<ObjectType> {
 		Some(ObjectType::new(cp.get(cp.get(*self)?.name)?.to_string()))
@@ -101,6 +111,11 @@ impl ConstantPool {
 		ConstantPool(values)
 	}
 
+	/// Every slot of the pool, the second slot of longs and doubles is [ConstantInfo::Unknown].
+	pub fn entries(&self) -> &[ConstantInfo] {
+		&self.0
+	}
+
 	pub fn raw_get(&self, index: u16) -> Option<&ConstantInfo> {
 		assert!(index >= 1);
 		self.0.get(index as usize - 1)
@@ -108,8 +123,7 @@ impl ConstantPool {
 
 	pub fn get<V: Constant>(&self, ptr: ConstPtr<V>) -> Option<&V> {
 		if ptr.0 >= 1 {
-			let info = &self.0[ptr.0 as usize - 1];
-			V::get(info)
+			V::get(self.0.get(ptr.0 as usize - 1)?)
 		} else {
 			None
 		}
@@ -140,6 +154,8 @@ pub enum ConstantInfo {
 	MethodType(MethodTypeConst),
 	Dynamic(DynamicConst),
 	InvokeDynamic(InvokeDynamicConst),
+	Module(ModuleConst),
+	Package(PackageConst),
 	Unusable,
 	Unknown,
 }
@@ -250,8 +266,8 @@ impl ConstantInfo {
 					})
 				},
 			)(input),
-			19 => panic!("Module is not supported"),
-			20 => panic!("Package is not supported"),
+			19 => map(be_cp, |name| ConstantInfo::Module(ModuleConst { name }))(input),
+			20 => map(be_cp, |name| ConstantInfo::Package(PackageConst { name }))(input),
 			_ => Err(nom::Err::Incomplete(Needed::Unknown)),
 		}
 	}
@@ -1,3 +1,5 @@
+use crate::impl_constant;
+
 #[derive(Copy, Clone, Debug)]
 pub struct IntegerConst {
 	pub bytes: i32,
@@ -17,3 +19,8 @@ pub struct LongConst {
 pub struct DoubleConst {
 	pub bytes: f64,
 }
+
+impl_constant!(Integer IntegerConst);
+impl_constant!(Float FloatConst);
+impl_constant!(Long LongConst);
+impl_constant!(Double DoubleConst);
@@ -2,15 +2,20 @@
 //! rvm-reader is responsible for parsing .class files.
 #![allow(dead_code)]
 
+pub use assembler::*;
 pub use attribute::*;
 pub use class::*;
 pub use code::*;
 pub use consts::*;
 pub use field::*;
 pub use method::*;
+pub use signature::*;
+pub use verifier::*;
+pub use writer::*;
 
 use crate::error::ParsingError;
 
+mod assembler;
 mod attribute;
 mod class;
 mod code;
@@ -18,5 +23,8 @@ mod consts;
 mod error;
 mod field;
 mod method;
+mod signature;
+mod verifier;
+mod writer;
 
 pub type IResult<'a, O> = nom::IResult<&'a [u8], O, ParsingError<'a>>;
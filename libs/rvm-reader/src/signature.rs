@@ -0,0 +1,466 @@
+//! Generic signatures (JVMS 4.7.9.1), these keep the type arguments which the descriptors erase.
+use rvm_core::{ArrayType, ObjectType, PrimitiveType, Type};
+
+/// `class Name<T extends Bound> extends Super<T> implements Interface<T>`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClassSignature {
+	pub type_parameters: Vec<TypeParameter>,
+	pub superclass: ClassTypeSignature,
+	pub interfaces: Vec<ClassTypeSignature>,
+}
+
+impl ClassSignature {
+	pub fn parse(string: &str) -> Option<ClassSignature> {
+		let (type_parameters, mut pos) = TypeParameter::parse_list(string)?;
+		let (superclass, length) = ClassTypeSignature::parse_len(&string[pos..])?;
+		pos += length;
+
+		let mut interfaces = Vec::new();
+		while pos < string.len() {
+			let (interface, length) = ClassTypeSignature::parse_len(&string[pos..])?;
+			interfaces.push(interface);
+			pos += length;
+		}
+
+		Some(ClassSignature {
+			type_parameters,
+			superclass,
+			interfaces,
+		})
+	}
+}
+
+/// `<T> Return name(Parameter) throws Exception`
+#[derive(Clone, Debug, PartialEq)]
+pub struct MethodSignature {
+	pub type_parameters: Vec<TypeParameter>,
+	pub parameters: Vec<TypeSignature>,
+	/// None if the method returns void.
+	pub returns: Option<TypeSignature>,
+	pub throws: Vec<ReferenceTypeSignature>,
+}
+
+impl MethodSignature {
+	pub fn parse(string: &str) -> Option<MethodSignature> {
+		let (type_parameters, mut pos) = TypeParameter::parse_list(string)?;
+		if string.as_bytes().get(pos) != Some(&b'(') {
+			return None;
+		}
+		pos += 1;
+
+		let mut parameters = Vec::new();
+		while *string.as_bytes().get(pos)? != b')' {
+			let (parameter, length) = TypeSignature::parse_len(&string[pos..])?;
+			parameters.push(parameter);
+			pos += length;
+		}
+		pos += 1;
+
+		let returns = if string.as_bytes().get(pos) == Some(&b'V') {
+			pos += 1;
+			None
+		} else {
+			let (returns, length) = TypeSignature::parse_len(&string[pos..])?;
+			pos += length;
+			Some(returns)
+		};
+
+		let mut throws = Vec::new();
+		while pos < string.len() {
+			if string.as_bytes()[pos] != b'^' {
+				return None;
+			}
+			let (exception, length) = ReferenceTypeSignature::parse_len(&string[pos + 1..])?;
+			throws.push(exception);
+			pos += length + 1;
+		}
+
+		Some(MethodSignature {
+			type_parameters,
+			parameters,
+			returns,
+			throws,
+		})
+	}
+}
+
+/// The signature of a field is a reference type, primitive fields never have one.
+pub type FieldSignature = ReferenceTypeSignature;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeParameter {
+	pub name: String,
+	pub class_bound: Option<ReferenceTypeSignature>,
+	pub interface_bounds: Vec<ReferenceTypeSignature>,
+}
+
+impl TypeParameter {
+	/// Parses the optional `<T:Bound;U:Bound;>` prefix of class and method signatures.
+	fn parse_list(string: &str) -> Option<(Vec<TypeParameter>, usize)> {
+		let mut parameters = Vec::new();
+		if !string.starts_with('<') {
+			return Some((parameters, 0));
+		}
+
+		let mut pos = 1;
+		while *string.as_bytes().get(pos)? != b'>' {
+			let (parameter, length) = TypeParameter::parse_len(&string[pos..])?;
+			parameters.push(parameter);
+			pos += length;
+		}
+
+		if parameters.is_empty() {
+			return None;
+		}
+		Some((parameters, pos + 1))
+	}
+
+	pub fn parse_len(string: &str) -> Option<(TypeParameter, usize)> {
+		let end = string.find(':')?;
+		if end == 0 {
+			return None;
+		}
+		let name = string[..end].to_string();
+		let mut pos = end + 1;
+
+		// The class bound may be empty, like in <T::Ljava/lang/Comparable<TT;>;>
+		let class_bound = match string.as_bytes().get(pos)? {
+			b':' => None,
+			_ => {
+				let (bound, length) = ReferenceTypeSignature::parse_len(&string[pos..])?;
+				pos += length;
+				Some(bound)
+			}
+		};
+
+		let mut interface_bounds = Vec::new();
+		while string.as_bytes().get(pos) == Some(&b':') {
+			let (bound, length) = ReferenceTypeSignature::parse_len(&string[pos + 1..])?;
+			interface_bounds.push(bound);
+			pos += length + 1;
+		}
+
+		Some((
+			TypeParameter {
+				name,
+				class_bound,
+				interface_bounds,
+			},
+			pos,
+		))
+	}
+
+	/// The first bound, which is what the type variable erases to.
+	pub fn bound(&self) -> Option<&ReferenceTypeSignature> {
+		self.class_bound
+			.as_ref()
+			.or_else(|| self.interface_bounds.first())
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeSignature {
+	Primitive(PrimitiveType),
+	Reference(ReferenceTypeSignature),
+}
+
+impl TypeSignature {
+	pub fn parse(string: &str) -> Option<TypeSignature> {
+		match Self::parse_len(string)? {
+			(ty, length) if length == string.len() => Some(ty),
+			_ => None,
+		}
+	}
+
+	pub fn parse_len(string: &str) -> Option<(TypeSignature, usize)> {
+		match string.as_bytes().first()? {
+			b'L' | b'T' | b'[' => ReferenceTypeSignature::parse_len(string)
+				.map(|(ty, length)| (TypeSignature::Reference(ty), length)),
+			_ => PrimitiveType::parse(string).map(|ty| (TypeSignature::Primitive(ty), 1)),
+		}
+	}
+
+	/// The type this erases to, type variables become their bound.
+	pub fn erase(&self, type_parameters: &[TypeParameter]) -> Type {
+		match self {
+			TypeSignature::Primitive(ty) => Type::Primitive(*ty),
+			TypeSignature::Reference(ty) => ty.erase(type_parameters),
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReferenceTypeSignature {
+	Class(ClassTypeSignature),
+	Variable(String),
+	Array(Box<TypeSignature>),
+}
+
+impl ReferenceTypeSignature {
+	pub fn parse(string: &str) -> Option<ReferenceTypeSignature> {
+		match Self::parse_len(string)? {
+			(ty, length) if length == string.len() => Some(ty),
+			_ => None,
+		}
+	}
+
+	pub fn parse_len(string: &str) -> Option<(ReferenceTypeSignature, usize)> {
+		match string.as_bytes().first()? {
+			b'L' => ClassTypeSignature::parse_len(string)
+				.map(|(ty, length)| (ReferenceTypeSignature::Class(ty), length)),
+			b'T' => {
+				let end = string.find(';')?;
+				Some((
+					ReferenceTypeSignature::Variable(string[1..end].to_string()),
+					end + 1,
+				))
+			}
+			b'[' => TypeSignature::parse_len(&string[1..]).map(|(component, length)| {
+				(
+					ReferenceTypeSignature::Array(Box::new(component)),
+					length + 1,
+				)
+			}),
+			_ => None,
+		}
+	}
+
+	/// The type this erases to, type variables become their bound and unknown ones Object.
+	pub fn erase(&self, type_parameters: &[TypeParameter]) -> Type {
+		match self {
+			ReferenceTypeSignature::Class(ty) => Type::Object(ty.ty.clone()),
+			ReferenceTypeSignature::Variable(name) => type_parameters
+				.iter()
+				.find(|parameter| &parameter.name == name)
+				.and_then(|parameter| parameter.bound())
+				.map(|bound| bound.erase(type_parameters))
+				.unwrap_or_else(|| Type::Object(ObjectType::Object())),
+			ReferenceTypeSignature::Array(component) => {
+				Type::Array(ArrayType::from_component(component.erase(type_parameters)))
+			}
+		}
+	}
+}
+
+/// A class with its type arguments, `Ljava/util/List<Ljava/lang/String;>;`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClassTypeSignature {
+	pub ty: ObjectType,
+	/// The arguments of the innermost class, the ones of the outer classes get dropped.
+	pub arguments: Vec<TypeArgument>,
+}
+
+impl ClassTypeSignature {
+	pub fn parse_len(string: &str) -> Option<(ClassTypeSignature, usize)> {
+		let bytes = string.as_bytes();
+		if *bytes.first()? != b'L' {
+			return None;
+		}
+
+		let mut name = String::new();
+		let mut arguments = Vec::new();
+		let mut pos = 1;
+		loop {
+			let end = pos + string[pos..].find(['<', '.', ';'])?;
+			name.push_str(&string[pos..end]);
+			pos = end;
+
+			arguments.clear();
+			if bytes[pos] == b'<' {
+				pos += 1;
+				while *bytes.get(pos)? != b'>' {
+					let (argument, length) = TypeArgument::parse_len(&string[pos..])?;
+					arguments.push(argument);
+					pos += length;
+				}
+				pos += 1;
+			}
+
+			match bytes.get(pos)? {
+				b';' => break,
+				b'.' => {
+					// Inner classes of generic classes, Outer<T>.Inner is Outer$Inner
+					name.push('$');
+					pos += 1;
+				}
+				_ => return None,
+			}
+		}
+
+		Some((
+			ClassTypeSignature {
+				ty: ObjectType::new(name),
+				arguments,
+			},
+			pos + 1,
+		))
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TypeArgument {
+	/// `?`
+	Any,
+	/// `T`
+	Exact(ReferenceTypeSignature),
+	/// `? extends T`
+	Extends(ReferenceTypeSignature),
+	/// `? super T`
+	Super(ReferenceTypeSignature),
+}
+
+impl TypeArgument {
+	pub fn parse_len(string: &str) -> Option<(TypeArgument, usize)> {
+		let (wildcard, pos) = match string.as_bytes().first()? {
+			b'*' => return Some((TypeArgument::Any, 1)),
+			b'+' => (Some(true), 1),
+			b'-' => (Some(false), 1),
+			_ => (None, 0),
+		};
+
+		let (ty, length) = ReferenceTypeSignature::parse_len(&string[pos..])?;
+		let argument = match wildcard {
+			None => TypeArgument::Exact(ty),
+			Some(true) => TypeArgument::Extends(ty),
+			Some(false) => TypeArgument::Super(ty),
+		};
+		Some((argument, pos + length))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn class(name: &str, arguments: Vec<TypeArgument>) -> ReferenceTypeSignature {
+		ReferenceTypeSignature::Class(ClassTypeSignature {
+			ty: ObjectType::new(name.to_string()),
+			arguments,
+		})
+	}
+
+	fn variable(name: &str) -> ReferenceTypeSignature {
+		ReferenceTypeSignature::Variable(name.to_string())
+	}
+
+	#[test]
+	fn type_parameter_bounds() {
+		let signature = ClassSignature::parse(
+			"<T:Ljava/lang/Number;U::Ljava/lang/Comparable<TU;>;:Ljava/io/Serializable;>Ljava/lang/Object;",
+		)
+		.unwrap();
+
+		let [t, u] = &signature.type_parameters[..] else {
+			panic!("Expected two type parameters");
+		};
+		assert_eq!(t.name, "T");
+		assert_eq!(t.class_bound, Some(class("java/lang/Number", vec![])));
+		assert!(t.interface_bounds.is_empty());
+
+		// The class bound is empty, so the first interface bound is the one it erases to.
+		let comparable = class(
+			"java/lang/Comparable",
+			vec![TypeArgument::Exact(variable("U"))],
+		);
+		assert_eq!(u.name, "U");
+		assert_eq!(u.class_bound, None);
+		assert_eq!(
+			u.interface_bounds,
+			vec![comparable.clone(), class("java/io/Serializable", vec![])]
+		);
+		assert_eq!(u.bound(), Some(&comparable));
+		assert_eq!(
+			variable("U").erase(&signature.type_parameters),
+			Type::Object(ObjectType::new("java/lang/Comparable".to_string()))
+		);
+		assert_eq!(
+			variable("V").erase(&signature.type_parameters),
+			Type::Object(ObjectType::Object())
+		);
+
+		assert_eq!(signature.superclass.ty, ObjectType::Object());
+		assert!(signature.interfaces.is_empty());
+		assert_eq!(ClassSignature::parse("<>Ljava/lang/Object;"), None);
+		assert_eq!(
+			ClassSignature::parse("<:Ljava/lang/Object;>Ljava/lang/Object;"),
+			None
+		);
+	}
+
+	#[test]
+	fn wildcards() {
+		let signature =
+			FieldSignature::parse("Ljava/util/Map<*+Ljava/lang/Number;-TT;[I>;").unwrap();
+		assert_eq!(
+			signature,
+			class(
+				"java/util/Map",
+				vec![
+					TypeArgument::Any,
+					TypeArgument::Extends(class("java/lang/Number", vec![])),
+					TypeArgument::Super(variable("T")),
+					TypeArgument::Exact(ReferenceTypeSignature::Array(Box::new(
+						TypeSignature::Primitive(PrimitiveType::Int)
+					))),
+				]
+			)
+		);
+
+		assert_eq!(FieldSignature::parse("Ljava/util/List<+>;"), None);
+		assert_eq!(FieldSignature::parse("Ljava/util/List<TT;"), None);
+	}
+
+	#[test]
+	fn inner_classes() {
+		let signature =
+			FieldSignature::parse("Ltests/Outer<TT;>.Inner<Ljava/lang/String;>;").unwrap();
+		assert_eq!(
+			signature,
+			class(
+				"tests/Outer$Inner",
+				vec![TypeArgument::Exact(class("java/lang/String", vec![]))]
+			)
+		);
+
+		// Only the innermost class keeps its arguments.
+		let signature = FieldSignature::parse("Ltests/Outer<TT;>.Middle.Inner;").unwrap();
+		assert_eq!(signature, class("tests/Outer$Middle$Inner", vec![]));
+		assert_eq!(FieldSignature::parse("Ltests/Outer<TT;>"), None);
+	}
+
+	#[test]
+	fn throws() {
+		let signature = MethodSignature::parse(
+			"<E:Ljava/lang/Exception;>(I[TE;)Ljava/util/List<TE;>;^TE;^Ljava/io/IOException;",
+		)
+		.unwrap();
+		assert_eq!(signature.type_parameters.len(), 1);
+		assert_eq!(
+			signature.parameters,
+			vec![
+				TypeSignature::Primitive(PrimitiveType::Int),
+				TypeSignature::Reference(ReferenceTypeSignature::Array(Box::new(
+					TypeSignature::Reference(variable("E"))
+				))),
+			]
+		);
+		assert_eq!(
+			signature.returns,
+			Some(TypeSignature::Reference(class(
+				"java/util/List",
+				vec![TypeArgument::Exact(variable("E"))]
+			)))
+		);
+		assert_eq!(
+			signature.throws,
+			vec![variable("E"), class("java/io/IOException", vec![])]
+		);
+
+		let signature = MethodSignature::parse("()V").unwrap();
+		assert_eq!(signature.returns, None);
+		assert!(signature.throws.is_empty());
+		assert_eq!(MethodSignature::parse("()V^"), None);
+		assert_eq!(MethodSignature::parse("()VLjava/io/IOException;"), None);
+		assert_eq!(MethodSignature::parse("(I"), None);
+	}
+}
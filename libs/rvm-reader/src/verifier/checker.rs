@@ -0,0 +1,290 @@
+use crate::verifier::frame::Frame;
+use crate::verifier::method::MethodVerifier;
+use crate::verifier::types::VType;
+use crate::verifier::Failure;
+use crate::{AttributeInfo, Inst, StackMapFrame, VerificationType};
+
+/// The frames of the stack map together with new ones for `targets`, in their shortest form and
+/// with the byte offsets of `positions`.
+///
+/// The code up to a new frame falls through from the frame before it, because jumps need frames,
+/// so the new frames follow the instructions from there without checking them.
+pub(super) fn stack_map(
+	verifier: &mut MethodVerifier,
+	targets: &[usize],
+	positions: &[usize],
+) -> Result<Vec<StackMapFrame>, Failure> {
+	let mut frames = decode_frames(verifier)?;
+	for &target in targets {
+		if frames[target].is_some() {
+			continue;
+		}
+
+		let start = (0..target).rev().find(|index| frames[*index].is_some());
+		let mut frame = match start {
+			Some(start) => frames[start].clone().unwrap(),
+			None => verifier.initial_frame()?,
+		};
+		for index in start.unwrap_or(0)..target {
+			verifier.execute(&mut frame, index)?;
+		}
+		frames[target] = Some(frame);
+	}
+
+	let mut map = vec![];
+	let mut previous_locals = verifier.parameters().to_vec();
+	let mut previous_offset = None;
+	for (index, frame) in frames.iter().enumerate() {
+		let Some(frame) = frame else {
+			continue;
+		};
+		let offset = positions[index];
+		let delta = match previous_offset {
+			None => offset,
+			Some(previous) => offset - previous - 1,
+		};
+		let Ok(offset_delta) = u16::try_from(delta) else {
+			invalid!("The stack map frame at {offset} is too far from the one before");
+		};
+		previous_offset = Some(offset);
+
+		let locals = compact(&frame.locals);
+		let stack = compact(&frame.stack);
+		let convert = |types: &[VType]| {
+			types
+				.iter()
+				.map(|ty| verification_type(verifier, ty, positions))
+				.collect::<Result<Vec<_>, _>>()
+		};
+		let same_start = locals.len().min(previous_locals.len());
+		let extends = locals[..same_start] == previous_locals[..same_start];
+		map.push(
+			match (
+				stack.len(),
+				locals.len() as isize - previous_locals.len() as isize,
+			) {
+				(0, 0) if extends => StackMapFrame::Same { offset_delta },
+				(1, 0) if extends => StackMapFrame::SameLocals1StackItem {
+					offset_delta,
+					stack: verification_type(verifier, &stack[0], positions)?,
+				},
+				(0, -3..=-1) if extends => StackMapFrame::Chop {
+					offset_delta,
+					chopped: (previous_locals.len() - locals.len()) as u8,
+				},
+				(0, 1..=3) if extends => StackMapFrame::Append {
+					offset_delta,
+					locals: convert(&locals[same_start..])?,
+				},
+				_ => StackMapFrame::Full {
+					offset_delta,
+					locals: convert(&locals)?,
+					stack: convert(&stack)?,
+				},
+			},
+		);
+		previous_locals = locals;
+	}
+	Ok(map)
+}
+
+/// The types like a stack map has them, where longs and doubles only appear once and the unused
+/// locals at the end are left out.
+fn compact(types: &[VType]) -> Vec<VType> {
+	let mut compact = vec![];
+	let mut index = 0;
+	while index < types.len() {
+		compact.push(types[index].clone());
+		index += types[index].size();
+	}
+	while compact.last() == Some(&VType::Top) {
+		compact.pop();
+	}
+	compact
+}
+
+fn verification_type(
+	verifier: &MethodVerifier,
+	ty: &VType,
+	positions: &[usize],
+) -> Result<VerificationType, Failure> {
+	Ok(match ty {
+		VType::Top => VerificationType::Top,
+		VType::Int => VerificationType::Integer,
+		VType::Float => VerificationType::Float,
+		VType::Long => VerificationType::Long,
+		VType::Double => VerificationType::Double,
+		VType::Null => VerificationType::Null,
+		VType::UninitializedThis => VerificationType::UninitializedThis,
+		VType::Uninitialized(offset) => {
+			let Some(index) = verifier.index_of(*offset) else {
+				invalid!("Uninitialized object without a new at {offset}");
+			};
+			VerificationType::Uninitialized {
+				offset: positions[index] as u16,
+			}
+		}
+		VType::Reference(name) => VerificationType::Object(verifier.class_constant(name)?),
+		VType::ReturnAddress(_) => invalid!("Return addresses can not be in a stack map frame"),
+	})
+}
+
+/// Type checks the code against the frames of its `StackMapTable`, see JVMS 4.10.1.
+pub(super) fn check(verifier: &mut MethodVerifier) -> Result<(), Failure> {
+	let frames = decode_frames(verifier)?;
+	let mut current = Some(verifier.initial_frame()?);
+
+	for index in 0..verifier.code.instructions.len() {
+		verifier.current = Some(verifier.offset(index));
+		// Class files with a stack map are not allowed to use them, see JVMS 4.9.1.
+		if let Inst::JSR(_) | Inst::JSR_W(_) | Inst::RET(_) = verifier.code.instructions[index] {
+			invalid!("Subroutines can not be type checked");
+		}
+
+		let frame = match (current.take(), &frames[index]) {
+			(Some(frame), Some(map_frame)) => {
+				if !verifier.is_frame_assignable(&frame, map_frame)? {
+					invalid!(
+						"The frame {frame:?} does not match the stack map frame {map_frame:?}"
+					);
+				}
+				map_frame.clone()
+			}
+			(None, Some(map_frame)) => map_frame.clone(),
+			(Some(frame), None) => frame,
+			(None, None) => invalid!("No stack map frame after an unconditional branch"),
+		};
+
+		for (handler, catch_type) in verifier.handlers(index) {
+			let exception = frame.with_exception(catch_type);
+			check_target(verifier, &frames, &exception, handler)?;
+		}
+
+		let mut after = frame;
+		verifier.execute(&mut after, index)?;
+
+		let (targets, falls_through) = verifier.successors(index)?;
+		for target in targets {
+			check_target(verifier, &frames, &after, target)?;
+		}
+
+		if falls_through {
+			if index + 1 == verifier.code.instructions.len() {
+				invalid!("Falling off the end of the code");
+			}
+			current = Some(after);
+		}
+	}
+	Ok(())
+}
+
+fn check_target(
+	verifier: &mut MethodVerifier,
+	frames: &[Option<Frame>],
+	frame: &Frame,
+	target: usize,
+) -> Result<(), Failure> {
+	let offset = verifier.offset(target);
+	let Some(map_frame) = &frames[target] else {
+		invalid!("No stack map frame at {offset}");
+	};
+	if !verifier.is_frame_assignable(frame, map_frame)? {
+		invalid!("The frame {frame:?} does not match the stack map frame at {offset}");
+	}
+	Ok(())
+}
+
+/// The frames of the stack map by instruction, see JVMS 4.7.4.
+fn decode_frames(verifier: &MethodVerifier) -> Result<Vec<Option<Frame>>, Failure> {
+	let mut frames = vec![None; verifier.code.instructions.len()];
+	let map = verifier
+		.code
+		.attribute_info
+		.iter()
+		.find_map(|attribute| match attribute {
+			AttributeInfo::StackMapTable { frames } => Some(frames),
+			_ => None,
+		});
+	let Some(map) = map else {
+		return Ok(frames);
+	};
+
+	// Longs and doubles only appear once in the locals of the stack map.
+	let mut locals = verifier.parameters().to_vec();
+	let mut offset: Option<usize> = None;
+	for map_frame in map {
+		let delta = map_frame.offset_delta() as usize;
+		let next = match offset {
+			None => delta,
+			Some(offset) => offset + delta + 1,
+		};
+		offset = Some(next);
+
+		let stack = match map_frame {
+			StackMapFrame::Same { .. } => vec![],
+			StackMapFrame::SameLocals1StackItem { stack, .. } => {
+				vec![convert(verifier, stack)?]
+			}
+			StackMapFrame::Chop { chopped, .. } => {
+				let Some(len) = locals.len().checked_sub(*chopped as usize) else {
+					invalid!("Chopping {chopped} locals from {} locals", locals.len());
+				};
+				locals.truncate(len);
+				vec![]
+			}
+			StackMapFrame::Append {
+				locals: appended, ..
+			} => {
+				for local in appended {
+					locals.push(convert(verifier, local)?);
+				}
+				vec![]
+			}
+			StackMapFrame::Full {
+				locals: full,
+				stack,
+				..
+			} => {
+				locals = full
+					.iter()
+					.map(|local| convert(verifier, local))
+					.collect::<Result<_, _>>()?;
+				stack
+					.iter()
+					.map(|value| convert(verifier, value))
+					.collect::<Result<_, _>>()?
+			}
+		};
+
+		let Some(index) = verifier.index_of(next) else {
+			invalid!("The stack map frame at {next} is not at an instruction");
+		};
+		frames[index] = Some(verifier.frame(&locals, &stack)?);
+	}
+	Ok(frames)
+}
+
+fn convert(verifier: &MethodVerifier, ty: &VerificationType) -> Result<VType, Failure> {
+	Ok(match ty {
+		VerificationType::Top => VType::Top,
+		VerificationType::Integer => VType::Int,
+		VerificationType::Float => VType::Float,
+		VerificationType::Double => VType::Double,
+		VerificationType::Long => VType::Long,
+		VerificationType::Null => VType::Null,
+		VerificationType::UninitializedThis => VType::UninitializedThis,
+		VerificationType::Object(class) => VType::reference(verifier.class_name(*class)?),
+		VerificationType::Uninitialized { offset } => {
+			let offset = *offset as usize;
+			match verifier
+				.index_of(offset)
+				.map(|index| &verifier.code.instructions[index])
+			{
+				Some(Inst::New(_)) => VType::Uninitialized(offset),
+				_ => {
+					invalid!("Uninitialized object of a stack map frame without a new at {offset}")
+				}
+			}
+		}
+	})
+}
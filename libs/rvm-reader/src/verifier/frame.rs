@@ -0,0 +1,249 @@
+use crate::verifier::types::{is_assignable, VType};
+use crate::verifier::{ClassHierarchy, Failure};
+use crate::StackInst;
+
+/// The types of the locals and the stack before an instruction.
+#[derive(Clone, Debug, PartialEq)]
+pub(super) struct Frame {
+	/// Always `max_locals` long, unused locals are [VType::Top].
+	pub locals: Vec<VType>,
+	pub stack: Vec<VType>,
+	/// `this` has to be constructed by a super constructor before the constructor returns.
+	pub this_uninit: bool,
+	max_stack: usize,
+}
+
+impl Frame {
+	pub fn new(max_locals: usize, max_stack: usize) -> Frame {
+		Frame {
+			locals: vec![VType::Top; max_locals],
+			stack: vec![],
+			this_uninit: false,
+			max_stack,
+		}
+	}
+
+	/// The frame at the start of an exception handler, with only the exception on the stack.
+	pub fn with_exception(&self, exception: VType) -> Frame {
+		Frame {
+			locals: self.locals.clone(),
+			stack: vec![exception],
+			this_uninit: self.this_uninit,
+			max_stack: self.max_stack,
+		}
+	}
+
+	pub fn push(&mut self, ty: VType) -> Result<(), Failure> {
+		let size = ty.size();
+		if self.stack.len() + size > self.max_stack {
+			invalid!("Exceeding max_stack of {}", self.max_stack);
+		}
+
+		self.stack.push(ty);
+		if size == 2 {
+			self.stack.push(VType::Top);
+		}
+		Ok(())
+	}
+
+	fn pop_slot(&mut self) -> Result<VType, Failure> {
+		match self.stack.pop() {
+			Some(ty) => Ok(ty),
+			None => invalid!("Popping from an empty stack"),
+		}
+	}
+
+	/// Pops a value which takes up one slot.
+	pub fn pop_category1(&mut self) -> Result<VType, Failure> {
+		let ty = self.pop_slot()?;
+		if ty == VType::Top {
+			invalid!("Popping half of a long or double");
+		}
+		Ok(ty)
+	}
+
+	/// Pops a value which takes up one or two slots.
+	pub fn pop_value(&mut self) -> Result<VType, Failure> {
+		if self.stack.last() != Some(&VType::Top) {
+			return self.pop_category1();
+		}
+
+		self.stack.pop();
+		let ty = self.pop_slot()?;
+		if ty.size() != 2 {
+			invalid!("Popping {ty:?} as a long or double");
+		}
+		Ok(ty)
+	}
+
+	/// Pops a value which has to be assignable to `expected`.
+	pub fn pop(
+		&mut self,
+		hierarchy: &mut dyn ClassHierarchy,
+		expected: &VType,
+	) -> Result<VType, Failure> {
+		let ty = match expected.size() {
+			2 => self.pop_value()?,
+			_ => self.pop_category1()?,
+		};
+		if !is_assignable(hierarchy, &ty, expected)? {
+			invalid!("Expected {expected:?} on the stack, but found {ty:?}");
+		}
+		Ok(ty)
+	}
+
+	pub fn pop_reference(&mut self) -> Result<VType, Failure> {
+		let ty = self.pop_category1()?;
+		if !ty.is_reference() {
+			invalid!("Expected a reference on the stack, but found {ty:?}");
+		}
+		Ok(ty)
+	}
+
+	pub fn local(&self, index: u16) -> Result<&VType, Failure> {
+		match self.locals.get(index as usize) {
+			Some(ty) => Ok(ty),
+			None => invalid!(
+				"Local {index} is outside of max_locals {}",
+				self.locals.len()
+			),
+		}
+	}
+
+	/// Overwrites a local, and the long or double which started in the slot before it.
+	pub fn store(&mut self, index: u16, ty: VType) -> Result<(), Failure> {
+		let index = index as usize;
+		let size = ty.size();
+		if index + size > self.locals.len() {
+			invalid!(
+				"Storing to local {index} outside of max_locals {}",
+				self.locals.len()
+			);
+		}
+
+		if index > 0 && self.locals[index - 1].size() == 2 {
+			self.locals[index - 1] = VType::Top;
+		}
+		self.locals[index] = ty;
+		if size == 2 {
+			self.locals[index + 1] = VType::Top;
+		}
+		Ok(())
+	}
+
+	/// Replaces every occurrence of an uninitialized object once its constructor got called.
+	pub fn initialize(&mut self, uninitialized: &VType, ty: VType) {
+		for slot in self.locals.iter_mut().chain(self.stack.iter_mut()) {
+			if slot == uninitialized {
+				*slot = ty.clone();
+			}
+		}
+	}
+
+	/// The dups and pops work on slots, so they are the only ones which can split a long.
+	pub fn stack_inst(&mut self, inst: &StackInst) -> Result<(), Failure> {
+		match inst {
+			StackInst::Pop => {
+				self.pop_category1()?;
+			}
+			StackInst::Pop2 => {
+				if self.pop_value()?.size() == 1 {
+					self.pop_category1()?;
+				}
+			}
+			StackInst::Dup => {
+				let value = self.pop_category1()?;
+				self.push_all([&value, &value])?;
+			}
+			StackInst::DupX1 => {
+				let value1 = self.pop_category1()?;
+				let value2 = self.pop_category1()?;
+				self.push_all([&value1, &value2, &value1])?;
+			}
+			StackInst::DupX2 => {
+				let value1 = self.pop_category1()?;
+				let value2 = self.pop_value()?;
+				if value2.size() == 2 {
+					self.push_all([&value1, &value2, &value1])?;
+				} else {
+					let value3 = self.pop_category1()?;
+					self.push_all([&value1, &value3, &value2, &value1])?;
+				}
+			}
+			StackInst::Dup2 => {
+				let value1 = self.pop_value()?;
+				if value1.size() == 2 {
+					self.push_all([&value1, &value1])?;
+				} else {
+					let value2 = self.pop_category1()?;
+					self.push_all([&value2, &value1, &value2, &value1])?;
+				}
+			}
+			StackInst::Dup2X1 => {
+				let value1 = self.pop_value()?;
+				if value1.size() == 2 {
+					let value2 = self.pop_category1()?;
+					self.push_all([&value1, &value2, &value1])?;
+				} else {
+					let value2 = self.pop_category1()?;
+					let value3 = self.pop_category1()?;
+					self.push_all([&value2, &value1, &value3, &value2, &value1])?;
+				}
+			}
+			StackInst::Dup2X2 => {
+				let value1 = self.pop_value()?;
+				if value1.size() == 2 {
+					let value2 = self.pop_value()?;
+					if value2.size() == 2 {
+						self.push_all([&value1, &value2, &value1])?;
+					} else {
+						let value3 = self.pop_category1()?;
+						self.push_all([&value1, &value3, &value2, &value1])?;
+					}
+				} else {
+					let value2 = self.pop_category1()?;
+					let value3 = self.pop_value()?;
+					if value3.size() == 2 {
+						self.push_all([&value2, &value1, &value3, &value2, &value1])?;
+					} else {
+						let value4 = self.pop_category1()?;
+						self.push_all([&value2, &value1, &value4, &value3, &value2, &value1])?;
+					}
+				}
+			}
+			StackInst::Swap => {
+				let value1 = self.pop_category1()?;
+				let value2 = self.pop_category1()?;
+				self.push_all([&value1, &value2])?;
+			}
+		}
+		Ok(())
+	}
+
+	fn push_all<const N: usize>(&mut self, values: [&VType; N]) -> Result<(), Failure> {
+		for value in values {
+			self.push(value.clone())?;
+		}
+		Ok(())
+	}
+}
+
+/// Whether the frame can flow into the one of a stack map, see JVMS 4.10.1.4.
+pub(super) fn is_frame_assignable(
+	hierarchy: &mut dyn ClassHierarchy,
+	from: &Frame,
+	to: &Frame,
+) -> eyre::Result<bool> {
+	if from.stack.len() != to.stack.len() || from.this_uninit && !to.this_uninit {
+		return Ok(false);
+	}
+
+	let from_slots = from.locals.iter().chain(&from.stack);
+	let to_slots = to.locals.iter().chain(&to.stack);
+	for (from, to) in from_slots.zip(to_slots) {
+		if !is_assignable(hierarchy, from, to)? {
+			return Ok(false);
+		}
+	}
+	Ok(true)
+}
@@ -0,0 +1,170 @@
+use crate::verifier::frame::Frame;
+use crate::verifier::method::MethodVerifier;
+use crate::verifier::types::VType;
+use crate::verifier::Failure;
+use crate::{Inst, LocalInst};
+
+/// Infers the frame before every instruction by following every path through the code, and
+/// merging the frames where paths meet, see JVMS 4.10.2.
+pub(super) fn infer(verifier: &mut MethodVerifier) -> Result<(), Failure> {
+	let len = verifier.code.instructions.len();
+	let mut frames: Vec<Option<Frame>> = vec![None; len];
+	frames[0] = Some(verifier.initial_frame()?);
+	let mut changed = vec![0];
+	// The merged frames at the rets of every subroutine, by the instruction it starts at.
+	let mut returns: Vec<Option<Frame>> = vec![None; len];
+	let mut modified: Vec<Option<Vec<bool>>> = vec![None; len];
+
+	while let Some(index) = changed.pop() {
+		verifier.current = Some(verifier.offset(index));
+		let frame = frames[index].clone().unwrap();
+
+		for (handler, catch_type) in verifier.handlers(index) {
+			let exception = frame.with_exception(catch_type);
+			merge_into(verifier, &mut frames, &mut changed, handler, exception)?;
+		}
+
+		let mut after = frame;
+		verifier.execute(&mut after, index)?;
+
+		let (targets, falls_through) = verifier.successors(index)?;
+		for target in targets {
+			merge_into(verifier, &mut frames, &mut changed, target, after.clone())?;
+		}
+		if falls_through {
+			if index + 1 == len {
+				invalid!("Falling off the end of the code");
+			}
+			merge_into(verifier, &mut frames, &mut changed, index + 1, after)?;
+			continue;
+		}
+
+		// A jsr continues after a ret of its subroutine, the subroutine and its callers can get
+		// reached in either order.
+		let (subroutine, callers) = match &verifier.code.instructions[index] {
+			Inst::JSR(_) | Inst::JSR_W(_) => {
+				let subroutine = verifier.subroutine(index).unwrap();
+				if returns[subroutine].is_none() {
+					continue;
+				}
+				(subroutine, vec![index])
+			}
+			Inst::RET(local) => {
+				let VType::ReturnAddress(subroutine) = *after.local(*local)? else {
+					unreachable!();
+				};
+				let merged = match &returns[subroutine] {
+					None => after,
+					Some(old) => {
+						let merged = verifier.merge(old, &after)?;
+						if merged == *old {
+							continue;
+						}
+						merged
+					}
+				};
+				returns[subroutine] = Some(merged);
+
+				let callers = (0..len)
+					.filter(|caller| {
+						frames[*caller].is_some()
+							&& verifier.subroutine(*caller) == Some(subroutine)
+					})
+					.collect();
+				(subroutine, callers)
+			}
+			_ => continue,
+		};
+
+		if modified[subroutine].is_none() {
+			modified[subroutine] = Some(modified_locals(verifier, subroutine)?);
+		}
+		let locals = modified[subroutine].as_ref().unwrap();
+		let returned = returns[subroutine].as_ref().unwrap();
+		for caller in callers {
+			if caller + 1 == len {
+				invalid!("Returning from a subroutine past the end of the code");
+			}
+			let frame = after_return(frames[caller].as_ref().unwrap(), returned, locals);
+			merge_into(verifier, &mut frames, &mut changed, caller + 1, frame)?;
+		}
+	}
+	Ok(())
+}
+
+fn merge_into(
+	verifier: &mut MethodVerifier,
+	frames: &mut [Option<Frame>],
+	changed: &mut Vec<usize>,
+	target: usize,
+	frame: Frame,
+) -> Result<(), Failure> {
+	let merged = match &frames[target] {
+		None => frame,
+		Some(old) => {
+			let merged = verifier.merge(old, &frame)?;
+			if merged == *old {
+				return Ok(());
+			}
+			merged
+		}
+	};
+
+	frames[target] = Some(merged);
+	if !changed.contains(&target) {
+		changed.push(target);
+	}
+	Ok(())
+}
+
+/// The locals which a subroutine, or a subroutine it calls, can store to before it returns.
+fn modified_locals(verifier: &MethodVerifier, subroutine: usize) -> Result<Vec<bool>, Failure> {
+	let code = verifier.code;
+	let mut modified = vec![false; code.max_locals as usize];
+	let mut visited = vec![false; code.instructions.len()];
+	let mut pending = vec![subroutine];
+	while let Some(index) = pending.pop() {
+		if index >= visited.len() || std::mem::replace(&mut visited[index], true) {
+			continue;
+		}
+
+		let (local, size) = match &code.instructions[index] {
+			Inst::Local(LocalInst::Store(kind, local)) => (
+				*local as usize,
+				VType::stack(*kind).map_or(1, |ty| ty.size()),
+			),
+			Inst::Local(LocalInst::Increment(_, local)) => (*local as usize, 1),
+			Inst::RET(_) => continue,
+			_ => (0, 0),
+		};
+		for slot in modified.iter_mut().skip(local).take(size) {
+			*slot = true;
+		}
+
+		let (targets, falls_through) = verifier.successors(index)?;
+		pending.extend(targets);
+		if falls_through || verifier.subroutine(index).is_some() {
+			pending.push(index + 1);
+		}
+	}
+	Ok(modified)
+}
+
+/// The frame after a jsr once its subroutine returned, the locals which the subroutine did not
+/// change keep their type from before the jsr.
+fn after_return(caller: &Frame, returned: &Frame, modified: &[bool]) -> Frame {
+	let mut frame = returned.clone();
+	for (index, modified) in modified.iter().enumerate() {
+		if !modified {
+			frame.locals[index] = caller.locals[index].clone();
+		}
+	}
+
+	// The subroutine can overwrite the second half of a long or double of the caller.
+	for index in 1..modified.len() {
+		if modified[index] && !modified[index - 1] && frame.locals[index - 1].size() == 2 {
+			frame.locals[index - 1] = VType::Top;
+		}
+	}
+	frame
+}
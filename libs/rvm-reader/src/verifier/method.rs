@@ -0,0 +1,655 @@
+use rvm_core::{Kind, MethodAccessFlags, MethodDescriptor, Type};
+
+use crate::verifier::frame::{is_frame_assignable, Frame};
+use crate::verifier::types::{
+	component_matches, is_assignable, merge, reference_name, VType, OBJECT, THROWABLE,
+};
+use crate::verifier::{class_name, raw_get, utf8, ClassHierarchy, Failure};
+use crate::{
+	ArrayInst, ClassConst, ClassInfo, Code, ComparisonInst, ConstInst, ConstPtr, ConstantInfo,
+	ConstantPool, FieldInst, FieldInstKind, Inst, InvokeInst, InvokeInstKind, JumpKind, LocalInst,
+	MathInst, MethodInfo,
+};
+
+/// What both ways of verifying need to know about a method, and how its instructions change a
+/// frame.
+pub(super) struct MethodVerifier<'a> {
+	pub hierarchy: &'a mut dyn ClassHierarchy,
+	cp: &'a ConstantPool,
+	class: &'a str,
+	super_class: Option<&'a str>,
+	pub code: &'a Code,
+	/// The instruction ranges, in indices, with the instruction handling exceptions thrown inside
+	/// of them and the type of those.
+	handlers: Vec<(usize, usize, usize, VType)>,
+	/// The types of `this` and the parameters, longs and doubles only appear once.
+	parameters: Vec<VType>,
+	returns: Option<VType>,
+	/// The byte offset of the instruction being verified.
+	pub current: Option<usize>,
+}
+
+impl<'a> MethodVerifier<'a> {
+	pub fn new(
+		class: &'a ClassInfo,
+		method: &'a MethodInfo,
+		code: &'a Code,
+		hierarchy: &'a mut dyn ClassHierarchy,
+	) -> Result<MethodVerifier<'a>, Failure> {
+		let cp = &class.cp;
+		let Some(name) = class_name(cp, class.this_class) else {
+			invalid!("Invalid this class");
+		};
+		let super_class = class_name(cp, class.super_class);
+		let (Some(method_name), Some(descriptor)) = (
+			utf8(cp, method.name_index.id()),
+			utf8(cp, method.descriptor_index.id()),
+		) else {
+			invalid!("Invalid method name or descriptor");
+		};
+		let Some(descriptor) = parse_method_descriptor(descriptor) else {
+			invalid!("Invalid method descriptor {descriptor}");
+		};
+
+		let mut parameters = vec![];
+		if !method.access_flags.contains(MethodAccessFlags::STATIC) {
+			// Only the constructor of java/lang/Object has no super constructor to call.
+			parameters.push(if method_name == "<init>" && name != OBJECT {
+				VType::UninitializedThis
+			} else {
+				VType::reference(name)
+			});
+		}
+		parameters.extend(descriptor.parameters.iter().map(VType::from_type));
+
+		if code.instructions.is_empty() {
+			invalid!("The method has no code");
+		}
+		if code.pc_map.positions().len() != code.instructions.len() + 1 {
+			invalid!("The byte offsets of the instructions are unknown");
+		}
+
+		let mut verifier = MethodVerifier {
+			hierarchy,
+			cp,
+			class: name,
+			super_class,
+			code,
+			handlers: vec![],
+			parameters,
+			returns: descriptor.returns.as_ref().map(VType::from_type),
+			current: None,
+		};
+		verifier.handlers = verifier.resolve_handlers()?;
+		Ok(verifier)
+	}
+
+	fn resolve_handlers(&mut self) -> Result<Vec<(usize, usize, usize, VType)>, Failure> {
+		let mut handlers = vec![];
+		let exceptions = match self.code.exception_handlers() {
+			Ok(exceptions) => exceptions,
+			Err(error) => invalid!("{error}"),
+		};
+		for exception in exceptions {
+			if exception.start >= exception.end {
+				invalid!(
+					"Exception handler {}..{} is empty",
+					exception.start,
+					exception.end
+				);
+			}
+
+			let catch_type = match exception.catch_type {
+				0 => VType::reference(THROWABLE),
+				index => VType::reference(self.class_name(ConstPtr::new(index))?),
+			};
+			if !is_assignable(self.hierarchy, &catch_type, &VType::reference(THROWABLE))? {
+				invalid!("Catching {catch_type:?}, which is not a Throwable");
+			}
+			handlers.push((
+				exception.start,
+				exception.end,
+				exception.handler,
+				catch_type,
+			));
+		}
+		Ok(handlers)
+	}
+
+	/// The frame at the start of the method, with `this` and the parameters in the locals.
+	pub fn initial_frame(&self) -> Result<Frame, Failure> {
+		self.frame(&self.parameters, &[])
+	}
+
+	/// The locals of the initial frame, which the frames of a stack map start from.
+	pub fn parameters(&self) -> &[VType] {
+		&self.parameters
+	}
+
+	/// A frame from locals and a stack where longs and doubles only appear once.
+	pub fn frame(&self, locals: &[VType], stack: &[VType]) -> Result<Frame, Failure> {
+		let mut frame = Frame::new(self.code.max_locals as usize, self.code.max_stack as usize);
+		let mut index = 0;
+		for local in locals {
+			if index + local.size() > frame.locals.len() {
+				invalid!(
+					"The locals don't fit into max_locals {}",
+					frame.locals.len()
+				);
+			}
+			frame.locals[index] = local.clone();
+			index += local.size();
+		}
+		for value in stack {
+			frame.push(value.clone())?;
+		}
+		frame.this_uninit = locals.contains(&VType::UninitializedThis);
+		Ok(frame)
+	}
+
+	/// The byte offset of an instruction.
+	pub fn offset(&self, index: usize) -> usize {
+		self.code.pc_map.positions()[index]
+	}
+
+	/// The index of the instruction at a byte offset.
+	pub fn index_of(&self, offset: usize) -> Option<usize> {
+		self.code
+			.pc_map
+			.index(offset)
+			.filter(|index| *index < self.code.instructions.len())
+	}
+
+	/// The handlers of the exceptions which the instruction can throw, with the exception type.
+	pub fn handlers(&self, index: usize) -> Vec<(usize, VType)> {
+		self.handlers
+			.iter()
+			.filter(|(start, end, _, _)| (*start..*end).contains(&index))
+			.map(|(_, _, handler, catch_type)| (*handler, catch_type.clone()))
+			.collect()
+	}
+
+	/// The instructions which get jumped to, and whether the next one can run afterwards.
+	pub fn successors(&self, index: usize) -> Result<(Vec<usize>, bool), Failure> {
+		let target = |offset: i32| {
+			let target = index as i64 + offset as i64;
+			match usize::try_from(target) {
+				Ok(target) if target < self.code.instructions.len() => Ok(target),
+				_ => invalid!("Jumping outside of the code"),
+			}
+		};
+
+		Ok(match &self.code.instructions[index] {
+			Inst::Jump(jump) => (vec![target(jump.offset)?], jump.kind.is_conditional()),
+			Inst::TableSwitch(switch) => {
+				let mut targets = vec![target(switch.default_offset)?];
+				for offset in &switch.offsets {
+					targets.push(target(*offset)?);
+				}
+				(targets, false)
+			}
+			Inst::LookupSwitch(switch) => {
+				let mut targets = vec![target(switch.default_offset)?];
+				for (_, offset) in &switch.pairs {
+					targets.push(target(*offset)?);
+				}
+				(targets, false)
+			}
+			// Where a subroutine returns to depends on the frame, so inference follows the rets.
+			Inst::JSR(offset) => (vec![target(offset.0 as i32)?], false),
+			Inst::JSR_W(offset) => (vec![target(offset.0)?], false),
+			Inst::Return(_) | Inst::Throw(_) | Inst::RET(_) => (vec![], false),
+			_ => (vec![], true),
+		})
+	}
+
+	/// The instruction which the subroutine of a jsr starts at.
+	pub fn subroutine(&self, index: usize) -> Option<usize> {
+		let offset = match &self.code.instructions[index] {
+			Inst::JSR(offset) => offset.0 as i32,
+			Inst::JSR_W(offset) => offset.0,
+			_ => return None,
+		};
+		usize::try_from(index as i64 + offset as i64)
+			.ok()
+			.filter(|target| *target < self.code.instructions.len())
+	}
+
+	pub fn is_frame_assignable(&mut self, from: &Frame, to: &Frame) -> Result<bool, Failure> {
+		Ok(is_frame_assignable(self.hierarchy, from, to)?)
+	}
+
+	/// The frame which both frames are assignable to, for where paths through the code meet.
+	pub fn merge(&mut self, a: &Frame, b: &Frame) -> Result<Frame, Failure> {
+		if a.stack.len() != b.stack.len() {
+			invalid!(
+				"Reached with {} and {} stack slots",
+				a.stack.len(),
+				b.stack.len()
+			);
+		}
+
+		let mut merged = a.clone();
+		for (slot, other) in merged.locals.iter_mut().zip(&b.locals) {
+			*slot = merge(self.hierarchy, slot, other)?;
+		}
+		for (slot, other) in merged.stack.iter_mut().zip(&b.stack) {
+			let ty = merge(self.hierarchy, slot, other)?;
+			if ty == VType::Top && *slot != VType::Top {
+				invalid!("Reached with {slot:?} and {other:?} on the stack");
+			}
+			*slot = ty;
+		}
+		merged.this_uninit |= b.this_uninit;
+		Ok(merged)
+	}
+
+	/// Applies an instruction to the frame before it, see JVMS 4.10.1.9.
+	pub fn execute(&mut self, frame: &mut Frame, index: usize) -> Result<(), Failure> {
+		let code = self.code;
+		match &code.instructions[index] {
+			Inst::Nop => {}
+			Inst::Const(inst) => {
+				let ty = match inst {
+					ConstInst::Null => VType::Null,
+					ConstInst::Int(_) => VType::Int,
+					ConstInst::Long(_) => VType::Long,
+					ConstInst::Float(_) => VType::Float,
+					ConstInst::Double(_) => VType::Double,
+					ConstInst::Ldc { id, cat2 } => self.ldc(*id, *cat2)?,
+				};
+				frame.push(ty)?;
+			}
+			Inst::Stack(inst) => frame.stack_inst(inst)?,
+			Inst::Array(inst) => self.array(frame, inst)?,
+			Inst::Math(inst) => {
+				let ty = VType::primitive(inst.ty());
+				match inst {
+					MathInst::Neg(_) => {
+						frame.pop(self.hierarchy, &ty)?;
+					}
+					MathInst::Shl(_) | MathInst::Shr(_) | MathInst::Ushr(_) => {
+						frame.pop(self.hierarchy, &VType::Int)?;
+						frame.pop(self.hierarchy, &ty)?;
+					}
+					_ => {
+						frame.pop(self.hierarchy, &ty)?;
+						frame.pop(self.hierarchy, &ty)?;
+					}
+				}
+				frame.push(ty)?;
+			}
+			Inst::Conversion(inst) => {
+				let (from, to) = inst.types();
+				frame.pop(self.hierarchy, &VType::primitive(from))?;
+				frame.push(VType::primitive(to))?;
+			}
+			Inst::Comparison(inst) => {
+				let ty = match inst {
+					ComparisonInst::DCMPG | ComparisonInst::DCMPL => VType::Double,
+					ComparisonInst::FCMPG | ComparisonInst::FCMPL => VType::Float,
+					ComparisonInst::LCMP => VType::Long,
+				};
+				frame.pop(self.hierarchy, &ty)?;
+				frame.pop(self.hierarchy, &ty)?;
+				frame.push(VType::Int)?;
+			}
+			Inst::Jump(inst) => match inst.kind {
+				JumpKind::IF_ACMPEQ | JumpKind::IF_ACMPNE => {
+					frame.pop_reference()?;
+					frame.pop_reference()?;
+				}
+				JumpKind::IFNULL | JumpKind::IFNONNULL => {
+					frame.pop_reference()?;
+				}
+				JumpKind::GOTO => {}
+				kind => {
+					for _ in 0..kind.args() {
+						frame.pop(self.hierarchy, &VType::Int)?;
+					}
+				}
+			},
+			Inst::Local(LocalInst::Load(kind, index)) => {
+				let ty = frame.local(*index)?.clone();
+				let valid = match VType::stack(*kind) {
+					Some(expected) => ty == expected,
+					None => ty.is_reference(),
+				};
+				if !valid {
+					invalid!("Loading {kind} from local {index}, which is {ty:?}");
+				}
+				frame.push(ty)?;
+			}
+			Inst::Local(LocalInst::Store(kind, index)) => {
+				let ty = match VType::stack(*kind) {
+					Some(expected) => frame.pop(self.hierarchy, &expected)?,
+					None => match frame.pop_category1()? {
+						ty @ VType::ReturnAddress(_) => ty,
+						ty if ty.is_reference() => ty,
+						ty => invalid!("Expected a reference on the stack, but found {ty:?}"),
+					},
+				};
+				frame.store(*index, ty)?;
+			}
+			Inst::Local(LocalInst::Increment(_, index)) => {
+				let ty = frame.local(*index)?;
+				if *ty != VType::Int {
+					invalid!("Incrementing local {index}, which is {ty:?}");
+				}
+			}
+			Inst::Return(inst) => {
+				match (inst.value, &self.returns) {
+					(None, None) => {}
+					(Some(kind), Some(returns)) => {
+						let valid = match VType::stack(kind) {
+							Some(expected) => expected == *returns,
+							None => returns.is_reference(),
+						};
+						if !valid {
+							invalid!("Returning {kind} from a method returning {returns:?}");
+						}
+						frame.pop(self.hierarchy, &returns.clone())?;
+					}
+					_ => invalid!("Wrong return instruction for the method"),
+				}
+				if frame.this_uninit {
+					invalid!("Returning before calling the super constructor");
+				}
+			}
+			Inst::New(inst) => {
+				let class = self.class_name(inst.class)?;
+				if class.starts_with('[') {
+					invalid!("Creating the array {class} with new");
+				}
+				frame.push(VType::Uninitialized(self.offset(index)))?;
+			}
+			Inst::Throw(_) => {
+				frame.pop(self.hierarchy, &VType::reference(THROWABLE))?;
+			}
+			Inst::CheckCast(inst) => {
+				frame.pop_reference()?;
+				frame.push(VType::reference(self.class_name(inst.value)?))?;
+			}
+			Inst::InstanceOf(inst) => {
+				self.class_name(inst.value)?;
+				frame.pop_reference()?;
+				frame.push(VType::Int)?;
+			}
+			Inst::Field(inst) => self.field(frame, inst)?,
+			Inst::Invoke(inst) => self.invoke(frame, inst)?,
+			Inst::JSR(_) | Inst::JSR_W(_) => match self.subroutine(index) {
+				Some(subroutine) => frame.push(VType::ReturnAddress(subroutine))?,
+				None => invalid!("Jumping outside of the code"),
+			},
+			Inst::RET(index) => {
+				let ty = frame.local(*index)?;
+				if !matches!(ty, VType::ReturnAddress(_)) {
+					invalid!("Returning from a subroutine to local {index}, which is {ty:?}");
+				}
+			}
+			Inst::LookupSwitch(_) | Inst::TableSwitch(_) => {
+				frame.pop(self.hierarchy, &VType::Int)?;
+			}
+			Inst::MONITORENTER | Inst::MONITOREXIT => {
+				frame.pop_reference()?;
+			}
+		}
+		Ok(())
+	}
+
+	fn ldc(&self, id: u16, cat2: bool) -> Result<VType, Failure> {
+		let ty = match raw_get(self.cp, id) {
+			Some(ConstantInfo::Integer(_)) => VType::Int,
+			Some(ConstantInfo::Float(_)) => VType::Float,
+			Some(ConstantInfo::Long(_)) => VType::Long,
+			Some(ConstantInfo::Double(_)) => VType::Double,
+			Some(ConstantInfo::String(_)) => VType::reference("java/lang/String"),
+			Some(ConstantInfo::Class(_)) => VType::reference("java/lang/Class"),
+			Some(ConstantInfo::MethodType(_)) => VType::reference("java/lang/invoke/MethodType"),
+			Some(ConstantInfo::MethodHandle(_)) => {
+				VType::reference("java/lang/invoke/MethodHandle")
+			}
+			Some(ConstantInfo::Dynamic(dynamic)) => {
+				let (_, descriptor) = self.name_and_type(dynamic.name_and_type.id())?;
+				match VType::parse(descriptor) {
+					Some(ty) => ty,
+					None => invalid!("Invalid descriptor {descriptor} of constant {id}"),
+				}
+			}
+			_ => invalid!("Constant {id} can't be loaded"),
+		};
+
+		if (ty.size() == 2) != cat2 {
+			invalid!("Loading {ty:?} with the wrong ldc");
+		}
+		Ok(ty)
+	}
+
+	fn array(&mut self, frame: &mut Frame, inst: &ArrayInst) -> Result<(), Failure> {
+		match inst {
+			ArrayInst::Length => {
+				let array = frame.pop_reference()?;
+				if array != VType::Null && array.component().is_none() {
+					invalid!("Getting the length of {array:?}, which is not an array");
+				}
+				frame.push(VType::Int)?;
+			}
+			ArrayInst::Load(kind) => {
+				frame.pop(self.hierarchy, &VType::Int)?;
+				let array = frame.pop_reference()?;
+				let ty = match array.component() {
+					_ if array == VType::Null && *kind == Kind::Reference => VType::Null,
+					_ if array == VType::Null => VType::from_type(&kind.weak_ty()),
+					Some(component) if component_matches(*kind, component) => match kind {
+						Kind::Reference => VType::reference(reference_name(component)),
+						kind => VType::from_type(&kind.weak_ty()),
+					},
+					_ => invalid!("Loading {kind} from {array:?}"),
+				};
+				frame.push(ty)?;
+			}
+			ArrayInst::Store(kind) => {
+				// The type of stored references is checked at runtime.
+				match kind {
+					Kind::Reference => frame.pop_reference()?,
+					kind => frame.pop(self.hierarchy, &VType::from_type(&kind.weak_ty()))?,
+				};
+				frame.pop(self.hierarchy, &VType::Int)?;
+				let array = frame.pop_reference()?;
+				let valid = match array.component() {
+					Some(component) => component_matches(*kind, component),
+					None => array == VType::Null,
+				};
+				if !valid {
+					invalid!("Storing {kind} into {array:?}");
+				}
+			}
+			ArrayInst::NewPrim(ty) => {
+				frame.pop(self.hierarchy, &VType::Int)?;
+				frame.push(VType::Reference(format!(
+					"[{}",
+					Type::Primitive(*ty).to_java()
+				)))?;
+			}
+			ArrayInst::NewRef(class) => {
+				let class = self.class_name(*class)?;
+				frame.pop(self.hierarchy, &VType::Int)?;
+				frame.push(VType::Reference(if class.starts_with('[') {
+					format!("[{class}")
+				} else {
+					format!("[L{class};")
+				}))?;
+			}
+			ArrayInst::NewMultiRef { class, dimensions } => {
+				let class = self.class_name(*class)?;
+				let depth = class.chars().take_while(|c| *c == '[').count();
+				if *dimensions == 0 || depth < *dimensions as usize {
+					invalid!("Creating {class} with {dimensions} dimensions");
+				}
+				for _ in 0..*dimensions {
+					frame.pop(self.hierarchy, &VType::Int)?;
+				}
+				frame.push(VType::reference(class))?;
+			}
+		}
+		Ok(())
+	}
+
+	fn field(&mut self, frame: &mut Frame, inst: &FieldInst) -> Result<(), Failure> {
+		let Some(ConstantInfo::Field(field)) = raw_get(self.cp, inst.value.id()) else {
+			invalid!("Constant {} is not a field", inst.value.id());
+		};
+		let class = self.class_name(field.class)?;
+		let (name, descriptor) = self.name_and_type(field.name_and_type.id())?;
+		let Some(ty) = VType::parse(descriptor) else {
+			invalid!("Invalid descriptor {descriptor} of field {name}");
+		};
+
+		match (inst.kind, inst.instance) {
+			(FieldInstKind::Get, false) => frame.push(ty)?,
+			(FieldInstKind::Put, false) => {
+				frame.pop(self.hierarchy, &ty)?;
+			}
+			(FieldInstKind::Get, true) => {
+				frame.pop(self.hierarchy, &VType::reference(class))?;
+				frame.push(ty)?;
+			}
+			(FieldInstKind::Put, true) => {
+				frame.pop(self.hierarchy, &ty)?;
+				// Constructors may set their own fields before calling the super constructor.
+				let object = frame.pop_category1()?;
+				let own_field = object == VType::UninitializedThis && class == self.class;
+				if !own_field && !is_assignable(self.hierarchy, &object, &VType::reference(class))?
+				{
+					invalid!("Setting the field {class}.{name} of {object:?}");
+				}
+			}
+		}
+		Ok(())
+	}
+
+	fn invoke(&mut self, frame: &mut Frame, inst: &InvokeInst) -> Result<(), Failure> {
+		let id = inst.value.id();
+		let (class, name_and_type) = match (raw_get(self.cp, id), inst.kind) {
+			(Some(ConstantInfo::InvokeDynamic(dynamic)), InvokeInstKind::Dynamic) => {
+				(None, dynamic.name_and_type)
+			}
+			(Some(ConstantInfo::Method(method)), kind)
+				if !matches!(kind, InvokeInstKind::Dynamic) =>
+			{
+				(Some(self.class_name(method.class)?), method.name_and_type)
+			}
+			(Some(ConstantInfo::Interface(method)), kind)
+				if !matches!(kind, InvokeInstKind::Dynamic) =>
+			{
+				(Some(self.class_name(method.class)?), method.name_and_type)
+			}
+			_ => invalid!("Constant {id} can't be called with {:?}", inst.kind),
+		};
+		let (name, descriptor) = self.name_and_type(name_and_type.id())?;
+		let Some(parsed) = parse_method_descriptor(descriptor) else {
+			invalid!("Invalid descriptor {descriptor} of method {name}");
+		};
+
+		let constructor = name == "<init>";
+		if name.starts_with('<') && !(constructor && matches!(inst.kind, InvokeInstKind::Special)) {
+			invalid!("Calling {name} with {:?}", inst.kind);
+		}
+
+		let mut slots = 1;
+		for parameter in parsed.parameters.iter().rev() {
+			let ty = VType::from_type(parameter);
+			slots += ty.size();
+			frame.pop(self.hierarchy, &ty)?;
+		}
+		if let InvokeInstKind::Interface(count) = inst.kind
+			&& count as usize != slots
+		{
+			invalid!("Calling {name} with a count of {count} instead of {slots}");
+		}
+
+		match (inst.kind, class) {
+			(InvokeInstKind::Static | InvokeInstKind::Dynamic, _) | (_, None) => {}
+			(InvokeInstKind::Special, Some(class)) if constructor => {
+				if parsed.returns.is_some() {
+					invalid!("The constructor {descriptor} does not return void");
+				}
+				let object = frame.pop_category1()?;
+				match object {
+					VType::UninitializedThis => {
+						if class != self.class && Some(class) != self.super_class {
+							invalid!("Constructing this with a constructor of {class}");
+						}
+						frame.initialize(&object, VType::reference(self.class));
+						frame.this_uninit = false;
+					}
+					VType::Uninitialized(offset) => {
+						let created = match self
+							.index_of(offset)
+							.map(|index| &self.code.instructions[index])
+						{
+							Some(Inst::New(new)) => self.class_name(new.class)?,
+							_ => invalid!("No new at {offset}"),
+						};
+						if created != class {
+							invalid!("Constructing {created} with a constructor of {class}");
+						}
+						frame.initialize(&object, VType::reference(class));
+					}
+					_ => invalid!("Constructing {object:?}, which is already constructed"),
+				}
+			}
+			(InvokeInstKind::Special, Some(_)) => {
+				frame.pop(self.hierarchy, &VType::reference(self.class))?;
+			}
+			(_, Some(class)) => {
+				frame.pop(self.hierarchy, &VType::reference(class))?;
+			}
+		}
+
+		if let Some(returns) = &parsed.returns {
+			frame.push(VType::from_type(returns))?;
+		}
+		Ok(())
+	}
+
+	pub fn class_name(&self, class: ConstPtr<ClassConst>) -> Result<&'a str, Failure> {
+		match class_name(self.cp, class) {
+			Some(name) if !name.is_empty() => Ok(name),
+			_ => invalid!("Constant {} is not a class", class.id()),
+		}
+	}
+
+	/// The class constant with a name, to write the type into a stack map frame.
+	pub fn class_constant(&self, name: &str) -> Result<ConstPtr<ClassConst>, Failure> {
+		let index = self.cp.entries().iter().position(|info| match info {
+			ConstantInfo::Class(class) => utf8(self.cp, class.name.id()) == Some(name),
+			_ => false,
+		});
+		match index {
+			Some(index) => Ok(ConstPtr::new(index as u16 + 1)),
+			None => invalid!("The constant pool has no class {name}"),
+		}
+	}
+
+	fn name_and_type(&self, index: u16) -> Result<(&'a str, &'a str), Failure> {
+		let Some(ConstantInfo::NameAndType(name_and_type)) = raw_get(self.cp, index) else {
+			invalid!("Constant {index} is not a name and type");
+		};
+		match (
+			utf8(self.cp, name_and_type.name.id()),
+			utf8(self.cp, name_and_type.descriptor.id()),
+		) {
+			(Some(name), Some(descriptor)) => Ok((name, descriptor)),
+			_ => invalid!("Constant {index} is not a name and type"),
+		}
+	}
+}
+
+/// Parses a method descriptor, without panicking on the empty ones.
+fn parse_method_descriptor(descriptor: &str) -> Option<MethodDescriptor> {
+	if !descriptor.starts_with('(') {
+		return None;
+	}
+	MethodDescriptor::parse(descriptor)
+}
@@ -0,0 +1,244 @@
+//! Checks that the code of a class is type safe before it runs, see JVMS 4.10.
+//!
+//! Classes from version 50 on are type checked against the frames of their `StackMapTable`. The
+//! frames of older classes get inferred by following every path through their code, which is
+//! also what version 50 classes fall back to when type checking fails.
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use eyre::bail;
+use rvm_core::ClassAccessFlags;
+
+use crate::verifier::method::MethodVerifier;
+use crate::{
+	AttributeInfo, ClassConst, ClassInfo, Code, ConstPtr, ConstantInfo, ConstantPool, MethodInfo,
+	StackMapFrame,
+};
+
+/// Ends the verification of a method with a [VerifyError].
+macro_rules! invalid {
+	($($arg:tt)*) => {
+		return Err($crate::verifier::Failure::Invalid(format!($($arg)*)))
+	};
+}
+
+mod checker;
+mod frame;
+mod inference;
+mod method;
+mod types;
+
+/// The classes which the verifier asks about, they should be looked up without loading them
+/// because the class which gets verified is not loaded yet.
+pub trait ClassHierarchy {
+	/// The super class of a class, `None` for `java/lang/Object`.
+	fn super_class(&mut self, name: &str) -> eyre::Result<Option<String>>;
+
+	fn is_interface(&mut self, name: &str) -> eyre::Result<bool>;
+}
+
+/// The code of a method is not type safe.
+#[derive(Clone, Debug)]
+pub struct VerifyError {
+	pub class: String,
+	/// The name and descriptor of the method, like `main([Ljava/lang/String;)V`.
+	pub method: String,
+	/// The byte offset of the instruction which failed to verify.
+	pub offset: Option<usize>,
+	pub message: String,
+}
+
+impl Error for VerifyError {}
+
+impl Display for VerifyError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		write!(f, "VerifyError: {}.{}", self.class, self.method)?;
+		if let Some(offset) = self.offset {
+			write!(f, " at {offset}")?;
+		}
+		write!(f, ": {}", self.message)
+	}
+}
+
+/// Invalid code becomes a [VerifyError], other errors like missing classes are returned as is.
+enum Failure {
+	Invalid(String),
+	Error(eyre::Report),
+}
+
+impl From<eyre::Report> for Failure {
+	fn from(error: eyre::Report) -> Self {
+		Failure::Error(error)
+	}
+}
+
+/// Verifies the code of every method, failures are a [VerifyError].
+pub fn verify(class: &ClassInfo, hierarchy: &mut dyn ClassHierarchy) -> eyre::Result<()> {
+	let invalid = |message: &str| VerifyError {
+		class: class_name(&class.cp, class.this_class)
+			.unwrap_or("?")
+			.to_string(),
+		method: String::new(),
+		offset: None,
+		message: message.to_string(),
+	};
+	let Some(name) = class_name(&class.cp, class.this_class) else {
+		return Err(invalid("Invalid this class").into());
+	};
+	let super_class = class_name(&class.cp, class.super_class);
+	if super_class.is_none() && (class.super_class.id() != 0 || name != "java/lang/Object") {
+		return Err(invalid("Invalid super class").into());
+	}
+
+	// The class is not loaded yet, so the hierarchy does not know it.
+	let mut hierarchy = WithClass {
+		name,
+		super_class,
+		interface: class.access_flags.contains(ClassAccessFlags::INTERFACE),
+		hierarchy,
+	};
+
+	for method in &class.methods {
+		let Some(code) = method
+			.attributes
+			.iter()
+			.find_map(|attribute| match attribute {
+				AttributeInfo::CodeAttribute { code } => Some(code),
+				_ => None,
+			})
+		else {
+			continue;
+		};
+
+		let method_name = format!(
+			"{}{}",
+			utf8(&class.cp, method.name_index.id()).unwrap_or("?"),
+			utf8(&class.cp, method.descriptor_index.id()).unwrap_or("?")
+		);
+		let mut offset = None;
+		let mut result = verify_method(
+			class,
+			method,
+			code,
+			&mut hierarchy,
+			class.major_version >= 50,
+			&mut offset,
+		);
+		if class.major_version == 50 && matches!(result, Err(Failure::Invalid(_))) {
+			result = verify_method(class, method, code, &mut hierarchy, false, &mut offset);
+		}
+
+		match result {
+			Ok(()) => {}
+			Err(Failure::Invalid(message)) => {
+				return Err(VerifyError {
+					class: name.to_string(),
+					method: method_name,
+					offset,
+					message,
+				}
+				.into());
+			}
+			Err(Failure::Error(error)) => {
+				return Err(error.wrap_err(format!("Verifying {name}.{method_name}")));
+			}
+		}
+	}
+	Ok(())
+}
+
+fn verify_method(
+	class: &ClassInfo,
+	method: &MethodInfo,
+	code: &Code,
+	hierarchy: &mut dyn ClassHierarchy,
+	type_check: bool,
+	offset: &mut Option<usize>,
+) -> Result<(), Failure> {
+	let mut verifier = MethodVerifier::new(class, method, code, hierarchy)?;
+	let result = if type_check {
+		checker::check(&mut verifier)
+	} else {
+		inference::infer(&mut verifier)
+	};
+	*offset = verifier.current;
+	result
+}
+
+/// The stack map of the code after its instructions moved to `positions`, with frames for
+/// `targets` as well, which writers need when they turn instructions into branch targets.
+pub(crate) fn stack_map(
+	class: &ClassInfo,
+	method: &MethodInfo,
+	code: &Code,
+	targets: &[usize],
+	positions: &[usize],
+) -> eyre::Result<Vec<StackMapFrame>> {
+	let mut hierarchy = Unchecked;
+	let result = MethodVerifier::new(class, method, code, &mut hierarchy)
+		.and_then(|mut verifier| checker::stack_map(&mut verifier, targets, positions));
+	match result {
+		Ok(frames) => Ok(frames),
+		Err(Failure::Invalid(message)) => bail!("Invalid stack map: {message}"),
+		Err(Failure::Error(error)) => Err(error),
+	}
+}
+
+/// Makes every reference assignable to every class, for following code without checking it.
+struct Unchecked;
+
+impl ClassHierarchy for Unchecked {
+	fn super_class(&mut self, name: &str) -> eyre::Result<Option<String>> {
+		Ok((name != types::OBJECT).then(|| types::OBJECT.to_string()))
+	}
+
+	fn is_interface(&mut self, _name: &str) -> eyre::Result<bool> {
+		Ok(true)
+	}
+}
+
+/// Answers for the class which gets verified, and asks the actual hierarchy about the others.
+struct WithClass<'a> {
+	name: &'a str,
+	super_class: Option<&'a str>,
+	interface: bool,
+	hierarchy: &'a mut dyn ClassHierarchy,
+}
+
+impl ClassHierarchy for WithClass<'_> {
+	fn super_class(&mut self, name: &str) -> eyre::Result<Option<String>> {
+		if name == self.name {
+			return Ok(self.super_class.map(str::to_string));
+		}
+		self.hierarchy.super_class(name)
+	}
+
+	fn is_interface(&mut self, name: &str) -> eyre::Result<bool> {
+		if name == self.name {
+			return Ok(self.interface);
+		}
+		self.hierarchy.is_interface(name)
+	}
+}
+
+/// Looks up constants without panicking on invalid indices, unlike [ConstantPool::get].
+fn utf8(cp: &ConstantPool, index: u16) -> Option<&str> {
+	match raw_get(cp, index)? {
+		ConstantInfo::UTF8(text) => Some(text.as_str()),
+		_ => None,
+	}
+}
+
+fn class_name(cp: &ConstantPool, class: ConstPtr<ClassConst>) -> Option<&str> {
+	match raw_get(cp, class.id())? {
+		ConstantInfo::Class(class) => utf8(cp, class.name.id()),
+		_ => None,
+	}
+}
+
+fn raw_get(cp: &ConstantPool, index: u16) -> Option<&ConstantInfo> {
+	if index == 0 {
+		return None;
+	}
+	cp.raw_get(index)
+}
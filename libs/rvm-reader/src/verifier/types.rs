@@ -0,0 +1,235 @@
+use std::collections::HashSet;
+
+use eyre::bail;
+
+use rvm_core::{Kind, PrimitiveType, StackKind, Type};
+
+use crate::verifier::ClassHierarchy;
+
+/// The types of the verifier, see JVMS 4.10.1.2. Booleans, bytes, chars and shorts are ints.
+///
+/// Longs and doubles take up two slots, the second one is always [VType::Top].
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(super) enum VType {
+	Top,
+	Int,
+	Float,
+	Long,
+	Double,
+	Null,
+	/// `this` inside of a constructor, before the super constructor got called.
+	UninitializedThis,
+	/// An object created by the `new` at this byte offset, which is not constructed yet.
+	Uninitialized(usize),
+	/// A class by its internal name (`java/lang/String`), or an array by its descriptor (`[I`).
+	Reference(String),
+	/// The address a `jsr` pushes for the subroutine starting at this instruction, which only
+	/// `astore` and `ret` can use.
+	ReturnAddress(usize),
+}
+
+pub(super) const OBJECT: &str = "java/lang/Object";
+pub(super) const THROWABLE: &str = "java/lang/Throwable";
+
+impl VType {
+	pub fn reference(name: &str) -> VType {
+		VType::Reference(name.to_string())
+	}
+
+	/// The type of a field or parameter descriptor, like `I` or `Ljava/lang/String;`.
+	pub fn parse(descriptor: &str) -> Option<VType> {
+		if descriptor.is_empty() {
+			return None;
+		}
+		Some(VType::from_type(&Type::parse(descriptor)?))
+	}
+
+	pub fn from_type(ty: &Type) -> VType {
+		match ty {
+			Type::Primitive(ty) => VType::primitive(*ty),
+			Type::Object(ty) => VType::reference(ty),
+			Type::Array(ty) => VType::Reference(ty.to_java()),
+		}
+	}
+
+	pub fn primitive(ty: PrimitiveType) -> VType {
+		match ty {
+			PrimitiveType::Float => VType::Float,
+			PrimitiveType::Long => VType::Long,
+			PrimitiveType::Double => VType::Double,
+			_ => VType::Int,
+		}
+	}
+
+	/// The type which the load, store and return instructions of the kind work with, references
+	/// are checked on their own.
+	pub fn stack(kind: StackKind) -> Option<VType> {
+		match kind {
+			StackKind::Int | StackKind::Char => Some(VType::Int),
+			StackKind::Long => Some(VType::Long),
+			StackKind::Float => Some(VType::Float),
+			StackKind::Double => Some(VType::Double),
+			StackKind::Reference => None,
+		}
+	}
+
+	pub fn size(&self) -> usize {
+		match self {
+			VType::Long | VType::Double => 2,
+			_ => 1,
+		}
+	}
+
+	pub fn is_reference(&self) -> bool {
+		matches!(
+			self,
+			VType::Null | VType::UninitializedThis | VType::Uninitialized(_) | VType::Reference(_)
+		)
+	}
+
+	/// The descriptor of the components if this is an array, like `I` for `[I`.
+	pub fn component(&self) -> Option<&str> {
+		match self {
+			VType::Reference(name) => name.strip_prefix('['),
+			_ => None,
+		}
+	}
+}
+
+/// The type of the elements an array instruction works with, `baload` also works on booleans.
+pub(super) fn component_matches(kind: Kind, component: &str) -> bool {
+	match kind {
+		Kind::Reference => component.starts_with(['L', '[']),
+		Kind::Byte | Kind::Boolean => component == "B" || component == "Z",
+		Kind::Char => component == "C",
+		Kind::Short => component == "S",
+		Kind::Int => component == "I",
+		Kind::Long => component == "J",
+		Kind::Float => component == "F",
+		Kind::Double => component == "D",
+	}
+}
+
+/// A class name or array descriptor from the descriptor of a reference, like `java/lang/String`
+/// for `Ljava/lang/String;`.
+pub(super) fn reference_name(descriptor: &str) -> &str {
+	descriptor
+		.strip_prefix('L')
+		.and_then(|name| name.strip_suffix(';'))
+		.unwrap_or(descriptor)
+}
+
+/// Whether a value of `from` can be used where `to` is expected, see JVMS 4.10.1.2.
+///
+/// Interfaces are treated like `java/lang/Object`, the checks for them happen at runtime.
+pub(super) fn is_assignable(
+	hierarchy: &mut dyn ClassHierarchy,
+	from: &VType,
+	to: &VType,
+) -> eyre::Result<bool> {
+	if from == to {
+		return Ok(true);
+	}
+
+	Ok(match (from, to) {
+		(_, VType::Top) => true,
+		(VType::Null, VType::Reference(_)) => true,
+		(VType::Reference(from), VType::Reference(to)) => is_subclass(hierarchy, from, to)?,
+		_ => false,
+	})
+}
+
+fn is_subclass(hierarchy: &mut dyn ClassHierarchy, from: &str, to: &str) -> eyre::Result<bool> {
+	if from == to || to == OBJECT {
+		return Ok(true);
+	}
+
+	if let Some(to) = to.strip_prefix('[') {
+		let Some(from) = from.strip_prefix('[') else {
+			return Ok(false);
+		};
+
+		// Arrays of primitives are only assignable to the exact same array.
+		return if from.starts_with(['L', '[']) && to.starts_with(['L', '[']) {
+			is_subclass(hierarchy, reference_name(from), reference_name(to))
+		} else {
+			Ok(from == to)
+		};
+	}
+
+	if from.starts_with('[') {
+		return Ok(matches!(to, "java/lang/Cloneable" | "java/io/Serializable"));
+	}
+
+	if hierarchy.is_interface(to)? {
+		return Ok(true);
+	}
+
+	Ok(super_classes(hierarchy, from)?
+		.iter()
+		.any(|class| class == to))
+}
+
+/// The most specific type both types are assignable to, used to join frames when inferring them.
+pub(super) fn merge(
+	hierarchy: &mut dyn ClassHierarchy,
+	a: &VType,
+	b: &VType,
+) -> eyre::Result<VType> {
+	if a == b {
+		return Ok(a.clone());
+	}
+
+	Ok(match (a, b) {
+		(VType::Null, VType::Reference(_)) => b.clone(),
+		(VType::Reference(_), VType::Null) => a.clone(),
+		(VType::Reference(a), VType::Reference(b)) => {
+			VType::Reference(common_super_class(hierarchy, a, b)?)
+		}
+		_ => VType::Top,
+	})
+}
+
+fn common_super_class(
+	hierarchy: &mut dyn ClassHierarchy,
+	a: &str,
+	b: &str,
+) -> eyre::Result<String> {
+	if let (Some(a), Some(b)) = (a.strip_prefix('['), b.strip_prefix('[')) {
+		if a.starts_with(['L', '[']) && b.starts_with(['L', '[']) {
+			let component = common_super_class(hierarchy, reference_name(a), reference_name(b))?;
+			return Ok(if component.starts_with('[') {
+				format!("[{component}")
+			} else {
+				format!("[L{component};")
+			});
+		}
+		return Ok(OBJECT.to_string());
+	}
+
+	if a.starts_with('[')
+		|| b.starts_with('[')
+		|| hierarchy.is_interface(a)?
+		|| hierarchy.is_interface(b)?
+	{
+		return Ok(OBJECT.to_string());
+	}
+
+	let supers: HashSet<String> = super_classes(hierarchy, a)?.into_iter().collect();
+	Ok(super_classes(hierarchy, b)?
+		.into_iter()
+		.find(|class| supers.contains(class))
+		.unwrap_or_else(|| OBJECT.to_string()))
+}
+
+/// The class followed by its super classes, up to `java/lang/Object`.
+fn super_classes(hierarchy: &mut dyn ClassHierarchy, name: &str) -> eyre::Result<Vec<String>> {
+	let mut classes = vec![name.to_string()];
+	while let Some(super_class) = hierarchy.super_class(classes.last().unwrap())? {
+		if classes.contains(&super_class) {
+			bail!("ClassCircularityError: {super_class}");
+		}
+		classes.push(super_class);
+	}
+	Ok(classes)
+}
@@ -0,0 +1,398 @@
+use eyre::{bail, Context};
+
+use crate::writer::{ClassWriter, Holder, Output};
+use crate::{
+	Annotation, AttributeInfo, ConstPtr, Constant, ElementValue, StackMapFrame, TypeAnnotation,
+	TypeAnnotationTarget, VerificationType,
+};
+
+impl<'a> ClassWriter<'a> {
+	pub(super) fn write_attributes(
+		&self,
+		out: &mut Vec<u8>,
+		attributes: &[AttributeInfo],
+		holder: Holder,
+	) -> eyre::Result<()> {
+		out.len_u16(attributes.len())?;
+		for attribute in attributes {
+			self.write_attribute(out, attribute, holder)
+				.wrap_err_with(|| format!("Attribute {}", attribute.name()))?;
+		}
+		Ok(())
+	}
+
+	fn write_attribute(
+		&self,
+		out: &mut Vec<u8>,
+		attribute: &AttributeInfo,
+		holder: Holder,
+	) -> eyre::Result<()> {
+		out.u16(self.name(attribute.name())?);
+
+		let mut data = Vec::new();
+		self.write_attribute_data(&mut data, attribute, holder)?;
+		out.u32(u32::try_from(data.len())?);
+		out.extend_from_slice(&data);
+		Ok(())
+	}
+
+	fn write_attribute_data(
+		&self,
+		out: &mut Vec<u8>,
+		attribute: &AttributeInfo,
+		holder: Holder,
+	) -> eyre::Result<()> {
+		match attribute {
+			AttributeInfo::ConstantValue { constant_index } => out.u16(*constant_index),
+			AttributeInfo::CodeAttribute { code } => match holder {
+				Holder::Method(method) => self.write_code(out, code, method)?,
+				_ => bail!("Code outside of a method"),
+			},
+			AttributeInfo::StackMapTable { frames } => {
+				let relocated;
+				let frames = match holder {
+					Holder::Code(relocation) => {
+						relocated = relocation.frames(frames)?;
+						&relocated
+					}
+					_ => frames,
+				};
+				out.len_u16(frames.len())?;
+				for frame in frames {
+					write_frame(out, frame)?;
+				}
+			}
+			AttributeInfo::Exceptions { exceptions } => write_ptrs(out, exceptions)?,
+			AttributeInfo::InnerClasses { classes } => {
+				out.len_u16(classes.len())?;
+				for class in classes {
+					out.u16(class.inner_class.id());
+					out.u16(class.outer_class.id());
+					out.u16(class.inner_name.id());
+					out.u16(class.access_flags.bits());
+				}
+			}
+			AttributeInfo::EnclosingMethod { class, method } => {
+				out.u16(class.id());
+				out.u16(method.id());
+			}
+			AttributeInfo::Synthetic | AttributeInfo::Deprecated => {}
+			AttributeInfo::Signature { signature } => out.u16(signature.id()),
+			AttributeInfo::SourceFile { source_file } => out.u16(source_file.id()),
+			AttributeInfo::SourceDebugExtension { debug_extension } => {
+				out.extend_from_slice(debug_extension);
+			}
+			AttributeInfo::LineNumberTable { line_numbers } => {
+				out.len_u16(line_numbers.len())?;
+				for line in line_numbers {
+					out.u16(pc(holder, line.start_pc)?);
+					out.u16(line.line_number);
+				}
+			}
+			AttributeInfo::LocalVariableTable { variables } => {
+				out.len_u16(variables.len())?;
+				for variable in variables {
+					let (start, length) = range(holder, variable.start_pc, variable.length)?;
+					out.u16(start);
+					out.u16(length);
+					out.u16(variable.name_index.id());
+					out.u16(variable.descriptor_index.id());
+					out.u16(variable.index);
+				}
+			}
+			AttributeInfo::LocalVariableTypeTable { variables } => {
+				out.len_u16(variables.len())?;
+				for variable in variables {
+					let (start, length) = range(holder, variable.start_pc, variable.length)?;
+					out.u16(start);
+					out.u16(length);
+					out.u16(variable.name_index.id());
+					out.u16(variable.signature_index.id());
+					out.u16(variable.index);
+				}
+			}
+			AttributeInfo::RuntimeVisibleAnnotations { annotations }
+			| AttributeInfo::RuntimeInvisibleAnnotations { annotations } => {
+				out.len_u16(annotations.len())?;
+				for annotation in annotations {
+					write_annotation(out, annotation)?;
+				}
+			}
+			AttributeInfo::RuntimeVisibleParameterAnnotations { parameters }
+			| AttributeInfo::RuntimeInvisibleParameterAnnotations { parameters } => {
+				out.len_u8(parameters.len())?;
+				for annotations in parameters {
+					out.len_u16(annotations.len())?;
+					for annotation in annotations {
+						write_annotation(out, annotation)?;
+					}
+				}
+			}
+			AttributeInfo::RuntimeVisibleTypeAnnotations { annotations }
+			| AttributeInfo::RuntimeInvisibleTypeAnnotations { annotations } => {
+				out.len_u16(annotations.len())?;
+				for annotation in annotations {
+					write_type_annotation(out, annotation, holder)?;
+				}
+			}
+			AttributeInfo::AnnotationDefault { value } => write_element_value(out, value)?,
+			AttributeInfo::BootstrapMethods { bootstrap_methods } => {
+				out.len_u16(bootstrap_methods.len())?;
+				for method in bootstrap_methods {
+					out.u16(method.bootstrap_method_ref.id());
+					out.len_u16(method.bootstrap_arguments.len())?;
+					for argument in &method.bootstrap_arguments {
+						out.u16(*argument);
+					}
+				}
+			}
+			AttributeInfo::MethodParameters { parameters } => {
+				out.len_u8(parameters.len())?;
+				for parameter in parameters {
+					out.u16(parameter.name.id());
+					out.u16(parameter.access_flags.bits());
+				}
+			}
+			AttributeInfo::Module { module } => {
+				out.u16(module.name.id());
+				out.u16(module.flags.bits());
+				out.u16(module.version.id());
+
+				out.len_u16(module.requires.len())?;
+				for requires in &module.requires {
+					out.u16(requires.module.id());
+					out.u16(requires.flags.bits());
+					out.u16(requires.version.id());
+				}
+
+				for exports in [&module.exports, &module.opens] {
+					out.len_u16(exports.len())?;
+					for exports in exports {
+						out.u16(exports.package.id());
+						out.u16(exports.flags.bits());
+						write_ptrs(out, &exports.to)?;
+					}
+				}
+
+				write_ptrs(out, &module.uses)?;
+				out.len_u16(module.provides.len())?;
+				for provides in &module.provides {
+					out.u16(provides.service.id());
+					write_ptrs(out, &provides.with)?;
+				}
+			}
+			AttributeInfo::ModulePackages { packages } => write_ptrs(out, packages)?,
+			AttributeInfo::ModuleMainClass { main_class } => out.u16(main_class.id()),
+			AttributeInfo::NestHost { host_class } => out.u16(host_class.id()),
+			AttributeInfo::NestMembers { classes } => write_ptrs(out, classes)?,
+			AttributeInfo::Record { components } => {
+				out.len_u16(components.len())?;
+				for component in components {
+					out.u16(component.name.id());
+					out.u16(component.descriptor.id());
+					self.write_attributes(out, &component.attributes, Holder::Other)?;
+				}
+			}
+			AttributeInfo::PermittedSubclasses { classes } => write_ptrs(out, classes)?,
+			AttributeInfo::Unknown { bytes, .. } => out.extend_from_slice(bytes),
+		}
+		Ok(())
+	}
+}
+
+/// Moves a byte offset of the code along with the instructions.
+fn pc(holder: Holder, pc: u16) -> eyre::Result<u16> {
+	match holder {
+		Holder::Code(relocation) => relocation.pc(pc as usize),
+		_ => Ok(pc),
+	}
+}
+
+/// Moves a range of the code given by its start and length.
+fn range(holder: Holder, start: u16, length: u16) -> eyre::Result<(u16, u16)> {
+	let end = start as usize + length as usize;
+	match holder {
+		Holder::Code(relocation) => {
+			let start = relocation.pc(start as usize)?;
+			Ok((start, relocation.pc(end)? - start))
+		}
+		_ => Ok((start, length)),
+	}
+}
+
+fn write_ptrs<V: Constant>(out: &mut Vec<u8>, ptrs: &[ConstPtr<V>]) -> eyre::Result<()> {
+	out.len_u16(ptrs.len())?;
+	for ptr in ptrs {
+		out.u16(ptr.id());
+	}
+	Ok(())
+}
+
+/// Frames get written in their shortest form, which is the one javac uses.
+fn write_frame(out: &mut Vec<u8>, frame: &StackMapFrame) -> eyre::Result<()> {
+	match frame {
+		StackMapFrame::Same { offset_delta } => {
+			if *offset_delta < 64 {
+				out.u8(*offset_delta as u8);
+			} else {
+				out.u8(251);
+				out.u16(*offset_delta);
+			}
+		}
+		StackMapFrame::SameLocals1StackItem {
+			offset_delta,
+			stack,
+		} => {
+			if *offset_delta < 64 {
+				out.u8(64 + *offset_delta as u8);
+			} else {
+				out.u8(247);
+				out.u16(*offset_delta);
+			}
+			write_verification_type(out, stack);
+		}
+		StackMapFrame::Chop {
+			offset_delta,
+			chopped,
+		} => {
+			out.u8(251 - chopped);
+			out.u16(*offset_delta);
+		}
+		StackMapFrame::Append {
+			offset_delta,
+			locals,
+		} => {
+			out.u8(251 + u8::try_from(locals.len())?);
+			out.u16(*offset_delta);
+			for local in locals {
+				write_verification_type(out, local);
+			}
+		}
+		StackMapFrame::Full {
+			offset_delta,
+			locals,
+			stack,
+		} => {
+			out.u8(255);
+			out.u16(*offset_delta);
+			for types in [locals, stack] {
+				out.len_u16(types.len())?;
+				for ty in types {
+					write_verification_type(out, ty);
+				}
+			}
+		}
+	}
+	Ok(())
+}
+
+fn write_verification_type(out: &mut Vec<u8>, ty: &VerificationType) {
+	match ty {
+		VerificationType::Top => out.u8(0),
+		VerificationType::Integer => out.u8(1),
+		VerificationType::Float => out.u8(2),
+		VerificationType::Double => out.u8(3),
+		VerificationType::Long => out.u8(4),
+		VerificationType::Null => out.u8(5),
+		VerificationType::UninitializedThis => out.u8(6),
+		VerificationType::Object(class) => {
+			out.u8(7);
+			out.u16(class.id());
+		}
+		VerificationType::Uninitialized { offset } => {
+			out.u8(8);
+			out.u16(*offset);
+		}
+	}
+}
+
+fn write_annotation(out: &mut Vec<u8>, annotation: &Annotation) -> eyre::Result<()> {
+	out.u16(annotation.ty.id());
+	out.len_u16(annotation.elements.len())?;
+	for element in &annotation.elements {
+		out.u16(element.name.id());
+		write_element_value(out, &element.value)?;
+	}
+	Ok(())
+}
+
+fn write_element_value(out: &mut Vec<u8>, value: &ElementValue) -> eyre::Result<()> {
+	let (tag, index) = match value {
+		ElementValue::Byte(value) => (b'B', value.id()),
+		ElementValue::Char(value) => (b'C', value.id()),
+		ElementValue::Double(value) => (b'D', value.id()),
+		ElementValue::Float(value) => (b'F', value.id()),
+		ElementValue::Int(value) => (b'I', value.id()),
+		ElementValue::Long(value) => (b'J', value.id()),
+		ElementValue::Short(value) => (b'S', value.id()),
+		ElementValue::Boolean(value) => (b'Z', value.id()),
+		ElementValue::String(value) => (b's', value.id()),
+		ElementValue::Class(value) => (b'c', value.id()),
+		ElementValue::Enum { ty, name } => {
+			out.u8(b'e');
+			out.u16(ty.id());
+			out.u16(name.id());
+			return Ok(());
+		}
+		ElementValue::Annotation(annotation) => {
+			out.u8(b'@');
+			return write_annotation(out, annotation);
+		}
+		ElementValue::Array(values) => {
+			out.u8(b'[');
+			out.len_u16(values.len())?;
+			for value in values {
+				write_element_value(out, value)?;
+			}
+			return Ok(());
+		}
+	};
+
+	out.u8(tag);
+	out.u16(index);
+	Ok(())
+}
+
+fn write_type_annotation(
+	out: &mut Vec<u8>,
+	annotation: &TypeAnnotation,
+	holder: Holder,
+) -> eyre::Result<()> {
+	out.u8(annotation.target_type);
+	match &annotation.target {
+		TypeAnnotationTarget::TypeParameter { index }
+		| TypeAnnotationTarget::FormalParameter { index } => out.u8(*index),
+		TypeAnnotationTarget::Supertype { index } | TypeAnnotationTarget::Throws { index } => {
+			out.u16(*index);
+		}
+		TypeAnnotationTarget::TypeParameterBound { parameter, bound } => {
+			out.u8(*parameter);
+			out.u8(*bound);
+		}
+		TypeAnnotationTarget::Empty => {}
+		TypeAnnotationTarget::LocalVariable { table } => {
+			out.len_u16(table.len())?;
+			for target in table {
+				let (start, length) = range(holder, target.start_pc, target.length)?;
+				out.u16(start);
+				out.u16(length);
+				out.u16(target.index);
+			}
+		}
+		TypeAnnotationTarget::Catch {
+			exception_table_index,
+		} => out.u16(*exception_table_index),
+		TypeAnnotationTarget::Offset { offset } => out.u16(pc(holder, *offset)?),
+		TypeAnnotationTarget::TypeArgument { offset, index } => {
+			out.u16(pc(holder, *offset)?);
+			out.u8(*index);
+		}
+	}
+
+	out.len_u8(annotation.path.len())?;
+	for entry in &annotation.path {
+		out.u8(entry.kind);
+		out.u8(entry.argument_index);
+	}
+	write_annotation(out, &annotation.annotation)
+}
@@ -0,0 +1,619 @@
+use eyre::{bail, ContextCompat};
+use num_traits::FromPrimitive;
+
+use rvm_core::{Kind, PrimitiveType, StackKind};
+
+use crate::verifier::stack_map;
+use crate::writer::{ClassWriter, Holder, Output};
+use crate::{
+	ArrayInst, AttributeInfo, Code, ComparisonInst, ConstInst, ConversionInst, FieldInstKind, Inst,
+	InvokeInstKind, JumpKind, LocalInst, MathInst, MethodInfo, Op, PcMap, StackInst, StackMapFrame,
+	VerificationType,
+};
+
+impl<'a> ClassWriter<'a> {
+	/// The exception table and the attributes of the code get their byte offsets moved to where
+	/// the instructions end up, which widened jumps shift.
+	pub(super) fn write_code(
+		&self,
+		out: &mut Vec<u8>,
+		code: &Code,
+		method: &MethodInfo,
+	) -> eyre::Result<()> {
+		out.u16(code.max_stack);
+		out.u16(code.max_locals);
+
+		let (bytecode, positions) = write_instructions(&code.instructions)?;
+		if code.pc_map.positions().len() != positions.len() {
+			bail!("The byte offsets of the instructions are unknown");
+		}
+		out.u32(u32::try_from(bytecode.len())?);
+		out.extend_from_slice(&bytecode);
+
+		// An inverted condition jumps over its goto_w to the next instruction, which makes that
+		// one a branch target which needs a frame.
+		let targets: Vec<_> = (0..code.instructions.len())
+			.filter(|&index| {
+				matches!(&code.instructions[index], Inst::Jump(jump) if jump.kind.is_conditional())
+					&& positions[index + 1] - positions[index] > 3
+			})
+			.map(|index| index + 1)
+			.collect();
+		let has_stack_map = code
+			.attribute_info
+			.iter()
+			.any(|attribute| matches!(attribute, AttributeInfo::StackMapTable { .. }));
+		let stack_map = if has_stack_map && !targets.is_empty() {
+			Some(stack_map(self.class, method, code, &targets, &positions)?)
+		} else {
+			None
+		};
+		let relocation = Relocation {
+			old: &code.pc_map,
+			new: &positions,
+			stack_map,
+		};
+
+		out.len_u16(code.exception_table.len())?;
+		for exception in &code.exception_table {
+			out.u16(relocation.pc(exception.start_pc as usize)?);
+			out.u16(relocation.pc(exception.end_pc as usize)?);
+			out.u16(relocation.pc(exception.handler_pc as usize)?);
+			out.u16(exception.catch_type);
+		}
+
+		self.write_attributes(out, &code.attribute_info, Holder::Code(&relocation))
+	}
+}
+
+/// Moves the byte offsets of the class file to the ones the instructions were written at.
+pub(super) struct Relocation<'b> {
+	old: &'b PcMap,
+	new: &'b [usize],
+	/// The whole stack map, if widened jumps needed new frames.
+	stack_map: Option<Vec<StackMapFrame>>,
+}
+
+impl Relocation<'_> {
+	/// The offset of an instruction or the end of the code.
+	pub fn pc(&self, pc: usize) -> eyre::Result<u16> {
+		let Some(index) = self.old.index(pc) else {
+			bail!("Byte offset {pc} is not at an instruction");
+		};
+		Ok(u16::try_from(self.new[index])?)
+	}
+
+	pub fn frames(&self, frames: &[StackMapFrame]) -> eyre::Result<Vec<StackMapFrame>> {
+		if let Some(stack_map) = &self.stack_map {
+			return Ok(stack_map.clone());
+		}
+
+		let mut relocated = Vec::with_capacity(frames.len());
+		let mut old = None;
+		let mut new = None;
+		for frame in frames {
+			let delta = frame.offset_delta() as usize;
+			let offset = old.map_or(delta, |old| old + delta + 1);
+			old = Some(offset);
+
+			let pc = self.pc(offset)? as usize;
+			let Some(delta) = new.map_or(Some(pc), |new: usize| pc.checked_sub(new + 1)) else {
+				bail!("The stack map frame at {offset} is out of order");
+			};
+			new = Some(pc);
+
+			let mut frame = frame.clone();
+			frame.set_offset_delta(u16::try_from(delta)?);
+			for ty in frame.types_mut() {
+				if let VerificationType::Uninitialized { offset } = ty {
+					*offset = self.pc(*offset as usize)?;
+				}
+			}
+			relocated.push(frame);
+		}
+		Ok(relocated)
+	}
+}
+
+/// Turns the jump offsets back from instructions into bytes.
+///
+/// Jumps and jsrs start out short and every one that does not reach gets widened, which moves
+/// the instructions after it, so this repeats until no more jumps need widening. Next to the bytes
+/// this returns the position of every instruction, followed by the length of the code.
+pub fn write_instructions(instructions: &[Inst]) -> eyre::Result<(Vec<u8>, Vec<usize>)> {
+	let mut wide = vec![false; instructions.len()];
+	let mut positions = vec![0; instructions.len() + 1];
+	loop {
+		let (code, new_positions) = encode(instructions, &wide, &positions)?;
+
+		let mut widened = false;
+		for (index, inst) in instructions.iter().enumerate() {
+			let offset = match inst {
+				Inst::Jump(jump) => jump.offset,
+				Inst::JSR(offset) => offset.0 as i32,
+				_ => continue,
+			};
+			if !wide[index] {
+				let target = target(instructions, index, offset)?;
+				let offset = new_positions[target] as i64 - new_positions[index] as i64;
+				if i16::try_from(offset).is_err() {
+					wide[index] = true;
+					widened = true;
+				}
+			}
+		}
+
+		if !widened && new_positions == positions {
+			return Ok((code, new_positions));
+		}
+		positions = new_positions;
+	}
+}
+
+fn target(instructions: &[Inst], index: usize, offset: i32) -> eyre::Result<usize> {
+	let target = index as i64 + offset as i64;
+	if target < 0 || target >= instructions.len() as i64 {
+		bail!("Instruction {index} jumps outside of the code to {target}");
+	}
+	Ok(target as usize)
+}
+
+/// Encodes the instructions with the byte offsets of the last layout, and returns the new one.
+fn encode(
+	instructions: &[Inst],
+	wide: &[bool],
+	positions: &[usize],
+) -> eyre::Result<(Vec<u8>, Vec<usize>)> {
+	let mut out = Vec::new();
+	let mut new_positions = Vec::with_capacity(positions.len());
+	for (index, inst) in instructions.iter().enumerate() {
+		new_positions.push(out.len());
+		let jump = |offset: i32| -> eyre::Result<i32> {
+			let target = target(instructions, index, offset)?;
+			Ok((positions[target] as i64 - positions[index] as i64) as i32)
+		};
+		encode_inst(&mut out, inst, wide[index], jump)?;
+	}
+	new_positions.push(out.len());
+	Ok((out, new_positions))
+}
+
+fn encode_inst(
+	out: &mut Vec<u8>,
+	inst: &Inst,
+	wide: bool,
+	jump: impl Fn(i32) -> eyre::Result<i32>,
+) -> eyre::Result<()> {
+	let pos = out.len();
+	match inst {
+		Inst::Nop => out.op(Op::NOP),
+		Inst::Const(inst) => encode_const(out, inst)?,
+		Inst::Stack(inst) => out.op(match inst {
+			StackInst::Dup => Op::DUP,
+			StackInst::DupX1 => Op::DUP_X1,
+			StackInst::DupX2 => Op::DUP_X2,
+			StackInst::Dup2 => Op::DUP2,
+			StackInst::Dup2X1 => Op::DUP2_X1,
+			StackInst::Dup2X2 => Op::DUP2_X2,
+			StackInst::Pop => Op::POP,
+			StackInst::Pop2 => Op::POP2,
+			StackInst::Swap => Op::SWAP,
+		}),
+		Inst::Array(inst) => encode_array(out, inst),
+		Inst::Math(inst) => out.op(math_op(inst).wrap_err_with(|| format!("{inst:?}"))?),
+		Inst::Conversion(inst) => out.op(match inst {
+			ConversionInst::D2F => Op::D2F,
+			ConversionInst::D2I => Op::D2I,
+			ConversionInst::D2L => Op::D2L,
+			ConversionInst::F2D => Op::F2D,
+			ConversionInst::F2I => Op::F2I,
+			ConversionInst::F2L => Op::F2L,
+			ConversionInst::I2B => Op::I2B,
+			ConversionInst::I2C => Op::I2C,
+			ConversionInst::I2D => Op::I2D,
+			ConversionInst::I2F => Op::I2F,
+			ConversionInst::I2L => Op::I2L,
+			ConversionInst::I2S => Op::I2S,
+			ConversionInst::L2D => Op::L2D,
+			ConversionInst::L2F => Op::L2F,
+			ConversionInst::L2I => Op::L2I,
+		}),
+		Inst::Comparison(inst) => out.op(match inst {
+			ComparisonInst::DCMPG => Op::DCMPG,
+			ComparisonInst::DCMPL => Op::DCMPL,
+			ComparisonInst::FCMPG => Op::FCMPG,
+			ComparisonInst::FCMPL => Op::FCMPL,
+			ComparisonInst::LCMP => Op::LCMP,
+		}),
+		Inst::Jump(inst) => {
+			let offset = jump(inst.offset)?;
+			match (wide, inst.kind) {
+				(false, kind) => {
+					out.op(jump_op(kind));
+					out.u16(offset as i16 as u16);
+				}
+				(true, JumpKind::GOTO) => {
+					out.op(Op::GOTO_W);
+					out.u32(offset as u32);
+				}
+				(true, kind) => {
+					// Jump over a goto_w if the condition does not hold.
+					out.op(jump_op(invert(kind)));
+					out.u16(8);
+					out.op(Op::GOTO_W);
+					out.u32((offset - 3) as u32);
+				}
+			}
+		}
+		Inst::Local(inst) => encode_local(out, inst)?,
+		Inst::Return(inst) => out.op(match inst.value {
+			None => Op::RETURN,
+			Some(StackKind::Int) => Op::IRETURN,
+			Some(StackKind::Long) => Op::LRETURN,
+			Some(StackKind::Float) => Op::FRETURN,
+			Some(StackKind::Double) => Op::DRETURN,
+			Some(StackKind::Reference) => Op::ARETURN,
+			Some(kind) => bail!("Can not return a {kind}"),
+		}),
+		Inst::New(inst) => {
+			out.op(Op::NEW);
+			out.u16(inst.class.id());
+		}
+		Inst::Throw(_) => out.op(Op::ATHROW),
+		Inst::CheckCast(inst) => {
+			out.op(Op::CHECKCAST);
+			out.u16(inst.value.id());
+		}
+		Inst::InstanceOf(inst) => {
+			out.op(Op::INSTANCEOF);
+			out.u16(inst.value.id());
+		}
+		Inst::Field(inst) => {
+			out.op(match (inst.instance, inst.kind) {
+				(true, FieldInstKind::Get) => Op::GETFIELD,
+				(true, FieldInstKind::Put) => Op::PUTFIELD,
+				(false, FieldInstKind::Get) => Op::GETSTATIC,
+				(false, FieldInstKind::Put) => Op::PUTSTATIC,
+			});
+			out.u16(inst.value.id());
+		}
+		Inst::Invoke(inst) => match inst.kind {
+			InvokeInstKind::Dynamic => {
+				out.op(Op::INVOKEDYNAMIC);
+				out.u16(inst.value.id());
+				out.u16(0);
+			}
+			InvokeInstKind::Interface(count) => {
+				out.op(Op::INVOKEINTERFACE);
+				out.u16(inst.value.id());
+				out.u8(count);
+				out.u8(0);
+			}
+			InvokeInstKind::Special => {
+				out.op(Op::INVOKESPECIAL);
+				out.u16(inst.value.id());
+			}
+			InvokeInstKind::Static => {
+				out.op(Op::INVOKESTATIC);
+				out.u16(inst.value.id());
+			}
+			InvokeInstKind::Virtual => {
+				out.op(Op::INVOKEVIRTUAL);
+				out.u16(inst.value.id());
+			}
+		},
+		Inst::JSR(offset) if !wide => {
+			out.op(Op::JSR);
+			out.u16(jump(offset.0 as i32)? as i16 as u16);
+		}
+		Inst::JSR(offset) => {
+			out.op(Op::JSR_W);
+			out.u32(jump(offset.0 as i32)? as u32);
+		}
+		Inst::JSR_W(offset) => {
+			out.op(Op::JSR_W);
+			out.u32(jump(offset.0)? as u32);
+		}
+		Inst::RET(index) => match u8::try_from(*index) {
+			Ok(index) => {
+				out.op(Op::RET);
+				out.u8(index);
+			}
+			Err(_) => {
+				out.op(Op::WIDE);
+				out.op(Op::RET);
+				out.u16(*index);
+			}
+		},
+		Inst::LookupSwitch(inst) => {
+			out.op(Op::LOOKUPSWITCH);
+			pad(out, pos);
+			out.u32(jump(inst.default_offset)? as u32);
+			out.len_u32(inst.pairs.len())?;
+			for (key, offset) in &inst.pairs {
+				out.u32(*key as u32);
+				out.u32(jump(*offset)? as u32);
+			}
+		}
+		Inst::TableSwitch(inst) => {
+			out.op(Op::TABLESWITCH);
+			pad(out, pos);
+			out.u32(jump(inst.default_offset)? as u32);
+			out.u32(inst.low as u32);
+			out.u32(inst.high as u32);
+			for offset in &inst.offsets {
+				out.u32(jump(*offset)? as u32);
+			}
+		}
+		Inst::MONITORENTER => out.op(Op::MONITORENTER),
+		Inst::MONITOREXIT => out.op(Op::MONITOREXIT),
+	}
+	Ok(())
+}
+
+/// Switches align their operands to 4 bytes from the start of the code.
+fn pad(out: &mut Vec<u8>, pos: usize) {
+	let padding = 3 - (pos % 4);
+	out.extend(std::iter::repeat_n(0, padding));
+}
+
+fn encode_const(out: &mut Vec<u8>, inst: &ConstInst) -> eyre::Result<()> {
+	match *inst {
+		ConstInst::Null => out.op(Op::ACONST_NULL),
+		ConstInst::Int(value @ -1..=5) => out.op(offset_op(Op::ICONST_0, value)?),
+		ConstInst::Int(value) => {
+			if let Ok(value) = i8::try_from(value) {
+				out.op(Op::BIPUSH);
+				out.u8(value as u8);
+			} else if let Ok(value) = i16::try_from(value) {
+				out.op(Op::SIPUSH);
+				out.u16(value as u16);
+			} else {
+				bail!("The int {value} has to be loaded from the constant pool");
+			}
+		}
+		ConstInst::Long(value @ 0..=1) => out.op(offset_op(Op::LCONST_0, value as i32)?),
+		ConstInst::Float(value) => {
+			let Some(index) = [0.0f32, 1.0, 2.0]
+				.iter()
+				.position(|constant| constant.to_bits() == value.to_bits())
+			else {
+				bail!("The float {value} has to be loaded from the constant pool");
+			};
+			out.op(offset_op(Op::FCONST_0, index as i32)?);
+		}
+		ConstInst::Double(value) => {
+			let Some(index) = [0.0f64, 1.0]
+				.iter()
+				.position(|constant| constant.to_bits() == value.to_bits())
+			else {
+				bail!("The double {value} has to be loaded from the constant pool");
+			};
+			out.op(offset_op(Op::DCONST_0, index as i32)?);
+		}
+		ConstInst::Long(value) => bail!("The long {value} has to be loaded from the constant pool"),
+		ConstInst::Ldc { id, cat2: true } => {
+			out.op(Op::LDC2_W);
+			out.u16(id);
+		}
+		ConstInst::Ldc { id, cat2: false } => match u8::try_from(id) {
+			Ok(id) => {
+				out.op(Op::LDC);
+				out.u8(id);
+			}
+			Err(_) => {
+				out.op(Op::LDC_W);
+				out.u16(id);
+			}
+		},
+	}
+	Ok(())
+}
+
+fn encode_array(out: &mut Vec<u8>, inst: &ArrayInst) {
+	match inst {
+		ArrayInst::Length => out.op(Op::ARRAYLENGTH),
+		ArrayInst::Load(kind) => out.op(match kind {
+			Kind::Reference => Op::AALOAD,
+			Kind::Boolean | Kind::Byte => Op::BALOAD,
+			Kind::Char => Op::CALOAD,
+			Kind::Short => Op::SALOAD,
+			Kind::Int => Op::IALOAD,
+			Kind::Long => Op::LALOAD,
+			Kind::Float => Op::FALOAD,
+			Kind::Double => Op::DALOAD,
+		}),
+		ArrayInst::Store(kind) => out.op(match kind {
+			Kind::Reference => Op::AASTORE,
+			Kind::Boolean | Kind::Byte => Op::BASTORE,
+			Kind::Char => Op::CASTORE,
+			Kind::Short => Op::SASTORE,
+			Kind::Int => Op::IASTORE,
+			Kind::Long => Op::LASTORE,
+			Kind::Float => Op::FASTORE,
+			Kind::Double => Op::DASTORE,
+		}),
+		ArrayInst::NewPrim(ty) => {
+			out.op(Op::NEWARRAY);
+			out.u8(match ty {
+				PrimitiveType::Boolean => 4,
+				PrimitiveType::Char => 5,
+				PrimitiveType::Float => 6,
+				PrimitiveType::Double => 7,
+				PrimitiveType::Byte => 8,
+				PrimitiveType::Short => 9,
+				PrimitiveType::Int => 10,
+				PrimitiveType::Long => 11,
+			});
+		}
+		ArrayInst::NewRef(class) => {
+			out.op(Op::ANEWARRAY);
+			out.u16(class.id());
+		}
+		ArrayInst::NewMultiRef { class, dimensions } => {
+			out.op(Op::MULTIANEWARRAY);
+			out.u16(class.id());
+			out.u8(*dimensions);
+		}
+	}
+}
+
+fn encode_local(out: &mut Vec<u8>, inst: &LocalInst) -> eyre::Result<()> {
+	let (short, op, index) = match *inst {
+		LocalInst::Load(kind, index) => {
+			let (short, op) = match kind {
+				StackKind::Int => (Op::ILOAD_0, Op::ILOAD),
+				StackKind::Long => (Op::LLOAD_0, Op::LLOAD),
+				StackKind::Float => (Op::FLOAD_0, Op::FLOAD),
+				StackKind::Double => (Op::DLOAD_0, Op::DLOAD),
+				StackKind::Reference => (Op::ALOAD_0, Op::ALOAD),
+				kind => bail!("Can not load a {kind} local"),
+			};
+			(short, op, index)
+		}
+		LocalInst::Store(kind, index) => {
+			let (short, op) = match kind {
+				StackKind::Int => (Op::ISTORE_0, Op::ISTORE),
+				StackKind::Long => (Op::LSTORE_0, Op::LSTORE),
+				StackKind::Float => (Op::FSTORE_0, Op::FSTORE),
+				StackKind::Double => (Op::DSTORE_0, Op::DSTORE),
+				StackKind::Reference => (Op::ASTORE_0, Op::ASTORE),
+				kind => bail!("Can not store a {kind} local"),
+			};
+			(short, op, index)
+		}
+		LocalInst::Increment(amount, index) => {
+			if let (Ok(index), Ok(amount)) = (u8::try_from(index), i8::try_from(amount)) {
+				out.op(Op::IINC);
+				out.u8(index);
+				out.u8(amount as u8);
+			} else {
+				out.op(Op::WIDE);
+				out.op(Op::IINC);
+				out.u16(index);
+				out.u16(amount as u16);
+			}
+			return Ok(());
+		}
+	};
+
+	if index <= 3 {
+		out.op(offset_op(short, index as i32)?);
+	} else if let Ok(index) = u8::try_from(index) {
+		out.op(op);
+		out.u8(index);
+	} else {
+		out.op(Op::WIDE);
+		out.op(op);
+		out.u16(index);
+	}
+	Ok(())
+}
+
+/// For the families of ops like `iconst_0` to `iconst_5`.
+fn offset_op(op: Op, offset: i32) -> eyre::Result<Op> {
+	Op::from_i32(op as i32 + offset).wrap_err("Invalid op")
+}
+
+fn math_op(inst: &MathInst) -> Option<Op> {
+	use PrimitiveType::{Double, Float, Int, Long};
+	Some(match *inst {
+		MathInst::Add(Int) => Op::IADD,
+		MathInst::Add(Long) => Op::LADD,
+		MathInst::Add(Float) => Op::FADD,
+		MathInst::Add(Double) => Op::DADD,
+		MathInst::Sub(Int) => Op::ISUB,
+		MathInst::Sub(Long) => Op::LSUB,
+		MathInst::Sub(Float) => Op::FSUB,
+		MathInst::Sub(Double) => Op::DSUB,
+		MathInst::Mul(Int) => Op::IMUL,
+		MathInst::Mul(Long) => Op::LMUL,
+		MathInst::Mul(Float) => Op::FMUL,
+		MathInst::Mul(Double) => Op::DMUL,
+		MathInst::Div(Int) => Op::IDIV,
+		MathInst::Div(Long) => Op::LDIV,
+		MathInst::Div(Float) => Op::FDIV,
+		MathInst::Div(Double) => Op::DDIV,
+		MathInst::Rem(Int) => Op::IREM,
+		MathInst::Rem(Long) => Op::LREM,
+		MathInst::Rem(Float) => Op::FREM,
+		MathInst::Rem(Double) => Op::DREM,
+		MathInst::Neg(Int) => Op::INEG,
+		MathInst::Neg(Long) => Op::LNEG,
+		MathInst::Neg(Float) => Op::FNEG,
+		MathInst::Neg(Double) => Op::DNEG,
+		MathInst::And(Int) => Op::IAND,
+		MathInst::And(Long) => Op::LAND,
+		MathInst::Or(Int) => Op::IOR,
+		MathInst::Or(Long) => Op::LOR,
+		MathInst::Xor(Int) => Op::IXOR,
+		MathInst::Xor(Long) => Op::LXOR,
+		MathInst::Shl(Int) => Op::ISHL,
+		MathInst::Shl(Long) => Op::LSHL,
+		MathInst::Shr(Int) => Op::ISHR,
+		MathInst::Shr(Long) => Op::LSHR,
+		MathInst::Ushr(Int) => Op::IUSHR,
+		MathInst::Ushr(Long) => Op::LUSHR,
+		_ => return None,
+	})
+}
+
+fn jump_op(kind: JumpKind) -> Op {
+	match kind {
+		JumpKind::IF_ACMPEQ => Op::IF_ACMPEQ,
+		JumpKind::IF_ACMPNE => Op::IF_ACMPNE,
+		JumpKind::IF_ICMPEQ => Op::IF_ICMPEQ,
+		JumpKind::IF_ICMPNE => Op::IF_ICMPNE,
+		JumpKind::IF_ICMPLT => Op::IF_ICMPLT,
+		JumpKind::IF_ICMPGE => Op::IF_ICMPGE,
+		JumpKind::IF_ICMPGT => Op::IF_ICMPGT,
+		JumpKind::IF_ICMPLE => Op::IF_ICMPLE,
+		JumpKind::IFEQ => Op::IFEQ,
+		JumpKind::IFNE => Op::IFNE,
+		JumpKind::IFLT => Op::IFLT,
+		JumpKind::IFGE => Op::IFGE,
+		JumpKind::IFGT => Op::IFGT,
+		JumpKind::IFLE => Op::IFLE,
+		JumpKind::IFNONNULL => Op::IFNONNULL,
+		JumpKind::IFNULL => Op::IFNULL,
+		JumpKind::GOTO => Op::GOTO,
+	}
+}
+
+fn invert(kind: JumpKind) -> JumpKind {
+	match kind {
+		JumpKind::IF_ACMPEQ => JumpKind::IF_ACMPNE,
+		JumpKind::IF_ACMPNE => JumpKind::IF_ACMPEQ,
+		JumpKind::IF_ICMPEQ => JumpKind::IF_ICMPNE,
+		JumpKind::IF_ICMPNE => JumpKind::IF_ICMPEQ,
+		JumpKind::IF_ICMPLT => JumpKind::IF_ICMPGE,
+		JumpKind::IF_ICMPGE => JumpKind::IF_ICMPLT,
+		JumpKind::IF_ICMPGT => JumpKind::IF_ICMPLE,
+		JumpKind::IF_ICMPLE => JumpKind::IF_ICMPGT,
+		JumpKind::IFEQ => JumpKind::IFNE,
+		JumpKind::IFNE => JumpKind::IFEQ,
+		JumpKind::IFLT => JumpKind::IFGE,
+		JumpKind::IFGE => JumpKind::IFLT,
+		JumpKind::IFGT => JumpKind::IFLE,
+		JumpKind::IFLE => JumpKind::IFGT,
+		JumpKind::IFNONNULL => JumpKind::IFNULL,
+		JumpKind::IFNULL => JumpKind::IFNONNULL,
+		JumpKind::GOTO => JumpKind::GOTO,
+	}
+}
+
+trait OpOutput {
+	fn op(&mut self, op: Op);
+	fn len_u32(&mut self, len: usize) -> eyre::Result<()>;
+}
+
+impl OpOutput for Vec<u8> {
+	fn op(&mut self, op: Op) {
+		self.push(op as u8);
+	}
+
+	fn len_u32(&mut self, len: usize) -> eyre::Result<()> {
+		self.u32(u32::try_from(len)?);
+		Ok(())
+	}
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use eyre::{bail, Context};
+
+use crate::writer::code::Relocation;
+use crate::{ClassInfo, ConstantInfo, ConstantPool, FieldInfo, MethodInfo};
+
+pub use code::write_instructions;
+
+mod attribute;
+mod code;
+
+/// Serializes a [ClassInfo] back into the bytes of a `.class` file.
+pub struct ClassWriter<'a> {
+	class: &'a ClassInfo,
+	/// Attributes only store their names as strings, so these get looked up in the pool.
+	names: HashMap<&'a str, u16>,
+}
+
+impl<'a> ClassWriter<'a> {
+	pub fn new(class: &'a ClassInfo) -> ClassWriter<'a> {
+		let mut names = HashMap::new();
+		for (index, info) in class.cp.entries().iter().enumerate() {
+			if let ConstantInfo::UTF8(text) = info {
+				names.entry(text.as_str()).or_insert(index as u16 + 1);
+			}
+		}
+
+		ClassWriter { class, names }
+	}
+
+	pub fn write(&self) -> eyre::Result<Vec<u8>> {
+		let class = self.class;
+		let mut out = Vec::new();
+		out.extend_from_slice(b"\xca\xfe\xba\xbe");
+		out.u16(class.minor_version);
+		out.u16(class.major_version);
+		write_constant_pool(&mut out, &class.cp).wrap_err("Constant Pool")?;
+
+		out.u16(class.access_flags.bits());
+		out.u16(class.this_class.id());
+		out.u16(class.super_class.id());
+		out.len_u16(class.interfaces.len())?;
+		for interface in &class.interfaces {
+			out.u16(interface.id());
+		}
+
+		out.len_u16(class.fields.len())?;
+		for field in &class.fields {
+			self.write_field(&mut out, field)
+				.wrap_err_with(|| format!("Field {}", class.cp[field.name_index].as_str()))?;
+		}
+
+		out.len_u16(class.methods.len())?;
+		for method in &class.methods {
+			self.write_method(&mut out, method)
+				.wrap_err_with(|| format!("Method {}", class.cp[method.name_index].as_str()))?;
+		}
+
+		self.write_attributes(&mut out, &class.attributes, Holder::Other)?;
+		Ok(out)
+	}
+
+	fn write_field(&self, out: &mut Vec<u8>, field: &FieldInfo) -> eyre::Result<()> {
+		out.u16(field.access_flags.bits());
+		out.u16(field.name_index.id());
+		out.u16(field.descriptor_index.id());
+		self.write_attributes(out, &field.attribute_info, Holder::Other)
+	}
+
+	fn write_method(&self, out: &mut Vec<u8>, method: &MethodInfo) -> eyre::Result<()> {
+		out.u16(method.access_flags.bits());
+		out.u16(method.name_index.id());
+		out.u16(method.descriptor_index.id());
+		self.write_attributes(out, &method.attributes, Holder::Method(method))
+	}
+
+	fn name(&self, name: &str) -> eyre::Result<u16> {
+		match self.names.get(name) {
+			Some(index) => Ok(*index),
+			None => bail!("The constant pool has no entry for the name {name}"),
+		}
+	}
+}
+
+/// What attributes belong to, the ones of code have byte offsets which move with the instructions.
+#[derive(Copy, Clone)]
+enum Holder<'b> {
+	Other,
+	Method(&'b MethodInfo),
+	Code(&'b Relocation<'b>),
+}
+
+fn write_constant_pool(out: &mut Vec<u8>, cp: &ConstantPool) -> eyre::Result<()> {
+	out.len_u16(cp.entries().len() + 1)?;
+	for info in cp.entries() {
+		match info {
+			ConstantInfo::UTF8(text) => {
+				let bytes = mutf8::utf8_to_mutf8(text.as_bytes())?;
+				out.u8(1);
+				out.len_u16(bytes.len())?;
+				out.extend_from_slice(&bytes);
+			}
+			ConstantInfo::Integer(value) => {
+				out.u8(3);
+				out.u32(value.bytes as u32);
+			}
+			ConstantInfo::Float(value) => {
+				out.u8(4);
+				out.u32(value.bytes.to_bits());
+			}
+			ConstantInfo::Long(value) => {
+				out.u8(5);
+				out.extend_from_slice(&value.bytes.to_be_bytes());
+			}
+			ConstantInfo::Double(value) => {
+				out.u8(6);
+				out.extend_from_slice(&value.bytes.to_bits().to_be_bytes());
+			}
+			ConstantInfo::Class(value) => {
+				out.u8(7);
+				out.u16(value.name.id());
+			}
+			ConstantInfo::String(value) => {
+				out.u8(8);
+				out.u16(value.string.id());
+			}
+			ConstantInfo::Field(value) => {
+				out.u8(9);
+				out.u16(value.class.id());
+				out.u16(value.name_and_type.id());
+			}
+			ConstantInfo::Method(value) => {
+				out.u8(10);
+				out.u16(value.class.id());
+				out.u16(value.name_and_type.id());
+			}
+			ConstantInfo::Interface(value) => {
+				out.u8(11);
+				out.u16(value.class.id());
+				out.u16(value.name_and_type.id());
+			}
+			ConstantInfo::NameAndType(value) => {
+				out.u8(12);
+				out.u16(value.name.id());
+				out.u16(value.descriptor.id());
+			}
+			ConstantInfo::MethodHandle(value) => {
+				out.u8(15);
+				out.u8(value.reference_kind);
+				out.u16(value.reference_index);
+			}
+			ConstantInfo::MethodType(value) => {
+				out.u8(16);
+				out.u16(value.descriptor.id());
+			}
+			ConstantInfo::Dynamic(value) => {
+				out.u8(17);
+				out.u16(value.bootstrap_method_attr_index);
+				out.u16(value.name_and_type.id());
+			}
+			ConstantInfo::InvokeDynamic(value) => {
+				out.u8(18);
+				out.u16(value.bootstrap_method_attr_index);
+				out.u16(value.name_and_type.id());
+			}
+			ConstantInfo::Module(value) => {
+				out.u8(19);
+				out.u16(value.name.id());
+			}
+			ConstantInfo::Package(value) => {
+				out.u8(20);
+				out.u16(value.name.id());
+			}
+			// The slot after a long or a double
+			ConstantInfo::Unknown => {}
+			ConstantInfo::Unusable => bail!("Unusable constant"),
+		}
+	}
+	Ok(())
+}
+
+/// Big endian writing, the counts fail if they do not fit.
+trait Output {
+	fn u8(&mut self, value: u8);
+	fn u16(&mut self, value: u16);
+	fn u32(&mut self, value: u32);
+
+	fn len_u8(&mut self, len: usize) -> eyre::Result<()> {
+		self.u8(u8::try_from(len).wrap_err("Too many entries")?);
+		Ok(())
+	}
+
+	fn len_u16(&mut self, len: usize) -> eyre::Result<()> {
+		self.u16(u16::try_from(len).wrap_err("Too many entries")?);
+		Ok(())
+	}
+}
+
+impl Output for Vec<u8> {
+	fn u8(&mut self, value: u8) {
+		self.push(value);
+	}
+
+	fn u16(&mut self, value: u16) {
+		self.extend_from_slice(&value.to_be_bytes());
+	}
+
+	fn u32(&mut self, value: u32) {
+		self.extend_from_slice(&value.to_be_bytes());
+	}
+}
@@ -1,9 +1,16 @@
-use crate::{AnyValue, FromJavaMulti, JavaTypedMulti, ToJavaMulti, Vm};
+use crate::{
+	AnyValue, CallType, FromJavaMulti, JavaTypedMulti, MethodIdentifier, Reference, Runtime,
+	ToJavaMulti, Vm,
+};
 use ahash::HashMap;
+use eyre::bail;
 use parking_lot::RwLock;
-use rvm_core::{MethodDescriptor, Type};
+use rvm_core::{MethodDescriptor, ObjectType, Type};
+use rvm_gc::GlobalHandle;
 use std::collections::hash_map::Entry;
+use std::fmt::{Debug, Formatter};
 use std::sync::Arc;
+use thiserror::Error;
 use tracing::warn;
 
 pub struct RustBinder {
@@ -20,30 +27,36 @@ impl RustBinder {
 		}
 	}
 	// https://docs.oracle.com/javase/1.5.0/docs/guide/jni/spec/design.html
+	/// Finds the binding of a native method, a binding which is bound to the name of the method
+	/// but has another descriptor is an error.
 	pub fn get_binding(
 		&self,
 		class_name: &str,
 		method_name: &str,
 		descriptor: &MethodDescriptor,
-	) -> Option<Arc<MethodBinding>> {
+	) -> eyre::Result<Option<Arc<MethodBinding>>> {
 		let inner = self.methods.read();
 
-		let short_name = MethodDescriptor::jni_short_name(class_name, method_name);
-		if let Some(Some(binding)) = inner.short_names.get(&short_name) {
-			if &binding.signature == descriptor {
-				return Some(binding.clone());
-			}
-		}
-
 		let long_name =
 			MethodDescriptor::jni_long_name(class_name, method_name, &descriptor.parameters);
 		if let Some(binding) = inner.long_names.get(&long_name) {
 			if &binding.signature == descriptor {
-				return Some(binding.clone());
+				return Ok(Some(binding.clone()));
+			}
+		}
+
+		let short_name = MethodDescriptor::jni_short_name(class_name, method_name);
+		if let Some(Some(binding)) = inner.short_names.get(&short_name) {
+			if &binding.signature != descriptor {
+				bail!(
+					"UnsatisfiedLinkError: The binding of {class_name}.{method_name} is {} but the method is {descriptor}",
+					binding.signature
+				);
 			}
+			return Ok(Some(binding.clone()));
 		}
 
-		None
+		Ok(None)
 	}
 	pub fn bind(&self, class_name: &str, method_name: &str, binding: MethodBinding) {
 		let mut inner = self.methods.write();
@@ -88,8 +101,14 @@ pub struct RustBinderInner {
 	long_names: HashMap<String, Arc<MethodBinding>>,
 }
 
+/// The function of a native method implemented in rust. It gets the calling thread, the instance
+/// for non-static methods and the parameters.
 pub struct MethodBinding {
-	function: Box<dyn Fn(&Vm, Vec<AnyValue>) -> eyre::Result<Option<AnyValue>> + Send + Sync>,
+	function: Box<
+		dyn Fn(&mut Runtime, Option<Reference>, Vec<AnyValue>) -> eyre::Result<Option<AnyValue>>
+			+ Send
+			+ Sync,
+	>,
 	signature: MethodDescriptor,
 }
 
@@ -103,23 +122,26 @@ fn single_or_none<V>(mut vec: Vec<V>) -> Option<V> {
 	}
 }
 impl MethodBinding {
+	/// The function throws a java exception by returning a [`Throwable`] error.
 	pub fn new<I, O, F>(function: F) -> Self
 	where
-		F: Fn(&Vm, I) -> O + Send + Sync + 'static,
+		F: Fn(&mut Runtime, Option<Reference>, I) -> O + Send + Sync + 'static,
 		I: FromJavaMulti + JavaTypedMulti,
 		O: ToJavaMulti + JavaTypedMulti,
 	{
-		let function =
-			move |runtime: &Vm, values: Vec<AnyValue>| -> eyre::Result<Option<AnyValue>> {
-				let input = I::from_vec(values, runtime)?;
-				let output = function(runtime, input);
-				let result = output.to_vec(runtime)?;
-				if result.len() > 1 {
-					panic!("Trying to return more than 1 value");
-				}
-
-				Ok(single_or_none(result))
-			};
+		let function = move |runtime: &mut Runtime,
+		                     this: Option<Reference>,
+		                     values: Vec<AnyValue>|
+		      -> eyre::Result<Option<AnyValue>> {
+			let input = I::from_vec(values, &runtime.vm)?;
+			let output = function(runtime, this, input);
+			let result = output.to_vec(runtime)?;
+			if result.len() > 1 {
+				panic!("Trying to return more than 1 value");
+			}
+
+			Ok(single_or_none(result))
+		};
 
 		let input_types = I::java_type_multi();
 		let output_type = single_or_none(O::java_type_multi());
@@ -133,7 +155,82 @@ impl MethodBinding {
 		}
 	}
 
-	pub fn call(&self, runtime: &Vm, parameters: Vec<AnyValue>) -> eyre::Result<Option<AnyValue>> {
-		(self.function)(runtime, parameters)
+	pub fn signature(&self) -> &MethodDescriptor {
+		&self.signature
+	}
+
+	/// `this` is the instance for non-static methods and `None` for static methods. A thrown
+	/// exception is returned as a [`Throwable`] error.
+	pub fn call(
+		&self,
+		runtime: &mut Runtime,
+		this: Option<Reference>,
+		parameters: Vec<AnyValue>,
+	) -> eyre::Result<Option<AnyValue>> {
+		(self.function)(runtime, this, parameters)
+	}
+}
+
+/// A java exception which a binding throws instead of returning, bindings return it as the error of
+/// a `Result<V, Throwable>`. The exception object is kept alive until the error gets dropped.
+#[derive(Error)]
+#[error("{class} was thrown")]
+pub struct Throwable {
+	pub class: ObjectType,
+	vm: Vm,
+	handle: GlobalHandle,
+}
+
+impl Throwable {
+	/// Creates an exception of `class` through its constructor without parameters.
+	pub fn new(runtime: &mut Runtime, class: &ObjectType) -> eyre::Result<Throwable> {
+		let id = runtime.resolve_class(&class.clone().into())?;
+		let instance = runtime.alloc_object(runtime.classes.get(id).to_instance())?;
+		// The object can move while the constructor runs.
+		let throwable = Throwable {
+			class: class.clone(),
+			vm: runtime.vm.clone(),
+			handle: runtime.gc.new_global(*instance.raw()),
+		};
+		runtime.run(
+			CallType::Special,
+			class,
+			&MethodIdentifier {
+				name: Arc::from("<init>"),
+				descriptor: Arc::from("()V"),
+			},
+			vec![AnyValue::Reference(throwable.reference())],
+		)?;
+		Ok(throwable)
+	}
+
+	/// Throws an existing exception object.
+	pub fn from_reference(vm: &Vm, reference: Reference) -> eyre::Result<Throwable> {
+		let instance = reference.to_instance()?;
+		let class = vm.classes.get(instance.class());
+		Ok(Throwable {
+			class: class.to_instance().ty.clone(),
+			vm: vm.clone(),
+			handle: vm.gc.new_global(reference),
+		})
+	}
+
+	/// The exception object, it moves when the heap gets compacted.
+	pub fn reference(&self) -> Reference {
+		self.vm.gc.global(self.handle)
+	}
+}
+
+impl Debug for Throwable {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Throwable")
+			.field("class", &self.class)
+			.finish_non_exhaustive()
+	}
+}
+
+impl Drop for Throwable {
+	fn drop(&mut self) {
+		self.vm.gc.remove_global(self.handle);
 	}
 }
@@ -0,0 +1,292 @@
+use crate::gc::GarbageCollector;
+use crate::{
+	AnyInstance, AnyValue, CallType, Class, FieldTable, FromJava, FromJavaMulti, InstanceRef,
+	JavaTypedMulti, Method, MethodIdentifier, Runtime, ToJava, ToJavaMulti, Vm,
+};
+use eyre::{bail, ContextCompat, WrapErr};
+use rvm_core::{Id, MethodAccessFlags, MethodDescriptor, ObjectType};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+impl<'r> Runtime<'r> {
+	/// Resolves a class to call its static methods, create objects or access its static fields.
+	pub fn class(&mut self, name: &str) -> eyre::Result<JavaClass<'_, 'r>> {
+		let ty = ObjectType::new(name.to_string());
+		let id = self.resolve_class(&ty.clone().into())?;
+		let class = self.classes.get(id);
+		if !class.is_instance() {
+			bail!("{name} is not an instance class");
+		}
+
+		Ok(JavaClass {
+			runtime: self,
+			ty,
+			class,
+		})
+	}
+
+	/// Allocates an object and runs the constructor `identifier` with parameters which already got
+	/// converted.
+	pub fn construct(
+		&mut self,
+		ty: &ObjectType,
+		identifier: &MethodIdentifier,
+		parameters: Vec<AnyValue>,
+	) -> eyre::Result<AnyInstance> {
+		let id = self.resolve_class(&ty.clone().into())?;
+		let class = self.classes.get(id);
+		let Some(class) = class.as_instance() else {
+			bail!("{ty} is not an instance class");
+		};
+		if class.methods.get_keyed(identifier).is_none() {
+			bail!(no_such_method(ty, identifier));
+		}
+
+		// The parameters can move while the object gets allocated.
+		let handles: Vec<_> = parameters
+			.iter()
+			.map(|value| match value {
+				AnyValue::Reference(reference) if !reference.is_null() => {
+					Some(self.gc.new_global(*reference))
+				}
+				_ => None,
+			})
+			.collect();
+		let instance = self.alloc_object(class);
+		let mut values = Vec::with_capacity(parameters.len() + 1);
+		for (value, handle) in parameters.into_iter().zip(handles) {
+			values.push(match handle {
+				Some(handle) => {
+					let reference = self.gc.global(handle);
+					self.gc.remove_global(handle);
+					AnyValue::Reference(reference)
+				}
+				None => value,
+			});
+		}
+		let instance = instance?;
+
+		// The object can move while the constructor runs.
+		let handle = self.gc.new_global(**instance);
+		values.insert(0, AnyValue::Reference(**instance));
+		let returned = self.run(CallType::Special, ty, identifier, values);
+		let reference = self.gc.global(handle);
+		self.gc.remove_global(handle);
+		returned?;
+
+		Ok(reference.to_instance()?.resolve(self.vm.clone()))
+	}
+}
+
+/// A class which gets used from rust, see [`Runtime::class`].
+pub struct JavaClass<'a, 'r> {
+	runtime: &'a mut Runtime<'r>,
+	ty: ObjectType,
+	class: Arc<Class>,
+}
+
+impl<'a, 'r> JavaClass<'a, 'r> {
+	/// Looks up a static method, the descriptor comes from the parameters `I` and returns `O`.
+	pub fn static_method<I, O>(self, name: &str) -> eyre::Result<JavaMethod<'a, 'r, I, O>>
+	where
+		I: ToJavaMulti + JavaTypedMulti,
+		O: FromJavaMulti + JavaTypedMulti,
+	{
+		let identifier = identifier::<I, O>(name);
+		let method = find_method(&self.runtime.vm, self.class.to_instance().id, &identifier)
+			.wrap_err_with(|| no_such_method(&self.ty, &identifier))?;
+		if !method.flags.contains(MethodAccessFlags::STATIC) {
+			bail!("{}.{} is not static", self.ty, identifier.name);
+		}
+
+		Ok(JavaMethod {
+			runtime: self.runtime,
+			call_type: CallType::Static,
+			ty: self.ty,
+			this: None,
+			identifier,
+			_marker: PhantomData,
+		})
+	}
+
+	/// Allocates an object and runs the constructor which takes the parameters `I`.
+	pub fn new_object<I>(self, parameters: I) -> eyre::Result<AnyInstance>
+	where
+		I: ToJavaMulti + JavaTypedMulti,
+	{
+		let identifier = identifier::<I, ()>("<init>");
+		let parameters = parameters.to_vec(self.runtime)?;
+		self.runtime.construct(&self.ty, &identifier, parameters)
+	}
+
+	pub fn get_static<V: FromJava>(&self, name: &str) -> eyre::Result<V> {
+		let value = find_static(&self.runtime.vm, &self.class, |fields| {
+			Some(fields.by_name(name)?.get())
+		})
+		.wrap_err_with(|| format!("NoSuchFieldError: {}.{name}", self.ty))?;
+		V::from_java(value, &self.runtime.vm)
+	}
+
+	pub fn set_static<V: ToJava>(&mut self, name: &str, value: V) -> eyre::Result<()> {
+		let value = value.to_java(self.runtime)?;
+		let vm = &self.runtime.vm;
+		find_static(vm, &self.class, |fields| {
+			fields.by_name(name)?;
+			Some(set_field(&vm.gc, fields, name, value))
+		})
+		.wrap_err_with(|| format!("NoSuchFieldError: {}.{name}", self.ty))?
+	}
+}
+
+impl AnyInstance {
+	/// Looks up a method which gets called virtually on this object, so overrides of subclasses
+	/// are called.
+	pub fn method<'a, 'r, I, O>(
+		&self,
+		runtime: &'a mut Runtime<'r>,
+		name: &str,
+	) -> eyre::Result<JavaMethod<'a, 'r, I, O>>
+	where
+		I: ToJavaMulti + JavaTypedMulti,
+		O: FromJavaMulti + JavaTypedMulti,
+	{
+		let ty = self.class().ty.clone();
+		let identifier = identifier::<I, O>(name);
+		let method = find_method(&runtime.vm, self.class_id(), &identifier)
+			.wrap_err_with(|| no_such_method(&ty, &identifier))?;
+		if method.flags.contains(MethodAccessFlags::STATIC) {
+			bail!("{ty}.{} is static", identifier.name);
+		}
+
+		Ok(JavaMethod {
+			runtime,
+			call_type: CallType::Virtual,
+			ty,
+			this: Some(self.raw),
+			identifier,
+			_marker: PhantomData,
+		})
+	}
+
+	pub fn get_field<V: FromJava>(&self, name: &str) -> eyre::Result<V> {
+		let field = self
+			.fields()
+			.by_name(name)
+			.wrap_err_with(|| format!("NoSuchFieldError: {}.{name}", self.class().ty))?;
+		V::from_java(field.get(), self.vm())
+	}
+
+	pub fn set_field<V: ToJava>(
+		&self,
+		runtime: &mut Runtime,
+		name: &str,
+		value: V,
+	) -> eyre::Result<()> {
+		// The value can allocate, so this object is held while it gets converted.
+		let handle = runtime.gc.new_global(*self.raw);
+		let value = value.to_java(runtime);
+		let instance = runtime
+			.gc
+			.global(handle)
+			.to_instance()?
+			.resolve(runtime.vm.clone());
+		runtime.gc.remove_global(handle);
+		set_field(&runtime.gc, instance.fields(), name, value?)
+			.wrap_err_with(|| format!("Setting {}.{name}", self.class().ty))
+	}
+}
+
+/// A method which got looked up with [`JavaClass::static_method`] or [`AnyInstance::method`].
+pub struct JavaMethod<'a, 'r, I, O> {
+	runtime: &'a mut Runtime<'r>,
+	call_type: CallType,
+	ty: ObjectType,
+	this: Option<InstanceRef>,
+	identifier: MethodIdentifier,
+	_marker: PhantomData<fn(I) -> O>,
+}
+
+impl<'a, 'r, I, O> JavaMethod<'a, 'r, I, O>
+where
+	I: ToJavaMulti + JavaTypedMulti,
+	O: FromJavaMulti + JavaTypedMulti,
+{
+	pub fn call(self, parameters: I) -> eyre::Result<O> {
+		// The object can move while the parameters convert.
+		let handle = self.this.map(|this| self.runtime.gc.new_global(*this));
+		let parameters = parameters.to_vec(self.runtime);
+		let mut values = Vec::new();
+		if let Some(handle) = handle {
+			values.push(AnyValue::Reference(self.runtime.gc.global(handle)));
+			self.runtime.gc.remove_global(handle);
+		}
+		values.extend(parameters?);
+
+		let returned = self
+			.runtime
+			.run(self.call_type, &self.ty, &self.identifier, values)?;
+		O::from_vec(returned.into_iter().collect(), &self.runtime.vm)
+	}
+}
+
+fn identifier<I: JavaTypedMulti, O: JavaTypedMulti>(name: &str) -> MethodIdentifier {
+	let descriptor = MethodDescriptor {
+		parameters: I::java_type_multi(),
+		returns: O::java_type_multi().pop(),
+	};
+	MethodIdentifier {
+		name: Arc::from(name),
+		descriptor: Arc::from(descriptor.to_string()),
+	}
+}
+
+fn no_such_method(ty: &ObjectType, identifier: &MethodIdentifier) -> String {
+	format!(
+		"NoSuchMethodError: {ty}.{}{}",
+		identifier.name, identifier.descriptor
+	)
+}
+
+/// Walks up the super classes until one declares the method.
+fn find_method(vm: &Vm, class: Id<Class>, identifier: &MethodIdentifier) -> Option<Method> {
+	let class = vm.classes.get(class);
+	let class = class.as_instance()?;
+	match class.methods.get_keyed(identifier) {
+		Some(method) => Some(method.clone()),
+		None => find_method(vm, class.super_class.as_ref()?.id, identifier),
+	}
+}
+
+/// Walks up the super classes until `func` finds the field in the static fields of one.
+fn find_static<V>(
+	vm: &Vm,
+	class: &Arc<Class>,
+	func: impl Fn(FieldTable) -> Option<V>,
+) -> Option<V> {
+	let class = class.as_instance()?;
+	match class
+		.try_companion()
+		.and_then(|_| func(class.static_fields()))
+	{
+		Some(value) => Some(value),
+		None => find_static(vm, &vm.classes.get(class.super_class.as_ref()?.id), func),
+	}
+}
+
+fn set_field(
+	gc: &GarbageCollector,
+	fields: FieldTable,
+	name: &str,
+	value: AnyValue,
+) -> eyre::Result<()> {
+	let field = fields
+		.by_name(name)
+		.wrap_err_with(|| format!("NoSuchFieldError: {name}"))?;
+	if field.kind() != value.kind() {
+		bail!("{name} is a {} field but got {value:?}", field.kind());
+	}
+
+	gc.write_barrier(|| field.get());
+	field.set(value);
+	Ok(())
+}
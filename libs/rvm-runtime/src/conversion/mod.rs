@@ -1,9 +1,12 @@
 mod multi;
+mod string;
+mod vec;
 
-use crate::{AnyValue, Reference, Vm};
+use crate::{AnyValue, Reference, Runtime, Vm};
 pub use multi::*;
 use rvm_core::{CastKindError, Kind, ObjectType, PrimitiveType, Type};
 use std::sync::Arc;
+pub(crate) use string::{alloc_string, read_string};
 
 //pub trait ToJavaType {
 //	fn java_type(&self, runtime: &Runtime) -> Type;
@@ -12,8 +15,9 @@ use std::sync::Arc;
 pub trait JavaTyped {
 	fn java_type() -> Type;
 }
+/// Converting can allocate, which is why it takes the runtime of the current thread.
 pub trait ToJava: Sized {
-	fn to_java(self, runtime: &Vm) -> eyre::Result<AnyValue>;
+	fn to_java(self, runtime: &mut Runtime) -> eyre::Result<AnyValue>;
 }
 
 pub trait FromJava: Sized {
@@ -23,7 +27,7 @@ pub trait FromJava: Sized {
 macro_rules! impl_simple {
 	($KIND:ident $($JAVA_TY:block)? $TY:ty) => {
 		impl ToJava for $TY {
-			fn to_java(self, _: &Vm) -> eyre::Result<AnyValue> {
+			fn to_java(self, _: &mut Runtime) -> eyre::Result<AnyValue> {
 				Ok(AnyValue::$KIND(self))
 			}
 		}
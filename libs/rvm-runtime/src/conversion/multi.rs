@@ -1,4 +1,5 @@
-use crate::{AnyValue, FromJava, JavaTyped, ToJava, Vm};
+use crate::gc::GlobalHandle;
+use crate::{AnyValue, FromJava, JavaTyped, Runtime, Throwable, ToJava, Vm};
 use eyre::{bail, Context};
 use rvm_core::Type;
 use std::sync::Arc;
@@ -8,7 +9,7 @@ pub trait FromJavaMulti: Sized {
 }
 
 pub trait ToJavaMulti: Sized {
-	fn to_vec(self, runtime: &Vm) -> eyre::Result<Vec<AnyValue>>;
+	fn to_vec(self, runtime: &mut Runtime) -> eyre::Result<Vec<AnyValue>>;
 }
 fn single_or_none<V>(mut vec: Vec<V>) -> Option<V> {
 	match vec.len() {
@@ -36,18 +37,82 @@ fn pop_counted<V: FromJava>(
 	Ok(value)
 }
 
+/// Holds the converted values of a tuple, references are kept as global handles because converting
+/// the next value can allocate and move them.
+struct Converted {
+	values: Vec<AnyValue>,
+	handles: Vec<(usize, GlobalHandle)>,
+}
+
+impl Converted {
+	fn new() -> Converted {
+		Converted {
+			values: Vec::new(),
+			handles: Vec::new(),
+		}
+	}
+
+	fn push(&mut self, runtime: &mut Runtime, value: eyre::Result<AnyValue>) -> eyre::Result<()> {
+		let value = match value {
+			Ok(value) => value,
+			Err(error) => {
+				for (_, handle) in self.handles.drain(..) {
+					runtime.gc.remove_global(handle);
+				}
+				return Err(error);
+			}
+		};
+
+		if let AnyValue::Reference(reference) = value {
+			if !reference.is_null() {
+				let handle = runtime.gc.new_global(reference);
+				self.handles.push((self.values.len(), handle));
+			}
+		}
+		self.values.push(value);
+		Ok(())
+	}
+
+	fn finish(mut self, runtime: &mut Runtime) -> Vec<AnyValue> {
+		for (i, handle) in self.handles.drain(..) {
+			self.values[i] = AnyValue::Reference(runtime.gc.global(handle));
+			runtime.gc.remove_global(handle);
+		}
+		self.values
+	}
+}
+
 // This is to select $V in the macro, else the loop will shit itself.
 fn count<V>() -> usize {
 	1
 }
 
 impl<V: ToJavaMulti> ToJavaMulti for eyre::Result<V> {
-	fn to_vec(self, runtime: &Vm) -> eyre::Result<Vec<AnyValue>> {
+	fn to_vec(self, runtime: &mut Runtime) -> eyre::Result<Vec<AnyValue>> {
+		let value = self?;
+		V::to_vec(value, runtime)
+	}
+}
+
+impl<V: JavaTypedMulti> JavaTypedMulti for eyre::Result<V> {
+	fn java_type_multi() -> Vec<Type> {
+		V::java_type_multi()
+	}
+}
+
+impl<V: ToJavaMulti> ToJavaMulti for Result<V, Throwable> {
+	fn to_vec(self, runtime: &mut Runtime) -> eyre::Result<Vec<AnyValue>> {
 		let value = self?;
 		V::to_vec(value, runtime)
 	}
 }
 
+impl<V: JavaTypedMulti> JavaTypedMulti for Result<V, Throwable> {
+	fn java_type_multi() -> Vec<Type> {
+		V::java_type_multi()
+	}
+}
+
 macro_rules! impl_from_java_multi {
     ($($V:ident),*) => {
 		impl<$($V: JavaTyped),*> JavaTypedMulti for ($($V),*) {
@@ -60,11 +125,14 @@ macro_rules! impl_from_java_multi {
 		}
 		impl<$($V: ToJava),*> ToJavaMulti for ($($V),*) {
 			#[allow(non_snake_case)]
-			fn to_vec(self, runtime: &Vm) -> eyre::Result<Vec<AnyValue>> {
-				let mut out = Vec::new();
+			fn to_vec(self, runtime: &mut Runtime) -> eyre::Result<Vec<AnyValue>> {
+				let mut out = Converted::new();
 				let ($($V),*) = self;
-				$(out.push($V::to_java($V, runtime)?);)*
-				Ok(out)
+				$(
+					let value = $V::to_java($V, runtime);
+					out.push(runtime, value)?;
+				)*
+				Ok(out.finish(runtime))
 			}
 		}
 		impl<$($V: FromJava),*> FromJavaMulti for ($($V),*) {
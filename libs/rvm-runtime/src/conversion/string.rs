@@ -0,0 +1,139 @@
+use crate::{AnyValue, Array, FromJava, JavaTyped, Reference, Runtime, ToJava, Vm};
+use eyre::{bail, ContextCompat};
+use rvm_core::{ObjectType, PrimitiveType, Type};
+
+const LATIN1: i8 = 0;
+const UTF16: i8 = 1;
+
+impl JavaTyped for String {
+	fn java_type() -> Type {
+		ObjectType::String().into()
+	}
+}
+
+impl JavaTyped for &str {
+	fn java_type() -> Type {
+		ObjectType::String().into()
+	}
+}
+
+impl ToJava for String {
+	fn to_java(self, runtime: &mut Runtime) -> eyre::Result<AnyValue> {
+		self.as_str().to_java(runtime)
+	}
+}
+
+impl ToJava for &str {
+	fn to_java(self, runtime: &mut Runtime) -> eyre::Result<AnyValue> {
+		let chars: Vec<u16> = self.encode_utf16().collect();
+		Ok(AnyValue::Reference(alloc_string(runtime, &chars)?))
+	}
+}
+
+impl FromJava for String {
+	fn from_java(value: AnyValue, runtime: &Vm) -> eyre::Result<Self> {
+		let reference = Reference::from_java(value, runtime)?;
+		if reference.is_null() {
+			bail!("NullPointerException: String is null");
+		}
+
+		// Java strings can contain unpaired surrogates, which rust strings can't.
+		Ok(String::from_utf16_lossy(&read_string(runtime, reference)?))
+	}
+}
+
+/// Creates a `java.lang.String` without running a constructor. The layout follows the loaded
+/// String class, which is a `char[]` up to Java 8 and a `byte[]` with a coder after.
+pub(crate) fn alloc_string(runtime: &mut Runtime, chars: &[u16]) -> eyre::Result<Reference> {
+	let id = runtime.resolve_class(&ObjectType::String().into())?;
+	let class = runtime.classes.get(id);
+	let class = class.to_instance();
+
+	let (array, coder) = if class.field_layout.get_keyed("coder").is_some() {
+		let (bytes, coder) = if chars.iter().all(|char| *char <= 0xFF) {
+			let bytes: Vec<i8> = chars.iter().map(|char| *char as u8 as i8).collect();
+			(bytes, LATIN1)
+		} else {
+			let bytes: Vec<i8> = chars
+				.iter()
+				.flat_map(|char| char.to_ne_bytes())
+				.map(|byte| byte as i8)
+				.collect();
+			(bytes, UTF16)
+		};
+
+		let array = runtime.alloc_array(&PrimitiveType::Byte.into(), bytes.len() as u32)?;
+		let mut typed = Array::<i8>::new(array);
+		for (i, byte) in bytes.into_iter().enumerate() {
+			typed.set(i as i32, byte);
+		}
+		(array, Some(coder))
+	} else {
+		let array = runtime.alloc_array(&PrimitiveType::Char.into(), chars.len() as u32)?;
+		let mut typed = Array::<u16>::new(array);
+		for (i, char) in chars.iter().enumerate() {
+			typed.set(i as i32, *char);
+		}
+		(array, None)
+	};
+
+	// The array can move while the string gets allocated.
+	let handle = runtime.gc.new_global(*array);
+	let string = runtime.alloc_object(class);
+	let array = runtime.gc.global(handle);
+	runtime.gc.remove_global(handle);
+	let string = string?;
+
+	let fields = string.fields();
+	fields
+		.by_name("value")
+		.wrap_err("String has no value")?
+		.set(AnyValue::Reference(array));
+	if let Some(coder) = coder {
+		fields
+			.by_name("coder")
+			.wrap_err("String has no coder")?
+			.set(AnyValue::Byte(coder));
+	}
+	Ok(**string)
+}
+
+/// Reads the UTF-16 characters of a `java.lang.String`, both the old `char[]` layout and the
+/// compact `byte[]` with a coder are supported.
+pub(crate) fn read_string(vm: &Vm, reference: Reference) -> eyre::Result<Vec<u16>> {
+	let instance = reference.to_instance()?.resolve(vm.clone());
+	if instance.class_id() != vm.std().c_string {
+		bail!("Object is not a string");
+	}
+
+	let fields = instance.fields();
+	let value = fields.by_name("value").wrap_err("String has no value")?;
+	let AnyValue::Reference(value) = value.get() else {
+		bail!("String value is not an array");
+	};
+	if value.is_null() {
+		return Ok(Vec::new());
+	}
+
+	let array = value.to_array()?;
+	if let Some(chars) = Array::<u16>::try_new(array) {
+		return Ok((0..chars.length()).map(|i| chars.get(i).unwrap()).collect());
+	}
+
+	let bytes = Array::<i8>::try_new(array).wrap_err("String value is not a char or byte array")?;
+	let coder = match fields.by_name("coder").map(|coder| coder.get()) {
+		Some(AnyValue::Byte(coder)) => coder,
+		_ => LATIN1,
+	};
+	let bytes: Vec<u8> = (0..bytes.length())
+		.map(|i| bytes.get(i).unwrap() as u8)
+		.collect();
+	Ok(if coder == LATIN1 {
+		bytes.into_iter().map(|byte| byte as u16).collect()
+	} else {
+		bytes
+			.chunks_exact(2)
+			.map(|pair| u16::from_ne_bytes([pair[0], pair[1]]))
+			.collect()
+	})
+}
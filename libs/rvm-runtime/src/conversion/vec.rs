@@ -0,0 +1,70 @@
+use crate::{AnyValue, ArrayRef, Class, FromJava, JavaTyped, Reference, Runtime, ToJava, Vm};
+use eyre::{bail, Context};
+use rvm_core::{ArrayType, Type};
+use std::sync::Arc;
+
+impl<V: JavaTyped> JavaTyped for Vec<V> {
+	fn java_type() -> Type {
+		ArrayType::from_component(V::java_type()).into()
+	}
+}
+
+impl<V: JavaTyped> JavaTyped for &[V] {
+	fn java_type() -> Type {
+		ArrayType::from_component(V::java_type()).into()
+	}
+}
+
+impl<V: ToJava + JavaTyped> ToJava for Vec<V> {
+	fn to_java(self, runtime: &mut Runtime) -> eyre::Result<AnyValue> {
+		let component = match V::java_type() {
+			Type::Primitive(primitive) => Arc::new(Class::from(primitive)),
+			ty => {
+				let id = runtime.resolve_class(&ty)?;
+				runtime.classes.get(id)
+			}
+		};
+		let array = runtime.alloc_array(&component, self.len() as u32)?;
+
+		// Converting an element can allocate, which can move the array.
+		let handle = runtime.gc.new_global(*array);
+		let result = self
+			.into_iter()
+			.enumerate()
+			.try_for_each(|(i, value)| -> eyre::Result<()> {
+				let value = value
+					.to_java(runtime)
+					.wrap_err_with(|| format!("Element {i} failed to convert!"))?;
+				ArrayRef::new(runtime.gc.global(handle)).set(i as i32, value);
+				Ok(())
+			});
+		let array = runtime.gc.global(handle);
+		runtime.gc.remove_global(handle);
+		result?;
+
+		Ok(AnyValue::Reference(array))
+	}
+}
+
+impl<V: ToJava + JavaTyped + Clone> ToJava for &[V] {
+	fn to_java(self, runtime: &mut Runtime) -> eyre::Result<AnyValue> {
+		self.to_vec().to_java(runtime)
+	}
+}
+
+impl<V: FromJava> FromJava for Vec<V> {
+	fn from_java(value: AnyValue, runtime: &Vm) -> eyre::Result<Self> {
+		let reference = Reference::from_java(value, runtime)?;
+		if reference.is_null() {
+			bail!("NullPointerException: Array is null");
+		}
+
+		let array = ArrayRef::from_java(value, runtime)?;
+		(0..array.length())
+			.map(|i| {
+				V::from_java(array.get(i).unwrap(), runtime)
+					.wrap_err_with(|| format!("Element {i} failed to convert!"))
+			})
+			.collect()
+	}
+}
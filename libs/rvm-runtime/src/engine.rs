@@ -11,12 +11,15 @@ use rvm_gc::GcSweeper;
 use rvm_reader::ConstantPool;
 
 use crate::value::AnyValue;
-use crate::Vm;
 use crate::{Method, MethodIdentifier};
+use crate::{ThreadContext, Vm};
 
 pub trait Engine: Send + Sync {
 	fn create_thread(&self, runtime: Vm, config: ThreadConfig) -> ThreadHandle;
 
+	/// Turns the current thread into a java thread, native threads use this to call into the vm.
+	fn attach_thread(&self, runtime: Vm, config: ThreadConfig) -> Box<dyn ThreadContext>;
+
 	fn compile_method(
 		&self,
 		runtime: &Pin<&Vm>,
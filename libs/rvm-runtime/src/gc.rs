@@ -1,4 +1,4 @@
-use crate::{ArrayRef, Class, InstanceClass, InstanceRef, Reference, ReferenceKind};
+use crate::{AnyValue, ArrayRef, Class, InstanceClass, InstanceRef, Reference, ReferenceKind};
 use rvm_core::{Id, Kind};
 pub use rvm_gc::*;
 
@@ -9,9 +9,9 @@ pub struct GarbageCollector {
 }
 
 impl GarbageCollector {
-	pub fn new(size: usize) -> Self {
+	pub fn new(size: usize, mode: GcMode) -> Self {
 		Self {
-			gc: rvm_gc::GarbageCollector::new(size),
+			gc: rvm_gc::GarbageCollector::with_mode(size, mode),
 		}
 	}
 
@@ -31,19 +31,96 @@ impl GarbageCollector {
 		self.gc.remove_frozen(*reference)
 	}
 
+	/// Keeps the object alive and where it is until it gets unpinned as often as it got pinned.
+	pub fn pin(&self, reference: Reference) {
+		self.gc.pin(*reference)
+	}
+
+	pub fn unpin(&self, reference: Reference) {
+		self.gc.unpin(*reference)
+	}
+
+	/// Keeps the object alive and tracks where it moves, until the handle gets removed.
+	pub fn new_global(&self, reference: Reference) -> GlobalHandle {
+		self.gc.new_global(*reference)
+	}
+
+	pub fn global(&self, handle: GlobalHandle) -> Reference {
+		Reference::new(self.gc.global(handle))
+	}
+
+	pub fn remove_global(&self, handle: GlobalHandle) {
+		self.gc.remove_global(handle)
+	}
+
+	pub fn new_weak(&self, reference: Reference) -> WeakHandle {
+		self.gc.new_weak(*reference)
+	}
+
+	/// The object of the weak handle, which is null once it has been collected.
+	pub fn weak(&self, handle: WeakHandle) -> Reference {
+		Reference::new(self.gc.weak(handle))
+	}
+
+	pub fn remove_weak(&self, handle: WeakHandle) {
+		self.gc.remove_weak(handle)
+	}
+
 	pub fn gc(&self) -> GCStatistics {
 		self.gc.gc()
 	}
 
+	/// Has to be called before a reference slot gets overwritten, `old` gives the value which is still in it.
+	/// This only reads the old value while a concurrent mark is running.
+	#[inline(always)]
+	pub fn write_barrier(&self, old: impl FnOnce() -> AnyValue) {
+		if self.gc.is_marking() {
+			if let AnyValue::Reference(old) = old() {
+				self.gc.write_barrier(*old);
+			}
+		}
+	}
+
 	pub fn used(&self) -> usize {
 		self.gc.used()
 	}
+
+	pub fn size(&self) -> usize {
+		self.gc.size()
+	}
+
+	pub fn subscribe(&self, subscriber: impl Fn(&GCStatistics) + Send + Sync + 'static) {
+		self.gc.subscribe(subscriber);
+	}
+
+	pub fn totals(&self) -> GcTotals {
+		self.gc.totals()
+	}
+
+	pub fn inspect<O>(&self, func: impl FnOnce(&HeapView<JavaUser>) -> O) -> O {
+		self.gc.inspect(func)
+	}
+	/// Allocates in the thread-local buffer of the sweeper if there is one, else on the shared heap.
+	fn alloc_raw(
+		&self,
+		sweeper: Option<&mut GcSweeper>,
+		data_size: usize,
+		header: JavaHeader,
+	) -> Result<GcRef, AllocationError> {
+		match sweeper {
+			Some(sweeper) => self.gc.alloc_tlab(sweeper, data_size, header),
+			None => self.gc.alloc_raw(data_size, header),
+		}
+	}
+
 	pub fn alloc_static_instance(
 		&self,
+		sweeper: Option<&mut GcSweeper>,
 		class: &InstanceClass,
 	) -> Result<InstanceRef, AllocationError> {
 		let fields = &class.static_field_layout;
-		let gc_ref = self.gc.alloc_raw(
+		let gc_ref = self.alloc_raw(
+			sweeper,
 			fields.fields_size as usize,
 			JavaHeader::InstanceStatic(InstanceHeader {
 				id: class.id,
@@ -53,9 +130,14 @@ impl GarbageCollector {
 
 		Ok(InstanceRef::new(Reference::new(gc_ref)))
 	}
-	pub fn alloc_instance(&self, class: &InstanceClass) -> Result<InstanceRef, AllocationError> {
+	pub fn alloc_instance(
+		&self,
+		sweeper: Option<&mut GcSweeper>,
+		class: &InstanceClass,
+	) -> Result<InstanceRef, AllocationError> {
 		let fields = &class.field_layout;
-		let gc_ref = self.gc.alloc_raw(
+		let gc_ref = self.alloc_raw(
+			sweeper,
 			fields.fields_size as usize,
 			JavaHeader::Instance(InstanceHeader {
 				id: class.id,
@@ -66,14 +148,20 @@ impl GarbageCollector {
 		Ok(InstanceRef::new(Reference::new(gc_ref)))
 	}
 
-	pub fn alloc_array(&self, component: &Class, length: u32) -> Result<ArrayRef, AllocationError> {
+	pub fn alloc_array(
+		&self,
+		sweeper: Option<&mut GcSweeper>,
+		component: &Class,
+		length: u32,
+	) -> Result<ArrayRef, AllocationError> {
 		let (component_id, kind) = match component {
 			Class::Instance(class) => (Some(class.id), Kind::Reference),
 			Class::Array(class) => (Some(class.id), Kind::Reference),
 			Class::Primitive(ty) => (None, ty.kind()),
 		};
 
-		let gc_ref = self.gc.alloc_raw(
+		let gc_ref = self.alloc_raw(
+			sweeper,
 			length as usize * kind.size(),
 			JavaHeader::Array(ArrayHeader {
 				component_id,
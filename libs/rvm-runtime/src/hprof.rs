@@ -0,0 +1,443 @@
+use crate::gc::{GcRef, HeapView, JavaHeader, JavaUser};
+use crate::{Class, Field, FieldLayout, InstanceClass, Runtime, Vm};
+use ahash::{HashMap, HashMapExt, HashSet, HashSetExt};
+use eyre::Context;
+use rvm_core::{Id, Kind};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::mem::take;
+use std::path::Path;
+use std::thread::spawn;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+const HEADER: &[u8] = b"JAVA PROFILE 1.0.2\0";
+const ID_SIZE: u32 = 8;
+/// Heap dump segments get written out once they reach this size.
+const SEGMENT_SIZE: usize = 1024 * 1024;
+/// We do not track allocation sites, so everything points to a single empty stack trace.
+const STACK_TRACE_SERIAL: u32 = 1;
+
+// Records
+const TAG_STRING: u8 = 0x01;
+const TAG_LOAD_CLASS: u8 = 0x02;
+const TAG_STACK_TRACE: u8 = 0x05;
+const TAG_HEAP_DUMP_SEGMENT: u8 = 0x1C;
+const TAG_HEAP_DUMP_END: u8 = 0x2C;
+
+// Heap dump sub-records
+const ROOT_JNI_GLOBAL: u8 = 0x01;
+const ROOT_JAVA_FRAME: u8 = 0x03;
+const ROOT_STICKY_CLASS: u8 = 0x05;
+const CLASS_DUMP: u8 = 0x20;
+const INSTANCE_DUMP: u8 = 0x21;
+const OBJ_ARRAY_DUMP: u8 = 0x22;
+const PRIM_ARRAY_DUMP: u8 = 0x23;
+
+impl Vm {
+	/// Writes a HPROF heap dump of the whole heap to `path`.
+	///
+	/// This stops every java thread, which means that this may not be called from a java thread,
+	/// use [`Runtime::dump_heap`] there instead.
+	pub fn dump_heap(&self, path: impl AsRef<Path>) -> eyre::Result<()> {
+		let path = path.as_ref();
+		let file = File::create(path)
+			.wrap_err_with(|| format!("Creating heap dump {}", path.display()))?;
+
+		let mut writer = HprofWriter::new(BufWriter::new(file))?;
+		self.gc
+			.inspect(|heap| writer.write_heap(self, heap))
+			.wrap_err("Writing heap dump")?;
+		writer.finish()?;
+
+		info!("Dumped heap to {}", path.display());
+		Ok(())
+	}
+}
+
+impl<'thread> Runtime<'thread> {
+	pub fn dump_heap(&mut self, path: impl AsRef<Path>) -> eyre::Result<()> {
+		let Some(thread) = &mut self.thread else {
+			return self.vm.dump_heap(path);
+		};
+
+		// This thread needs to be stopped like all the others, so the dump runs on another thread.
+		let vm = self.vm.clone();
+		let path = path.as_ref().to_path_buf();
+		let handle = spawn(move || vm.dump_heap(path));
+		thread.wait_until_gc();
+		handle.join().unwrap()
+	}
+}
+
+struct HprofWriter<W: Write> {
+	out: W,
+	strings: HashMap<String, u64>,
+	segment: Vec<u8>,
+}
+
+impl<W: Write> HprofWriter<W> {
+	fn new(mut out: W) -> eyre::Result<HprofWriter<W>> {
+		let millis = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+		out.write_all(HEADER)?;
+		out.write_all(&ID_SIZE.to_be_bytes())?;
+		out.write_all(&millis.to_be_bytes())?;
+
+		let mut writer = HprofWriter {
+			out,
+			strings: HashMap::new(),
+			segment: Vec::new(),
+		};
+
+		let mut trace = Vec::new();
+		trace.extend(STACK_TRACE_SERIAL.to_be_bytes());
+		// Thread serial and the frame count
+		trace.extend(0u32.to_be_bytes());
+		trace.extend(0u32.to_be_bytes());
+		writer.record(TAG_STACK_TRACE, &trace)?;
+		Ok(writer)
+	}
+
+	fn write_heap(&mut self, vm: &Vm, heap: &HeapView<JavaUser>) -> eyre::Result<()> {
+		let loaded = vm.classes.loaded();
+		let mut classes: HashMap<Id<Class>, &InstanceClass> = HashMap::new();
+		let mut class_ids: HashMap<Id<Class>, u64> = HashMap::new();
+		// The class mirrors and static instances are part of the class dumps, so they are not dumped as objects.
+		let mut skipped: HashSet<u64> = HashSet::new();
+		for class in &loaded {
+			match &**class {
+				Class::Instance(class) => {
+					let id = match class.try_companion() {
+						Some(companion) => {
+							skipped.insert(object_id(**companion.static_ref));
+							object_id(**companion.class)
+						}
+						None => synthetic_class_id(class.id),
+					};
+
+					classes.insert(class.id, class);
+					class_ids.insert(class.id, id);
+				}
+				Class::Array(class) => {
+					class_ids.insert(class.id, synthetic_class_id(class.id));
+				}
+				Class::Primitive(_) => {}
+			}
+		}
+		skipped.extend(class_ids.values().copied());
+
+		let object_class = vm.std.read().map(|std| class_ids[&std.c_object]);
+		let mut array_classes: HashMap<Option<Id<Class>>, u64> = HashMap::new();
+		for (serial, class) in loaded.iter().enumerate() {
+			let (id, name) = match &**class {
+				// The internal name, like `java/lang/String`.
+				Class::Instance(class) => (class.id, (*class.ty).to_string()),
+				Class::Array(array) => {
+					if array.component.kind().is_ref() {
+						array_classes.insert(array.component_id, class_ids[&array.id]);
+					}
+					(array.id, format!("[{}", array.component.to_java()))
+				}
+				Class::Primitive(_) => continue,
+			};
+
+			let class_id = class_ids[&id];
+			let name = self.string(&name)?;
+			let mut body = Vec::new();
+			body.extend((serial as u32 + 1).to_be_bytes());
+			body.extend(class_id.to_be_bytes());
+			body.extend(STACK_TRACE_SERIAL.to_be_bytes());
+			body.extend(name.to_be_bytes());
+			self.record(TAG_LOAD_CLASS, &body)?;
+
+			match &**class {
+				Class::Instance(class) => self.class_dump(class, &classes, &class_ids)?,
+				_ => self.array_class_dump(class_id, object_class.unwrap_or(0))?,
+			}
+
+			self.segment.push(ROOT_STICKY_CLASS);
+			self.segment.extend(class_id.to_be_bytes());
+			self.flush_segment(false)?;
+		}
+
+		for (thread, roots) in heap.thread_roots.iter().enumerate() {
+			for root in roots {
+				self.segment.push(ROOT_JAVA_FRAME);
+				self.segment.extend(object_id(*root).to_be_bytes());
+				self.segment.extend((thread as u32 + 1).to_be_bytes());
+				// Unknown frame
+				self.segment.extend(u32::MAX.to_be_bytes());
+			}
+		}
+		for root in heap.frozen.iter().chain(&heap.globals).chain(&heap.pinned) {
+			let id = object_id(*root);
+			self.segment.push(ROOT_JNI_GLOBAL);
+			self.segment.extend(id.to_be_bytes());
+			self.segment.extend(id.to_be_bytes());
+		}
+
+		let mut result = Ok(());
+		heap.walk(|reference| {
+			if result.is_err() || skipped.contains(&object_id(reference)) {
+				return;
+			}
+
+			match reference.header().user() {
+				JavaHeader::Instance(header) => {
+					if let Some(class) = classes.get(&header.id) {
+						self.instance_dump(reference, class, &classes, &class_ids);
+					}
+				}
+				JavaHeader::InstanceStatic(_) => {}
+				JavaHeader::Array(header) => {
+					let length = header.length as usize;
+					if header.kind.is_ref() {
+						let class = array_classes.get(&header.component_id);
+						self.object_array_dump(reference, length, class.copied().unwrap_or(0));
+					} else {
+						self.primitive_array_dump(reference, length, header.kind);
+					}
+				}
+			}
+
+			result = self.flush_segment(false);
+		});
+		result
+	}
+
+	fn class_dump(
+		&mut self,
+		class: &InstanceClass,
+		classes: &HashMap<Id<Class>, &InstanceClass>,
+		class_ids: &HashMap<Id<Class>, u64>,
+	) -> eyre::Result<()> {
+		let super_id = class
+			.super_class
+			.as_ref()
+			.and_then(|super_class| class_ids.get(&super_class.id).copied())
+			.unwrap_or(0);
+
+		let mut body = Vec::new();
+		body.extend(class_ids[&class.id].to_be_bytes());
+		body.extend(STACK_TRACE_SERIAL.to_be_bytes());
+		body.extend(super_id.to_be_bytes());
+		// Class loader, signers, protection domain and the two reserved ids
+		for _ in 0..5 {
+			body.extend(0u64.to_be_bytes());
+		}
+		body.extend(class.field_layout.fields_size.to_be_bytes());
+		// Constant pool
+		body.extend(0u16.to_be_bytes());
+
+		let statics = class
+			.try_companion()
+			.map(|companion| (ordered_fields(&class.static_field_layout, 0), companion));
+		match statics {
+			Some((fields, companion)) => {
+				body.extend((fields.len() as u16).to_be_bytes());
+				for (name, field) in fields {
+					let kind = field.ty.kind();
+					body.extend(self.string(name)?.to_be_bytes());
+					body.push(basic_type(kind));
+					unsafe {
+						let ptr = companion.static_ref.data_ptr().add(field.offset as usize);
+						write_value(&mut body, ptr, kind);
+					}
+				}
+			}
+			None => body.extend(0u16.to_be_bytes()),
+		}
+
+		let fields = own_fields(class, classes);
+		body.extend((fields.len() as u16).to_be_bytes());
+		for (name, field) in fields {
+			body.extend(self.string(name)?.to_be_bytes());
+			body.push(basic_type(field.ty.kind()));
+		}
+
+		self.segment.push(CLASS_DUMP);
+		self.segment.extend(body);
+		Ok(())
+	}
+
+	fn array_class_dump(&mut self, class_id: u64, object_class: u64) -> eyre::Result<()> {
+		self.segment.push(CLASS_DUMP);
+		self.segment.extend(class_id.to_be_bytes());
+		self.segment.extend(STACK_TRACE_SERIAL.to_be_bytes());
+		self.segment.extend(object_class.to_be_bytes());
+		for _ in 0..5 {
+			self.segment.extend(0u64.to_be_bytes());
+		}
+		// Instance size, constant pool, statics and fields
+		self.segment.extend(0u32.to_be_bytes());
+		for _ in 0..3 {
+			self.segment.extend(0u16.to_be_bytes());
+		}
+		Ok(())
+	}
+
+	fn instance_dump(
+		&mut self,
+		reference: GcRef,
+		class: &InstanceClass,
+		classes: &HashMap<Id<Class>, &InstanceClass>,
+		class_ids: &HashMap<Id<Class>, u64>,
+	) {
+		// The values go from the class itself up to the root of the hierarchy.
+		let mut values = Vec::new();
+		let mut current = Some(class);
+		while let Some(class) = current {
+			for (_, field) in own_fields(class, classes) {
+				unsafe {
+					let ptr = reference.data_ptr().add(field.offset as usize);
+					write_value(&mut values, ptr, field.ty.kind());
+				}
+			}
+
+			current = class
+				.super_class
+				.as_ref()
+				.and_then(|super_class| classes.get(&super_class.id).copied());
+		}
+
+		self.segment.push(INSTANCE_DUMP);
+		self.segment.extend(object_id(reference).to_be_bytes());
+		self.segment.extend(STACK_TRACE_SERIAL.to_be_bytes());
+		self.segment.extend(class_ids[&class.id].to_be_bytes());
+		self.segment.extend((values.len() as u32).to_be_bytes());
+		self.segment.extend(values);
+	}
+
+	fn object_array_dump(&mut self, reference: GcRef, length: usize, class_id: u64) {
+		self.segment.push(OBJ_ARRAY_DUMP);
+		self.segment.extend(object_id(reference).to_be_bytes());
+		self.segment.extend(STACK_TRACE_SERIAL.to_be_bytes());
+		self.segment.extend((length as u32).to_be_bytes());
+		self.segment.extend(class_id.to_be_bytes());
+		for i in 0..length {
+			unsafe {
+				let ptr = reference.data_ptr().add(i * Kind::Reference.size());
+				write_value(&mut self.segment, ptr, Kind::Reference);
+			}
+		}
+	}
+
+	fn primitive_array_dump(&mut self, reference: GcRef, length: usize, kind: Kind) {
+		self.segment.push(PRIM_ARRAY_DUMP);
+		self.segment.extend(object_id(reference).to_be_bytes());
+		self.segment.extend(STACK_TRACE_SERIAL.to_be_bytes());
+		self.segment.extend((length as u32).to_be_bytes());
+		self.segment.push(basic_type(kind));
+		for i in 0..length {
+			unsafe {
+				let ptr = reference.data_ptr().add(i * kind.size());
+				write_value(&mut self.segment, ptr, kind);
+			}
+		}
+	}
+
+	fn string(&mut self, value: &str) -> eyre::Result<u64> {
+		if let Some(id) = self.strings.get(value) {
+			return Ok(*id);
+		}
+
+		let id = self.strings.len() as u64 + 1;
+		let mut body = Vec::new();
+		body.extend(id.to_be_bytes());
+		body.extend(value.as_bytes());
+		self.record(TAG_STRING, &body)?;
+		self.strings.insert(value.to_string(), id);
+		Ok(id)
+	}
+
+	fn record(&mut self, tag: u8, body: &[u8]) -> eyre::Result<()> {
+		self.out.write_all(&[tag])?;
+		// Microseconds since the header timestamp
+		self.out.write_all(&0u32.to_be_bytes())?;
+		self.out.write_all(&(body.len() as u32).to_be_bytes())?;
+		self.out.write_all(body)?;
+		Ok(())
+	}
+
+	fn flush_segment(&mut self, force: bool) -> eyre::Result<()> {
+		if self.segment.is_empty() || (!force && self.segment.len() < SEGMENT_SIZE) {
+			return Ok(());
+		}
+
+		let segment = take(&mut self.segment);
+		self.record(TAG_HEAP_DUMP_SEGMENT, &segment)
+	}
+
+	fn finish(mut self) -> eyre::Result<()> {
+		self.flush_segment(true)?;
+		self.record(TAG_HEAP_DUMP_END, &[])?;
+		self.out.flush()?;
+		Ok(())
+	}
+}
+
+fn object_id(reference: GcRef) -> u64 {
+	if reference.is_null() {
+		return 0;
+	}
+	reference.data_ptr() as u64
+}
+
+/// Classes without a mirror object get an odd id, which never collides with the aligned object ids.
+fn synthetic_class_id(id: Id<Class>) -> u64 {
+	((id.idx() as u64) << 1) | 1
+}
+
+/// The fields of the layout in declaration order, skipping the first `skip` fields.
+fn ordered_fields(layout: &FieldLayout, skip: usize) -> Vec<(&String, &Field)> {
+	let mut fields: Vec<_> = layout
+		.iter_keys_unordered()
+		.filter(|(id, _, _)| id.idx() as usize > skip)
+		.collect();
+	fields.sort_by_key(|(id, _, _)| *id);
+	fields
+		.into_iter()
+		.map(|(_, name, field)| (name, field))
+		.collect()
+}
+
+/// The instance fields which this class declares itself, the layout also contains the super class fields.
+fn own_fields<'a>(
+	class: &'a InstanceClass,
+	classes: &HashMap<Id<Class>, &InstanceClass>,
+) -> Vec<(&'a String, &'a Field)> {
+	let super_fields = class
+		.super_class
+		.as_ref()
+		.and_then(|super_class| classes.get(&super_class.id))
+		.map(|super_class| super_class.field_layout.len())
+		.unwrap_or(0);
+	ordered_fields(&class.field_layout, super_fields)
+}
+
+fn basic_type(kind: Kind) -> u8 {
+	match kind {
+		Kind::Reference => 2,
+		Kind::Boolean => 4,
+		Kind::Char => 5,
+		Kind::Float => 6,
+		Kind::Double => 7,
+		Kind::Byte => 8,
+		Kind::Short => 9,
+		Kind::Int => 10,
+		Kind::Long => 11,
+	}
+}
+
+/// Writes the value at the pointer in big endian, references get written as their object id.
+unsafe fn write_value(out: &mut Vec<u8>, ptr: *const u8, kind: Kind) {
+	match kind {
+		Kind::Reference => {
+			out.extend((ptr.cast::<usize>().read_unaligned() as u64).to_be_bytes());
+		}
+		Kind::Boolean | Kind::Byte => out.push(ptr.read()),
+		Kind::Char | Kind::Short => out.extend(ptr.cast::<u16>().read_unaligned().to_be_bytes()),
+		Kind::Int | Kind::Float => out.extend(ptr.cast::<u32>().read_unaligned().to_be_bytes()),
+		Kind::Long | Kind::Double => out.extend(ptr.cast::<u64>().read_unaligned().to_be_bytes()),
+	}
+}
@@ -8,29 +8,34 @@
 
 use crate::engine::{Engine, ThreadConfig, ThreadHandle};
 use crate::gc::GarbageCollector;
-use crate::native::JNILinker;
+use crate::native::{JNILinker, JavaVM, NativeFrames};
 use ahash::HashMap;
 pub use binding::*;
+pub use call::*;
 pub use conversion::*;
 use eyre::Context;
 pub use object::*;
 use parking_lot::{Mutex, RwLock};
 use rvm_core::{Id, ObjectType, Type};
-use rvm_gc::{AllocationError, GcSweeper};
+use rvm_gc::{AllocationError, GCStatistics, GcMode, GcSweeper};
 use std::cell::Cell;
 use std::ops::{Deref, DerefMut};
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
 use std::sync::{Arc, Weak};
-use std::thread::{spawn, Builder, Thread};
+use std::thread::{spawn, yield_now, Builder, Thread};
 use std::time::Instant;
 use tracing::debug;
 pub use value::*;
 
 mod binding;
+mod call;
 mod class_loader;
 mod conversion;
 pub mod engine;
 pub mod error;
 pub mod gc;
+mod hprof;
+mod management;
 pub mod native;
 mod object;
 pub mod prelude;
@@ -60,6 +65,26 @@ pub trait ThreadContext: 'static {
 	fn yield_gc(&mut self);
 	fn wait_until_gc(&mut self);
 
+	/// The sweeper of this thread, allocations use its thread-local allocation buffer.
+	fn gc_sweeper(&mut self) -> &mut GcSweeper;
+
+	/// The local references of the native methods running on this thread.
+	fn native_frames(&mut self) -> &mut NativeFrames;
+
+	/// Lets collections go on without the thread while it runs native code, they go through its
+	/// roots until it comes back with [ThreadContext::leave_native].
+	///
+	/// # Safety
+	/// The thread context must not move or get used until the thread comes back.
+	unsafe fn enter_native(&mut self);
+
+	/// Waits for the collection which goes through the roots, returns false if the thread was not
+	/// in native code.
+	fn leave_native(&mut self) -> bool;
+
+	/// Stops a thread which was attached with [Vm::attach_thread].
+	fn detach(self: Box<Self>);
+
 	fn run(
 		&mut self,
 		call_type: CallType,
@@ -75,34 +100,46 @@ pub struct Runtime<'thread> {
 }
 
 impl<'thread> Runtime<'thread> {
-	pub fn gc(&mut self) {
+	pub fn gc(&mut self) -> GCStatistics {
 		if let Some(thread) = &mut self.thread {
 			let runtime = self.vm.clone();
 			// TODO gc-thread
-			let handle = spawn(move || {
-				runtime.gc.gc();
-			});
+			let handle = spawn(move || runtime.gc.gc());
 			thread.wait_until_gc();
-			handle.join().unwrap();
+			// A concurrent collection stops this thread again once marking is done.
+			while !handle.is_finished() {
+				thread.yield_gc();
+				yield_now();
+			}
+			handle.join().unwrap()
 		} else {
 			// TODO gc-thread?
 			// This is not a managed context, so we make this be the gc-thread
-			self.vm.gc.gc();
+			self.vm.gc.gc()
 		}
 	}
 
 	fn try_gc_op<O>(
 		&mut self,
-		mut func: impl FnMut(&mut Vm) -> Result<O, AllocationError>,
+		mut func: impl FnMut(&Vm, Option<&mut GcSweeper>) -> Result<O, AllocationError>,
 	) -> Result<O, AllocationError> {
-		for _ in 0..5 {
-			match func(&mut self.vm) {
+		let mut attempts = 0;
+		while attempts < 5 {
+			let sweeper = self.thread.as_mut().map(|thread| thread.gc_sweeper());
+			match func(&self.vm, sweeper) {
 				Ok(value) => {
 					return Ok(value);
 				}
 				Err(AllocationError::OutOfHeap) => {
+					attempts += 1;
 					self.gc();
 				}
+				Err(AllocationError::Collecting) => {
+					// Only threads with a sweeper can get this, the collector is waiting for us.
+					if let Some(thread) = &mut self.thread {
+						thread.yield_gc();
+					}
+				}
 				err => {
 					err?;
 				}
@@ -126,16 +163,19 @@ impl<'thread> Runtime<'thread> {
 			*vm.std.write() = Some(std);
 		}
 
-		let id = resolver
-			.resolve(ty)
-			.wrap_err_with(|| format!("Resolving class {ty:?}"))?;
-
+		// The classes which got loaded before a failure, like the super classes, still get linked.
+		let id = resolver.resolve(ty);
 		resolver.link_all(self).wrap_err("Linking")?;
-		Ok(id)
+		id.wrap_err_with(|| format!("Resolving class {ty:?}"))
 	}
 
 	pub fn alloc_object(&mut self, class: &InstanceClass) -> Result<AnyInstance, AllocationError> {
-		self.try_gc_op(|runtime| Ok(runtime.gc.alloc_instance(class)?.resolve(runtime.clone())))
+		self.try_gc_op(|runtime, sweeper| {
+			Ok(runtime
+				.gc
+				.alloc_instance(sweeper, class)?
+				.resolve(runtime.clone()))
+		})
 	}
 
 	pub fn alloc_array(
@@ -143,14 +183,14 @@ impl<'thread> Runtime<'thread> {
 		component: &Class,
 		length: u32,
 	) -> Result<ArrayRef, AllocationError> {
-		self.try_gc_op(|runtime| runtime.gc.alloc_array(component, length))
+		self.try_gc_op(|runtime, sweeper| runtime.gc.alloc_array(sweeper, component, length))
 	}
 
 	pub fn alloc_static_instance(
 		&mut self,
 		class: &InstanceClass,
 	) -> Result<InstanceRef, AllocationError> {
-		self.try_gc_op(|runtime| runtime.gc.alloc_static_instance(class))
+		self.try_gc_op(|runtime, sweeper| runtime.gc.alloc_static_instance(sweeper, class))
 	}
 
 	pub fn run(
@@ -163,12 +203,17 @@ impl<'thread> Runtime<'thread> {
 		if let Some(thread) = &mut self.thread {
 			thread.run(call_type, ty, method, parameters)
 		} else {
-			// TODO look into this
-			let thread = self.vm.create_thread(ThreadConfig {
+			// Rust code calls from outside of the vm, the call runs on a thread attached for it.
+			let mut thread = self.vm.attach_thread(ThreadConfig {
 				name: "run".to_string(),
 			});
-			thread.run(ty.clone(), method.clone(), parameters);
-			thread.join()
+			let returned = catch_unwind(AssertUnwindSafe(|| {
+				thread.run(call_type, ty, method, parameters)
+			}));
+			// The thread has to be detached even when the engine panics, else every collection
+			// would wait on it.
+			thread.detach();
+			returned.unwrap_or_else(|panic| resume_unwind(panic))
 		}
 	}
 }
@@ -181,6 +226,19 @@ impl<'a> Deref for Runtime<'a> {
 	}
 }
 
+pub struct VmConfig {
+	pub heap_size: usize,
+	/// Concurrent marking needs a write barrier from the engine on every reference store.
+	pub gc_mode: GcMode,
+	/// The system properties, `-D` options of the invocation API end up here.
+	pub properties: HashMap<String, String>,
+	/// Verifies the bytecode of classes when they get loaded, `-Xverify:none` turns it off.
+	pub verify: bool,
+	/// Also verifies the classes of the boot class path, which like in HotSpot only
+	/// `-Xverify:all` does.
+	pub verify_boot: bool,
+}
+
 /// A runtime which (almost never) conforms to [The Java Virtual Machine Specification, Java SE 19 Edition][jvms]
 ///
 /// The runtime includes a bootstrap class source, a classloader
@@ -193,13 +251,30 @@ pub struct Vm {
 
 impl Vm {
 	pub fn new(heap_size: usize, engine: Box<dyn Engine>) -> Vm {
+		Self::with_config(
+			VmConfig {
+				heap_size,
+				gc_mode: GcMode::default(),
+				properties: HashMap::default(),
+				verify: true,
+				verify_boot: false,
+			},
+			engine,
+		)
+	}
+
+	pub fn with_config(config: VmConfig, engine: Box<dyn Engine>) -> Vm {
+		let bindings = RustBinder::new();
+		management::bind_management(&bindings);
 		Vm {
-			inner: Arc::new(InnerVm {
-				classes: ClassLoader::new(),
+			inner: Arc::new_cyclic(|inner| InnerVm {
+				classes: ClassLoader::new(config.verify, config.verify_boot),
 				engine,
-				gc: GarbageCollector::new(heap_size),
-				bindings: RustBinder::new(),
+				gc: GarbageCollector::new(config.heap_size, config.gc_mode),
+				bindings,
 				linker: Mutex::new(JNILinker::new()),
+				java_vm: JavaVM::new(inner.clone()),
+				properties: RwLock::new(config.properties),
 				started: Instant::now(),
 				std: RwLock::new(None),
 			}),
@@ -210,6 +285,11 @@ impl Vm {
 		self.inner.engine.create_thread(self.clone(), config)
 	}
 
+	/// Makes the current thread a java thread, it has to be detached with [ThreadContext::detach].
+	pub fn attach_thread(&self, config: ThreadConfig) -> Box<dyn ThreadContext> {
+		self.inner.engine.attach_thread(self.clone(), config)
+	}
+
 	pub fn is_instance_of(&self, instance: InstanceRef, id: Id<Class>) -> bool {
 		let mut this_id = instance.header().id;
 
@@ -311,6 +391,8 @@ pub struct InnerVm {
 	pub gc: GarbageCollector,
 	pub bindings: RustBinder,
 	pub linker: Mutex<JNILinker>,
+	pub java_vm: JavaVM,
+	pub properties: RwLock<HashMap<String, String>>,
 	pub started: Instant,
 	pub std: RwLock<Option<StdClasses>>,
 }
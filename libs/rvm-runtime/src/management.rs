@@ -0,0 +1,32 @@
+use crate::{MethodBinding, RustBinder};
+
+/// Binds the natives which `java.lang.Runtime` and `java.lang.management` use to report on the heap.
+pub(crate) fn bind_management(bindings: &RustBinder) {
+	bindings.bind(
+		"java/lang/Runtime",
+		"totalMemory",
+		MethodBinding::new(|runtime, _, ()| runtime.gc.size() as i64),
+	);
+	bindings.bind(
+		"java/lang/Runtime",
+		"maxMemory",
+		MethodBinding::new(|runtime, _, ()| runtime.gc.size() as i64),
+	);
+	bindings.bind(
+		"java/lang/Runtime",
+		"freeMemory",
+		MethodBinding::new(|runtime, _, ()| (runtime.gc.size() - runtime.gc.used()) as i64),
+	);
+
+	// The backing natives of GarbageCollectorMXBean, we only have a single collector.
+	bindings.bind(
+		"sun/management/GarbageCollectorImpl",
+		"getCollectionCount",
+		MethodBinding::new(|runtime, _, ()| runtime.gc.totals().collections as i64),
+	);
+	bindings.bind(
+		"sun/management/GarbageCollectorImpl",
+		"getCollectionTime",
+		MethodBinding::new(|runtime, _, ()| runtime.gc.totals().pause_time.as_millis() as i64),
+	);
+}
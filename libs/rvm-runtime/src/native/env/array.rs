@@ -0,0 +1,221 @@
+use crate::native::env::{JNIEnv, NativePrimitive, RawEnv};
+use crate::{AnyValue, Array, ArrayRef, Reference};
+use eyre::{bail, eyre};
+use jni_sys::*;
+use std::ffi::c_void;
+use std::ptr::{null_mut, slice_from_raw_parts_mut};
+
+impl JNIEnv {
+	pub(super) fn get_array(&mut self, handle: jarray) -> eyre::Result<ArrayRef> {
+		let reference = self.get(handle);
+		if reference.is_null() {
+			bail!("Null pointer exception");
+		}
+		Ok(reference.to_array()?)
+	}
+
+	pub(super) fn get_typed_array<V: NativePrimitive>(
+		&mut self,
+		handle: jarray,
+	) -> eyre::Result<Array<V>> {
+		let array = self.get_array(handle)?;
+		Array::try_new(array).ok_or_else(|| {
+			eyre!(
+				"Array of {} is not an array of {}",
+				array.component_kind(),
+				V::KIND
+			)
+		})
+	}
+}
+
+/// Checks that `start..start + len` lies inside of an array of `length`.
+fn check_region(length: i32, start: jsize, len: jsize) -> eyre::Result<()> {
+	if start < 0 || len < 0 || start as i64 + len as i64 > length as i64 {
+		bail!(
+			"ArrayIndexOutOfBoundsException: {start}..{} of {length}",
+			start as i64 + len as i64
+		);
+	}
+	Ok(())
+}
+
+unsafe fn set_is_copy(is_copy: *mut jboolean, value: jboolean) {
+	if !is_copy.is_null() {
+		*is_copy = value;
+	}
+}
+
+pub(super) unsafe extern "system" fn get_array_length(env: *mut RawEnv, array: jarray) -> jsize {
+	JNIEnv::with(env, 0, |env| Ok(env.get_array(array)?.length()))
+}
+
+pub(super) unsafe extern "system" fn new_object_array(
+	env: *mut RawEnv,
+	len: jsize,
+	clazz: jclass,
+	init: jobject,
+) -> jobjectArray {
+	JNIEnv::with(env, null_mut(), |env| {
+		if len < 0 {
+			bail!("NegativeArraySizeException: {len}");
+		}
+
+		let class = env.get_class(clazz)?;
+		let array = env.runtime().alloc_array(&class, len as u32)?;
+		// The allocation might have moved the initial element
+		let init = env.get(init);
+		let mut typed = Array::<Reference>::new(array);
+		for i in 0..len {
+			typed.set(i, init);
+		}
+		Ok(env.new_local(*array))
+	})
+}
+
+pub(super) unsafe extern "system" fn get_object_array_element(
+	env: *mut RawEnv,
+	array: jobjectArray,
+	index: jsize,
+) -> jobject {
+	JNIEnv::with(env, null_mut(), |env| {
+		let array = Array::<Reference>::try_new(env.get_array(array)?)
+			.ok_or_else(|| eyre!("Not an object array"))?;
+		let value = array
+			.get(index)
+			.ok_or_else(|| eyre!("ArrayIndexOutOfBoundsException: {index}"))?;
+		Ok(env.new_local(value))
+	})
+}
+
+pub(super) unsafe extern "system" fn set_object_array_element(
+	env: *mut RawEnv,
+	array: jobjectArray,
+	index: jsize,
+	value: jobject,
+) {
+	JNIEnv::with(env, (), |env| {
+		let mut array = Array::<Reference>::try_new(env.get_array(array)?)
+			.ok_or_else(|| eyre!("Not an object array"))?;
+		check_region(array.length(), index, 1)?;
+
+		let value = env.get(value);
+		env.vm
+			.gc
+			.write_barrier(|| AnyValue::Reference(array.get(index).unwrap()));
+		array.set(index, value);
+		Ok(())
+	})
+}
+
+pub(super) unsafe extern "system" fn new_array<V: NativePrimitive>(
+	env: *mut RawEnv,
+	len: jsize,
+) -> jarray {
+	JNIEnv::with(env, null_mut(), |env| {
+		if len < 0 {
+			bail!("NegativeArraySizeException: {len}");
+		}
+
+		let array = env.runtime().alloc_array(&V::TYPE.into(), len as u32)?;
+		Ok(env.new_local(*array))
+	})
+}
+
+/// The elements are always copied, the heap compacts so the array could move while native code
+/// holds the pointer.
+pub(super) unsafe extern "system" fn get_array_elements<V: NativePrimitive>(
+	env: *mut RawEnv,
+	array: jarray,
+	is_copy: *mut jboolean,
+) -> *mut V {
+	JNIEnv::with(env, null_mut(), |env| {
+		let array = env.get_typed_array::<V>(array)?;
+		let elements: Box<[V]> = (0..array.length()).map(|i| array.get(i).unwrap()).collect();
+		set_is_copy(is_copy, JNI_TRUE);
+		Ok(Box::into_raw(elements) as *mut V)
+	})
+}
+
+pub(super) unsafe extern "system" fn release_array_elements<V: NativePrimitive>(
+	env: *mut RawEnv,
+	array: jarray,
+	elems: *mut V,
+	mode: jint,
+) {
+	JNIEnv::with(env, (), |env| {
+		let mut array = env.get_typed_array::<V>(array)?;
+		let length = array.length();
+		if mode != JNI_ABORT {
+			for i in 0..length {
+				array.set(i, *elems.add(i as usize));
+			}
+		}
+		if mode != JNI_COMMIT {
+			let elements = slice_from_raw_parts_mut(elems, length as usize);
+			drop(Box::from_raw(elements));
+		}
+		Ok(())
+	})
+}
+
+pub(super) unsafe extern "system" fn get_array_region<V: NativePrimitive>(
+	env: *mut RawEnv,
+	array: jarray,
+	start: jsize,
+	len: jsize,
+	buf: *mut V,
+) {
+	JNIEnv::with(env, (), |env| {
+		let array = env.get_typed_array::<V>(array)?;
+		check_region(array.length(), start, len)?;
+		for i in 0..len {
+			*buf.add(i as usize) = array.get(start + i).unwrap();
+		}
+		Ok(())
+	})
+}
+
+pub(super) unsafe extern "system" fn set_array_region<V: NativePrimitive>(
+	env: *mut RawEnv,
+	array: jarray,
+	start: jsize,
+	len: jsize,
+	buf: *const V,
+) {
+	JNIEnv::with(env, (), |env| {
+		let mut array = env.get_typed_array::<V>(array)?;
+		check_region(array.length(), start, len)?;
+		for i in 0..len {
+			array.set(start + i, *buf.add(i as usize));
+		}
+		Ok(())
+	})
+}
+
+/// The array gets pinned instead of copied, so it does not move while native code holds it.
+pub(super) unsafe extern "system" fn get_primitive_array_critical(
+	env: *mut RawEnv,
+	array: jarray,
+	is_copy: *mut jboolean,
+) -> *mut c_void {
+	JNIEnv::with(env, null_mut(), |env| {
+		let array = env.get_array(array)?;
+		env.vm.gc.pin(*array);
+		set_is_copy(is_copy, JNI_FALSE);
+		Ok(array.data_ptr() as *mut c_void)
+	})
+}
+
+pub(super) unsafe extern "system" fn release_primitive_array_critical(
+	env: *mut RawEnv,
+	array: jarray,
+	_: *mut c_void,
+	_: jint,
+) {
+	JNIEnv::with(env, (), |env| {
+		let array = env.get_array(array)?;
+		env.vm.gc.unpin(*array);
+		Ok(())
+	})
+}
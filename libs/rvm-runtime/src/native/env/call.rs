@@ -0,0 +1,244 @@
+use crate::native::env::class::get_method;
+use crate::native::env::{JNIEnv, NativeReturn, RawEnv};
+use crate::{AnyValue, CallType, MethodIdentifier};
+use eyre::bail;
+use jni_sys::*;
+use rvm_core::{Kind, MethodAccessFlags, Type};
+use std::ffi::{c_void, VaList};
+use std::ptr::null_mut;
+
+/// Where the arguments of a `Call*Method` function come from.
+enum Arguments<'a, 'f> {
+	Variadic(&'a mut VaList<'f>),
+	Array(*const jvalue),
+}
+
+impl Arguments<'_, '_> {
+	/// Varargs follow the C promotion rules, everything smaller than an int is passed as one
+	/// and floats are passed as doubles.
+	unsafe fn read(self, env: &mut JNIEnv, parameters: &[Type]) -> Vec<AnyValue> {
+		let mut values = Vec::with_capacity(parameters.len());
+		match self {
+			Arguments::Variadic(args) => {
+				for parameter in parameters {
+					values.push(match parameter.kind() {
+						Kind::Boolean => AnyValue::Boolean(args.next_arg::<jint>() != 0),
+						Kind::Byte => AnyValue::Byte(args.next_arg::<jint>() as jbyte),
+						Kind::Char => AnyValue::Char(args.next_arg::<jint>() as jchar),
+						Kind::Short => AnyValue::Short(args.next_arg::<jint>() as jshort),
+						Kind::Int => AnyValue::Int(args.next_arg::<jint>()),
+						Kind::Long => AnyValue::Long(args.next_arg::<jlong>()),
+						Kind::Float => AnyValue::Float(args.next_arg::<jdouble>() as jfloat),
+						Kind::Double => AnyValue::Double(args.next_arg::<jdouble>()),
+						Kind::Reference => {
+							AnyValue::Reference(env.get(args.next_arg::<*mut c_void>() as jobject))
+						}
+					});
+				}
+			}
+			Arguments::Array(args) => {
+				for (i, parameter) in parameters.iter().enumerate() {
+					let value = *args.add(i);
+					values.push(match parameter.kind() {
+						Kind::Boolean => AnyValue::Boolean(value.z),
+						Kind::Byte => AnyValue::Byte(value.b),
+						Kind::Char => AnyValue::Char(value.c),
+						Kind::Short => AnyValue::Short(value.s),
+						Kind::Int => AnyValue::Int(value.i),
+						Kind::Long => AnyValue::Long(value.j),
+						Kind::Float => AnyValue::Float(value.f),
+						Kind::Double => AnyValue::Double(value.d),
+						Kind::Reference => AnyValue::Reference(env.get(value.l)),
+					});
+				}
+			}
+		}
+		values
+	}
+}
+
+impl JNIEnv {
+	/// Calls the method, `this` is `None` for static methods.
+	unsafe fn call_method<R: NativeReturn>(
+		&mut self,
+		call_type: CallType,
+		this: Option<jobject>,
+		method: jmethodID,
+		arguments: Arguments,
+	) -> eyre::Result<R> {
+		let (class_id, method_id) = get_method(method)?;
+		let class = self.vm.classes.get(class_id);
+		let class = class.to_instance();
+		let method = class.methods.get(method_id);
+		if method.flags.contains(MethodAccessFlags::STATIC) != this.is_none() {
+			bail!("Static method called as an instance method or the other way around");
+		}
+
+		let mut parameters = Vec::with_capacity(method.desc.parameters.len() + 1);
+		if let Some(this) = this {
+			let this = self.get(this);
+			if this.is_null() {
+				bail!("Null pointer exception");
+			}
+			parameters.push(AnyValue::Reference(this));
+		}
+		parameters.extend(arguments.read(self, &method.desc.parameters));
+
+		let identifier = MethodIdentifier {
+			name: method.name.clone().into(),
+			descriptor: method.desc.to_string().into(),
+		};
+		let returned = self
+			.runtime()
+			.run(call_type, &class.ty, &identifier, parameters)?;
+		R::from_return(self, returned)
+	}
+
+	unsafe fn new_object(
+		&mut self,
+		clazz: jclass,
+		method: jmethodID,
+		arguments: Arguments,
+	) -> eyre::Result<jobject> {
+		let class = self.get_instance_class(clazz)?;
+		let (_, method_id) = get_method(method)?;
+		if class.methods.get(method_id).name != "<init>" {
+			bail!("NewObject needs the method id of a constructor");
+		}
+
+		let instance = self.runtime().alloc_object(&class)?;
+		let instance = self.new_local(*instance.raw());
+		self.call_method::<()>(CallType::Special, Some(instance), method, arguments)?;
+		Ok(instance)
+	}
+}
+
+pub(super) unsafe extern "C" fn call_method<R: NativeReturn>(
+	env: *mut RawEnv,
+	obj: jobject,
+	method: jmethodID,
+	args: ...
+) -> R {
+	call_method_v(env, obj, method, args)
+}
+
+pub(super) unsafe extern "system" fn call_method_v<R: NativeReturn>(
+	env: *mut RawEnv,
+	obj: jobject,
+	method: jmethodID,
+	mut args: VaList,
+) -> R {
+	JNIEnv::with(env, R::ZERO, |env| {
+		let arguments = Arguments::Variadic(&mut args);
+		env.call_method(CallType::Virtual, Some(obj), method, arguments)
+	})
+}
+
+pub(super) unsafe extern "system" fn call_method_a<R: NativeReturn>(
+	env: *mut RawEnv,
+	obj: jobject,
+	method: jmethodID,
+	args: *const jvalue,
+) -> R {
+	JNIEnv::with(env, R::ZERO, |env| {
+		env.call_method(CallType::Virtual, Some(obj), method, Arguments::Array(args))
+	})
+}
+
+pub(super) unsafe extern "C" fn call_nonvirtual_method<R: NativeReturn>(
+	env: *mut RawEnv,
+	obj: jobject,
+	clazz: jclass,
+	method: jmethodID,
+	args: ...
+) -> R {
+	call_nonvirtual_method_v(env, obj, clazz, method, args)
+}
+
+pub(super) unsafe extern "system" fn call_nonvirtual_method_v<R: NativeReturn>(
+	env: *mut RawEnv,
+	obj: jobject,
+	_: jclass,
+	method: jmethodID,
+	mut args: VaList,
+) -> R {
+	JNIEnv::with(env, R::ZERO, |env| {
+		let arguments = Arguments::Variadic(&mut args);
+		env.call_method(CallType::Special, Some(obj), method, arguments)
+	})
+}
+
+pub(super) unsafe extern "system" fn call_nonvirtual_method_a<R: NativeReturn>(
+	env: *mut RawEnv,
+	obj: jobject,
+	_: jclass,
+	method: jmethodID,
+	args: *const jvalue,
+) -> R {
+	JNIEnv::with(env, R::ZERO, |env| {
+		env.call_method(CallType::Special, Some(obj), method, Arguments::Array(args))
+	})
+}
+
+pub(super) unsafe extern "C" fn call_static_method<R: NativeReturn>(
+	env: *mut RawEnv,
+	clazz: jclass,
+	method: jmethodID,
+	args: ...
+) -> R {
+	call_static_method_v(env, clazz, method, args)
+}
+
+pub(super) unsafe extern "system" fn call_static_method_v<R: NativeReturn>(
+	env: *mut RawEnv,
+	_: jclass,
+	method: jmethodID,
+	mut args: VaList,
+) -> R {
+	JNIEnv::with(env, R::ZERO, |env| {
+		let arguments = Arguments::Variadic(&mut args);
+		env.call_method(CallType::Static, None, method, arguments)
+	})
+}
+
+pub(super) unsafe extern "system" fn call_static_method_a<R: NativeReturn>(
+	env: *mut RawEnv,
+	_: jclass,
+	method: jmethodID,
+	args: *const jvalue,
+) -> R {
+	JNIEnv::with(env, R::ZERO, |env| {
+		env.call_method(CallType::Static, None, method, Arguments::Array(args))
+	})
+}
+
+pub(super) unsafe extern "C" fn new_object(
+	env: *mut RawEnv,
+	clazz: jclass,
+	method: jmethodID,
+	args: ...
+) -> jobject {
+	new_object_v(env, clazz, method, args)
+}
+
+pub(super) unsafe extern "system" fn new_object_v(
+	env: *mut RawEnv,
+	clazz: jclass,
+	method: jmethodID,
+	mut args: VaList,
+) -> jobject {
+	JNIEnv::with(env, null_mut(), |env| {
+		env.new_object(clazz, method, Arguments::Variadic(&mut args))
+	})
+}
+
+pub(super) unsafe extern "system" fn new_object_a(
+	env: *mut RawEnv,
+	clazz: jclass,
+	method: jmethodID,
+	args: *const jvalue,
+) -> jobject {
+	JNIEnv::with(env, null_mut(), |env| {
+		env.new_object(clazz, method, Arguments::Array(args))
+	})
+}
@@ -0,0 +1,422 @@
+use crate::native::env::string::decode_modified_utf8;
+use crate::native::env::{JNIEnv, RawEnv};
+use crate::native::NativeName;
+use crate::{Class, Field, InstanceClass, Method, MethodIdentifier, Vm};
+use eyre::{bail, eyre, ContextCompat};
+use jni_sys::*;
+use rvm_core::{Id, MethodAccessFlags, ObjectType, StorageValue, Type};
+use std::ffi::{c_char, c_void, CStr};
+use std::ptr::null_mut;
+use std::sync::Arc;
+
+/// Method and field ids point at nothing, they pack the id of the class together with the id of the
+/// member inside of it. The top bit keeps them from ever being null.
+fn pack_id<V: StorageValue<Idx = u16>>(class: Id<Class>, member: Id<V>) -> usize {
+	1 << 48 | (class.idx() as usize) << 16 | member.idx() as usize
+}
+
+fn unpack_id<V: StorageValue<Idx = u16>>(id: usize) -> eyre::Result<(Id<Class>, Id<V>)> {
+	if id >> 48 != 1 {
+		bail!("Invalid member id {id:#x}");
+	}
+	unsafe {
+		Ok((
+			Id::new((id >> 16) & u32::MAX as usize),
+			Id::new(id & u16::MAX as usize),
+		))
+	}
+}
+
+pub(super) fn method_id(class: Id<Class>, method: Id<Method>) -> jmethodID {
+	pack_id(class, method) as jmethodID
+}
+
+pub(super) fn field_id(class: Id<Class>, field: Id<Field>) -> jfieldID {
+	pack_id(class, field) as jfieldID
+}
+
+pub(super) fn get_method(id: jmethodID) -> eyre::Result<(Id<Class>, Id<Method>)> {
+	unpack_id(id as usize)
+}
+
+pub(super) fn get_field(id: jfieldID) -> eyre::Result<(Id<Class>, Id<Field>)> {
+	unpack_id(id as usize)
+}
+
+/// Reads a string in the modified UTF-8 of the JVM, where supplementary characters are surrogate pairs.
+pub(super) unsafe fn c_str(string: *const c_char) -> eyre::Result<String> {
+	if string.is_null() {
+		bail!("Null string");
+	}
+	let chars = decode_modified_utf8(CStr::from_ptr(string).to_bytes())?;
+	Ok(String::from_utf16(&chars)?)
+}
+
+impl JNIEnv {
+	/// Finds the class which the `java.lang.Class` object belongs to.
+	pub(super) fn get_class(&mut self, handle: jclass) -> eyre::Result<Arc<Class>> {
+		let reference = self.get(handle);
+		if reference.is_null() {
+			bail!("Null class");
+		}
+
+		let id = self
+			.vm
+			.classes
+			.get_object(reference)
+			.wrap_err("Object is not a class")?;
+		Ok(self.vm.classes.get(id))
+	}
+
+	pub(super) fn get_instance_class(&mut self, handle: jclass) -> eyre::Result<InstanceClass> {
+		let class = self.get_class(handle)?;
+		Ok(class.to_instance().clone())
+	}
+
+	pub(super) fn class_object(&mut self, id: Id<Class>) -> eyre::Result<jclass> {
+		let class = self.vm.classes.get(id);
+		let class = class
+			.as_instance()
+			.wrap_err("Only instance classes have a class object")?;
+		let companion = class
+			.try_companion()
+			.wrap_err("Class has never been linked")?;
+		Ok(self.new_local(*companion.class))
+	}
+}
+
+/// Walks up the super classes and interfaces of `class`.
+pub(super) fn is_assignable(vm: &Vm, class: Id<Class>, to: Id<Class>) -> bool {
+	if class == to {
+		return true;
+	}
+
+	let class = vm.classes.get(class);
+	let Some(class) = class.as_instance() else {
+		return false;
+	};
+
+	class
+		.interfaces
+		.iter()
+		.any(|interface| is_assignable(vm, interface.id, to))
+		|| class
+			.super_class
+			.as_ref()
+			.is_some_and(|super_class| is_assignable(vm, super_class.id, to))
+}
+
+pub(super) unsafe extern "system" fn get_version(_: *mut RawEnv) -> jint {
+	JNI_VERSION_1_8
+}
+
+pub(super) unsafe extern "system" fn define_class(
+	env: *mut RawEnv,
+	_: *const c_char,
+	_: jobject,
+	_: *const jbyte,
+	_: jsize,
+) -> jclass {
+	JNIEnv::unsupported(env, "DefineClass", null_mut())
+}
+
+pub(super) unsafe extern "system" fn find_class(env: *mut RawEnv, name: *const c_char) -> jclass {
+	JNIEnv::with(env, null_mut(), |env| {
+		let name = c_str(name)?;
+		if name.starts_with('[') {
+			bail!("Array classes have no class object");
+		}
+
+		let id = env
+			.runtime()
+			.resolve_class(&Type::Object(ObjectType::new(name.to_string())))?;
+		env.class_object(id)
+	})
+}
+
+pub(super) unsafe extern "system" fn get_superclass(env: *mut RawEnv, sub: jclass) -> jclass {
+	JNIEnv::with(env, null_mut(), |env| {
+		let class = env.get_instance_class(sub)?;
+		match class.super_class {
+			Some(super_class) => env.class_object(super_class.id),
+			None => Ok(null_mut()),
+		}
+	})
+}
+
+pub(super) unsafe extern "system" fn is_assignable_from(
+	env: *mut RawEnv,
+	sub: jclass,
+	sup: jclass,
+) -> jboolean {
+	JNIEnv::with(env, JNI_FALSE, |env| {
+		let sub = env.get_class(sub)?.id();
+		let sup = env.get_class(sup)?.id();
+		Ok(is_assignable(&env.vm, sub, sup))
+	})
+}
+
+pub(super) unsafe extern "system" fn get_object_class(env: *mut RawEnv, obj: jobject) -> jclass {
+	JNIEnv::with(env, null_mut(), |env| {
+		let instance = env.get(obj).to_instance()?;
+		env.class_object(instance.class())
+	})
+}
+
+pub(super) unsafe extern "system" fn is_instance_of(
+	env: *mut RawEnv,
+	obj: jobject,
+	clazz: jclass,
+) -> jboolean {
+	JNIEnv::with(env, JNI_FALSE, |env| {
+		let reference = env.get(obj);
+		let class = env.get_class(clazz)?.id();
+		if reference.is_null() {
+			return Ok(JNI_TRUE);
+		}
+
+		Ok(match reference.to_instance() {
+			Ok(instance) => env.vm.is_instance_of(instance, class),
+			// Arrays only extend Object
+			Err(_) => class == env.vm.std().c_object,
+		})
+	})
+}
+
+pub(super) unsafe extern "system" fn alloc_object(env: *mut RawEnv, clazz: jclass) -> jobject {
+	JNIEnv::with(env, null_mut(), |env| {
+		let class = env.get_instance_class(clazz)?;
+		let instance = env.runtime().alloc_object(&class)?;
+		Ok(env.new_local(*instance.raw()))
+	})
+}
+
+fn find_method(
+	vm: &Vm,
+	mut class: Id<Class>,
+	method: &MethodIdentifier,
+) -> Option<(Id<Class>, Id<Method>)> {
+	loop {
+		let arc = vm.classes.get(class);
+		let instance = arc.as_instance()?;
+		if let Some(id) = instance.methods.get_id(method) {
+			return Some((class, id));
+		}
+
+		class = instance.super_class.as_ref()?.id;
+	}
+}
+
+unsafe fn get_method_id(
+	env: *mut RawEnv,
+	clazz: jclass,
+	name: *const c_char,
+	sig: *const c_char,
+	is_static: bool,
+) -> jmethodID {
+	JNIEnv::with(env, null_mut(), |env| {
+		let class = env.get_instance_class(clazz)?;
+		let identifier = MethodIdentifier {
+			name: c_str(name)?.into(),
+			descriptor: c_str(sig)?.into(),
+		};
+
+		let (class_id, id) = find_method(&env.vm, class.id, &identifier).wrap_err_with(|| {
+			eyre!(
+				"NoSuchMethodError: {}.{}{}",
+				class.ty,
+				identifier.name,
+				identifier.descriptor
+			)
+		})?;
+
+		let method_class = env.vm.classes.get(class_id);
+		let method = method_class.to_instance().methods.get(id);
+		if method.flags.contains(MethodAccessFlags::STATIC) != is_static {
+			bail!(
+				"Method {}{} is {}static",
+				identifier.name,
+				identifier.descriptor,
+				if is_static { "not " } else { "" }
+			);
+		}
+
+		Ok(method_id(class_id, id))
+	})
+}
+
+pub(super) unsafe extern "system" fn get_instance_method_id(
+	env: *mut RawEnv,
+	clazz: jclass,
+	name: *const c_char,
+	sig: *const c_char,
+) -> jmethodID {
+	get_method_id(env, clazz, name, sig, false)
+}
+
+pub(super) unsafe extern "system" fn get_static_method_id(
+	env: *mut RawEnv,
+	clazz: jclass,
+	name: *const c_char,
+	sig: *const c_char,
+) -> jmethodID {
+	get_method_id(env, clazz, name, sig, true)
+}
+
+unsafe fn get_field_id(
+	env: *mut RawEnv,
+	clazz: jclass,
+	name: *const c_char,
+	sig: *const c_char,
+	is_static: bool,
+) -> jfieldID {
+	JNIEnv::with(env, null_mut(), |env| {
+		let class = env.get_instance_class(clazz)?;
+		let name = c_str(name)?;
+		let ty = Type::parse(&c_str(sig)?).wrap_err("Invalid field signature")?;
+
+		let layout = match is_static {
+			true => &class.static_field_layout,
+			false => &class.field_layout,
+		};
+		let id = layout
+			.get_id(&name)
+			.wrap_err_with(|| eyre!("NoSuchFieldError: {}.{name}", class.ty))?;
+		let field = layout.get(id);
+		if field.ty != ty {
+			bail!("Field {}.{name} is a {} not a {ty}", class.ty, field.ty);
+		}
+
+		Ok(field_id(class.id, id))
+	})
+}
+
+pub(super) unsafe extern "system" fn get_instance_field_id(
+	env: *mut RawEnv,
+	clazz: jclass,
+	name: *const c_char,
+	sig: *const c_char,
+) -> jfieldID {
+	get_field_id(env, clazz, name, sig, false)
+}
+
+pub(super) unsafe extern "system" fn get_static_field_id(
+	env: *mut RawEnv,
+	clazz: jclass,
+	name: *const c_char,
+	sig: *const c_char,
+) -> jfieldID {
+	get_field_id(env, clazz, name, sig, true)
+}
+
+pub(super) unsafe extern "system" fn from_reflected_method(
+	env: *mut RawEnv,
+	_: jobject,
+) -> jmethodID {
+	JNIEnv::unsupported(env, "FromReflectedMethod", null_mut())
+}
+
+pub(super) unsafe extern "system" fn from_reflected_field(
+	env: *mut RawEnv,
+	_: jobject,
+) -> jfieldID {
+	JNIEnv::unsupported(env, "FromReflectedField", null_mut())
+}
+
+pub(super) unsafe extern "system" fn to_reflected_method(
+	env: *mut RawEnv,
+	_: jclass,
+	_: jmethodID,
+	_: jboolean,
+) -> jobject {
+	JNIEnv::unsupported(env, "ToReflectedMethod", null_mut())
+}
+
+pub(super) unsafe extern "system" fn to_reflected_field(
+	env: *mut RawEnv,
+	_: jclass,
+	_: jfieldID,
+	_: jboolean,
+) -> jobject {
+	JNIEnv::unsupported(env, "ToReflectedField", null_mut())
+}
+
+pub(super) unsafe extern "system" fn register_natives(
+	env: *mut RawEnv,
+	clazz: jclass,
+	methods: *const JNINativeMethod,
+	n_methods: jint,
+) -> jint {
+	JNIEnv::with(env, JNI_ERR, |env| {
+		let class = env.get_instance_class(clazz)?;
+		for i in 0..n_methods.max(0) as usize {
+			let native = &*methods.add(i);
+			let identifier = MethodIdentifier {
+				name: c_str(native.name)?.into(),
+				descriptor: c_str(native.signature)?.into(),
+			};
+
+			let method = class
+				.methods
+				.get_id(&identifier)
+				.map(|id| class.methods.get(id))
+				.filter(|method| method.flags.contains(MethodAccessFlags::NATIVE))
+				.wrap_err_with(|| {
+					eyre!(
+						"NoSuchMethodError: {}.{}{} is not a native method",
+						class.ty,
+						identifier.name,
+						identifier.descriptor
+					)
+				})?;
+
+			let name = NativeName::new(&class.ty, &method.name, &method.desc);
+			let function = std::mem::transmute::<*mut c_void, extern "C" fn()>(native.fnPtr);
+			env.vm.linker.lock().register(&class.ty, &name, function);
+		}
+		Ok(JNI_OK)
+	})
+}
+
+pub(super) unsafe extern "system" fn unregister_natives(env: *mut RawEnv, clazz: jclass) -> jint {
+	JNIEnv::with(env, JNI_ERR, |env| {
+		let class = env.get_instance_class(clazz)?;
+		env.vm.linker.lock().unregister(&class.ty);
+		Ok(JNI_OK)
+	})
+}
+
+pub(super) unsafe extern "system" fn get_java_vm(env: *mut RawEnv, vm: *mut *mut JavaVM) -> jint {
+	*vm = JNIEnv::from_raw(env).vm.java_vm.as_raw();
+	JNI_OK
+}
+
+pub(super) unsafe extern "system" fn get_module(env: *mut RawEnv, _: jclass) -> jobject {
+	JNIEnv::unsupported(env, "GetModule", null_mut())
+}
+
+pub(super) unsafe extern "system" fn new_direct_byte_buffer(
+	env: *mut RawEnv,
+	_: *mut c_void,
+	_: jlong,
+) -> jobject {
+	JNIEnv::unsupported(env, "NewDirectByteBuffer", null_mut())
+}
+
+pub(super) unsafe extern "system" fn get_direct_buffer_address(
+	env: *mut RawEnv,
+	_: jobject,
+) -> *mut c_void {
+	JNIEnv::unsupported(env, "GetDirectBufferAddress", null_mut())
+}
+
+pub(super) unsafe extern "system" fn get_direct_buffer_capacity(
+	env: *mut RawEnv,
+	_: jobject,
+) -> jlong {
+	JNIEnv::unsupported(env, "GetDirectBufferCapacity", -1)
+}
+
+pub(super) unsafe extern "system" fn is_virtual_thread(_: *mut RawEnv, _: jobject) -> jboolean {
+	JNI_FALSE
+}
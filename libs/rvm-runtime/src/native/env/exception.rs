@@ -0,0 +1,110 @@
+use crate::native::env::class::c_str;
+use crate::native::env::{JNIEnv, RawEnv};
+use crate::{AnyValue, CallType, MethodIdentifier};
+use eyre::bail;
+use jni_sys::*;
+use std::ffi::c_char;
+use std::ptr::null_mut;
+use std::sync::Arc;
+
+impl JNIEnv {
+	/// The engine has no exception handling yet, a thrown exception stays pending until the native
+	/// method returns and then fails the call.
+	fn throw(&mut self, obj: jthrowable) -> eyre::Result<()> {
+		let reference = self.get(obj);
+		if reference.is_null() {
+			bail!("Null pointer exception");
+		}
+
+		self.frames().exception = Some(reference);
+		Ok(())
+	}
+}
+
+pub(super) unsafe extern "system" fn throw(env: *mut RawEnv, obj: jthrowable) -> jint {
+	JNIEnv::with(env, JNI_ERR, |env| {
+		env.throw(obj)?;
+		Ok(JNI_OK)
+	})
+}
+
+pub(super) unsafe extern "system" fn throw_new(
+	env: *mut RawEnv,
+	clazz: jclass,
+	msg: *const c_char,
+) -> jint {
+	JNIEnv::with(env, JNI_ERR, |env| {
+		let class = env.get_instance_class(clazz)?;
+		let message = match msg.is_null() {
+			true => null_mut(),
+			false => {
+				let message: Vec<u16> = c_str(msg)?.encode_utf16().collect();
+				env.new_string(&message)?
+			}
+		};
+
+		let throwable = env.runtime().alloc_object(&class)?;
+		let throwable = env.new_local(*throwable.raw());
+		let parameters = vec![
+			AnyValue::Reference(env.get(throwable)),
+			AnyValue::Reference(env.get(message)),
+		];
+		env.runtime().run(
+			CallType::Special,
+			&class.ty,
+			&MethodIdentifier {
+				name: Arc::from("<init>"),
+				descriptor: Arc::from("(Ljava/lang/String;)V"),
+			},
+			parameters,
+		)?;
+
+		env.throw(throwable)?;
+		Ok(JNI_OK)
+	})
+}
+
+pub(super) unsafe extern "system" fn exception_occurred(env: *mut RawEnv) -> jthrowable {
+	JNIEnv::enter(env, |env| match env.frames().exception {
+		Some(exception) => env.new_local(exception),
+		None => null_mut(),
+	})
+}
+
+pub(super) unsafe extern "system" fn exception_describe(env: *mut RawEnv) {
+	JNIEnv::enter(env, |env| {
+		let Some(exception) = env.frames().exception else {
+			return;
+		};
+
+		if let Ok(instance) = exception.to_instance() {
+			let class = env.vm.classes.get(instance.class());
+			eprintln!("Exception in native method: {}", class.cloned_ty());
+		}
+	})
+}
+
+/// Errors of the JNI functions are cleared with the exception, native code has no other way to
+/// recover from them.
+pub(super) unsafe extern "system" fn exception_clear(env: *mut RawEnv) {
+	JNIEnv::enter(env, |env| {
+		let frames = env.frames();
+		frames.exception = None;
+		frames.error = None;
+	})
+}
+
+pub(super) unsafe extern "system" fn fatal_error(_: *mut RawEnv, msg: *const c_char) -> ! {
+	eprintln!(
+		"FATAL ERROR in native method: {}",
+		c_str(msg).unwrap_or_else(|_| "<invalid>".into())
+	);
+	std::process::abort()
+}
+
+pub(super) unsafe extern "system" fn exception_check(env: *mut RawEnv) -> jboolean {
+	JNIEnv::enter(env, |env| {
+		let frames = env.frames();
+		frames.exception.is_some() || frames.error.is_some()
+	})
+}
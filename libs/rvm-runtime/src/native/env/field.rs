@@ -0,0 +1,110 @@
+use crate::native::env::class::get_field as unpack_field;
+use crate::native::env::{JNIEnv, NativeValue, RawEnv};
+use crate::{DynField2, FieldTable, InstanceClass};
+use eyre::bail;
+use jni_sys::{jclass, jfieldID, jobject};
+
+impl JNIEnv {
+	/// Runs `func` on an instance field of `obj`, the instance is only valid until the next allocation.
+	fn with_field<R>(
+		&mut self,
+		obj: jobject,
+		field: jfieldID,
+		func: impl FnOnce(&mut JNIEnv, DynField2) -> eyre::Result<R>,
+	) -> eyre::Result<R> {
+		let (class_id, field_id) = unpack_field(field)?;
+		let reference = self.get(obj);
+		if reference.is_null() {
+			bail!("Null pointer exception");
+		}
+
+		let instance = reference.to_instance()?;
+		if !self.vm.is_instance_of(instance, class_id) {
+			bail!("Object does not have the field");
+		}
+
+		// Sub classes keep the fields of their super class at the same offsets.
+		let class = self.vm.classes.get(class_id);
+		let fields =
+			unsafe { FieldTable::new(&class.to_instance().field_layout, instance.data_ptr()) };
+		func(self, fields.by_id(field_id))
+	}
+
+	fn with_static_field<R>(
+		&mut self,
+		field: jfieldID,
+		func: impl FnOnce(&mut JNIEnv, DynField2) -> eyre::Result<R>,
+	) -> eyre::Result<R> {
+		let (class_id, field_id) = unpack_field(field)?;
+		let class = self.vm.classes.get(class_id);
+		let class: &InstanceClass = class.to_instance();
+		func(self, class.static_fields().by_id(field_id))
+	}
+}
+
+fn check_kind<V: NativeValue>(field: &DynField2) -> eyre::Result<()> {
+	if field.kind() != V::KIND {
+		bail!("Field is a {} not a {}", field.kind(), V::KIND);
+	}
+	Ok(())
+}
+
+pub(super) unsafe extern "system" fn get_field<V: NativeValue>(
+	env: *mut RawEnv,
+	obj: jobject,
+	field: jfieldID,
+) -> V {
+	JNIEnv::with(env, V::ZERO, |env| {
+		env.with_field(obj, field, |env, field| {
+			check_kind::<V>(&field)?;
+			V::from_java(env, field.get())
+		})
+	})
+}
+
+pub(super) unsafe extern "system" fn set_field<V: NativeValue>(
+	env: *mut RawEnv,
+	obj: jobject,
+	field: jfieldID,
+	value: V,
+) {
+	JNIEnv::with(env, (), |env| {
+		let value = value.to_java(env);
+		env.with_field(obj, field, |env, field| {
+			check_kind::<V>(&field)?;
+			env.vm.gc.write_barrier(|| field.get());
+			field.set(value);
+			Ok(())
+		})
+	})
+}
+
+pub(super) unsafe extern "system" fn get_static_field<V: NativeValue>(
+	env: *mut RawEnv,
+	_: jclass,
+	field: jfieldID,
+) -> V {
+	JNIEnv::with(env, V::ZERO, |env| {
+		env.with_static_field(field, |env, field| {
+			check_kind::<V>(&field)?;
+			V::from_java(env, field.get())
+		})
+	})
+}
+
+pub(super) unsafe extern "system" fn set_static_field<V: NativeValue>(
+	env: *mut RawEnv,
+	_: jclass,
+	field: jfieldID,
+	value: V,
+) {
+	JNIEnv::with(env, (), |env| {
+		let value = value.to_java(env);
+		env.with_static_field(field, |env, field| {
+			check_kind::<V>(&field)?;
+			env.vm.gc.write_barrier(|| field.get());
+			field.set(value);
+			Ok(())
+		})
+	})
+}
@@ -0,0 +1,213 @@
+mod array;
+mod call;
+mod class;
+mod exception;
+mod field;
+mod reference;
+mod string;
+mod table;
+
+use crate::native::NativeFrames;
+use crate::{AnyValue, Reference, Runtime, ThreadContext, Vm};
+use eyre::eyre;
+use jni_sys::{jboolean, jbyte, jchar, jdouble, jfloat, jint, jlong, jobject, jshort};
+use jni_sys::{JNINativeInterface_, JNI_FALSE};
+use rvm_core::{CastKindError, Kind, PrimitiveType};
+use std::mem::transmute;
+use std::ptr::null_mut;
+
+/// The `JNIEnv` which native code sees.
+pub(super) type RawEnv = jni_sys::JNIEnv;
+
+/// The environment of a native method call, C code gets a pointer to this as its `JNIEnv*`.
+#[repr(C)]
+pub struct JNIEnv {
+	functions: *const JNINativeInterface_,
+	vm: Vm,
+	thread: *mut (dyn ThreadContext + 'static),
+}
+
+impl JNIEnv {
+	/// The environment may not outlive the thread context.
+	pub fn new(vm: Vm, thread: &mut dyn ThreadContext) -> JNIEnv {
+		JNIEnv {
+			functions: &table::FUNCTIONS.0,
+			vm,
+			thread: unsafe { transmute::<*mut dyn ThreadContext, _>(thread) },
+		}
+	}
+
+	/// # Safety
+	/// The pointer has to come from [JNIEnv::as_raw] of an environment which is still alive.
+	pub unsafe fn from_raw<'a>(env: *mut RawEnv) -> &'a mut JNIEnv {
+		&mut *(env as *mut JNIEnv)
+	}
+
+	pub fn as_raw(&mut self) -> *mut RawEnv {
+		self as *mut JNIEnv as *mut RawEnv
+	}
+
+	pub fn vm(&self) -> &Vm {
+		&self.vm
+	}
+
+	pub fn runtime(&mut self) -> Runtime<'_> {
+		Runtime {
+			vm: self.vm.clone(),
+			thread: Some(unsafe { &mut *self.thread }),
+		}
+	}
+
+	pub fn frames(&mut self) -> &mut NativeFrames {
+		unsafe { (*self.thread).native_frames() }
+	}
+
+	pub fn new_local(&mut self, reference: Reference) -> jobject {
+		self.frames().new_local(reference)
+	}
+
+	/// Resolves a local, global or weak reference, collected weak references are null.
+	pub fn get(&mut self, handle: jobject) -> Reference {
+		match reference::Handle::decode(handle) {
+			reference::Handle::Local => self.frames().get(handle),
+			reference::Handle::Global(handle) => self.vm.gc.global(handle),
+			reference::Handle::Weak(handle) => self.vm.gc.weak(handle),
+		}
+	}
+
+	/// Runs native code, collections go through the roots of the thread instead of waiting for it.
+	pub(crate) unsafe fn native<R>(&mut self, func: impl FnOnce() -> R) -> R {
+		(*self.thread).enter_native();
+		let output = func();
+		(*self.thread).leave_native();
+		output
+	}
+
+	/// Brings the thread back into the vm while a JNI function runs, it uses the roots of the thread.
+	unsafe fn enter<R>(env: *mut RawEnv, func: impl FnOnce(&mut JNIEnv) -> R) -> R {
+		let env = JNIEnv::from_raw(env);
+		let native = (*env.thread).leave_native();
+		let output = func(env);
+		if native {
+			(*env.thread).enter_native();
+		}
+		output
+	}
+
+	/// Runs the body of a JNI function. Native code can't handle our errors, so they are kept until
+	/// the native method returns and the fallback gets returned to the native code instead.
+	unsafe fn with<R>(
+		env: *mut RawEnv,
+		fallback: R,
+		func: impl FnOnce(&mut JNIEnv) -> eyre::Result<R>,
+	) -> R {
+		JNIEnv::enter(env, |env| match func(env) {
+			Ok(value) => value,
+			Err(error) => {
+				env.frames().error.get_or_insert(error);
+				fallback
+			}
+		})
+	}
+
+	unsafe fn unsupported<R>(env: *mut RawEnv, function: &str, fallback: R) -> R {
+		JNIEnv::with(env, fallback, |_| {
+			Err(eyre!("JNI function {function} is not supported"))
+		})
+	}
+}
+
+/// A value which native code can pass to and get from the JNI functions.
+pub(super) trait NativeValue: Copy {
+	const ZERO: Self;
+	const KIND: Kind;
+
+	fn to_java(self, env: &mut JNIEnv) -> AnyValue;
+	fn from_java(env: &mut JNIEnv, value: AnyValue) -> eyre::Result<Self>;
+}
+
+/// The primitive values which have their own array type.
+pub(super) trait NativePrimitive: NativeValue + crate::Value {
+	const TYPE: PrimitiveType;
+}
+
+/// The return value of a java method call, void methods return `()`.
+pub(super) trait NativeReturn: Copy {
+	const ZERO: Self;
+
+	fn from_return(env: &mut JNIEnv, value: Option<AnyValue>) -> eyre::Result<Self>;
+}
+
+impl<V: NativeValue> NativeReturn for V {
+	const ZERO: Self = <V as NativeValue>::ZERO;
+
+	fn from_return(env: &mut JNIEnv, value: Option<AnyValue>) -> eyre::Result<Self> {
+		let value = value.ok_or_else(|| eyre!("Method did not return a {}", V::KIND))?;
+		V::from_java(env, value)
+	}
+}
+
+impl NativeReturn for () {
+	const ZERO: Self = ();
+
+	fn from_return(_: &mut JNIEnv, _: Option<AnyValue>) -> eyre::Result<Self> {
+		Ok(())
+	}
+}
+
+macro_rules! impl_primitive {
+	($TY:ty, $KIND:ident, $ZERO:expr) => {
+		impl NativeValue for $TY {
+			const ZERO: Self = $ZERO;
+			const KIND: Kind = Kind::$KIND;
+
+			fn to_java(self, _: &mut JNIEnv) -> AnyValue {
+				AnyValue::$KIND(self)
+			}
+
+			fn from_java(_: &mut JNIEnv, value: AnyValue) -> eyre::Result<Self> {
+				match value {
+					AnyValue::$KIND(value) => Ok(value),
+					_ => Err(CastKindError {
+						expected: Kind::$KIND,
+						found: value.kind(),
+					}
+					.into()),
+				}
+			}
+		}
+
+		impl NativePrimitive for $TY {
+			const TYPE: PrimitiveType = PrimitiveType::$KIND;
+		}
+	};
+}
+
+impl_primitive!(jboolean, Boolean, JNI_FALSE);
+impl_primitive!(jbyte, Byte, 0);
+impl_primitive!(jchar, Char, 0);
+impl_primitive!(jshort, Short, 0);
+impl_primitive!(jint, Int, 0);
+impl_primitive!(jlong, Long, 0);
+impl_primitive!(jfloat, Float, 0.0);
+impl_primitive!(jdouble, Double, 0.0);
+
+impl NativeValue for jobject {
+	const ZERO: Self = null_mut();
+	const KIND: Kind = Kind::Reference;
+
+	fn to_java(self, env: &mut JNIEnv) -> AnyValue {
+		AnyValue::Reference(env.get(self))
+	}
+
+	fn from_java(env: &mut JNIEnv, value: AnyValue) -> eyre::Result<Self> {
+		match value {
+			AnyValue::Reference(reference) => Ok(env.new_local(reference)),
+			_ => Err(CastKindError {
+				expected: Kind::Reference,
+				found: value.kind(),
+			}
+			.into()),
+		}
+	}
+}
@@ -0,0 +1,132 @@
+use crate::gc::{GlobalHandle, WeakHandle};
+use crate::native::env::{JNIEnv, RawEnv};
+use jni_sys::*;
+use std::ptr::null_mut;
+
+/// Local references are small indices, global and weak ones are told apart from them by a tag.
+const GLOBAL_TAG: usize = 1 << 40;
+const WEAK_TAG: usize = 2 << 40;
+const TAG_MASK: usize = 3 << 40;
+
+pub(super) enum Handle {
+	Local,
+	Global(GlobalHandle),
+	Weak(WeakHandle),
+}
+
+impl Handle {
+	pub fn decode(handle: jobject) -> Handle {
+		let handle = handle as usize;
+		let index = (handle & !TAG_MASK).wrapping_sub(1) as u32;
+		match handle & TAG_MASK {
+			GLOBAL_TAG => Handle::Global(GlobalHandle::from_index(index)),
+			WEAK_TAG => Handle::Weak(WeakHandle::from_index(index)),
+			_ => Handle::Local,
+		}
+	}
+}
+
+fn global_handle(handle: GlobalHandle) -> jobject {
+	(GLOBAL_TAG | (handle.index() as usize + 1)) as jobject
+}
+
+fn weak_handle(handle: WeakHandle) -> jweak {
+	(WEAK_TAG | (handle.index() as usize + 1)) as jweak
+}
+
+pub(super) unsafe extern "system" fn push_local_frame(env: *mut RawEnv, capacity: jint) -> jint {
+	JNIEnv::enter(env, |env| env.frames().push_frame(capacity.max(0) as usize));
+	JNI_OK
+}
+
+pub(super) unsafe extern "system" fn pop_local_frame(env: *mut RawEnv, result: jobject) -> jobject {
+	JNIEnv::enter(env, |env| {
+		let result = env.get(result);
+		env.frames().pop_frame();
+		env.new_local(result)
+	})
+}
+
+pub(super) unsafe extern "system" fn new_local_ref(env: *mut RawEnv, obj: jobject) -> jobject {
+	JNIEnv::enter(env, |env| {
+		let reference = env.get(obj);
+		env.new_local(reference)
+	})
+}
+
+pub(super) unsafe extern "system" fn delete_local_ref(env: *mut RawEnv, obj: jobject) {
+	JNIEnv::enter(env, |env| env.frames().delete_local(obj));
+}
+
+pub(super) unsafe extern "system" fn ensure_local_capacity(
+	env: *mut RawEnv,
+	capacity: jint,
+) -> jint {
+	JNIEnv::enter(env, |env| {
+		env.frames().ensure_capacity(capacity.max(0) as usize)
+	});
+	JNI_OK
+}
+
+pub(super) unsafe extern "system" fn is_same_object(
+	env: *mut RawEnv,
+	obj1: jobject,
+	obj2: jobject,
+) -> jboolean {
+	JNIEnv::enter(env, |env| env.get(obj1) == env.get(obj2))
+}
+
+pub(super) unsafe extern "system" fn get_object_ref_type(
+	env: *mut RawEnv,
+	obj: jobject,
+) -> jobjectRefType {
+	match Handle::decode(obj) {
+		Handle::Global(_) => jobjectRefType::JNIGlobalRefType,
+		Handle::Weak(_) => jobjectRefType::JNIWeakGlobalRefType,
+		Handle::Local if JNIEnv::enter(env, |env| env.frames().is_local(obj)) => {
+			jobjectRefType::JNILocalRefType
+		}
+		Handle::Local => jobjectRefType::JNIInvalidRefType,
+	}
+}
+
+pub(super) unsafe extern "system" fn new_global_ref(env: *mut RawEnv, obj: jobject) -> jobject {
+	JNIEnv::enter(env, |env| {
+		let reference = env.get(obj);
+		if reference.is_null() {
+			return null_mut();
+		}
+		global_handle(env.vm.gc.new_global(reference))
+	})
+}
+
+pub(super) unsafe extern "system" fn delete_global_ref(env: *mut RawEnv, obj: jobject) {
+	if let Handle::Global(handle) = Handle::decode(obj) {
+		JNIEnv::from_raw(env).vm.gc.remove_global(handle);
+	}
+}
+
+pub(super) unsafe extern "system" fn new_weak_global_ref(env: *mut RawEnv, obj: jobject) -> jweak {
+	JNIEnv::enter(env, |env| {
+		let reference = env.get(obj);
+		if reference.is_null() {
+			return null_mut();
+		}
+		weak_handle(env.vm.gc.new_weak(reference))
+	})
+}
+
+pub(super) unsafe extern "system" fn delete_weak_global_ref(env: *mut RawEnv, obj: jweak) {
+	if let Handle::Weak(handle) = Handle::decode(obj) {
+		JNIEnv::from_raw(env).vm.gc.remove_weak(handle);
+	}
+}
+
+/// Objects have no monitors yet, the engine does not run threads which share objects.
+pub(super) unsafe extern "system" fn monitor_enter(_: *mut RawEnv, _: jobject) -> jint {
+	JNI_OK
+}
+
+pub(super) unsafe extern "system" fn monitor_exit(_: *mut RawEnv, _: jobject) -> jint {
+	JNI_OK
+}
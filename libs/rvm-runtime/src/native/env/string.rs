@@ -0,0 +1,255 @@
+use crate::conversion::{alloc_string, read_string};
+use crate::native::env::{JNIEnv, RawEnv};
+use eyre::{bail, eyre};
+use jni_sys::*;
+use std::alloc::{alloc, dealloc, handle_alloc_error, Layout};
+use std::ffi::c_char;
+use std::ffi::CStr;
+use std::ptr::null_mut;
+
+impl JNIEnv {
+	pub(super) fn get_string(&mut self, handle: jstring) -> eyre::Result<Vec<u16>> {
+		let reference = self.get(handle);
+		if reference.is_null() {
+			bail!("Null pointer exception");
+		}
+
+		read_string(&self.vm, reference)
+	}
+
+	pub(super) fn new_string(&mut self, chars: &[u16]) -> eyre::Result<jstring> {
+		let string = alloc_string(&mut self.runtime(), chars)?;
+		Ok(self.new_local(string))
+	}
+}
+
+/// Encodes UTF-16 as the modified UTF-8 of the JVM, nulls take two bytes and supplementary
+/// characters are encoded as their surrogate pairs.
+fn encode_modified_utf8(chars: &[u16]) -> Vec<u8> {
+	let mut out = Vec::with_capacity(chars.len());
+	for &char in chars {
+		match char {
+			0x0001..=0x007F => out.push(char as u8),
+			0x0000 | 0x0080..=0x07FF => {
+				out.push(0xC0 | (char >> 6) as u8);
+				out.push(0x80 | (char & 0x3F) as u8);
+			}
+			_ => {
+				out.push(0xE0 | (char >> 12) as u8);
+				out.push(0x80 | ((char >> 6) & 0x3F) as u8);
+				out.push(0x80 | (char & 0x3F) as u8);
+			}
+		}
+	}
+	out
+}
+
+pub(super) fn decode_modified_utf8(bytes: &[u8]) -> eyre::Result<Vec<u16>> {
+	fn next(bytes: &mut impl Iterator<Item = u8>) -> eyre::Result<u16> {
+		bytes
+			.next()
+			.filter(|byte| byte & 0xC0 == 0x80)
+			.map(|byte| (byte & 0x3F) as u16)
+			.ok_or_else(|| eyre!("Invalid modified UTF-8"))
+	}
+
+	let mut out = Vec::with_capacity(bytes.len());
+	let mut bytes = bytes.iter().copied();
+	while let Some(byte) = bytes.next() {
+		out.push(match byte {
+			0x00..=0x7F => byte as u16,
+			0xC0..=0xDF => ((byte & 0x1F) as u16) << 6 | next(&mut bytes)?,
+			0xE0..=0xEF => {
+				let high = next(&mut bytes)?;
+				((byte & 0x0F) as u16) << 12 | high << 6 | next(&mut bytes)?
+			}
+			_ => bail!("Invalid modified UTF-8"),
+		});
+	}
+	Ok(out)
+}
+
+/// The offset of the buffer behind the length header which [leak_buffer] puts in front of it.
+fn buffer_layout<V>(len: usize) -> (Layout, usize) {
+	Layout::new::<usize>()
+		.extend(Layout::array::<V>(len).unwrap())
+		.unwrap()
+}
+
+/// Hands a buffer to native code, it comes back through [free_buffer]. Strings may contain nulls,
+/// so the length is kept in front of the buffer.
+unsafe fn leak_buffer<V: Copy>(buffer: Vec<V>) -> *mut V {
+	let (layout, offset) = buffer_layout::<V>(buffer.len());
+	let header = alloc(layout);
+	if header.is_null() {
+		handle_alloc_error(layout);
+	}
+
+	(header as *mut usize).write(buffer.len());
+	let data = header.add(offset) as *mut V;
+	data.copy_from_nonoverlapping(buffer.as_ptr(), buffer.len());
+	data
+}
+
+unsafe fn free_buffer<V>(buffer: *const V) {
+	let (_, offset) = buffer_layout::<V>(0);
+	let header = (buffer as *mut u8).sub(offset);
+	let len = (header as *const usize).read();
+	dealloc(header, buffer_layout::<V>(len).0);
+}
+
+unsafe fn set_is_copy(is_copy: *mut jboolean) {
+	if !is_copy.is_null() {
+		*is_copy = JNI_TRUE;
+	}
+}
+
+fn check_region(length: usize, start: jsize, len: jsize) -> eyre::Result<()> {
+	if start < 0 || len < 0 || start as usize + len as usize > length {
+		bail!(
+			"StringIndexOutOfBoundsException: {start}..{} of {length}",
+			start as i64 + len as i64
+		);
+	}
+	Ok(())
+}
+
+pub(super) unsafe extern "system" fn new_string(
+	env: *mut RawEnv,
+	unicode: *const jchar,
+	len: jsize,
+) -> jstring {
+	JNIEnv::with(env, null_mut(), |env| {
+		let chars = match len {
+			0 => &[][..],
+			_ => std::slice::from_raw_parts(unicode, len as usize),
+		};
+		env.new_string(chars)
+	})
+}
+
+pub(super) unsafe extern "system" fn get_string_length(env: *mut RawEnv, str: jstring) -> jsize {
+	JNIEnv::with(env, 0, |env| Ok(env.get_string(str)?.len() as jsize))
+}
+
+pub(super) unsafe extern "system" fn get_string_chars(
+	env: *mut RawEnv,
+	str: jstring,
+	is_copy: *mut jboolean,
+) -> *const jchar {
+	JNIEnv::with(env, null_mut(), |env| {
+		let chars = env.get_string(str)?;
+		set_is_copy(is_copy);
+		Ok(leak_buffer(chars))
+	})
+}
+
+pub(super) unsafe extern "system" fn release_string_chars(
+	_: *mut RawEnv,
+	_: jstring,
+	chars: *const jchar,
+) {
+	free_buffer(chars);
+}
+
+pub(super) unsafe extern "system" fn new_string_utf(
+	env: *mut RawEnv,
+	utf: *const c_char,
+) -> jstring {
+	JNIEnv::with(env, null_mut(), |env| {
+		if utf.is_null() {
+			return Ok(null_mut());
+		}
+
+		let chars = decode_modified_utf8(CStr::from_ptr(utf).to_bytes())?;
+		env.new_string(&chars)
+	})
+}
+
+pub(super) unsafe extern "system" fn get_string_utf_length(
+	env: *mut RawEnv,
+	str: jstring,
+) -> jsize {
+	JNIEnv::with(env, 0, |env| {
+		Ok(encode_modified_utf8(&env.get_string(str)?).len() as jsize)
+	})
+}
+
+pub(super) unsafe extern "system" fn get_string_utf_length_as_long(
+	env: *mut RawEnv,
+	str: jstring,
+) -> jlong {
+	JNIEnv::with(env, 0, |env| {
+		Ok(encode_modified_utf8(&env.get_string(str)?).len() as jlong)
+	})
+}
+
+pub(super) unsafe extern "system" fn get_string_utf_chars(
+	env: *mut RawEnv,
+	str: jstring,
+	is_copy: *mut jboolean,
+) -> *const c_char {
+	JNIEnv::with(env, null_mut(), |env| {
+		let mut bytes = encode_modified_utf8(&env.get_string(str)?);
+		bytes.push(0);
+		set_is_copy(is_copy);
+		Ok(leak_buffer(bytes) as *const c_char)
+	})
+}
+
+pub(super) unsafe extern "system" fn release_string_utf_chars(
+	_: *mut RawEnv,
+	_: jstring,
+	chars: *const c_char,
+) {
+	free_buffer(chars);
+}
+
+pub(super) unsafe extern "system" fn get_string_region(
+	env: *mut RawEnv,
+	str: jstring,
+	start: jsize,
+	len: jsize,
+	buf: *mut jchar,
+) {
+	JNIEnv::with(env, (), |env| {
+		let chars = env.get_string(str)?;
+		check_region(chars.len(), start, len)?;
+		let region = &chars[start as usize..(start + len) as usize];
+		buf.copy_from_nonoverlapping(region.as_ptr(), region.len());
+		Ok(())
+	})
+}
+
+pub(super) unsafe extern "system" fn get_string_utf_region(
+	env: *mut RawEnv,
+	str: jstring,
+	start: jsize,
+	len: jsize,
+	buf: *mut c_char,
+) {
+	JNIEnv::with(env, (), |env| {
+		let chars = env.get_string(str)?;
+		check_region(chars.len(), start, len)?;
+		let bytes = encode_modified_utf8(&chars[start as usize..(start + len) as usize]);
+		buf.copy_from_nonoverlapping(bytes.as_ptr() as *const c_char, bytes.len());
+		*buf.add(bytes.len()) = 0;
+		Ok(())
+	})
+}
+
+pub(super) unsafe extern "system" fn get_string_critical(
+	env: *mut RawEnv,
+	string: jstring,
+	is_copy: *mut jboolean,
+) -> *const jchar {
+	get_string_chars(env, string, is_copy)
+}
+
+pub(super) unsafe extern "system" fn release_string_critical(
+	env: *mut RawEnv,
+	string: jstring,
+	chars: *const jchar,
+) {
+	release_string_chars(env, string, chars)
+}
@@ -0,0 +1,257 @@
+use crate::native::env::{array, call, class, exception, field, reference, string};
+use jni_sys::*;
+use std::ptr::null_mut;
+
+/// The `V` functions take a [std::ffi::VaList] where jni-sys only knows an opaque `va_list`, both
+/// are passed the same way so the pointer can be reinterpreted.
+macro_rules! va_list {
+	($function:expr) => {
+		unsafe { std::mem::transmute::<*const (), _>($function as *const ()) }
+	};
+}
+
+pub(super) struct FunctionTable(pub JNINativeInterface_);
+
+// The table only holds function pointers.
+unsafe impl Sync for FunctionTable {}
+
+pub(super) static FUNCTIONS: FunctionTable = FunctionTable(JNINativeInterface_ {
+	reserved: JNINativeInterface__reserved {
+		reserved0: null_mut(),
+		reserved1: null_mut(),
+		reserved2: null_mut(),
+		reserved3: null_mut(),
+		GetVersion: class::get_version,
+		DefineClass: class::define_class,
+		FindClass: class::find_class,
+		FromReflectedMethod: class::from_reflected_method,
+		FromReflectedField: class::from_reflected_field,
+		ToReflectedMethod: class::to_reflected_method,
+		GetSuperclass: class::get_superclass,
+		IsAssignableFrom: class::is_assignable_from,
+		ToReflectedField: class::to_reflected_field,
+		Throw: exception::throw,
+		ThrowNew: exception::throw_new,
+		ExceptionOccurred: exception::exception_occurred,
+		ExceptionDescribe: exception::exception_describe,
+		ExceptionClear: exception::exception_clear,
+		FatalError: exception::fatal_error,
+		PushLocalFrame: reference::push_local_frame,
+		PopLocalFrame: reference::pop_local_frame,
+		NewGlobalRef: reference::new_global_ref,
+		DeleteGlobalRef: reference::delete_global_ref,
+		DeleteLocalRef: reference::delete_local_ref,
+		IsSameObject: reference::is_same_object,
+		NewLocalRef: reference::new_local_ref,
+		EnsureLocalCapacity: reference::ensure_local_capacity,
+		AllocObject: class::alloc_object,
+		NewObject: call::new_object,
+		NewObjectV: va_list!(call::new_object_v),
+		NewObjectA: call::new_object_a,
+		GetObjectClass: class::get_object_class,
+		IsInstanceOf: class::is_instance_of,
+		GetMethodID: class::get_instance_method_id,
+		CallObjectMethod: call::call_method::<jobject>,
+		CallObjectMethodV: va_list!(call::call_method_v::<jobject>),
+		CallObjectMethodA: call::call_method_a::<jobject>,
+		CallBooleanMethod: call::call_method::<jboolean>,
+		CallBooleanMethodV: va_list!(call::call_method_v::<jboolean>),
+		CallBooleanMethodA: call::call_method_a::<jboolean>,
+		CallByteMethod: call::call_method::<jbyte>,
+		CallByteMethodV: va_list!(call::call_method_v::<jbyte>),
+		CallByteMethodA: call::call_method_a::<jbyte>,
+		CallCharMethod: call::call_method::<jchar>,
+		CallCharMethodV: va_list!(call::call_method_v::<jchar>),
+		CallCharMethodA: call::call_method_a::<jchar>,
+		CallShortMethod: call::call_method::<jshort>,
+		CallShortMethodV: va_list!(call::call_method_v::<jshort>),
+		CallShortMethodA: call::call_method_a::<jshort>,
+		CallIntMethod: call::call_method::<jint>,
+		CallIntMethodV: va_list!(call::call_method_v::<jint>),
+		CallIntMethodA: call::call_method_a::<jint>,
+		CallLongMethod: call::call_method::<jlong>,
+		CallLongMethodV: va_list!(call::call_method_v::<jlong>),
+		CallLongMethodA: call::call_method_a::<jlong>,
+		CallFloatMethod: call::call_method::<jfloat>,
+		CallFloatMethodV: va_list!(call::call_method_v::<jfloat>),
+		CallFloatMethodA: call::call_method_a::<jfloat>,
+		CallDoubleMethod: call::call_method::<jdouble>,
+		CallDoubleMethodV: va_list!(call::call_method_v::<jdouble>),
+		CallDoubleMethodA: call::call_method_a::<jdouble>,
+		CallVoidMethod: call::call_method::<()>,
+		CallVoidMethodV: va_list!(call::call_method_v::<()>),
+		CallVoidMethodA: call::call_method_a::<()>,
+		CallNonvirtualObjectMethod: call::call_nonvirtual_method::<jobject>,
+		CallNonvirtualObjectMethodV: va_list!(call::call_nonvirtual_method_v::<jobject>),
+		CallNonvirtualObjectMethodA: call::call_nonvirtual_method_a::<jobject>,
+		CallNonvirtualBooleanMethod: call::call_nonvirtual_method::<jboolean>,
+		CallNonvirtualBooleanMethodV: va_list!(call::call_nonvirtual_method_v::<jboolean>),
+		CallNonvirtualBooleanMethodA: call::call_nonvirtual_method_a::<jboolean>,
+		CallNonvirtualByteMethod: call::call_nonvirtual_method::<jbyte>,
+		CallNonvirtualByteMethodV: va_list!(call::call_nonvirtual_method_v::<jbyte>),
+		CallNonvirtualByteMethodA: call::call_nonvirtual_method_a::<jbyte>,
+		CallNonvirtualCharMethod: call::call_nonvirtual_method::<jchar>,
+		CallNonvirtualCharMethodV: va_list!(call::call_nonvirtual_method_v::<jchar>),
+		CallNonvirtualCharMethodA: call::call_nonvirtual_method_a::<jchar>,
+		CallNonvirtualShortMethod: call::call_nonvirtual_method::<jshort>,
+		CallNonvirtualShortMethodV: va_list!(call::call_nonvirtual_method_v::<jshort>),
+		CallNonvirtualShortMethodA: call::call_nonvirtual_method_a::<jshort>,
+		CallNonvirtualIntMethod: call::call_nonvirtual_method::<jint>,
+		CallNonvirtualIntMethodV: va_list!(call::call_nonvirtual_method_v::<jint>),
+		CallNonvirtualIntMethodA: call::call_nonvirtual_method_a::<jint>,
+		CallNonvirtualLongMethod: call::call_nonvirtual_method::<jlong>,
+		CallNonvirtualLongMethodV: va_list!(call::call_nonvirtual_method_v::<jlong>),
+		CallNonvirtualLongMethodA: call::call_nonvirtual_method_a::<jlong>,
+		CallNonvirtualFloatMethod: call::call_nonvirtual_method::<jfloat>,
+		CallNonvirtualFloatMethodV: va_list!(call::call_nonvirtual_method_v::<jfloat>),
+		CallNonvirtualFloatMethodA: call::call_nonvirtual_method_a::<jfloat>,
+		CallNonvirtualDoubleMethod: call::call_nonvirtual_method::<jdouble>,
+		CallNonvirtualDoubleMethodV: va_list!(call::call_nonvirtual_method_v::<jdouble>),
+		CallNonvirtualDoubleMethodA: call::call_nonvirtual_method_a::<jdouble>,
+		CallNonvirtualVoidMethod: call::call_nonvirtual_method::<()>,
+		CallNonvirtualVoidMethodV: va_list!(call::call_nonvirtual_method_v::<()>),
+		CallNonvirtualVoidMethodA: call::call_nonvirtual_method_a::<()>,
+		GetFieldID: class::get_instance_field_id,
+		GetObjectField: field::get_field::<jobject>,
+		GetBooleanField: field::get_field::<jboolean>,
+		GetByteField: field::get_field::<jbyte>,
+		GetCharField: field::get_field::<jchar>,
+		GetShortField: field::get_field::<jshort>,
+		GetIntField: field::get_field::<jint>,
+		GetLongField: field::get_field::<jlong>,
+		GetFloatField: field::get_field::<jfloat>,
+		GetDoubleField: field::get_field::<jdouble>,
+		SetObjectField: field::set_field::<jobject>,
+		SetBooleanField: field::set_field::<jboolean>,
+		SetByteField: field::set_field::<jbyte>,
+		SetCharField: field::set_field::<jchar>,
+		SetShortField: field::set_field::<jshort>,
+		SetIntField: field::set_field::<jint>,
+		SetLongField: field::set_field::<jlong>,
+		SetFloatField: field::set_field::<jfloat>,
+		SetDoubleField: field::set_field::<jdouble>,
+		GetStaticMethodID: class::get_static_method_id,
+		CallStaticObjectMethod: call::call_static_method::<jobject>,
+		CallStaticObjectMethodV: va_list!(call::call_static_method_v::<jobject>),
+		CallStaticObjectMethodA: call::call_static_method_a::<jobject>,
+		CallStaticBooleanMethod: call::call_static_method::<jboolean>,
+		CallStaticBooleanMethodV: va_list!(call::call_static_method_v::<jboolean>),
+		CallStaticBooleanMethodA: call::call_static_method_a::<jboolean>,
+		CallStaticByteMethod: call::call_static_method::<jbyte>,
+		CallStaticByteMethodV: va_list!(call::call_static_method_v::<jbyte>),
+		CallStaticByteMethodA: call::call_static_method_a::<jbyte>,
+		CallStaticCharMethod: call::call_static_method::<jchar>,
+		CallStaticCharMethodV: va_list!(call::call_static_method_v::<jchar>),
+		CallStaticCharMethodA: call::call_static_method_a::<jchar>,
+		CallStaticShortMethod: call::call_static_method::<jshort>,
+		CallStaticShortMethodV: va_list!(call::call_static_method_v::<jshort>),
+		CallStaticShortMethodA: call::call_static_method_a::<jshort>,
+		CallStaticIntMethod: call::call_static_method::<jint>,
+		CallStaticIntMethodV: va_list!(call::call_static_method_v::<jint>),
+		CallStaticIntMethodA: call::call_static_method_a::<jint>,
+		CallStaticLongMethod: call::call_static_method::<jlong>,
+		CallStaticLongMethodV: va_list!(call::call_static_method_v::<jlong>),
+		CallStaticLongMethodA: call::call_static_method_a::<jlong>,
+		CallStaticFloatMethod: call::call_static_method::<jfloat>,
+		CallStaticFloatMethodV: va_list!(call::call_static_method_v::<jfloat>),
+		CallStaticFloatMethodA: call::call_static_method_a::<jfloat>,
+		CallStaticDoubleMethod: call::call_static_method::<jdouble>,
+		CallStaticDoubleMethodV: va_list!(call::call_static_method_v::<jdouble>),
+		CallStaticDoubleMethodA: call::call_static_method_a::<jdouble>,
+		CallStaticVoidMethod: call::call_static_method::<()>,
+		CallStaticVoidMethodV: va_list!(call::call_static_method_v::<()>),
+		CallStaticVoidMethodA: call::call_static_method_a::<()>,
+		GetStaticFieldID: class::get_static_field_id,
+		GetStaticObjectField: field::get_static_field::<jobject>,
+		GetStaticBooleanField: field::get_static_field::<jboolean>,
+		GetStaticByteField: field::get_static_field::<jbyte>,
+		GetStaticCharField: field::get_static_field::<jchar>,
+		GetStaticShortField: field::get_static_field::<jshort>,
+		GetStaticIntField: field::get_static_field::<jint>,
+		GetStaticLongField: field::get_static_field::<jlong>,
+		GetStaticFloatField: field::get_static_field::<jfloat>,
+		GetStaticDoubleField: field::get_static_field::<jdouble>,
+		SetStaticObjectField: field::set_static_field::<jobject>,
+		SetStaticBooleanField: field::set_static_field::<jboolean>,
+		SetStaticByteField: field::set_static_field::<jbyte>,
+		SetStaticCharField: field::set_static_field::<jchar>,
+		SetStaticShortField: field::set_static_field::<jshort>,
+		SetStaticIntField: field::set_static_field::<jint>,
+		SetStaticLongField: field::set_static_field::<jlong>,
+		SetStaticFloatField: field::set_static_field::<jfloat>,
+		SetStaticDoubleField: field::set_static_field::<jdouble>,
+		NewString: string::new_string,
+		GetStringLength: string::get_string_length,
+		GetStringChars: string::get_string_chars,
+		ReleaseStringChars: string::release_string_chars,
+		NewStringUTF: string::new_string_utf,
+		GetStringUTFLength: string::get_string_utf_length,
+		GetStringUTFChars: string::get_string_utf_chars,
+		ReleaseStringUTFChars: string::release_string_utf_chars,
+		GetArrayLength: array::get_array_length,
+		NewObjectArray: array::new_object_array,
+		GetObjectArrayElement: array::get_object_array_element,
+		SetObjectArrayElement: array::set_object_array_element,
+		NewBooleanArray: array::new_array::<jboolean>,
+		NewByteArray: array::new_array::<jbyte>,
+		NewCharArray: array::new_array::<jchar>,
+		NewShortArray: array::new_array::<jshort>,
+		NewIntArray: array::new_array::<jint>,
+		NewLongArray: array::new_array::<jlong>,
+		NewFloatArray: array::new_array::<jfloat>,
+		NewDoubleArray: array::new_array::<jdouble>,
+		GetBooleanArrayElements: array::get_array_elements::<jboolean>,
+		GetByteArrayElements: array::get_array_elements::<jbyte>,
+		GetCharArrayElements: array::get_array_elements::<jchar>,
+		GetShortArrayElements: array::get_array_elements::<jshort>,
+		GetIntArrayElements: array::get_array_elements::<jint>,
+		GetLongArrayElements: array::get_array_elements::<jlong>,
+		GetFloatArrayElements: array::get_array_elements::<jfloat>,
+		GetDoubleArrayElements: array::get_array_elements::<jdouble>,
+		ReleaseBooleanArrayElements: array::release_array_elements::<jboolean>,
+		ReleaseByteArrayElements: array::release_array_elements::<jbyte>,
+		ReleaseCharArrayElements: array::release_array_elements::<jchar>,
+		ReleaseShortArrayElements: array::release_array_elements::<jshort>,
+		ReleaseIntArrayElements: array::release_array_elements::<jint>,
+		ReleaseLongArrayElements: array::release_array_elements::<jlong>,
+		ReleaseFloatArrayElements: array::release_array_elements::<jfloat>,
+		ReleaseDoubleArrayElements: array::release_array_elements::<jdouble>,
+		GetBooleanArrayRegion: array::get_array_region::<jboolean>,
+		GetByteArrayRegion: array::get_array_region::<jbyte>,
+		GetCharArrayRegion: array::get_array_region::<jchar>,
+		GetShortArrayRegion: array::get_array_region::<jshort>,
+		GetIntArrayRegion: array::get_array_region::<jint>,
+		GetLongArrayRegion: array::get_array_region::<jlong>,
+		GetFloatArrayRegion: array::get_array_region::<jfloat>,
+		GetDoubleArrayRegion: array::get_array_region::<jdouble>,
+		SetBooleanArrayRegion: array::set_array_region::<jboolean>,
+		SetByteArrayRegion: array::set_array_region::<jbyte>,
+		SetCharArrayRegion: array::set_array_region::<jchar>,
+		SetShortArrayRegion: array::set_array_region::<jshort>,
+		SetIntArrayRegion: array::set_array_region::<jint>,
+		SetLongArrayRegion: array::set_array_region::<jlong>,
+		SetFloatArrayRegion: array::set_array_region::<jfloat>,
+		SetDoubleArrayRegion: array::set_array_region::<jdouble>,
+		RegisterNatives: class::register_natives,
+		UnregisterNatives: class::unregister_natives,
+		MonitorEnter: reference::monitor_enter,
+		MonitorExit: reference::monitor_exit,
+		GetJavaVM: class::get_java_vm,
+		GetStringRegion: string::get_string_region,
+		GetStringUTFRegion: string::get_string_utf_region,
+		GetPrimitiveArrayCritical: array::get_primitive_array_critical,
+		ReleasePrimitiveArrayCritical: array::release_primitive_array_critical,
+		GetStringCritical: string::get_string_critical,
+		ReleaseStringCritical: string::release_string_critical,
+		NewWeakGlobalRef: reference::new_weak_global_ref,
+		DeleteWeakGlobalRef: reference::delete_weak_global_ref,
+		ExceptionCheck: exception::exception_check,
+		NewDirectByteBuffer: class::new_direct_byte_buffer,
+		GetDirectBufferAddress: class::get_direct_buffer_address,
+		GetDirectBufferCapacity: class::get_direct_buffer_capacity,
+		GetObjectRefType: reference::get_object_ref_type,
+		GetModule: class::get_module,
+		IsVirtualThread: class::is_virtual_thread,
+		GetStringUTFLengthAsLong: string::get_string_utf_length_as_long,
+	},
+});
@@ -0,0 +1,81 @@
+use crate::Reference;
+use jni_sys::jobject;
+use std::ptr::null_mut;
+
+/// The references which native code running on a thread holds, every native call gets its own frame.
+/// All of these are roots, the thread has to mark and remap them like its stack.
+#[derive(Default)]
+pub struct NativeFrames {
+	locals: Vec<Reference>,
+	frames: Vec<usize>,
+	/// The throwable which native code has thrown and not cleared yet.
+	pub exception: Option<Reference>,
+	/// An error which happened inside of a JNI function, the native method fails with it once it returns.
+	pub error: Option<eyre::Report>,
+}
+
+impl NativeFrames {
+	pub fn push_frame(&mut self, capacity: usize) {
+		self.frames.push(self.locals.len());
+		self.locals.reserve(capacity);
+	}
+
+	pub fn pop_frame(&mut self) {
+		let start = self
+			.frames
+			.pop()
+			.expect("Popped a local frame which was never pushed");
+		self.locals.truncate(start);
+	}
+
+	pub fn ensure_capacity(&mut self, capacity: usize) {
+		self.locals.reserve(capacity);
+	}
+
+	/// Creates a local reference in the current frame, null references stay null.
+	pub fn new_local(&mut self, reference: Reference) -> jobject {
+		if reference.is_null() {
+			return null_mut();
+		}
+
+		self.locals.push(reference);
+		self.locals.len() as jobject
+	}
+
+	pub fn get(&self, handle: jobject) -> Reference {
+		match (handle as usize).checked_sub(1) {
+			Some(index) => self.locals.get(index).copied().unwrap_or(Reference::NULL),
+			None => Reference::NULL,
+		}
+	}
+
+	pub fn delete_local(&mut self, handle: jobject) {
+		if let Some(index) = (handle as usize).checked_sub(1) {
+			if let Some(reference) = self.locals.get_mut(index) {
+				*reference = Reference::NULL;
+			}
+		}
+	}
+
+	pub fn is_local(&self, handle: jobject) -> bool {
+		(handle as usize)
+			.checked_sub(1)
+			.is_some_and(|index| index < self.locals.len())
+	}
+
+	pub fn visit_refs(&self, mut visitor: impl FnMut(Reference)) {
+		for reference in self.locals.iter().chain(&self.exception) {
+			if !reference.is_null() {
+				visitor(*reference);
+			}
+		}
+	}
+
+	pub fn map_refs(&mut self, mut mapper: impl FnMut(Reference) -> Reference) {
+		for reference in self.locals.iter_mut().chain(&mut self.exception) {
+			if !reference.is_null() {
+				*reference = mapper(*reference);
+			}
+		}
+	}
+}
@@ -1,11 +1,14 @@
-use crate::{AnyValue, Reference, Vm};
+use crate::native::invocation::enter_env;
+use crate::native::JNIEnv;
+use crate::{AnyValue, Reference, Runtime, Throwable};
+use eyre::{bail, eyre, ContextCompat};
+use jni_sys::jobject;
 use rvm_core::Kind;
 use std::mem::transmute;
-use std::sync::Arc;
 
 pub struct JNIFunction {
 	function: extern "C" fn(),
-	signature: Option<JNIFunctionSignature>,
+	signature: JNIFunctionSignature,
 }
 
 #[derive(Clone)]
@@ -20,107 +23,225 @@ impl JNIFunction {
 	pub unsafe fn new(function: extern "C" fn(), desc: JNIFunctionSignature) -> Self {
 		Self {
 			function,
-			signature: Some(desc),
+			signature: desc,
 		}
 	}
 
+	/// Calls the native function, `this` is the instance or the class object for static methods.
 	pub fn call(
 		&self,
-		runtime: &Vm,
+		runtime: &mut Runtime,
+		this: Reference,
 		parameters: &[AnyValue],
-		returns: Option<Kind>,
-	) -> Option<AnyValue> {
-		todo!("JNIEnv is blocking")
-		//if let Some(signature) = &self.signature {
-		// 			assert_eq!(returns, signature.returns);
-		// 			assert_eq!(
-		// 				signature.parameters.len(),
-		// 				parameters.len(),
-		// 				"Parameter count missmatch"
-		// 			);
-		// 			for (i, kind) in signature.parameters.iter().enumerate() {
-		// 				assert_eq!(
-		// 					parameters[i].kind(),
-		// 					*kind,
-		// 					"Parameter {i} kind does not match"
-		// 				);
-		// 			}
-		// 		}
-		//
-		// 		let runtime_ptr = Arc::into_raw(runtime.clone());
-		// 		macro_rules! param {
-		// 			($($V:ty),*) => {
-		// 				unsafe {
-		// 					let f = transmute::<extern "C" fn(), extern "C" fn(*const Runtime, $($V),*) -> usize>(self.function);
-		// 					let mut i = 0usize;
-		// 					f(
-		// 						runtime_ptr,
-		// 						$(
-		// 						self.param((&mut i) as &mut $V, parameters),
-		// 						)*
-		// 					)
-		// 				}
-		// 			};
-		// 		}
-		//
-		// 		let value = match parameters.len() {
-		// 			#[allow(unused)]
-		// 			0 => param!(),
-		// 			1 => param!(usize),
-		// 			2 => param!(usize, usize),
-		// 			3 => param!(usize, usize, usize),
-		// 			4 => param!(usize, usize, usize, usize),
-		// 			5 => param!(usize, usize, usize, usize, usize),
-		// 			6 => param!(usize, usize, usize, usize, usize, usize),
-		// 			_ => {
-		// 				panic!()
-		// 			}
-		// 		};
-		//
-		// 		unsafe {
-		// 			// We decrement our strong reference count
-		// 			Arc::from_raw(runtime_ptr);
-		// 		}
-		//
-		// 		returns.map(|returns| Self::convert_from(value, returns))
+	) -> eyre::Result<Option<AnyValue>> {
+		let signature = &self.signature;
+		if signature.parameters.len() != parameters.len() {
+			bail!(
+				"Expected {} parameters but got {}",
+				signature.parameters.len(),
+				parameters.len()
+			);
+		}
+		for (i, (kind, value)) in signature.parameters.iter().zip(parameters).enumerate() {
+			if *kind != value.kind() {
+				bail!(
+					"Parameter {i} is {} but the method takes {kind}",
+					value.kind()
+				);
+			}
+		}
+
+		let vm = runtime.vm.clone();
+		let thread = runtime
+			.thread
+			.as_deref_mut()
+			.wrap_err("Native methods can only be called from a java thread")?;
+		let mut env = JNIEnv::new(vm, thread);
+
+		env.frames().push_frame(parameters.len() + 1);
+		let returned = enter_env(&mut env, |env| self.invoke(env, this, parameters));
+		let frames = env.frames();
+		frames.pop_frame();
+
+		let error = frames.error.take();
+		let exception = frames.exception.take();
+		if let Some(error) = error {
+			return Err(error.wrap_err("Error inside of a JNI function"));
+		}
+		// The pending exception gets thrown like the one of a rust binding.
+		if let Some(exception) = exception {
+			return Err(Throwable::from_reference(env.vm(), exception)?.into());
+		}
+
+		returned
 	}
 
-	//unsafe fn param(&self, idx: &mut usize, parameters: &[AnyValue]) -> usize {
-	//	let index = *idx;
-	//	*idx += 1;
-	//	let value = parameters[index];
-	//	if let Some(signature) = &self.signature {
-	//		let kind = signature.parameters[index];
-	//		if value.kind() != kind {
-	//			panic!("Missmatched types, {} != {}", value.kind(), kind);
-	//		}
-	//	}
-	//	Self::convert_to(value)
-	//}
-	//fn convert_from(value: usize, kind: Kind) -> AnyValue {
-	//	match kind {
-	//		Kind::Byte => AnyValue::Byte(value as _),
-	//		Kind::Short => AnyValue::Short(value as _),
-	//		Kind::Int => AnyValue::Int(value as _),
-	//		Kind::Long => AnyValue::Long(value as _),
-	//		Kind::Char => AnyValue::Char(value as _),
-	//		Kind::Float => AnyValue::Float(value as _),
-	//		Kind::Double => AnyValue::Double(value as _),
-	//		Kind::Boolean => AnyValue::Boolean(value != 0),
-	//		Kind::Reference => AnyValue::Reference(Reference::new(value as _)),
-	//	}
-	//}
-	//fn convert_to(value: AnyValue) -> usize {
-	//	match value {
-	//		AnyValue::Byte(v) => v as usize,
-	//		AnyValue::Short(v) => v as usize,
-	//		AnyValue::Int(v) => v as usize,
-	//		AnyValue::Long(v) => v as usize,
-	//		AnyValue::Char(v) => v as usize,
-	//		AnyValue::Float(v) => v as usize,
-	//		AnyValue::Double(v) => v as usize,
-	//		AnyValue::Boolean(v) => v as usize,
-	//		AnyValue::Reference(v) => v.0 as usize,
-	//	}
-	//}
+	fn invoke(
+		&self,
+		env: &mut JNIEnv,
+		this: Reference,
+		parameters: &[AnyValue],
+	) -> eyre::Result<Option<AnyValue>> {
+		let mut arguments = NativeArguments::default();
+		arguments.push_integer(env.as_raw() as usize);
+		arguments.push_integer(env.new_local(this) as usize);
+		for value in parameters {
+			match *value {
+				AnyValue::Byte(v) => arguments.push_integer(v as usize),
+				AnyValue::Short(v) => arguments.push_integer(v as usize),
+				AnyValue::Int(v) => arguments.push_integer(v as usize),
+				AnyValue::Long(v) => arguments.push_integer(v as usize),
+				AnyValue::Char(v) => arguments.push_integer(v as usize),
+				AnyValue::Boolean(v) => arguments.push_integer(v as usize),
+				AnyValue::Float(v) => arguments.push_float(v.to_bits() as u64),
+				AnyValue::Double(v) => arguments.push_float(v.to_bits()),
+				AnyValue::Reference(v) => arguments.push_integer(env.new_local(v) as usize),
+			}
+		}
+
+		let returns = self.signature.returns;
+		let float = returns.is_some_and(|v| v.is_floating());
+		let value = unsafe { env.native(|| arguments.call(self.function, float))? };
+
+		Ok(returns.map(|kind| match kind {
+			Kind::Byte => AnyValue::Byte(value as i8),
+			Kind::Short => AnyValue::Short(value as i16),
+			Kind::Int => AnyValue::Int(value as i32),
+			Kind::Long => AnyValue::Long(value as i64),
+			Kind::Char => AnyValue::Char(value as u16),
+			Kind::Boolean => AnyValue::Boolean(value as u8 != 0),
+			Kind::Float => AnyValue::Float(f32::from_bits(value as u32)),
+			Kind::Double => AnyValue::Double(f64::from_bits(value)),
+			Kind::Reference => AnyValue::Reference(env.get(value as usize as jobject)),
+		}))
+	}
+}
+
+#[cfg(all(target_arch = "x86_64", not(windows)))]
+const INTEGER_REGISTERS: usize = 6;
+#[cfg(all(target_arch = "aarch64", not(target_vendor = "apple"), not(windows)))]
+const INTEGER_REGISTERS: usize = 8;
+const FLOAT_REGISTERS: usize = 8;
+const STACK_SLOTS: usize = 16;
+
+/// The arguments of a native call, sorted into the registers the C calling convention passes them in.
+///
+/// Integers and floats get their registers assigned separately, so once every register is filled the
+/// remaining arguments all land on the stack in their original order. This lets us call any descriptor
+/// through a single function type, the callee ignores the registers and stack slots it does not read.
+#[derive(Default)]
+struct NativeArguments {
+	integers: Vec<usize>,
+	floats: Vec<u64>,
+	stack: Vec<usize>,
+}
+
+impl NativeArguments {
+	fn push_integer(&mut self, value: usize) {
+		if self.integers.len() < INTEGER_REGISTERS {
+			self.integers.push(value);
+		} else {
+			self.stack.push(value);
+		}
+	}
+
+	/// Floats get passed in the low bits of the register or stack slot.
+	fn push_float(&mut self, bits: u64) {
+		if self.floats.len() < FLOAT_REGISTERS {
+			self.floats.push(bits);
+		} else {
+			self.stack.push(bits as usize);
+		}
+	}
+
+	/// Returns the raw bits of the return register, `float` picks the floating point one.
+	unsafe fn call(self, function: extern "C" fn(), float: bool) -> eyre::Result<u64> {
+		if self.stack.len() > STACK_SLOTS {
+			return Err(eyre!(
+				"Native methods with more than {STACK_SLOTS} stack arguments are not supported"
+			));
+		}
+
+		let mut integers = [0; INTEGER_REGISTERS];
+		integers[..self.integers.len()].copy_from_slice(&self.integers);
+		let mut floats = [0.0; FLOAT_REGISTERS];
+		for (register, bits) in floats.iter_mut().zip(&self.floats) {
+			*register = f64::from_bits(*bits);
+		}
+		let mut stack = [0; STACK_SLOTS];
+		stack[..self.stack.len()].copy_from_slice(&self.stack);
+
+		Ok(if float {
+			invoke::<f64>(function, &integers, &floats, &stack)?.to_bits()
+		} else {
+			invoke::<usize>(function, &integers, &floats, &stack)? as u64
+		})
+	}
+}
+
+#[cfg(not(any(
+	all(target_arch = "x86_64", not(windows)),
+	all(target_arch = "aarch64", not(target_vendor = "apple"), not(windows))
+)))]
+const INTEGER_REGISTERS: usize = 0;
+
+#[cfg(not(any(
+	all(target_arch = "x86_64", not(windows)),
+	all(target_arch = "aarch64", not(target_vendor = "apple"), not(windows))
+)))]
+unsafe fn invoke<R>(
+	_: extern "C" fn(),
+	_: &[usize; INTEGER_REGISTERS],
+	_: &[f64; FLOAT_REGISTERS],
+	_: &[usize; STACK_SLOTS],
+) -> eyre::Result<R> {
+	bail!("Native calls are not supported on this platform")
+}
+
+#[rustfmt::skip]
+#[cfg(all(target_arch = "x86_64", not(windows)))]
+unsafe fn invoke<R>(
+	function: extern "C" fn(),
+	i: &[usize; INTEGER_REGISTERS],
+	f: &[f64; FLOAT_REGISTERS],
+	s: &[usize; STACK_SLOTS],
+) -> eyre::Result<R> {
+	type Function<R> = unsafe extern "C" fn(
+		usize, usize, usize, usize, usize, usize,
+		f64, f64, f64, f64, f64, f64, f64, f64,
+		usize, usize, usize, usize, usize, usize, usize, usize,
+		usize, usize, usize, usize, usize, usize, usize, usize,
+	) -> R;
+
+	let function: Function<R> = transmute(function);
+	Ok(function(
+		i[0], i[1], i[2], i[3], i[4], i[5],
+		f[0], f[1], f[2], f[3], f[4], f[5], f[6], f[7],
+		s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7],
+		s[8], s[9], s[10], s[11], s[12], s[13], s[14], s[15],
+	))
+}
+
+#[rustfmt::skip]
+#[cfg(all(target_arch = "aarch64", not(target_vendor = "apple"), not(windows)))]
+unsafe fn invoke<R>(
+	function: extern "C" fn(),
+	i: &[usize; INTEGER_REGISTERS],
+	f: &[f64; FLOAT_REGISTERS],
+	s: &[usize; STACK_SLOTS],
+) -> eyre::Result<R> {
+	type Function<R> = unsafe extern "C" fn(
+		usize, usize, usize, usize, usize, usize, usize, usize,
+		f64, f64, f64, f64, f64, f64, f64, f64,
+		usize, usize, usize, usize, usize, usize, usize, usize,
+		usize, usize, usize, usize, usize, usize, usize, usize,
+	) -> R;
+
+	let function: Function<R> = transmute(function);
+	Ok(function(
+		i[0], i[1], i[2], i[3], i[4], i[5], i[6], i[7],
+		f[0], f[1], f[2], f[3], f[4], f[5], f[6], f[7],
+		s[0], s[1], s[2], s[3], s[4], s[5], s[6], s[7],
+		s[8], s[9], s[10], s[11], s[12], s[13], s[14], s[15],
+	))
 }
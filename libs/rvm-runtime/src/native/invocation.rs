@@ -0,0 +1,435 @@
+use crate::engine::{Engine, ThreadConfig};
+use crate::native::JNIEnv;
+use crate::{
+	ClassSource, DirectoryClassSource, InnerVm, JarClassSource, ThreadContext, Vm, VmConfig,
+};
+use ahash::HashMap;
+use eyre::{bail, eyre, Context};
+use jni_sys::*;
+use parking_lot::Mutex;
+use rvm_gc::GcMode;
+use std::cell::{Cell, RefCell};
+use std::ffi::{c_void, CStr};
+use std::fs::read;
+use std::path::PathBuf;
+use std::ptr::null_mut;
+use std::sync::{Arc, Weak};
+
+type RawEnv = jni_sys::JNIEnv;
+
+/// The `JavaVM*` which native code sees, every vm owns one.
+#[repr(C)]
+pub struct JavaVM {
+	functions: *const JNIInvokeInterface_,
+	vm: Weak<InnerVm>,
+}
+
+// The table only holds function pointers.
+unsafe impl Send for JavaVM {}
+unsafe impl Sync for JavaVM {}
+
+struct InvokeTable(JNIInvokeInterface_);
+
+unsafe impl Sync for InvokeTable {}
+
+static INVOKE_FUNCTIONS: InvokeTable = InvokeTable(JNIInvokeInterface_ {
+	reserved: JNIInvokeInterface__reserved {
+		reserved0: null_mut(),
+		reserved1: null_mut(),
+		reserved2: null_mut(),
+		DestroyJavaVM: destroy_java_vm,
+		AttachCurrentThread: attach_current_thread,
+		DetachCurrentThread: detach_current_thread,
+		GetEnv: get_env,
+		AttachCurrentThreadAsDaemon: attach_current_thread_as_daemon,
+	},
+});
+
+/// The vms created through [create_java_vm], they live until they get destroyed.
+static CREATED: Mutex<Vec<Vm>> = Mutex::new(Vec::new());
+
+thread_local! {
+	/// The environment of the native code which currently runs on this thread.
+	static CURRENT_ENV: Cell<*mut JNIEnv> = const { Cell::new(null_mut()) };
+	static ATTACHED: RefCell<Option<AttachedThread>> = const { RefCell::new(None) };
+}
+
+/// A native thread which attached itself to a vm, it gets detached when the thread exits.
+struct AttachedThread {
+	// The environment points into the thread context, so it has to go first.
+	env: Box<JNIEnv>,
+	thread: Option<Box<dyn ThreadContext>>,
+}
+
+impl Drop for AttachedThread {
+	fn drop(&mut self) {
+		if let Some(thread) = self.thread.take() {
+			thread.detach();
+		}
+	}
+}
+
+impl JavaVM {
+	pub(crate) fn new(vm: Weak<InnerVm>) -> JavaVM {
+		JavaVM {
+			functions: &INVOKE_FUNCTIONS.0,
+			vm,
+		}
+	}
+
+	pub fn as_raw(&self) -> *mut jni_sys::JavaVM {
+		self as *const JavaVM as *mut jni_sys::JavaVM
+	}
+
+	/// # Safety
+	/// The pointer has to come from [JavaVM::as_raw].
+	pub unsafe fn from_raw<'a>(vm: *mut jni_sys::JavaVM) -> &'a JavaVM {
+		&*(vm as *const JavaVM)
+	}
+
+	/// The vm is gone once it got destroyed and the last thread stopped using it.
+	pub fn vm(&self) -> Option<Vm> {
+		self.vm.upgrade().map(|inner| Vm { inner })
+	}
+}
+
+/// Makes `env` the environment which [get_env] returns while `func` runs.
+pub(crate) fn enter_env<R>(env: &mut JNIEnv, func: impl FnOnce(&mut JNIEnv) -> R) -> R {
+	let previous = CURRENT_ENV.replace(env as *mut JNIEnv);
+	let output = func(env);
+	CURRENT_ENV.set(previous);
+	output
+}
+
+/// The options of `JNI_CreateJavaVM` which the vm understands.
+struct InitOptions {
+	config: VmConfig,
+	boot_class_path: Vec<PathBuf>,
+	class_path: Vec<PathBuf>,
+	verbose_gc: bool,
+}
+
+const DEFAULT_HEAP_SIZE: usize = 64 * 1024 * 1024;
+
+unsafe fn parse_options(args: &JavaVMInitArgs) -> eyre::Result<InitOptions> {
+	let mut options = InitOptions {
+		config: VmConfig {
+			heap_size: DEFAULT_HEAP_SIZE,
+			gc_mode: GcMode::default(),
+			properties: HashMap::default(),
+			verify: true,
+			verify_boot: false,
+		},
+		boot_class_path: vec![],
+		class_path: vec![],
+		verbose_gc: false,
+	};
+
+	for i in 0..args.nOptions.max(0) as usize {
+		let option = &*args.options.add(i);
+		let string = CStr::from_ptr(option.optionString)
+			.to_str()
+			.wrap_err("Option is not valid UTF-8")?;
+
+		if let Some(size) = string.strip_prefix("-Xmx") {
+			options.config.heap_size = parse_size(size)?;
+		} else if let Some(property) = string.strip_prefix("-D") {
+			let (key, value) = property.split_once('=').unwrap_or((property, ""));
+			if key == "java.class.path" {
+				options.class_path.extend(
+					value
+						.split(':')
+						.filter(|path| !path.is_empty())
+						.map(PathBuf::from),
+				);
+			}
+			options
+				.config
+				.properties
+				.insert(key.to_string(), value.to_string());
+		} else if let Some(paths) = string.strip_prefix("-Xbootclasspath/a:") {
+			options.boot_class_path.extend(
+				paths
+					.split(':')
+					.filter(|path| !path.is_empty())
+					.map(PathBuf::from),
+			);
+		} else if matches!(string, "-Xverify:none" | "-noverify") {
+			options.config.verify = false;
+			options.config.verify_boot = false;
+		} else if string == "-Xverify:remote" {
+			options.config.verify = true;
+			options.config.verify_boot = false;
+		} else if string == "-Xverify:all" {
+			options.config.verify = true;
+			options.config.verify_boot = true;
+		} else if string == "-verbose" || string == "-verbose:gc" {
+			options.verbose_gc = true;
+		} else if string.starts_with("-verbose:") {
+			// There is nothing to log about classes and jni yet.
+		} else if matches!(string, "vfprintf" | "exit" | "abort") {
+			// The hooks are never called, the vm prints with the standard library and panics.
+		} else if !args.ignoreUnrecognized {
+			bail!("Unrecognized option {string}");
+		}
+	}
+
+	Ok(options)
+}
+
+/// Parses the size of `-Xmx`, which is in bytes unless it ends with `k`, `m` or `g`.
+fn parse_size(size: &str) -> eyre::Result<usize> {
+	let (number, unit) = match size.char_indices().last() {
+		Some((i, 'k' | 'K')) => (&size[..i], 1024),
+		Some((i, 'm' | 'M')) => (&size[..i], 1024 * 1024),
+		Some((i, 'g' | 'G')) => (&size[..i], 1024 * 1024 * 1024),
+		_ => (size, 1),
+	};
+
+	let number: usize = number
+		.parse()
+		.wrap_err_with(|| format!("Invalid heap size {size}"))?;
+	number
+		.checked_mul(unit)
+		.ok_or_else(|| eyre!("Heap size {size} is too large"))
+}
+
+/// A directory or a jar of the class path.
+fn class_source(path: PathBuf) -> eyre::Result<Box<dyn ClassSource>> {
+	if path.is_dir() {
+		Ok(Box::new(DirectoryClassSource::new(path)?))
+	} else {
+		let data = read(&path).wrap_err_with(|| format!("Reading {}", path.display()))?;
+		Ok(Box::new(JarClassSource::new(data)?))
+	}
+}
+
+fn create_vm(options: InitOptions, engine: Box<dyn Engine>) -> eyre::Result<Vm> {
+	let vm = Vm::with_config(options.config, engine);
+	for path in options.boot_class_path {
+		vm.classes.add_boot_source(class_source(path)?);
+	}
+	for path in options.class_path {
+		vm.classes.add_source(class_source(path)?);
+	}
+
+	if options.verbose_gc {
+		vm.gc.subscribe(|statistics| {
+			println!(
+				"[GC #{}] {}K->{}K({}K)",
+				statistics.collection,
+				statistics.heap_before / 1024,
+				statistics.heap_after / 1024,
+				statistics.heap_size / 1024
+			);
+		});
+	}
+
+	Ok(vm)
+}
+
+/// The body of `JNI_CreateJavaVM`, engines export it with [crate::export_jni_create_java_vm]
+/// because the runtime can't create an engine on its own.
+///
+/// # Safety
+/// The pointers have to be valid as described by the JNI invocation API.
+pub unsafe fn create_java_vm(
+	pvm: *mut *mut jni_sys::JavaVM,
+	penv: *mut *mut c_void,
+	args: *mut c_void,
+	engine: impl FnOnce() -> Box<dyn Engine>,
+) -> jint {
+	let args = &*(args as *mut JavaVMInitArgs);
+	if !is_supported(args.version) {
+		return JNI_EVERSION;
+	}
+
+	if ATTACHED.with_borrow(|attached| attached.is_some()) {
+		return JNI_EEXIST;
+	}
+
+	let options = match parse_options(args) {
+		Ok(options) => options,
+		Err(error) => {
+			eprintln!("Could not create the java vm: {error:?}");
+			return JNI_EINVAL;
+		}
+	};
+
+	let vm = match create_vm(options, engine()) {
+		Ok(vm) => vm,
+		Err(error) => {
+			eprintln!("Could not create the java vm: {error:?}");
+			return JNI_ERR;
+		}
+	};
+
+	*pvm = vm.java_vm.as_raw();
+	*penv = match attach(&vm, "main".to_string()) {
+		Ok(env) => env as *mut c_void,
+		Err(error) => return error,
+	};
+	CREATED.lock().push(vm);
+	JNI_OK
+}
+
+pub(super) fn is_supported(version: jint) -> bool {
+	matches!(
+		version,
+		JNI_VERSION_1_1 | JNI_VERSION_1_2 | JNI_VERSION_1_4 | JNI_VERSION_1_6 | JNI_VERSION_1_8
+	)
+}
+
+/// Attaches the current thread to `vm`, a thread which is already attached keeps its environment.
+/// The thread stays in native code, collections only wait for it while it calls into the vm.
+fn attach(vm: &Vm, name: String) -> Result<*mut RawEnv, jint> {
+	ATTACHED.with_borrow_mut(|attached| {
+		if let Some(attached) = attached {
+			// A thread can only be attached to one vm at a time.
+			if !Arc::ptr_eq(&attached.env.vm().inner, &vm.inner) {
+				return Err(JNI_ERR);
+			}
+			return Ok(attached.env.as_raw());
+		}
+
+		let mut thread = vm.attach_thread(ThreadConfig { name });
+		let mut env = Box::new(JNIEnv::new(vm.clone(), &mut *thread));
+		// Local references of an attached thread live until it detaches.
+		env.frames().push_frame(16);
+		unsafe { thread.enter_native() };
+		let attached = attached.insert(AttachedThread {
+			env,
+			thread: Some(thread),
+		});
+		Ok(attached.env.as_raw())
+	})
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn JNI_GetDefaultJavaVMInitArgs(args: *mut c_void) -> jint {
+	let args = &mut *(args as *mut JavaVMInitArgs);
+	if !is_supported(args.version) {
+		return JNI_EVERSION;
+	}
+
+	args.nOptions = 0;
+	args.options = null_mut();
+	args.ignoreUnrecognized = false;
+	JNI_OK
+}
+
+#[no_mangle]
+pub unsafe extern "system" fn JNI_GetCreatedJavaVMs(
+	vm_buf: *mut *mut jni_sys::JavaVM,
+	buf_len: jsize,
+	n_vms: *mut jsize,
+) -> jint {
+	let created = CREATED.lock();
+	for (i, vm) in created.iter().take(buf_len.max(0) as usize).enumerate() {
+		*vm_buf.add(i) = vm.java_vm.as_raw();
+	}
+	if !n_vms.is_null() {
+		*n_vms = created.len() as jsize;
+	}
+	JNI_OK
+}
+
+/// Expands to the `JNI_CreateJavaVM` export of a vm which runs on the engine `$engine`, the
+/// engine crate is the one which gets built as the `libjvm` of C hosts.
+#[macro_export]
+macro_rules! export_jni_create_java_vm {
+	($engine:expr) => {
+		#[no_mangle]
+		pub unsafe extern "system" fn JNI_CreateJavaVM(
+			pvm: *mut *mut $crate::native::jni_sys::JavaVM,
+			penv: *mut *mut std::ffi::c_void,
+			args: *mut std::ffi::c_void,
+		) -> $crate::native::jni_sys::jint {
+			$crate::native::create_java_vm(pvm, penv, args, || Box::new($engine))
+		}
+	};
+}
+
+/// Detaches the current thread and forgets about the vm, it gets dropped once the threads which
+/// still run on it are done.
+unsafe extern "system" fn destroy_java_vm(vm: *mut jni_sys::JavaVM) -> jint {
+	let java_vm = JavaVM::from_raw(vm);
+	detach_current_thread(vm);
+	CREATED
+		.lock()
+		.retain(|vm| !std::ptr::eq(&vm.java_vm, java_vm));
+	JNI_OK
+}
+
+unsafe extern "system" fn attach_current_thread(
+	vm: *mut jni_sys::JavaVM,
+	penv: *mut *mut c_void,
+	args: *mut c_void,
+) -> jint {
+	let Some(vm) = JavaVM::from_raw(vm).vm() else {
+		return JNI_ERR;
+	};
+
+	let mut name = "native".to_string();
+	let args = args as *mut JavaVMAttachArgs;
+	if !args.is_null() {
+		if !is_supported((*args).version) {
+			return JNI_EVERSION;
+		}
+		if !(*args).name.is_null() {
+			name = CStr::from_ptr((*args).name).to_string_lossy().into_owned();
+		}
+	}
+
+	match attach(&vm, name) {
+		Ok(env) => {
+			*penv = env as *mut c_void;
+			JNI_OK
+		}
+		Err(error) => error,
+	}
+}
+
+/// There are no daemon threads, the vm never waits for other threads when it gets destroyed.
+unsafe extern "system" fn attach_current_thread_as_daemon(
+	vm: *mut jni_sys::JavaVM,
+	penv: *mut *mut c_void,
+	args: *mut c_void,
+) -> jint {
+	attach_current_thread(vm, penv, args)
+}
+
+unsafe extern "system" fn detach_current_thread(_: *mut jni_sys::JavaVM) -> jint {
+	if !CURRENT_ENV.get().is_null() {
+		// A thread can't detach while it is running java code.
+		return JNI_ERR;
+	}
+
+	// Dropping the thread detaches it.
+	match ATTACHED.take() {
+		Some(_) => JNI_OK,
+		None => JNI_EDETACHED,
+	}
+}
+
+unsafe extern "system" fn get_env(
+	_: *mut jni_sys::JavaVM,
+	penv: *mut *mut c_void,
+	version: jint,
+) -> jint {
+	*penv = null_mut();
+	if !is_supported(version) {
+		return JNI_EVERSION;
+	}
+
+	let env = match CURRENT_ENV.get() {
+		env if !env.is_null() => (*env).as_raw(),
+		_ => match ATTACHED.with_borrow_mut(|attached| attached.as_mut().map(|a| a.env.as_raw())) {
+			Some(env) => env,
+			None => return JNI_EDETACHED,
+		},
+	};
+
+	*penv = env as *mut c_void;
+	JNI_OK
+}
@@ -0,0 +1,79 @@
+use crate::engine::ThreadConfig;
+use crate::native::invocation::{enter_env, is_supported};
+use crate::native::{JNIEnv, JNILinker, JNIOnLoad};
+use crate::{Runtime, ThreadContext};
+use eyre::{bail, eyre};
+use std::path::{Path, PathBuf};
+use std::ptr::null_mut;
+
+impl<'thread> Runtime<'thread> {
+	/// Finds a library in the `java.library.path` and loads it, like `System.loadLibrary`.
+	pub fn load_library(&mut self, name: &str) -> eyre::Result<()> {
+		let path = self
+			.find_library(name)
+			.ok_or_else(|| eyre!("UnsatisfiedLinkError: no {name} in java.library.path"))?;
+		self.load_library_path(path)
+	}
+
+	/// Loads the library at `path` and runs its `JNI_OnLoad`, like `System.load`.
+	pub fn load_library_path(&mut self, path: impl AsRef<Path>) -> eyre::Result<()> {
+		let Some(on_load) = self.linker.lock().link_library(path.as_ref())? else {
+			return Ok(());
+		};
+
+		let version = match self.thread.as_deref_mut() {
+			Some(thread) => self.vm.call_on_load(thread, on_load)?,
+			None => {
+				// Rust code loads libraries from outside of the vm, JNI_OnLoad still needs a thread.
+				let mut thread = self.vm.attach_thread(ThreadConfig {
+					name: "JNI_OnLoad".to_string(),
+				});
+				let version = self.vm.call_on_load(&mut *thread, on_load);
+				thread.detach();
+				version?
+			}
+		};
+
+		if !is_supported(version) {
+			bail!(
+				"UnsatisfiedLinkError: {} needs unsupported JNI version {version:#x}",
+				path.as_ref().display()
+			);
+		}
+		Ok(())
+	}
+
+	/// The file of the library `name` in the first directory of the `java.library.path` which has it.
+	pub fn find_library(&self, name: &str) -> Option<PathBuf> {
+		let file = JNILinker::library_name(name);
+		let properties = self.properties.read();
+		let library_path = properties.get("java.library.path")?;
+		std::env::split_paths(library_path)
+			.map(|dir| dir.join(&file))
+			.find(|path| path.is_file())
+	}
+}
+
+impl crate::Vm {
+	fn call_on_load(
+		&self,
+		thread: &mut dyn ThreadContext,
+		on_load: JNIOnLoad,
+	) -> eyre::Result<i32> {
+		let mut env = JNIEnv::new(self.clone(), thread);
+		env.frames().push_frame(16);
+		let version = enter_env(&mut env, |env| unsafe {
+			env.native(|| on_load(self.java_vm.as_raw(), null_mut()))
+		});
+		let frames = env.frames();
+		frames.pop_frame();
+
+		if let Some(error) = frames.error.take() {
+			return Err(error.wrap_err("Error inside of JNI_OnLoad"));
+		}
+		if frames.exception.take().is_some() {
+			bail!("JNI_OnLoad threw an exception");
+		}
+		Ok(version)
+	}
+}
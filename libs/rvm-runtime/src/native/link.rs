@@ -1,17 +1,22 @@
 use crate::MethodBinding;
 use either::Either;
+use eyre::Context;
+use jni_sys::jint;
 #[cfg(unix)]
 use libloading::os::unix as imp;
 #[cfg(windows)]
 use libloading::os::windows as imp;
 use libloading::Library;
-use std::collections::hash_map::Entry;
+use rvm_core::MethodDescriptor;
 use std::collections::HashMap;
-use std::ffi::CString;
-use std::panic::UnwindSafe;
+use std::ffi::c_void;
+use std::fmt::Write;
 use std::path::Path;
 use tracing::{debug, trace};
 
+/// The `JNI_OnLoad` of a library, it returns the JNI version which the library needs.
+pub type JNIOnLoad = unsafe extern "system" fn(*mut jni_sys::JavaVM, *mut c_void) -> jint;
+
 pub struct JNILinker {
 	libraries: HashMap<String, Library>,
 	symbols: HashMap<String, JNISymbol>,
@@ -19,9 +24,51 @@ pub struct JNILinker {
 
 enum JNISymbol {
 	Library(imp::Symbol<extern "C" fn()>),
+	/// Registered through `RegisterNatives`, these are always found under their long name.
+	Registered {
+		class: String,
+		function: extern "C" fn(),
+	},
 	Rust(MethodBinding),
 }
 
+/// The symbols which a native method is looked up as, the long name includes the parameters so
+/// overloaded methods can be told apart.
+#[derive(Clone, Debug)]
+pub struct NativeName {
+	pub short: String,
+	pub long: String,
+}
+
+impl NativeName {
+	pub fn new(class: &str, method: &str, descriptor: &MethodDescriptor) -> NativeName {
+		let mut short = "Java_".to_string();
+		mangle(&mut short, class);
+		short.push('_');
+		mangle(&mut short, method);
+
+		let descriptor = descriptor.to_string();
+		let parameters = &descriptor[1..descriptor.find(')').unwrap()];
+		let mut long = format!("{short}__");
+		mangle(&mut long, parameters);
+		NativeName { short, long }
+	}
+}
+
+/// Escapes a name the way the JNI specification does for native symbols.
+fn mangle(out: &mut String, name: &str) {
+	for unit in name.encode_utf16() {
+		match char::from_u32(unit as u32) {
+			Some('/') => out.push('_'),
+			Some('_') => out.push_str("_1"),
+			Some(';') => out.push_str("_2"),
+			Some('[') => out.push_str("_3"),
+			Some(char) if char.is_ascii_alphanumeric() => out.push(char),
+			_ => write!(out, "_0{unit:04x}").unwrap(),
+		}
+	}
+}
+
 impl JNILinker {
 	pub fn new() -> JNILinker {
 		JNILinker {
@@ -29,46 +76,89 @@ impl JNILinker {
 			symbols: Default::default(),
 		}
 	}
-	pub fn link_library<P: AsRef<Path>>(&mut self, file: P) {
-		unsafe {
-			let str = file.as_ref().to_str().unwrap().to_string();
-			let library = Library::new(file.as_ref()).unwrap();
-			self.libraries.insert(str, library);
+
+	/// Loads the library, the `JNI_OnLoad` of it still has to be called when one is returned.
+	/// Loading a library twice does nothing.
+	pub fn link_library<P: AsRef<Path>>(&mut self, file: P) -> eyre::Result<Option<JNIOnLoad>> {
+		let path = file.as_ref();
+		let key = path.to_string_lossy().to_string();
+		if self.libraries.contains_key(&key) {
+			return Ok(None);
 		}
+
+		debug!("Loading library {key}");
+		let library = unsafe { Library::new(path) }
+			.wrap_err_with(|| format!("Could not load library {key}"))?;
+		let on_load = unsafe { library.get::<JNIOnLoad>(b"JNI_OnLoad") }
+			.ok()
+			.map(|on_load| *on_load);
+		self.libraries.insert(key, library);
+		Ok(on_load)
+	}
+
+	/// The file name of a library on this platform, `foo` is `libfoo.so` on linux.
+	pub fn library_name(name: &str) -> String {
+		libloading::library_filename(name)
+			.to_string_lossy()
+			.to_string()
 	}
 
 	pub unsafe fn link(&mut self, name: String, func: MethodBinding) {
 		self.symbols.insert(name, JNISymbol::Rust(func));
 	}
 
+	/// Links a native method of `class` to a function which native code gave us.
+	pub unsafe fn register(&mut self, class: &str, name: &NativeName, function: extern "C" fn()) {
+		self.symbols.insert(
+			name.long.clone(),
+			JNISymbol::Registered {
+				class: class.to_string(),
+				function,
+			},
+		);
+	}
+
+	/// Removes every function which got registered for `class`.
+	pub fn unregister(&mut self, class: &str) {
+		self.symbols.retain(|_, symbol| {
+			!matches!(symbol, JNISymbol::Registered { class: registered, .. } if registered == class)
+		});
+	}
+
 	pub fn get<V>(
 		&mut self,
-		name: &str,
+		name: &NativeName,
 		func: impl FnOnce(Either<extern "C" fn(), &MethodBinding>) -> V,
 	) -> Option<V> {
-		let symbol = match self.symbols.entry(name.to_string()) {
-			Entry::Occupied(entry) => entry.into_mut(),
-			Entry::Vacant(entry) => unsafe {
-				'load: {
-					debug!("Linking native method {name}");
-					for (lib_name, library) in &self.libraries {
-						trace!("Checking {lib_name}");
-						let string = CString::new(name).unwrap();
-
-						if let Ok(value) = library.get::<extern "C" fn()>(string.as_bytes()) {
-							let symbol = value.into_raw();
-							break 'load entry.insert(JNISymbol::Library(symbol));
-						}
-					}
-
-					return None;
-				}
-			},
+		let key = match [&name.long, &name.short]
+			.into_iter()
+			.find(|key| self.symbols.contains_key(*key))
+		{
+			Some(key) => key,
+			None => self.load_symbol(name)?,
 		};
 
-		Some(func(match symbol {
+		Some(func(match &self.symbols[key] {
 			JNISymbol::Library(symbol) => Either::Left(**symbol),
+			JNISymbol::Registered { function, .. } => Either::Left(*function),
 			JNISymbol::Rust(binding) => Either::Right(binding),
 		}))
 	}
+
+	/// Searches the libraries for the short name first and then for the long name.
+	fn load_symbol<'a>(&mut self, name: &'a NativeName) -> Option<&'a String> {
+		debug!("Linking native method {}", name.short);
+		for key in [&name.short, &name.long] {
+			for (lib_name, library) in &self.libraries {
+				trace!("Checking {lib_name} for {key}");
+				if let Ok(value) = unsafe { library.get::<extern "C" fn()>(key.as_bytes()) } {
+					let symbol = unsafe { value.into_raw() };
+					self.symbols.insert(key.clone(), JNISymbol::Library(symbol));
+					return Some(key);
+				}
+			}
+		}
+
+		None
+	}
 }
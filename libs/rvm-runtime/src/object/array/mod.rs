@@ -1,6 +1,7 @@
 pub use class::*;
 pub use object::*;
+pub use pinned::*;
 
 mod class;
 mod object;
-
+mod pinned;
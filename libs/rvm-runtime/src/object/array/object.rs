@@ -2,8 +2,8 @@ use crate::conversion::{FromJava, JavaTyped, ToJava};
 use crate::gc::{ArrayHeader, JavaHeader};
 use crate::object::bindable::Bindable;
 use crate::{
-	read_arr, write_arr, AnyValue, Castable, Class, JavaKind, Reference, ReferenceKind, UnionValue,
-	Value, ValueCell, Vm,
+	read_arr, write_arr, AnyValue, Castable, Class, JavaKind, Reference, ReferenceKind, Runtime,
+	UnionValue, Value, ValueCell, Vm,
 };
 use eyre::ContextCompat;
 use rvm_core::{
@@ -146,7 +146,7 @@ impl ArrayRef {
 }
 
 impl ToJava for ArrayRef {
-	fn to_java(self, runtime: &Vm) -> eyre::Result<AnyValue> {
+	fn to_java(self, runtime: &mut Runtime) -> eyre::Result<AnyValue> {
 		self.reference.to_java(runtime)
 	}
 }
@@ -243,7 +243,7 @@ impl<V: Value> Array<V> {
 // 	}
 // }
 impl<V> ToJava for Array<V> {
-	fn to_java(self, runtime: &Vm) -> eyre::Result<AnyValue> {
+	fn to_java(self, runtime: &mut Runtime) -> eyre::Result<AnyValue> {
 		self.array.to_java(runtime)
 	}
 }
@@ -0,0 +1,78 @@
+use crate::{Array, Value, Vm};
+use eyre::bail;
+use rvm_core::Kind;
+use std::ops::Deref;
+use std::slice::{from_raw_parts, from_raw_parts_mut};
+
+impl<V: Value> Array<V> {
+	/// Borrows the elements of a primitive array as a slice for bulk access. The array is pinned
+	/// until the view gets dropped, so it stays alive and does not move when the heap compacts.
+	///
+	/// Array handles are `Copy` and the same array can be pinned more than once, so the view only
+	/// hands out shared slices. Writing goes through [`PinnedArray::as_mut_slice`].
+	///
+	/// ```compile_fail
+	/// # use rvm_runtime::{Array, Vm};
+	/// fn write(vm: &Vm, array: Array<i32>) -> eyre::Result<()> {
+	///     let mut first = array.pin(vm)?;
+	///     let second = array.pin(vm)?;
+	///     first[0] = second[0] + 1;
+	///     Ok(())
+	/// }
+	/// ```
+	pub fn pin(self, vm: &Vm) -> eyre::Result<PinnedArray<V>> {
+		// The collector rewrites the elements of reference arrays while they are borrowed.
+		if V::kind() == Kind::Reference {
+			bail!("Arrays of references can not be pinned");
+		}
+
+		vm.gc.pin(**self);
+		Ok(PinnedArray {
+			vm: vm.clone(),
+			array: self,
+		})
+	}
+}
+
+/// The elements of a pinned array, see [`Array::pin`].
+pub struct PinnedArray<V: Value> {
+	vm: Vm,
+	array: Array<V>,
+}
+
+impl<V: Value> PinnedArray<V> {
+	pub fn array(&self) -> Array<V> {
+		self.array
+	}
+
+	/// The elements as a mutable slice.
+	///
+	/// # Safety
+	/// Nothing else may access the array while the slice is alive, neither other views of it nor
+	/// java code or other handles to it.
+	pub unsafe fn as_mut_slice(&mut self) -> &mut [V] {
+		from_raw_parts_mut(
+			self.array.data_ptr() as *mut V,
+			self.array.length() as usize,
+		)
+	}
+}
+
+impl<V: Value> Deref for PinnedArray<V> {
+	type Target = [V];
+
+	fn deref(&self) -> &Self::Target {
+		unsafe {
+			from_raw_parts(
+				self.array.data_ptr() as *const V,
+				self.array.length() as usize,
+			)
+		}
+	}
+}
+
+impl<V: Value> Drop for PinnedArray<V> {
+	fn drop(&mut self) {
+		self.vm.gc.unpin(**self.array);
+	}
+}
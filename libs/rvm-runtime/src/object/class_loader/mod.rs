@@ -8,29 +8,46 @@ use std::ops::Deref;
 use std::sync::Arc;
 use tracing::{debug, info, instrument, warn};
 
-use rvm_core::{Id, Kind, ObjectType, Storage, Type};
-use rvm_reader::ClassInfo;
+use rvm_core::{ClassAccessFlags, Id, Kind, ObjectType, Storage, Type};
+use rvm_reader::{verify, ClassHierarchy, ClassInfo};
 
 use crate::object::class::Class;
-use crate::{ArrayClass, CallType, InstanceClass, MethodIdentifier, Runtime, Vm};
+use crate::{ArrayClass, CallType, InstanceClass, MethodIdentifier, Reference, Runtime, Vm};
 
 pub use source::*;
 
 pub struct ClassLoader {
-	sources: Mutex<Vec<Box<dyn ClassSource>>>,
+	/// The sources and whether they are on the boot class path.
+	sources: Mutex<Vec<(Box<dyn ClassSource>, bool)>>,
 	classes: RwLock<Storage<Type, Class, Option<Arc<Class>>>>,
+	/// The super class of a class and whether it is an interface, for the verifier.
+	headers: Mutex<HashMap<String, (Option<String>, bool)>>,
+	/// The class of every `java.lang.Class` object, for going back from the object to the class.
+	objects: RwLock<HashMap<Reference, Id<Class>>>,
+	verify: bool,
+	verify_boot: bool,
 }
 
 impl ClassLoader {
-	pub fn new() -> ClassLoader {
+	pub fn new(verify: bool, verify_boot: bool) -> ClassLoader {
 		ClassLoader {
 			sources: Mutex::new(Vec::new()),
 			classes: RwLock::new(Storage::new()),
+			headers: Mutex::new(HashMap::new()),
+			objects: RwLock::new(HashMap::new()),
+			verify,
+			verify_boot,
 		}
 	}
 
 	pub fn add_source(&self, source: Box<dyn ClassSource>) {
-		self.sources.lock().push(source);
+		self.sources.lock().push((source, false));
+	}
+
+	/// Adds a source of the boot class path, like `rt.zip`, which only gets verified with
+	/// `-Xverify:all`.
+	pub fn add_boot_source(&self, source: Box<dyn ClassSource>) {
+		self.sources.lock().push((source, true));
 	}
 
 	pub fn get(&self, id: Id<Class>) -> Arc<Class> {
@@ -54,6 +71,22 @@ impl ClassLoader {
 		self.classes.read().get_id(ty)
 	}
 
+	/// The class which the `java.lang.Class` object belongs to.
+	pub fn get_object(&self, object: Reference) -> Option<Id<Class>> {
+		self.objects.read().get(&object).copied()
+	}
+
+	/// All of the classes which have been fully loaded.
+	pub fn loaded(&self) -> Vec<Arc<Class>> {
+		self.classes
+			.read()
+			.iter()
+			.iter()
+			.flatten()
+			.cloned()
+			.collect()
+	}
+
 	// Make this return Arc<Class>??
 	//pub fn resolve(&self, runtime: &Runtime, desc: &Type) -> eyre::Result<Id<Class>> {
 	//	// if its in the match the lock wont get dropped
@@ -157,6 +190,51 @@ impl ClassLoader {
 	//	Ok(id)
 	//}
 
+	/// Reads the header of a class from the sources, without loading the class.
+	fn header(&self, name: &str) -> eyre::Result<(Option<String>, bool)> {
+		if let Some(header) = self.headers.lock().get(name) {
+			return Ok(header.clone());
+		}
+
+		let ty = ObjectType::new(name.to_string());
+		let mut data = None;
+		for (source, _) in self.sources.lock().iter() {
+			data = source
+				.try_load(&ty)
+				.wrap_err("Failed to load class from source")?;
+			if data.is_some() {
+				break;
+			}
+		}
+		let Some(data) = data else {
+			bail!("NoClassDefFoundError: {name}");
+		};
+
+		let info = ClassInfo::parse_complete(&data).wrap_err("Failed to parse .class file")?;
+		self.add_header(name, &info)
+	}
+
+	fn add_header(&self, name: &str, info: &ClassInfo) -> eyre::Result<(Option<String>, bool)> {
+		// Only java/lang/Object has no super class.
+		let super_class = if info.super_class.id() == 0 {
+			None
+		} else {
+			let class = info.cp.get(info.super_class).wrap_err_with(|| {
+				format!("ClassFormatError: Invalid super class index in {name}")
+			})?;
+			let super_name = info.cp.get(class.name).wrap_err_with(|| {
+				format!("ClassFormatError: Invalid super class name index in {name}")
+			})?;
+			Some(super_name.to_string())
+		};
+		let header = (
+			super_class,
+			info.access_flags.contains(ClassAccessFlags::INTERFACE),
+		);
+		self.headers.lock().insert(name.to_string(), header.clone());
+		Ok(header)
+	}
+
 	fn allocate_id(&self, ty: Type) -> Id<Class> {
 		self.classes.write().push(ty, None)
 	}
@@ -257,6 +335,8 @@ impl<'a> ClassResolver<'a> {
 					to_initialize.push(id);
 				}
 
+				let object = *new_class.companion().class;
+				self.cl.objects.write().insert(object, id);
 				self.cl
 					.classes
 					.write()
@@ -338,7 +418,8 @@ impl<'a> ClassResolver<'a> {
 
 	fn load_instance(&mut self, id: Id<Class>, ty: &ObjectType) -> eyre::Result<InstanceClass> {
 		let guard = self.cl.sources.lock();
-		for source in guard.iter() {
+		for (source, boot) in guard.iter() {
+			let boot = *boot;
 			let Some(data) = source
 				.try_load(ty)
 				.wrap_err("Failed to load class from source")?
@@ -350,6 +431,10 @@ impl<'a> ClassResolver<'a> {
 			drop(guard);
 
 			let info = ClassInfo::parse_complete(&data).wrap_err("Failed to parse .class file")?;
+			self.cl.add_header(ty, &info)?;
+			if (boot && self.cl.verify_boot) || (!boot && self.cl.verify) {
+				verify(&info, self)?;
+			}
 			let class = InstanceClass::new(id, info, self)?;
 			return Ok(class);
 		}
@@ -358,6 +443,16 @@ impl<'a> ClassResolver<'a> {
 	}
 }
 
+impl ClassHierarchy for ClassResolver<'_> {
+	fn super_class(&mut self, name: &str) -> eyre::Result<Option<String>> {
+		Ok(self.cl.header(name)?.0)
+	}
+
+	fn is_interface(&mut self, name: &str) -> eyre::Result<bool> {
+		Ok(self.cl.header(name)?.1)
+	}
+}
+
 impl<'a> Drop for ClassResolver<'a> {
 	fn drop(&mut self) {
 		assert!(self.to_link.is_empty());
@@ -146,9 +146,13 @@ impl InstanceClass {
 		let class = ctx.classes.get(class);
 		let class = class.to_instance();
 
+		// The companion is referenced from the class, so it has to stay alive and in place.
 		let result = ctx.alloc_object(class)?;
+		ctx.gc.pin(**result);
+		let static_ref = ctx.alloc_static_instance(self)?;
+		ctx.gc.pin(*static_ref);
 		let companion = ClassCompanion {
-			static_ref: ctx.alloc_static_instance(self)?,
+			static_ref,
 			class: result.raw(),
 		};
 
@@ -158,6 +162,10 @@ impl InstanceClass {
 		})
 	}
 
+	pub fn try_companion(&self) -> Option<&ClassCompanion> {
+		self.companion.as_ref()
+	}
+
 	pub fn companion(&self) -> &ClassCompanion {
 		self.companion
 			.as_ref()
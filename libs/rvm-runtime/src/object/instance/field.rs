@@ -86,11 +86,15 @@ impl FieldLayout {
 		output.sort_by(|(v0, _, _), (v1, _, _)| v0.cmp(v1));
 
 		// Create offsets, ensure that all reference fields are first.
+		// The rest goes from the largest to the smallest kind, so every field is aligned.
 		let mut ref_fields = 0;
 		let mut fields_size = 0;
 		{
 			let mut fields: Vec<&mut Field> = output.iter_mut().map(|(_, f, _)| f).collect();
-			fields.sort_by(|v0, v1| v1.ty.kind().is_ref().cmp(&v0.ty.kind().is_ref()));
+			fields.sort_by_key(|v| {
+				let kind = v.ty.kind();
+				(!kind.is_ref(), std::cmp::Reverse(kind.size()))
+			});
 
 			for field in fields {
 				field.offset = fields_size;
@@ -1,7 +1,8 @@
 use crate::conversion::{FromJava, JavaTyped, ToJava};
 use crate::object::bindable::Bindable;
 use crate::{
-	AnyInstance, AnyValue, Class, InstanceCell, JavaKind, Reference, Returnable, ValueCell, Vm,
+	AnyInstance, AnyValue, Class, InstanceCell, JavaKind, Reference, Returnable, Runtime,
+	ValueCell, Vm,
 };
 use rvm_core::{CastTypeError, Id, Kind, ObjectType, Type, Typed};
 use std::ops::{Deref, DerefMut};
@@ -9,6 +10,9 @@ use std::ops::{Deref, DerefMut};
 pub trait InstanceBinding {
 	fn ty() -> ObjectType;
 	fn bind(instance: &AnyInstance) -> Self;
+
+	/// The object this got bound to, instance methods get called on it.
+	fn instance(&self) -> &AnyInstance;
 }
 
 impl<B: InstanceBinding> JavaKind for B {
@@ -83,7 +87,7 @@ impl<B: InstanceBinding> Instance<B> {
 	}
 }
 impl<B: InstanceBinding> ToJava for Instance<B> {
-	fn to_java(self, runtime: &Vm) -> eyre::Result<AnyValue> {
+	fn to_java(self, runtime: &mut Runtime) -> eyre::Result<AnyValue> {
 		self.instance.to_java(runtime)
 	}
 }
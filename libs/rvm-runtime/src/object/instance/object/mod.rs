@@ -13,7 +13,7 @@ use crate::conversion::{FromJava, JavaTyped, ToJava};
 use crate::gc::{InstanceHeader, JavaHeader};
 use crate::{
 	read_arr, write_arr, AnyValue, Castable, Class, Field, InstanceClass, Reference, ReferenceKind,
-	UnionValue, Value, Vm,
+	Runtime, UnionValue, Value, Vm,
 };
 
 #[derive(Copy, Clone)]
@@ -54,10 +54,10 @@ impl InstanceRef {
 	}
 
 	pub fn header(&self) -> &InstanceHeader {
-		let JavaHeader::Instance(header) = self.reference.header().user() else {
-			panic!("Wrong header type");
-		};
-		header
+		match self.reference.header().user() {
+			JavaHeader::Instance(header) | JavaHeader::InstanceStatic(header) => header,
+			JavaHeader::Array(_) => panic!("Wrong header type"),
+		}
 	}
 
 	pub fn class(&self) -> Id<Class> {
@@ -133,7 +133,7 @@ impl InstanceRef {
 }
 
 impl ToJava for InstanceRef {
-	fn to_java(self, runtime: &Vm) -> eyre::Result<AnyValue> {
+	fn to_java(self, runtime: &mut Runtime) -> eyre::Result<AnyValue> {
 		self.reference.to_java(runtime)
 	}
 }
@@ -260,7 +260,7 @@ impl Deref for AnyInstance {
 	}
 }
 impl ToJava for AnyInstance {
-	fn to_java(self, runtime: &Vm) -> eyre::Result<AnyValue> {
+	fn to_java(self, runtime: &mut Runtime) -> eyre::Result<AnyValue> {
 		self.raw.to_java(runtime)
 	}
 }
@@ -181,17 +181,18 @@ impl AnyValue {
 		}
 	}
 
+	/// Only the bytes of the kind get written, fields are packed so the rest can be another field.
 	pub unsafe fn write(self, ptr: *mut UnionValue) {
 		match self {
-			AnyValue::Byte(v) => ptr.write(UnionValue { byte: v }),
-			AnyValue::Short(v) => ptr.write(UnionValue { short: v }),
-			AnyValue::Int(v) => ptr.write(UnionValue { int: v }),
-			AnyValue::Long(v) => ptr.write(UnionValue { long: v }),
-			AnyValue::Char(v) => ptr.write(UnionValue { char: v }),
-			AnyValue::Float(v) => ptr.write(UnionValue { float: v }),
-			AnyValue::Double(v) => ptr.write(UnionValue { double: v }),
-			AnyValue::Boolean(v) => ptr.write(UnionValue { boolean: v }),
-			AnyValue::Reference(v) => ptr.write(UnionValue { reference: v }),
+			AnyValue::Byte(v) => ptr.cast::<i8>().write_unaligned(v),
+			AnyValue::Short(v) => ptr.cast::<i16>().write_unaligned(v),
+			AnyValue::Int(v) => ptr.cast::<i32>().write_unaligned(v),
+			AnyValue::Long(v) => ptr.cast::<i64>().write_unaligned(v),
+			AnyValue::Char(v) => ptr.cast::<u16>().write_unaligned(v),
+			AnyValue::Float(v) => ptr.cast::<f32>().write_unaligned(v),
+			AnyValue::Double(v) => ptr.cast::<f64>().write_unaligned(v),
+			AnyValue::Boolean(v) => ptr.cast::<bool>().write_unaligned(v),
+			AnyValue::Reference(v) => ptr.cast::<Reference>().write_unaligned(v),
 		}
 	}
 	pub unsafe fn read(ptr: UnionValue, kind: Kind) -> Self {
@@ -1,40 +1,10 @@
-#![feature(try_blocks)]
 #![feature(exit_status_error)]
 
-use rust_format::{Formatter, RustFmt};
-use rvm_bind::JavaBinder;
-use rvm_class::{ClassLoader, DirectoryClassSource, JarClassSource};
-use rvm_core::{MethodDescriptor, ObjectType, PrimitiveType, Type};
-use rvm_reader::ClassInfo;
-use std::collections::HashMap;
-use std::fmt::Write;
-use std::fs::{metadata, read, read_dir, File};
-use std::ops::Deref;
+use rvm_bind::build::Builder;
+use std::env;
+use std::fs::{metadata, read_dir, File};
 use std::path::{Path, PathBuf};
 use std::process::Command;
-use std::string::String;
-use std::{env, fmt, fs, io};
-//let item_struct: ItemStruct = parse(item.clone()).unwrap();
-//
-// 	let package = attr.to_string();
-// 	let class_name = item_struct.ident.to_string();
-//
-// 	let mut bytecode_dir =
-// 		PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap()).join("bytecode");
-// 	for package in package.split("/") {
-// 		bytecode_dir.push(package);
-// 	}
-// 	bytecode_dir.push(format!("{class_name}.class"));
-//
-// 	if !bytecode_dir.exists() {
-// 		panic!(".class file {bytecode_dir:?} does not exist.");
-// 	}
-//
-// 	let file = read(&bytecode_dir).unwrap();
-// 	let info = ClassInfo::parse_complete(&file).unwrap();
-//
-// 	for method in info.methods {}
-// 	item
 
 fn get_paths() -> Vec<PathBuf> {
 	let mut paths = vec![];
@@ -112,8 +82,10 @@ fn compile_java(files: &[PathBuf], current_dir: &Path) {
 	);
 	process.current_dir(current_dir.join("src")).arg("-Xlint");
 
+	// The classes are checked in, the version must not depend on the javac which happens to be used.
+	process.args(["--release", "17"]);
 	process.arg("-XDignore.symbol.file=true");
-	process.arg("-g:vars,source"); // Include variable/source file informations
+	process.arg("-g"); // Include line number, variable and source file informations
 
 	let bytecode_dir = current_dir.join("bytecode");
 	std::fs::create_dir_all(&bytecode_dir).expect("Could not create bytecode dir");
@@ -140,376 +112,96 @@ fn compile_java(files: &[PathBuf], current_dir: &Path) {
 	status.exit_ok().expect("javac not successful");
 }
 
-fn main() {
-	println!("cargo::rerun-if-changed=build.rs");
-	println!("cargo::rerun-if-changed=src");
-	let paths = get_paths();
-	let needs_recompile = check_needs_recompile(&paths);
-
-	//if !needs_recompile {
-	//	return;
-	//}
-
-	let current_dir = std::env::current_dir().unwrap();
-	compile_java(&paths, &current_dir);
-
-	let mut binder = JavaBinder {
-		loader: ClassLoader::new(),
-		to_bind: vec![],
-	};
-
-	binder.loader.add_source(Box::new(
-		DirectoryClassSource::new(PathBuf::from("bytecode")).unwrap(),
-	));
-	binder.loader.add_source(Box::new(
-		JarClassSource::new(read("../../rt.zip").expect("Could not load rt.zip (java std)"))
-			.unwrap(),
-	));
-
-	//let mut root = PackageNode::default();
-	for x in paths {
-		let mut simple: PathBuf = x.components().skip(1).collect();
-		simple.set_extension("");
-
-		let parts: Vec<String> = simple
-			.components()
-			.map(|v| v.as_os_str().to_str().unwrap().to_string())
-			.collect();
-		let full_name = parts.join("/");
-
-		binder.bind(&ObjectType::new(full_name)).unwrap();
-
-		//let mut buf = PathBuf::from("bytecode").join(simple);
-		//buf.set_extension("class");
-		//
-		//let vec = read(&buf).unwrap();
-		//let info = ClassInfo::parse_complete(&vec).unwrap();
-		//
-		//root.insert(ClassData { info, source: buf });
+/// The JDK which javac belongs to, its headers are needed for the native test libraries.
+fn java_home() -> Option<PathBuf> {
+	if let Some(java_home) = env::var_os("JAVA_HOME") {
+		return Some(PathBuf::from(java_home));
 	}
 
-	binder.bind(&ObjectType::Class()).unwrap();
-
-	let rust_file = binder.compile();
-
-	let out_dir = env::var_os("OUT_DIR").unwrap();
-	let dest_path = Path::new(&out_dir).join("java_bindings.rs");
-	fs::write(&dest_path, rust_file).unwrap();
-	//let mut code_out = String::new();
-	//let compiler = RustCompiler { root };
-	//compiler.write_node(&compiler.root, &mut code_out).unwrap();
-	//
-	//let formatted_code = RustFmt::default().format_str(&code_out).unwrap_or(code_out);
-	//let out_dir = env::var_os("OUT_DIR").unwrap();
-	//let dest_path = Path::new(&out_dir).join("java_bindings.rs");
-	//
-	//fs::write(&dest_path, formatted_code).unwrap();
+	let path = env::var_os("PATH")?;
+	let javac = env::split_paths(&path)
+		.map(|dir| dir.join("javac"))
+		.find(|javac| javac.exists())?;
+	let javac = javac.canonicalize().unwrap();
+	Some(javac.parent().unwrap().parent().unwrap().to_path_buf())
 }
 
-pub struct ClassData {
-	info: ClassInfo,
-	source: PathBuf,
-}
-#[derive(Default)]
-pub struct PackageNode {
-	full_name: String,
-	packages: HashMap<String, PackageNode>,
-	file: Option<ClassData>,
-}
-
-impl PackageNode {
-	pub fn insert(&mut self, data: ClassData) {
-		let name = data.info.full_name();
-
-		let path_parts: Vec<String> = name.split("/").map(|v| v.to_string()).collect();
-
-		self.insert_raw(path_parts, data);
+/// Builds every C source into a `lib{name}.so` inside of `OUT_DIR`, tests link them from there.
+fn compile_natives(out_dir: &Path, java_home: &Path) {
+	let mut sources = vec![];
+	walk_dir(PathBuf::from("src"), &mut sources);
+	sources.retain(|path| path.extension().is_some_and(|extension| extension == "c"));
+
+	let include = java_home.join("include");
+	for source in sources {
+		let name = source.file_stem().unwrap().to_str().unwrap();
+		let mut process = Command::new(env::var("CC").unwrap_or("cc".to_string()));
+		process
+			.args(["-shared", "-fPIC", "-O2"])
+			.arg("-I")
+			.arg(&include)
+			.arg("-I")
+			.arg(include.join(env::consts::OS))
+			.arg(&source)
+			.arg("-o")
+			.arg(out_dir.join(format!("lib{name}.so")));
+
+		let status = process.status().expect("Could not start the C compiler");
+		status.exit_ok().expect("C compiler not successful");
 	}
+}
 
-	fn insert_raw(&mut self, mut parts: Vec<String>, data: ClassData) {
-		if parts.len() == 0 {
-			if let Some(existing) = &self.file {
-				panic!(
-					"Conflicting class name {} {:?} with {:?}",
-					data.info.full_name(),
-					existing.source,
-					data.source,
-				);
-			}
+fn main() {
+	println!("cargo::rerun-if-changed=build.rs");
+	println!("cargo::rerun-if-changed=src");
+	println!("cargo::rerun-if-env-changed=JAVA_HOME");
+	println!("cargo::rerun-if-changed=../../rt.zip");
+	println!("cargo::rustc-check-cfg=cfg(jdk, rt)");
 
-			self.file = Some(data);
+	// The compiled classes are checked in, so without a JDK the tests run on those.
+	let java_home = java_home();
+	let paths = get_paths();
+	if check_needs_recompile(&paths) {
+		if java_home.is_some() {
+			let current_dir = env::current_dir().unwrap();
+			compile_java(&paths, &current_dir);
 		} else {
-			let package = parts.remove(0);
-			let package_name = format!("{}/{package}", self.full_name)
-				.trim_start_matches("/")
-				.to_string();
-			let node = self.packages.entry(package).or_default();
-			node.full_name = package_name;
-			node.insert_raw(parts, data);
+			println!("cargo:warning=Could not find a JDK, the tests run on the checked in classes");
 		}
 	}
-}
-
-pub struct RustCompiler {
-	root: PackageNode,
-}
 
-impl RustCompiler {
-	pub fn class_to_rust(&self, package: &str, class: &str) -> String {
-		class
-			.trim_start_matches(package)
-			.replace("/", "::")
-			.trim_start_matches("::")
-			.to_string()
-	}
-	pub fn write_type(&self, ty: &Type, package: &str, out: &mut String) {
-		match ty {
-			Type::Primitive(primitive) => match primitive {
-				PrimitiveType::Boolean => out.push_str("bool"),
-				PrimitiveType::Byte => out.push_str("i8"),
-				PrimitiveType::Short => out.push_str("i16"),
-				PrimitiveType::Int => out.push_str("i32"),
-				PrimitiveType::Long => out.push_str("i64"),
-				PrimitiveType::Char => out.push_str("char"),
-				PrimitiveType::Float => out.push_str("f32"),
-				PrimitiveType::Double => out.push_str("f64"),
-			},
-			Type::Object(object) => {
-				if object == &ObjectType::Object() {
-					out.push_str("rvm_runtime::Reference");
-				} else {
-					out.push_str("rvm_runtime::Instance<");
-					out.push_str(&self.class_to_rust(package, object));
-					out.push('>');
-				}
-			}
-			Type::Array(array) => {
-				out.push_str("rvm_runtime::Array<");
-				self.write_type(&array.component, package, out);
-				out.push('>');
-			}
-		}
+	// The bindings and the tests which run on the java runtime need the rt.zip of a JDK 8.
+	let out_dir = env::var_os("OUT_DIR").unwrap();
+	if Path::new("../../rt.zip").exists() {
+		println!("cargo::rustc-cfg=rt");
+		Builder::new()
+			.classpath("bytecode")
+			.classpath("../../rt.zip")
+			.package("core")
+			.package("tests")
+			.class("java/lang/Class")
+			.output(Path::new(&out_dir).join("java_bindings.rs"))
+			.cargo_rerun_if_changed(true)
+			.generate()
+			.expect("Could not generate the java bindings");
+	} else {
+		println!("cargo:warning=Could not find ../../rt.zip, only the assembled tests run");
 	}
-	pub fn write_node(&self, node: &PackageNode, out: &mut String) -> fmt::Result {
-		let parent_node = node;
-		for (key, node) in &node.packages {
-			if let Some(data) = &node.file {
-				let info = &data.info;
-				let full_name = info.full_name();
-				let (package, name) = match full_name.rsplit_once("/") {
-					Some((package, name)) => (package.to_string(), name.to_string()),
-					None => (String::new(), full_name.clone()),
-				};
-
-				let super_class = info.cp[info.cp[info.super_class].name].to_string();
-				let super_name = if super_class != *ObjectType::Object() {
-					Some(self.class_to_rust(&package, &super_class))
-				} else {
-					None
-				};
 
-				writeln!(out, "// {}", parent_node.full_name)?;
-				writeln!(out, "#[derive(Copy, Clone)]")?;
-				writeln!(out, "pub struct {name} {{")?;
-				if let Some(super_name) = &super_name {
-					writeln!(out, "  base: {super_name},")?;
-				}
-				for field in &info.fields {
-					let field_name = info.cp[field.name_index].to_string();
-					let field_descriptor = info.cp[field.descriptor_index].to_string();
-					let field_type = Type::parse(&field_descriptor).unwrap();
-					write!(out, "  pub {field_name}: rvm_runtime::TypedField<")?;
-					self.write_type(&field_type, &package, out);
-					writeln!(out, ">,")?;
-				}
-				writeln!(out, "}}")?;
-
-				// InstanceBinding
-				writeln!(out, "impl rvm_runtime::InstanceBinding for {name} {{")?;
-				writeln!(out, "  fn ty() -> rvm_core::ObjectType {{")?;
-				writeln!(out, "    rvm_core::ObjectType::new(Self::TY)")?;
-				writeln!(out, "  }}")?;
-				writeln!(
-					out,
-					"  fn bind(instance: &rvm_runtime::AnyInstance) -> Self {{"
-				)?;
-				writeln!(out, "  let fields = instance.fields();")?;
-				writeln!(out, "    {name} {{")?;
-				if let Some(super_name) = &super_name {
-					writeln!(out, "    base: {super_name}::bind(instance),")?;
-				}
-				for field in &info.fields {
-					let field_name = info.cp[field.name_index].to_string();
-					writeln!(
-						out,
-						"    {field_name}: fields.by_name_typed(\"{field_name}\").unwrap(),"
-					)?;
-				}
-				writeln!(out, "    }}")?;
-				writeln!(out, "  }}")?;
-				writeln!(out, "}}")?;
-
-				if let Some(super_name) = &super_name {
-					writeln!(
-						out,
-						"impl std::ops::Deref for {name} {{
-						type Target = {super_name};
-
-						fn deref(&self) -> &Self::Target {{
-							&self.base
-						}}
-					}}
-
-					impl std::ops::DerefMut for {name} {{
-						fn deref_mut(&mut self) -> &mut Self::Target {{
-							&mut self.base
-						}}
-					}}"
-					)?;
-				}
-
-				//#[derive(Clone, Copy)]
-				// pub struct ExtendedObject {
-				// 	base: SimpleObject,
-				// 	another_field: TypedField<i64>,
-				// }
-				//
-				// impl InstanceBinding for ExtendedObject {
-				// 	fn ty() -> ObjectType {
-				// 		ObjectType::new("testing/object/ExtendedObject")
-				// 	}
-				//
-				// 	fn bind(instance: &AnyInstance) -> Self {
-				// 		ExtendedObject {
-				// 			base: SimpleObject::bind(instance),
-				// 			another_field: instance.field_named("anotherField").unwrap().typed(),
-				// 		}
-				// 	}
-				// }
-				// Methods
-				writeln!(out, "impl {name} {{")?;
-				writeln!(out, "	pub const TY: &'static str =\"{full_name}\";")?;
-
-				// Check what method names need descriptors
-				let mut method_names = HashMap::<String, usize>::new();
-				for method in &info.methods {
-					let method_name = info.cp[method.name_index].to_string();
-					*method_names.entry(method_name).or_default() += 1;
-				}
-				for method in &info.methods {
-					let method_name = info.cp[method.name_index].to_string();
-					let descriptor = info.cp[method.descriptor_index].to_string();
-					let descriptor = MethodDescriptor::parse(&descriptor).unwrap();
-
-					let mut rust_method_name = method_name.replace(['<', '>'], "_");
-					if *method_names.get(&method_name).unwrap() > 1 {
-						let parameters: Vec<String> = descriptor
-							.parameters
-							.iter()
-							.map(|v| v.to_string())
-							.collect();
-						for char in parameters.join("").to_string().chars() {
-							if char.is_ascii_alphanumeric() {
-								rust_method_name.push(char);
-							} else {
-								rust_method_name.push('_');
-							}
-						}
-					}
-
-					// Descriptor method
-					writeln!(
-						out,
-						"pub fn {rust_method_name}_descriptor() -> rvm_runtime::MethodIdentifier {{"
-					)?;
-
-					writeln!(out, "rvm_runtime::MethodIdentifier {{")?;
-					writeln!(out, "	name: std::sync::Arc::from(\"{method_name}\"),")?;
-					writeln!(out, "	descriptor: std::sync::Arc::from(\"{descriptor}\"),")?;
-					writeln!(out, "}}")?;
-					writeln!(out, "}}")?;
-
-					// Simple call method
-
-					write!(
-						out,
-						"pub fn {rust_method_name}(runtime: &rvm_runtime::Runtime,"
-					)?;
-
-					for (i, ty) in descriptor.parameters.iter().enumerate() {
-						write!(out, "v{i}: ")?;
-						self.write_type(ty, &parent_node.full_name, out);
-						write!(out, ",")?;
-					}
-					write!(out, ") -> eyre::Result<")?;
-					match &descriptor.returns {
-						None => {
-							out.push_str("()");
-						}
-						Some(value) => {
-							self.write_type(value, &parent_node.full_name, out);
-						}
-					}
-					writeln!(out, "> {{")?;
-
-					// BODY {
-					writeln!(out, "let output = runtime.simple_run(<Self as rvm_runtime::InstanceBinding>::ty(), Self::{rust_method_name}_descriptor(),")?;
-
-					writeln!(out, "vec![")?;
-					for (i, _ty) in descriptor.parameters.iter().enumerate() {
-						writeln!(out, "rvm_runtime::ToJava::to_java(v{i}, runtime)?,")?;
-					}
-					writeln!(out, "],)?;")?;
-
-					match descriptor.returns {
-						Some(_) => {
-							writeln!(out, "let output = output.expect(\"expected return\");")?;
-							writeln!(out, "rvm_runtime::FromJava::from_java(output, runtime)")?;
-						}
-						None => {
-							writeln!(
-								out,
-								"if !output.is_none() {{ panic!(\"Returned on void\"); }}"
-							)?;
-							writeln!(out, "	Ok(())")?;
-						}
-					}
-					writeln!(out, "}}")?;
-					//}
-				}
-				// Java ObjectType
-				//writeln!(out, "pub fn ty() -> rvm_core::ObjectType {{")?;
-				//writeln!(out, "  rvm_core::ObjectType::new(Self::TY)")?;
-				//writeln!(out, "}}")?;
-				writeln!(out, "}}")?;
-
-				// Java Typed
-				writeln!(out, "impl rvm_runtime::JavaTyped for {name} {{")?;
-				writeln!(out, "  fn java_type() -> rvm_core::Type {{")?;
-				writeln!(
-					out,
-					"    <Self as rvm_runtime::InstanceBinding>::ty().into()"
-				)?;
-				writeln!(out, "  }}")?;
-				writeln!(out, "}}")?;
-			} else {
-				writeln!(out, "pub mod {key} {{")?;
-				self.write_node(node, out)?;
-				writeln!(out, "}}")?;
-			}
-		}
-
-		Ok(())
+	// The native libraries need the headers of a JDK.
+	if let Some(java_home) = java_home {
+		println!("cargo::rustc-cfg=jdk");
+		compile_natives(Path::new(&out_dir), &java_home);
 	}
 }
 
 fn walk_dir(path: PathBuf, paths: &mut Vec<PathBuf>) {
 	for dir in read_dir(&path).unwrap() {
 		let entry = dir.unwrap();
-		if entry.path().extension().map(|v| v.to_str().unwrap()) == Some("java") {
-			paths.push(entry.path());
-		} else if entry.metadata().unwrap().is_dir() {
+		if entry.metadata().unwrap().is_dir() {
 			walk_dir(path.join(entry.file_name()), paths);
+		} else {
+			paths.push(entry.path());
 		}
 	}
 }
@@ -1,62 +1,70 @@
 #![feature(exit_status_error)]
 #![feature(try_blocks)]
 #![feature(arbitrary_self_types)]
-use crate::core::load_test_sdk;
 use eyre::Context;
-use rvm_core::{MethodDescriptor, ObjectType, Type};
+use rvm_core::{FieldAccessFlags, MethodAccessFlags, MethodDescriptor, ObjectType, Type};
 use rvm_engine_ben::BenBinding;
+use rvm_reader::{AttributeInfo, ClassBuilder, ClassInfo, Code};
 use rvm_runtime::{
-	AnyValue, ClassSource, Instance, JarClassSource, MethodBinding, MethodIdentifier, Reference,
-	Runtime, Vm,
+	AnyValue, ClassSource, FromJavaMulti, JavaTypedMulti, MethodIdentifier, Reference, Runtime,
+	ToJavaMulti, Vm, VmConfig,
 };
 use std::borrow::Borrow;
-use std::fs::read;
 use std::io::Result;
-use std::sync::{Arc, LazyLock};
 use std::time::Instant;
-use tracing::info;
 use walkdir::WalkDir;
 
+#[cfg(rt)]
 pub use bindings::*;
+#[cfg(rt)]
+pub use sdk::*;
+#[cfg(rt)]
 mod bindings;
+#[cfg(rt)]
 mod core;
+#[cfg(rt)]
+mod sdk;
 #[cfg(test)]
 mod tests;
 
-static RT_ZIP: LazyLock<Arc<JarClassSource>> = LazyLock::new(|| {
-	//let data = read("../../rt.zip").unwrap();
-	Arc::new(JarClassSource::new(include_bytes!("../../../rt.zip").to_vec()).unwrap())
-});
-
-pub fn load_sdk(runtime: &Vm) {
-	runtime.classes.add_source(Box::new(RT_ZIP.clone()));
+/// The classes which the runtime needs before anything runs, for tests which only run assembled
+/// classes and do not need the java runtime of `rt.zip`.
+pub fn core_classes() -> eyre::Result<MemoryClassSource> {
+	let mut classes = vec![];
+	for name in ["java/lang/Object", "java/lang/Class", "java/lang/String"] {
+		let mut class = ClassBuilder::new(name);
+		class.default_constructor()?;
+		if name == "java/lang/String" {
+			class.field(
+				FieldAccessFlags::PRIVATE | FieldAccessFlags::FINAL,
+				"value",
+				"[C",
+			);
+		}
+		classes.push((ObjectType::new(name), class.write()?));
+	}
+	Ok(MemoryClassSource::new(classes))
+}
 
-	runtime.bindings.bind(
-		"java/lang/Object",
-		"registerNatives",
-		MethodBinding::new(|runtime, _: ()| {
-			info!("Hi natives");
-		}),
-	);
+/// Classes which only exist in memory, like assembled or rewritten ones.
+pub struct MemoryClassSource {
+	classes: Vec<(ObjectType, Vec<u8>)>,
+}
 
-	runtime.bindings.bind(
-		"java/lang/Class",
-		"registerNatives",
-		MethodBinding::new(|runtime, _: ()| {
-			info!("Hi natives");
-		}),
-	);
+impl MemoryClassSource {
+	pub fn new(classes: Vec<(ObjectType, Vec<u8>)>) -> MemoryClassSource {
+		MemoryClassSource { classes }
+	}
+}
 
-	runtime.bindings.bind(
-		"java/lang/Class",
-		"desiredAssertionStatus0",
-		MethodBinding::new(
-			|runtime, class: Instance<bindings::java::lang::Class>| -> bool {
-				info!("Assertion!!");
-				false
-			},
-		),
-	);
+impl ClassSource for MemoryClassSource {
+	fn try_load(&self, ty: &ObjectType) -> eyre::Result<Option<Vec<u8>>> {
+		Ok(self
+			.classes
+			.iter()
+			.find(|(name, _)| name == ty)
+			.map(|(_, bytes)| bytes.clone()))
+	}
 }
 
 pub struct SimpleClassTest {
@@ -91,17 +99,89 @@ impl SimpleMethodTest {
 	}
 }
 
-pub fn launch(heap_size: usize) -> Runtime<'static> {
+/// The flags of the static methods which the tests assemble.
+pub const STATIC: MethodAccessFlags = MethodAccessFlags::PUBLIC.union(MethodAccessFlags::STATIC);
+
+/// The configuration of the tests which run assembled classes.
+pub fn config(verify: bool) -> VmConfig {
+	VmConfig {
+		heap_size: 1024,
+		gc_mode: Default::default(),
+		properties: Default::default(),
+		verify,
+		verify_boot: false,
+	}
+}
+
+/// A runtime with the [core_classes] on the boot class path instead of `rt.zip`, followed by
+/// `classes`.
+pub fn launch_with(
+	classes: Vec<(&str, Vec<u8>)>,
+	config: VmConfig,
+) -> eyre::Result<Runtime<'static>> {
 	rvm_core::init();
-	let runtime = Vm::new(heap_size, Box::new(BenBinding::new()));
+	let vm = Vm::with_config(config, Box::new(BenBinding::new()));
+	vm.classes.add_boot_source(Box::new(core_classes()?));
+	vm.classes.add_source(Box::new(MemoryClassSource::new(
+		classes
+			.into_iter()
+			.map(|(name, bytes)| (ObjectType::new(name), bytes))
+			.collect(),
+	)));
 
-	load_sdk(&runtime);
-	load_test_sdk(&runtime);
+	Ok(Runtime { vm, thread: None })
+}
 
-	Runtime {
-		vm: runtime,
-		thread: None,
-	}
+/// Calls a static method of a class.
+pub fn call<I, O>(
+	runtime: &mut Runtime,
+	class: &str,
+	method: &str,
+	parameters: I,
+) -> eyre::Result<O>
+where
+	I: ToJavaMulti + JavaTypedMulti,
+	O: FromJavaMulti + JavaTypedMulti,
+{
+	runtime
+		.class(class)?
+		.static_method::<I, O>(method)?
+		.call(parameters)
+}
+
+/// The code of the first method with the name.
+pub fn method_code<'a>(info: &'a ClassInfo, name: &str) -> &'a Code {
+	info.methods
+		.iter()
+		.find(|method| info.cp[method.name_index].as_str() == name)
+		.and_then(|method| {
+			method
+				.attributes
+				.iter()
+				.find_map(|attribute| match attribute {
+					AttributeInfo::CodeAttribute { code } => Some(code),
+					_ => None,
+				})
+		})
+		.unwrap()
+}
+
+/// Like [method_code], for changing the code.
+pub fn method_code_mut<'a>(info: &'a mut ClassInfo, name: &str) -> &'a mut Code {
+	let cp = &info.cp;
+	info.methods
+		.iter_mut()
+		.find(|method| cp[method.name_index].as_str() == name)
+		.and_then(|method| {
+			method
+				.attributes
+				.iter_mut()
+				.find_map(|attribute| match attribute {
+					AttributeInfo::CodeAttribute { code } => Some(code),
+					_ => None,
+				})
+		})
+		.unwrap()
 }
 
 pub fn compile(runtime: &Vm, sources: &[(&str, &str)]) -> Result<()> {
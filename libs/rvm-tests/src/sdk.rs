@@ -0,0 +1,56 @@
+use crate::bindings;
+use crate::core::load_test_sdk;
+use rvm_engine_ben::BenBinding;
+use rvm_runtime::{Instance, JarClassSource, MethodBinding, Runtime, Vm};
+use std::fs::read;
+use std::sync::{Arc, LazyLock};
+use tracing::info;
+
+static RT_ZIP: LazyLock<Arc<JarClassSource>> = LazyLock::new(|| {
+	let data = read("../../rt.zip").expect("Could not read ../../rt.zip");
+	Arc::new(JarClassSource::new(data).unwrap())
+});
+
+pub fn load_sdk(runtime: &Vm) {
+	runtime.classes.add_boot_source(Box::new(RT_ZIP.clone()));
+
+	runtime.bindings.bind(
+		"java/lang/Object",
+		"registerNatives",
+		MethodBinding::new(|_, _, _: ()| {
+			info!("Hi natives");
+		}),
+	);
+
+	runtime.bindings.bind(
+		"java/lang/Class",
+		"registerNatives",
+		MethodBinding::new(|_, _, _: ()| {
+			info!("Hi natives");
+		}),
+	);
+
+	runtime.bindings.bind(
+		"java/lang/Class",
+		"desiredAssertionStatus0",
+		MethodBinding::new(
+			|_, _, class: Instance<bindings::java::lang::Class>| -> bool {
+				info!("Assertion!!");
+				false
+			},
+		),
+	);
+}
+
+pub fn launch(heap_size: usize) -> Runtime<'static> {
+	rvm_core::init();
+	let runtime = Vm::new(heap_size, Box::new(BenBinding::new()));
+
+	load_sdk(&runtime);
+	load_test_sdk(&runtime);
+
+	Runtime {
+		vm: runtime,
+		thread: None,
+	}
+}
@@ -37,4 +37,42 @@ public class ArrayTest extends Object {
 	public static Object getValueRef(Object[] array, int index) {
 		return array[index];
 	}
+
+	public static int sum(int[] values) {
+		int sum = 0;
+		for (int i = 0; i < values.length; i++) {
+			sum += values[i];
+		}
+		return sum;
+	}
+
+	public static int[] range(int length) {
+		int[] values = new int[length];
+		for (int i = 0; i < length; i++) {
+			values[i] = i;
+		}
+		return values;
+	}
+
+	public static String[] echoStrings(String[] values) {
+		return values;
+	}
+
+	public static int sumThroughNative(int[] values) {
+		return sumNative(values);
+	}
+
+	public static native int sumNative(int[] values);
+
+	public static byte[] bytes(int length) {
+		return new byte[length];
+	}
+
+	public static int sumBytes(byte[] bytes) {
+		int sum = 0;
+		for (int i = 0; i < bytes.length; i++) {
+			sum += bytes[i];
+		}
+		return sum;
+	}
 }
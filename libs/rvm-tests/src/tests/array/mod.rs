@@ -1,5 +1,5 @@
 use rvm_core::PrimitiveType;
-use rvm_runtime::{Array, Class, Reference};
+use rvm_runtime::{Array, Class, MethodBinding, Reference};
 
 use crate::bindings::tests::array::ArrayTest;
 use crate::{launch, load_sdk};
@@ -116,3 +116,76 @@ fn ref_arrays() -> eyre::Result<()> {
 
 	Ok(())
 }
+
+#[test]
+fn vec_conversion() -> eyre::Result<()> {
+	let mut runtime = launch(1024);
+
+	let class = runtime.class("tests/array/ArrayTest")?;
+	let sum = class.static_method::<Vec<i32>, i32>("sum")?;
+	assert_eq!(sum.call(vec![1, 2, 3, 4])?, 10);
+
+	let class = runtime.class("tests/array/ArrayTest")?;
+	let range = class.static_method::<i32, Vec<i32>>("range")?;
+	assert_eq!(range.call(4)?, vec![0, 1, 2, 3]);
+
+	let class = runtime.class("tests/array/ArrayTest")?;
+	let sum = class.static_method::<&[i32], i32>("sum")?;
+	assert_eq!(sum.call(&[5, 6][..])?, 11);
+	Ok(())
+}
+
+#[test]
+fn vec_of_strings() -> eyre::Result<()> {
+	let mut runtime = launch(1024);
+
+	let values = vec!["Cake".to_string(), String::new(), "🍰".to_string()];
+	let class = runtime.class("tests/array/ArrayTest")?;
+	let echo = class.static_method::<Vec<String>, Vec<String>>("echoStrings")?;
+	assert_eq!(echo.call(values.clone())?, values);
+	Ok(())
+}
+
+#[test]
+fn vec_binding() -> eyre::Result<()> {
+	let mut runtime = launch(1024);
+	runtime.bindings.bind(
+		"tests/array/ArrayTest",
+		"sumNative",
+		MethodBinding::new(|_, _, values: Vec<i32>| -> i32 { values.iter().sum() }),
+	);
+
+	let class = runtime.class("tests/array/ArrayTest")?;
+	let sum = class.static_method::<Vec<i32>, i32>("sumThroughNative")?;
+	assert_eq!(sum.call(vec![1, 2, 3])?, 6);
+	Ok(())
+}
+
+#[test]
+fn pinned() -> eyre::Result<()> {
+	let mut runtime = launch(1024);
+
+	// Garbage in front of the array, which would make it move when the heap compacts.
+	runtime.alloc_array(&PrimitiveType::Int.into(), 16)?;
+	let bytes = ArrayTest::bytes(&mut runtime, 4)?;
+	let reference = **bytes;
+
+	let mut pinned = bytes.pin(&runtime.vm)?;
+	runtime.gc();
+	assert_eq!(**pinned.array(), reference);
+
+	// Nothing else uses the array while it gets written.
+	unsafe { pinned.as_mut_slice() }.copy_from_slice(&[1, 2, 3, 4]);
+	assert_eq!(&pinned[..], &[1, 2, 3, 4]);
+	assert_eq!(ArrayTest::sumBytes(&mut runtime, pinned.array())?, 10);
+
+	// More views of the same array can read it at the same time.
+	let second = bytes.pin(&runtime.vm)?;
+	assert_eq!(&second[..], &pinned[..]);
+	drop(pinned);
+	drop(second);
+
+	let objects = ArrayTest::singleRefArray(&mut runtime)?;
+	assert!(objects.pin(&runtime.vm).is_err());
+	Ok(())
+}
@@ -0,0 +1,363 @@
+use crate::{call, config, launch_with, method_code, STATIC};
+use rvm_core::{FieldAccessFlags, PrimitiveType, StackKind};
+use rvm_reader::{
+	BranchOffset, ClassBuilder, ClassInfo, CodeBuilder, ConstantInfo, Inst, JumpKind, MathInst,
+	PoolBuilder, StackInst,
+};
+
+type Method = (
+	&'static str,
+	&'static str,
+	fn(&mut CodeBuilder) -> eyre::Result<()>,
+);
+
+fn class(name: &str, methods: &[Method]) -> eyre::Result<ClassBuilder> {
+	let mut class = ClassBuilder::new(name);
+	class.default_constructor()?;
+	for (name, descriptor, build) in methods {
+		class.method(STATIC, name, descriptor, *build)?;
+	}
+	Ok(class)
+}
+
+fn sum(code: &mut CodeBuilder) -> eyre::Result<()> {
+	let start = code.label();
+	let end = code.label();
+	code.int(0).store(StackKind::Int, 1);
+	code.place(start)
+		.load(StackKind::Int, 0)
+		.jump(JumpKind::IFLE, end)
+		.load(StackKind::Int, 1)
+		.load(StackKind::Int, 0)
+		.inst(Inst::Math(MathInst::Add(PrimitiveType::Int)))
+		.store(StackKind::Int, 1)
+		.increment(0, -1)
+		.jump(JumpKind::GOTO, start);
+	code.place(end)
+		.load(StackKind::Int, 1)
+		.return_value(StackKind::Int);
+	Ok(())
+}
+
+#[test]
+fn labels() -> eyre::Result<()> {
+	let name = "tests/assembler/Labels";
+	let info = class(name, &[("sum", "(I)I", sum)])?.build()?;
+	let code = method_code(&info, "sum");
+	assert_eq!(code.max_stack, 2);
+	assert_eq!(code.max_locals, 2);
+
+	let mut runtime = launch_with(
+		vec![(name, class(name, &[("sum", "(I)I", sum)])?.write()?)],
+		config(true),
+	)?;
+	assert_eq!(call::<i32, i32>(&mut runtime, name, "sum", 10)?, 55);
+	assert_eq!(call::<i32, i32>(&mut runtime, name, "sum", 0)?, 0);
+	Ok(())
+}
+
+#[test]
+fn stack() -> eyre::Result<()> {
+	let name = "tests/assembler/Stack";
+	let methods: &[Method] = &[
+		// a - b, through a detour over the stack.
+		("sub", "(II)I", |code| {
+			code.load(StackKind::Int, 0)
+				.load(StackKind::Int, 1)
+				.inst(Inst::Stack(StackInst::Swap))
+				.inst(Inst::Stack(StackInst::DupX1))
+				.inst(Inst::Stack(StackInst::Pop))
+				.inst(Inst::Math(MathInst::Sub(PrimitiveType::Int)))
+				.return_value(StackKind::Int);
+			Ok(())
+		}),
+		// (a + b) * 2, with both longs taking up two slots.
+		("double", "(JJ)J", |code| {
+			code.load(StackKind::Long, 0)
+				.load(StackKind::Long, 2)
+				.inst(Inst::Math(MathInst::Add(PrimitiveType::Long)))
+				.inst(Inst::Stack(StackInst::Dup2))
+				.inst(Inst::Math(MathInst::Add(PrimitiveType::Long)))
+				.return_value(StackKind::Long);
+			Ok(())
+		}),
+	];
+
+	let info = class(name, methods)?.build()?;
+	assert_eq!(method_code(&info, "sub").max_stack, 3);
+	assert_eq!(method_code(&info, "sub").max_locals, 2);
+	assert_eq!(method_code(&info, "double").max_stack, 4);
+	assert_eq!(method_code(&info, "double").max_locals, 4);
+	assert_eq!(method_code(&info, "<init>").max_locals, 1);
+
+	let mut runtime = launch_with(vec![(name, class(name, methods)?.write()?)], config(true))?;
+	assert_eq!(call::<_, i32>(&mut runtime, name, "sub", (10, 3))?, 7);
+	assert_eq!(
+		call::<(i64, i64), i64>(&mut runtime, name, "double", (1 << 40, 2))?,
+		(1 << 41) + 4
+	);
+	Ok(())
+}
+
+#[test]
+fn constants() -> eyre::Result<()> {
+	let name = "tests/assembler/Constants";
+	let methods: &[Method] = &[
+		("int", "()I", |code| {
+			code.int(1 << 20).return_value(StackKind::Int);
+			Ok(())
+		}),
+		("long", "()J", |code| {
+			code.long(-(1 << 40)).return_value(StackKind::Long);
+			Ok(())
+		}),
+		("double", "()D", |code| {
+			code.double(0.5).return_value(StackKind::Double);
+			Ok(())
+		}),
+		("string", "()Ljava/lang/String;", |code| {
+			code.string("Cake").return_value(StackKind::Reference);
+			Ok(())
+		}),
+	];
+
+	let mut runtime = launch_with(vec![(name, class(name, methods)?.write()?)], config(true))?;
+	assert_eq!(call::<_, i32>(&mut runtime, name, "int", ())?, 1 << 20);
+	assert_eq!(call::<_, i64>(&mut runtime, name, "long", ())?, -(1 << 40));
+	assert_eq!(call::<_, f64>(&mut runtime, name, "double", ())?, 0.5);
+	assert_eq!(call::<_, String>(&mut runtime, name, "string", ())?, "Cake");
+	Ok(())
+}
+
+#[test]
+fn fields() -> eyre::Result<()> {
+	let name = "tests/assembler/Fields";
+	let mut class = class(name, &[])?;
+	class.field(
+		FieldAccessFlags::PUBLIC | FieldAccessFlags::STATIC,
+		"value",
+		"I",
+	);
+	class.method(STATIC, "swap", "(I)I", |code| {
+		code.get_static("tests/assembler/Fields", "value", "I")
+			.load(StackKind::Int, 0)
+			.put_static("tests/assembler/Fields", "value", "I")
+			.return_value(StackKind::Int);
+		Ok(())
+	})?;
+
+	let mut runtime = launch_with(vec![(name, class.write()?)], config(true))?;
+	assert_eq!(call::<i32, i32>(&mut runtime, name, "swap", 4)?, 0);
+	assert_eq!(call::<i32, i32>(&mut runtime, name, "swap", 2)?, 4);
+	Ok(())
+}
+
+#[test]
+fn table_switch() -> eyre::Result<()> {
+	let name = "tests/assembler/TableSwitch";
+	let methods: &[Method] = &[("pick", "(I)I", |code| {
+		let default = code.label();
+		let targets = [code.label(), code.label(), code.label()];
+		code.load(StackKind::Int, 0)
+			.table_switch(1, default, &targets);
+		for (index, target) in targets.into_iter().enumerate() {
+			code.place(target)
+				.int(index as i32 * 10)
+				.return_value(StackKind::Int);
+		}
+		code.place(default).int(-1).return_value(StackKind::Int);
+		Ok(())
+	})];
+
+	let mut runtime = launch_with(vec![(name, class(name, methods)?.write()?)], config(true))?;
+	for (key, value) in [(0, -1), (1, 0), (2, 10), (3, 20), (4, -1)] {
+		assert_eq!(call::<i32, i32>(&mut runtime, name, "pick", key)?, value);
+	}
+	Ok(())
+}
+
+#[test]
+fn lookup_switch() -> eyre::Result<()> {
+	let mut class = ClassBuilder::new("tests/assembler/LookupSwitch");
+	class.method(STATIC, "pick", "(I)I", |code| {
+		let default = code.label();
+		let high = code.label();
+		let low = code.label();
+		code.load(StackKind::Int, 0)
+			.lookup_switch(default, &[(1000, high), (-5, low)]);
+		code.place(low).int(1).return_value(StackKind::Int);
+		code.place(high).int(2).return_value(StackKind::Int);
+		code.place(default).int(0).return_value(StackKind::Int);
+		Ok(())
+	})?;
+
+	let info = ClassInfo::parse_complete(&class.write()?)?;
+	let Inst::LookupSwitch(switch) = &method_code(&info, "pick").instructions[1] else {
+		panic!("Expected a lookupswitch");
+	};
+	assert_eq!(switch.pairs, [(-5, 1), (1000, 3)]);
+	assert_eq!(switch.default_offset, 5);
+	Ok(())
+}
+
+#[test]
+fn subroutines() -> eyre::Result<()> {
+	let name = "tests/assembler/Subroutines";
+	// x * 3, by calling a subroutine which adds x to local 1 three times.
+	let methods: &[Method] = &[("triple", "(I)I", |code| {
+		let subroutine = code.label();
+		code.int(0)
+			.store(StackKind::Int, 1)
+			.jsr(subroutine)
+			.jsr(subroutine)
+			.jsr(subroutine)
+			.load(StackKind::Int, 1)
+			.return_value(StackKind::Int);
+		code.place(subroutine)
+			.store(StackKind::Reference, 2)
+			.load(StackKind::Int, 1)
+			.load(StackKind::Int, 0)
+			.inst(Inst::Math(MathInst::Add(PrimitiveType::Int)))
+			.store(StackKind::Int, 1)
+			.ret(2);
+		Ok(())
+	})];
+
+	let info = ClassInfo::parse_complete(&class(name, methods)?.write()?)?;
+	let code = method_code(&info, "triple");
+	assert_eq!(code.max_stack, 2);
+	assert_eq!(code.max_locals, 3);
+	// The offsets are in instructions like the ones of jumps.
+	assert!(matches!(code.instructions[2], Inst::JSR(BranchOffset(5))));
+	assert!(matches!(code.instructions[4], Inst::JSR(BranchOffset(3))));
+
+	let mut runtime = launch_with(vec![(name, class(name, methods)?.write()?)], config(true))?;
+	assert_eq!(call::<i32, i32>(&mut runtime, name, "triple", 7)?, 21);
+	Ok(())
+}
+
+#[test]
+fn exception_table() -> eyre::Result<()> {
+	let mut class = ClassBuilder::new("tests/assembler/Catch");
+	class.method(STATIC, "safe", "(II)I", |code| {
+		let start = code.label();
+		let end = code.label();
+		let handler = code.label();
+		code.try_catch(start, end, handler, Some("java/lang/ArithmeticException"));
+		code.place(start)
+			.load(StackKind::Int, 0)
+			.load(StackKind::Int, 1)
+			.inst(Inst::Math(MathInst::Div(PrimitiveType::Int)));
+		code.place(end).return_value(StackKind::Int);
+		code.place(handler)
+			.store(StackKind::Reference, 2)
+			.int(-1)
+			.return_value(StackKind::Int);
+		Ok(())
+	})?;
+
+	let info = ClassInfo::parse_complete(&class.write()?)?;
+	let code = method_code(&info, "safe");
+	assert_eq!(code.max_stack, 2);
+	assert_eq!(code.max_locals, 3);
+
+	// iload_0, iload_1 and idiv take a byte each.
+	let [exception] = &code.exception_table[..] else {
+		panic!("Expected one handler");
+	};
+	assert_eq!(exception.start_pc, 0);
+	assert_eq!(exception.end_pc, 3);
+	assert_eq!(exception.handler_pc, 4);
+	let ConstantInfo::Class(catch_type) = info.cp.raw_get(exception.catch_type).unwrap() else {
+		panic!("Expected a class");
+	};
+	assert_eq!(
+		info.cp[catch_type.name].as_str(),
+		"java/lang/ArithmeticException"
+	);
+	Ok(())
+}
+
+#[test]
+fn pool() {
+	let mut pool = PoolBuilder::new();
+	let name = pool.utf8("value");
+	let long = pool.long(5);
+	let after = pool.integer(5);
+	assert_eq!(pool.utf8("value"), name);
+	assert_eq!(pool.long(5), long);
+	// Longs take up two slots.
+	assert_eq!(after.id(), long.id() + 2);
+
+	let field = pool.field("tests/Pool", "value", "I");
+	let method = pool.method("tests/Pool", "value", "I");
+	assert_ne!(field.id(), method.id());
+	assert_eq!(pool.field("tests/Pool", "value", "I"), field);
+}
+
+#[test]
+fn invalid() {
+	let methods: [fn(&mut CodeBuilder) -> eyre::Result<()>; 4] = [
+		// A label which is never placed.
+		|code| {
+			let label = code.label();
+			code.jump(JumpKind::GOTO, label);
+			Ok(())
+		},
+		// Popping more than there is.
+		|code| {
+			code.inst(Inst::Stack(StackInst::Pop)).return_void();
+			Ok(())
+		},
+		// Reaching the same instruction with different stacks.
+		|code| {
+			let end = code.label();
+			code.load(StackKind::Int, 0)
+				.jump(JumpKind::IFEQ, end)
+				.int(1);
+			code.place(end).return_void();
+			Ok(())
+		},
+		// Running past the end.
+		|code| {
+			code.int(1).inst(Inst::Stack(StackInst::Pop));
+			Ok(())
+		},
+	];
+
+	for build in methods {
+		let mut class = ClassBuilder::new("tests/assembler/Invalid");
+		assert!(class.method(STATIC, "invalid", "(I)V", build).is_err());
+	}
+}
+
+#[test]
+fn wide_nop() -> eyre::Result<()> {
+	let name = "tests/assembler/WideNop";
+	let mut class = ClassBuilder::new(name);
+	class.method(STATIC, "run", "()V", |code| {
+		code.inst(Inst::Nop)
+			.inst(Inst::Nop)
+			.inst(Inst::Nop)
+			.inst(Inst::Nop)
+			.return_void();
+		Ok(())
+	})?;
+	let mut bytes = class.write()?;
+
+	// Turns the nops into wide, nop and its index.
+	let start = bytes
+		.windows(5)
+		.position(|code| code == [0x00, 0x00, 0x00, 0x00, 0xb1])
+		.expect("The code of the method");
+	bytes[start] = 0xc4;
+
+	let Err(error) = ClassInfo::parse_complete(&bytes) else {
+		panic!("wide nop should not parse");
+	};
+	assert!(
+		format!("{error:?}").contains("wide can only modify"),
+		"{error:?}"
+	);
+	Ok(())
+}
@@ -0,0 +1,32 @@
+package tests.attributes;
+
+@Deprecated(since = "1.2", forRemoval = true)
+public sealed interface Shape permits Shape.Circle, Shape.Square {
+	int size() throws Exception;
+
+	final class Circle implements Shape {
+		int radius;
+
+		public int size() {
+			int size = 0;
+			for (int i = 0; i < radius; i++) {
+				size += radius * 3;
+			}
+			return size;
+		}
+
+		public int scaled(int factor) {
+			try {
+				return radius * 1000 / factor;
+			} catch (ArithmeticException e) {
+				return 0;
+			}
+		}
+	}
+
+	final class Square implements Shape {
+		public int size() throws Exception {
+			return 4;
+		}
+	}
+}
@@ -0,0 +1,219 @@
+use crate::method_code;
+use rvm_reader::{AttributeInfo, ClassInfo, ElementValue, ExceptionHandler, MethodInfo};
+use std::fs::read;
+use walkdir::WalkDir;
+
+fn class(name: &str) -> ClassInfo {
+	let bytes = read(format!("bytecode/tests/attributes/{name}.class")).unwrap();
+	ClassInfo::parse_complete(&bytes).unwrap()
+}
+
+fn method<'a>(info: &'a ClassInfo, name: &str) -> &'a MethodInfo {
+	info.methods
+		.iter()
+		.find(|method| info.cp[method.name_index].as_str() == name)
+		.unwrap()
+}
+
+fn class_names(info: &ClassInfo, attribute: &AttributeInfo) -> Vec<String> {
+	let classes = match attribute {
+		AttributeInfo::PermittedSubclasses { classes }
+		| AttributeInfo::NestMembers { classes }
+		| AttributeInfo::Exceptions {
+			exceptions: classes,
+		} => classes,
+		_ => panic!("Attribute has no classes"),
+	};
+
+	classes
+		.iter()
+		.map(|class| info.cp[info.cp[*class].name].to_string())
+		.collect()
+}
+
+#[test]
+fn known() {
+	for entry in WalkDir::new("bytecode") {
+		let entry = entry.unwrap();
+		if entry
+			.path()
+			.extension()
+			.is_none_or(|extension| extension != "class")
+		{
+			continue;
+		}
+
+		let info = ClassInfo::parse_complete(&read(entry.path()).unwrap()).unwrap();
+		let attributes = info
+			.attributes
+			.iter()
+			.chain(info.fields.iter().flat_map(|field| &field.attribute_info))
+			.chain(info.methods.iter().flat_map(|method| &method.attributes));
+		for attribute in attributes {
+			if let AttributeInfo::Unknown { name, .. } = attribute {
+				panic!("{:?} has the unknown attribute {name}", entry.path());
+			}
+		}
+	}
+}
+
+#[test]
+fn class_attributes() {
+	let info = class("Shape");
+
+	let mut source_file = None;
+	let mut permitted = vec![];
+	let mut members = vec![];
+	let mut deprecated = false;
+	let mut annotations = vec![];
+	for attribute in &info.attributes {
+		match attribute {
+			AttributeInfo::SourceFile { source_file: file } => {
+				source_file = Some(info.cp[*file].to_string());
+			}
+			AttributeInfo::PermittedSubclasses { .. } => {
+				permitted = class_names(&info, attribute);
+			}
+			AttributeInfo::NestMembers { .. } => members = class_names(&info, attribute),
+			AttributeInfo::Deprecated => deprecated = true,
+			AttributeInfo::RuntimeVisibleAnnotations {
+				annotations: values,
+			} => {
+				annotations = values.clone();
+			}
+			_ => {}
+		}
+	}
+
+	assert_eq!(source_file.as_deref(), Some("Shape.java"));
+	assert!(deprecated);
+	assert_eq!(
+		permitted,
+		[
+			"tests/attributes/Shape$Circle",
+			"tests/attributes/Shape$Square"
+		]
+	);
+	members.sort();
+	assert_eq!(permitted, members);
+
+	assert_eq!(annotations.len(), 1);
+	let annotation = &annotations[0];
+	assert_eq!(info.cp[annotation.ty].as_str(), "Ljava/lang/Deprecated;");
+	for element in &annotation.elements {
+		match (info.cp[element.name].as_str(), &element.value) {
+			("since", ElementValue::String(value)) => assert_eq!(info.cp[*value].as_str(), "1.2"),
+			("forRemoval", ElementValue::Boolean(value)) => assert_eq!(info.cp[*value].bytes, 1),
+			(name, value) => panic!("Unexpected element {name} = {value:?}"),
+		}
+	}
+}
+
+#[test]
+fn nest_host() {
+	let info = class("Shape$Square");
+	let host = info
+		.attributes
+		.iter()
+		.find_map(|attribute| match attribute {
+			AttributeInfo::NestHost { host_class } => Some(&info.cp[info.cp[*host_class].name]),
+			_ => None,
+		});
+	assert_eq!(host.unwrap().as_str(), "tests/attributes/Shape");
+
+	let inner = info
+		.attributes
+		.iter()
+		.find_map(|attribute| match attribute {
+			AttributeInfo::InnerClasses { classes } => Some(classes),
+			_ => None,
+		});
+	assert!(inner
+		.unwrap()
+		.iter()
+		.any(|class| info.cp[class.inner_name].as_str() == "Square"));
+}
+
+#[test]
+fn exceptions() {
+	let info = class("Shape$Square");
+	let size = method(&info, "size");
+	let exceptions = size
+		.attributes
+		.iter()
+		.find(|attribute| matches!(attribute, AttributeInfo::Exceptions { .. }))
+		.unwrap();
+	assert_eq!(class_names(&info, exceptions), ["java/lang/Exception"]);
+}
+
+#[test]
+fn stack_map_table() {
+	let info = class("Shape$Circle");
+	let code = method_code(&info, "size");
+
+	let frames = code
+		.attribute_info
+		.iter()
+		.find_map(|attribute| match attribute {
+			AttributeInfo::StackMapTable { frames } => Some(frames),
+			_ => None,
+		})
+		.unwrap();
+	// The loop condition and the code after the loop.
+	assert_eq!(frames.len(), 2);
+}
+
+#[test]
+fn instruction_space() -> eyre::Result<()> {
+	let info = class("Shape$Circle");
+	let code = method_code(&info, "scaled");
+
+	// getfield and sipush take three bytes each.
+	let pc_map = &code.pc_map;
+	assert_eq!(pc_map.pc(6), Some(10));
+	assert_eq!(pc_map.pc(10), Some(14));
+	assert_eq!(pc_map.index(11), Some(7));
+	assert_eq!(pc_map.index(14), Some(10));
+	assert_eq!(pc_map.index(2), None);
+
+	let [handler] = code.exception_handlers()?[..] else {
+		panic!("Expected one handler");
+	};
+	assert_eq!(
+		handler,
+		ExceptionHandler {
+			start: 0,
+			end: 6,
+			handler: 7,
+			catch_type: code.exception_table[0].catch_type,
+		}
+	);
+
+	let mut locals: Vec<_> = code
+		.local_variables()?
+		.iter()
+		.map(|local| {
+			(
+				info.cp[local.name_index].to_string(),
+				local.start,
+				local.end,
+			)
+		})
+		.collect();
+	locals.sort();
+	assert_eq!(
+		locals,
+		[
+			("e".to_string(), 8, 10),
+			("factor".to_string(), 0, 10),
+			("this".to_string(), 0, 10)
+		]
+	);
+	let lines: Vec<_> = code
+		.line_numbers()?
+		.iter()
+		.map(|line| (line.start, line.line_number))
+		.collect();
+	assert_eq!(lines, [(0, 20), (7, 21), (8, 22)]);
+	Ok(())
+}
@@ -0,0 +1,13 @@
+package tests.bind;
+
+public abstract class Polygon implements Shape {
+	public int sides;
+
+	public Polygon(int sides) {
+		this.sides = sides;
+	}
+
+	public int sides() {
+		return sides;
+	}
+}
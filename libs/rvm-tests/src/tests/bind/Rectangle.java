@@ -0,0 +1,25 @@
+package tests.bind;
+
+public class Rectangle extends Polygon {
+	public int width;
+	public int height;
+
+	public Rectangle(int width, int height) {
+		super(4);
+		this.width = width;
+		this.height = height;
+	}
+
+	public Rectangle(int size) {
+		this(size, size);
+	}
+
+	public int area() {
+		return width * height;
+	}
+
+	public void scale(int factor) {
+		width *= factor;
+		height *= factor;
+	}
+}
@@ -0,0 +1,5 @@
+package tests.bind;
+
+public interface Shape {
+	int area();
+}
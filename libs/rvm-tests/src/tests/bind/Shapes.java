@@ -0,0 +1,7 @@
+package tests.bind;
+
+public class Shapes {
+	public static Shape square(int size) {
+		return new Square(size);
+	}
+}
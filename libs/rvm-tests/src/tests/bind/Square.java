@@ -0,0 +1,16 @@
+package tests.bind;
+
+public class Square extends Rectangle {
+	public Square(int size) {
+		super(size);
+	}
+
+	@Override
+	public int area() {
+		return half() * 2;
+	}
+
+	private int half() {
+		return width * height / 2;
+	}
+}
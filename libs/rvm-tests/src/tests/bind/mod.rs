@@ -0,0 +1,53 @@
+use crate::bindings::tests::bind::{Rectangle, ShapeInterface, Shapes, Square};
+use crate::launch;
+use rvm_runtime::Runtime;
+
+fn runtime() -> Runtime<'static> {
+	launch(1024)
+}
+
+#[test]
+fn constructor() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	let rectangle = Rectangle::new_i32_i32(&mut runtime, 2, 3)?;
+	assert_eq!(*rectangle.width, 2);
+	assert_eq!(*rectangle.height, 3);
+	assert_eq!(*rectangle.sides, 4);
+
+	let rectangle = Rectangle::new_i32(&mut runtime, 5)?;
+	assert_eq!(*rectangle.width, 5);
+	Ok(())
+}
+
+#[test]
+fn instance_method() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	let rectangle = Rectangle::new_i32_i32(&mut runtime, 2, 3)?;
+	rectangle.scale(&mut runtime, 2)?;
+	assert_eq!(*rectangle.width, 4);
+	assert_eq!(rectangle.area(&mut runtime)?, 24);
+
+	// Methods of the super class are reached through deref.
+	assert_eq!(rectangle.sides(&mut runtime)?, 4);
+	Ok(())
+}
+
+#[test]
+fn virtual_dispatch() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	let square = Square::new(&mut runtime, 3)?;
+	let rectangle = square.cast_to::<Rectangle>();
+	assert_eq!(rectangle.area(&mut runtime)?, 8);
+	Ok(())
+}
+
+#[test]
+fn interface() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	let rectangle = Rectangle::new_i32_i32(&mut runtime, 2, 3)?;
+	assert_eq!(ShapeInterface::area(&*rectangle, &mut runtime)?, 6);
+
+	let shape = Shapes::square(&mut runtime, 4)?;
+	assert_eq!(shape.area(&mut runtime)?, 16);
+	Ok(())
+}
@@ -0,0 +1,166 @@
+use crate::{core_classes, STATIC};
+use rvm_bind::build::Builder;
+use rvm_core::{FieldAccessFlags, ObjectType, StackKind};
+use rvm_reader::{AttributeInfo, ClassBuilder, ClassWriter};
+use rvm_runtime::ClassSource;
+use std::env;
+use std::fs::{create_dir_all, read_to_string, remove_dir_all, write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// Writes `builder/a/Kept`, which uses `builder/b/Skipped`, to a new directory for the classpath.
+fn classes(test: &str, kept_result: i32) -> eyre::Result<PathBuf> {
+	let dir = env::temp_dir().join(format!("rvm-bind-{test}-{}", process::id()));
+	let _ = remove_dir_all(&dir);
+
+	let mut kept = ClassBuilder::new("builder/a/Kept");
+	kept.default_constructor()?;
+	kept.method(STATIC, "keep", "()I", |code| {
+		code.int(kept_result).return_value(StackKind::Int);
+		Ok(())
+	})?;
+	kept.method(STATIC, "take", "(Lbuilder/b/Skipped;)V", |code| {
+		code.return_void();
+		Ok(())
+	})?;
+
+	// Both fields have a ConstantValue, only the final one is a constant.
+	let value = kept.pool().integer(kept_result).id();
+	kept.pool().utf8("ConstantValue");
+	kept.field(
+		FieldAccessFlags::PUBLIC | FieldAccessFlags::STATIC | FieldAccessFlags::FINAL,
+		"LIMIT",
+		"I",
+	)
+	.field(
+		FieldAccessFlags::PUBLIC | FieldAccessFlags::STATIC,
+		"counter",
+		"I",
+	);
+	let mut kept = kept.build()?;
+	for field in &mut kept.fields {
+		field.attribute_info.push(AttributeInfo::ConstantValue {
+			constant_index: value,
+		});
+	}
+
+	let mut skipped = ClassBuilder::new("builder/b/Skipped");
+	skipped.default_constructor()?;
+
+	let mut files = vec![
+		("builder/a/Kept", ClassWriter::new(&kept).write()?),
+		("builder/b/Skipped", skipped.write()?),
+	];
+	// The core classes stand in for rt.zip.
+	let core = core_classes()?;
+	for name in ["java/lang/Object", "java/lang/Class", "java/lang/String"] {
+		files.push((name, core.try_load(&ObjectType::new(name))?.unwrap()));
+	}
+	for (name, bytes) in files {
+		let path = dir.join(name).with_extension("class");
+		create_dir_all(path.parent().unwrap())?;
+		write(path, bytes)?;
+	}
+	Ok(dir)
+}
+
+fn builder(classpath: &Path, output: &Path) -> Builder {
+	Builder::new()
+		.classpath(classpath)
+		.package("builder")
+		.output(output)
+}
+
+#[test]
+fn exclude() -> eyre::Result<()> {
+	let dir = classes("exclude", 1)?;
+	let output = dir.join("bindings.rs");
+	builder(&dir, &output).exclude("builder/b").generate()?;
+
+	let bindings = read_to_string(&output)?;
+	assert!(bindings.contains("pub struct Kept"));
+	assert!(bindings.contains("fn keep("));
+	// Methods using excluded classes are left out as well.
+	assert!(!bindings.contains("pub struct Skipped"));
+	assert!(!bindings.contains("fn take("));
+	remove_dir_all(dir)?;
+	Ok(())
+}
+
+#[test]
+fn constants() -> eyre::Result<()> {
+	let dir = classes("constants", 7)?;
+	let output = dir.join("bindings.rs");
+	builder(&dir, &output).generate()?;
+
+	let bindings = read_to_string(&output)?;
+	assert!(bindings.contains("pub const LIMIT: i32 = 7;"));
+	// The other static field keeps its accessors.
+	assert!(!bindings.contains("const COUNTER"));
+	assert!(bindings.contains("fn get_counter("));
+	remove_dir_all(dir)?;
+	Ok(())
+}
+
+#[test]
+fn include() -> eyre::Result<()> {
+	let dir = classes("include", 1)?;
+	let output = dir.join("bindings.rs");
+	builder(&dir, &output).include("builder/b").generate()?;
+
+	let bindings = read_to_string(&output)?;
+	assert!(bindings.contains("pub struct Skipped"));
+	assert!(!bindings.contains("pub struct Kept"));
+	assert!(!bindings.contains("pub struct Object"));
+	remove_dir_all(dir)?;
+	Ok(())
+}
+
+#[test]
+fn unchanged_inputs() -> eyre::Result<()> {
+	let dir = classes("unchanged", 1)?;
+	let output = dir.join("bindings.rs");
+	assert!(builder(&dir, &output).generate()?);
+	assert!(!builder(&dir, &output).generate()?);
+
+	// Other options or classes make the hash change.
+	assert!(builder(&dir, &output).exclude("builder/b").generate()?);
+	assert!(!builder(&dir, &output).exclude("builder/b").generate()?);
+	classes("unchanged", 2)?;
+	assert!(builder(&dir, &output).exclude("builder/b").generate()?);
+
+	// Bindings which got edited are replaced.
+	write(&output, "// Edited\n")?;
+	assert!(builder(&dir, &output).exclude("builder/b").generate()?);
+	remove_dir_all(dir)?;
+	Ok(())
+}
+
+#[test]
+fn binary_options() -> eyre::Result<()> {
+	let dir = classes("binary", 1)?;
+	let output = dir.join("bindings.rs");
+	let args = [
+		"-cp",
+		dir.to_str().unwrap(),
+		"-p",
+		"builder",
+		"--exclude",
+		"builder/b",
+		"-o",
+		output.to_str().unwrap(),
+	];
+
+	let builder = Builder::from_args(args.map(String::from))?.unwrap();
+	assert!(builder.generate()?);
+	let bindings = read_to_string(&output)?;
+	assert!(bindings.contains("pub struct Kept"));
+	assert!(!bindings.contains("pub struct Skipped"));
+
+	assert!(Builder::from_args(["--help".to_string()])?.is_none());
+	assert!(Builder::from_args(["--class".to_string()]).is_err());
+	assert!(Builder::from_args(["--unknown".to_string(), "value".to_string()]).is_err());
+	assert!(Builder::from_args([]).unwrap().unwrap().generate().is_err());
+	remove_dir_all(dir)?;
+	Ok(())
+}
@@ -0,0 +1,20 @@
+package tests.call;
+
+public class Counter {
+	public static int created;
+	public int value;
+
+	public Counter(int value) {
+		this.value = value;
+		created += 1;
+	}
+
+	public static long sum(long left, long right) {
+		return left + right;
+	}
+
+	public int next(int step) {
+		value += step;
+		return value;
+	}
+}
@@ -0,0 +1,12 @@
+package tests.call;
+
+public class DoubleCounter extends Counter {
+	public DoubleCounter(int value) {
+		super(value);
+	}
+
+	@Override
+	public int next(int step) {
+		return super.next(step * 2);
+	}
+}
@@ -0,0 +1,74 @@
+use crate::launch;
+use rvm_runtime::Runtime;
+
+fn runtime() -> Runtime<'static> {
+	launch(1024)
+}
+
+#[test]
+fn static_method() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	let value = runtime
+		.class("tests/call/Counter")?
+		.static_method::<(i64, i64), i64>("sum")?
+		.call((20, 22))?;
+	assert_eq!(value, 42);
+	Ok(())
+}
+
+#[test]
+fn missing_method() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	let class = runtime.class("tests/call/Counter")?;
+	assert!(class.static_method::<(i32, i64), i64>("sum").is_err());
+	Ok(())
+}
+
+#[test]
+fn new_object() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	let counter = runtime.class("tests/call/Counter")?.new_object(5)?;
+	assert_eq!(counter.get_field::<i32>("value")?, 5);
+	assert_eq!(
+		runtime
+			.class("tests/call/Counter")?
+			.get_static::<i32>("created")?,
+		1
+	);
+	Ok(())
+}
+
+#[test]
+fn virtual_method() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	let counter = runtime.class("tests/call/Counter")?.new_object(1)?;
+	assert_eq!(
+		counter.method::<i32, i32>(&mut runtime, "next")?.call(2)?,
+		3
+	);
+
+	// Overrides get called through the method of the super class.
+	let counter = runtime.class("tests/call/DoubleCounter")?.new_object(1)?;
+	assert_eq!(
+		counter.method::<i32, i32>(&mut runtime, "next")?.call(2)?,
+		5
+	);
+	Ok(())
+}
+
+#[test]
+fn fields() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	let counter = runtime.class("tests/call/Counter")?.new_object(0)?;
+	counter.set_field(&mut runtime, "value", 41)?;
+	assert_eq!(
+		counter.method::<i32, i32>(&mut runtime, "next")?.call(1)?,
+		42
+	);
+	assert!(counter.set_field(&mut runtime, "value", 1.0f32).is_err());
+
+	let mut class = runtime.class("tests/call/Counter")?;
+	class.set_static("created", 7)?;
+	assert_eq!(class.get_static::<i32>("created")?, 7);
+	Ok(())
+}
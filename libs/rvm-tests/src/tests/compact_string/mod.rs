@@ -0,0 +1,86 @@
+use crate::{call, config, core_classes, MemoryClassSource, STATIC};
+use rvm_core::{FieldAccessFlags, ObjectType, StackKind};
+use rvm_engine_ben::BenBinding;
+use rvm_reader::ClassBuilder;
+use rvm_runtime::{AnyValue, ClassSource, FromJava, Runtime, ToJava, Vm};
+
+/// A runtime with the `byte[]` and coder layout of `java.lang.String` from Java 9 on.
+fn runtime() -> eyre::Result<Runtime<'static>> {
+	let core = core_classes()?;
+	let mut classes = vec![];
+	for name in ["java/lang/Object", "java/lang/Class"] {
+		let ty = ObjectType::new(name);
+		let bytes = core.try_load(&ty)?.unwrap();
+		classes.push((ty, bytes));
+	}
+
+	let flags = FieldAccessFlags::PRIVATE | FieldAccessFlags::FINAL;
+	let mut string = ClassBuilder::new("java/lang/String");
+	string.default_constructor()?;
+	string
+		.field(flags, "value", "[B")
+		.field(flags, "coder", "B");
+	classes.push((ObjectType::new("java/lang/String"), string.write()?));
+
+	let mut echo = ClassBuilder::new("tests/compact_string/Echo");
+	echo.default_constructor()?;
+	echo.method(
+		STATIC,
+		"echo",
+		"(Ljava/lang/String;)Ljava/lang/String;",
+		|code| {
+			code.load(StackKind::Reference, 0)
+				.return_value(StackKind::Reference);
+			Ok(())
+		},
+	)?;
+	classes.push((ObjectType::new("tests/compact_string/Echo"), echo.write()?));
+
+	rvm_core::init();
+	let vm = Vm::with_config(config(true), Box::new(BenBinding::new()));
+	vm.classes
+		.add_boot_source(Box::new(MemoryClassSource::new(classes)));
+	Ok(Runtime { vm, thread: None })
+}
+
+/// The coder and the length of the `byte[]` of a string.
+fn layout(runtime: &Runtime, value: &AnyValue) -> eyre::Result<(i8, i32)> {
+	let AnyValue::Reference(reference) = value else {
+		panic!("Expected a string");
+	};
+	let instance = reference.to_instance()?.resolve(runtime.vm.clone());
+	let fields = instance.fields();
+	let Some(AnyValue::Byte(coder)) = fields.by_name("coder").map(|field| field.get()) else {
+		panic!("Expected a coder");
+	};
+	let Some(AnyValue::Reference(bytes)) = fields.by_name("value").map(|field| field.get()) else {
+		panic!("Expected a value");
+	};
+	Ok((coder, bytes.to_array()?.length()))
+}
+
+#[test]
+fn latin1() -> eyre::Result<()> {
+	let mut runtime = runtime()?;
+	let value = "Küche".to_java(&mut runtime)?;
+	// One byte per character.
+	assert_eq!(layout(&runtime, &value)?, (0, 5));
+	assert_eq!(String::from_java(value, &runtime.vm)?, "Küche");
+
+	let returned: String = call(&mut runtime, "tests/compact_string/Echo", "echo", "Küche")?;
+	assert_eq!(returned, "Küche");
+	Ok(())
+}
+
+#[test]
+fn utf16() -> eyre::Result<()> {
+	let mut runtime = runtime()?;
+	// The cake is a surrogate pair.
+	let value = "🍰 Cake".to_java(&mut runtime)?;
+	assert_eq!(layout(&runtime, &value)?, (1, 14));
+	assert_eq!(String::from_java(value, &runtime.vm)?, "🍰 Cake");
+
+	let returned: String = call(&mut runtime, "tests/compact_string/Echo", "echo", "🍰 Cake")?;
+	assert_eq!(returned, "🍰 Cake");
+	Ok(())
+}
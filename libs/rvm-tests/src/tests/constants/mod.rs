@@ -6,6 +6,7 @@ use std::mem::transmute;
 use std::ops::Deref;
 
 pub struct Hello {
+	_this: AnyInstance,
 	field: f32,
 	// base: Dog
 }
@@ -40,13 +41,17 @@ impl InstanceBinding for Hello {
 	fn bind(instance: &AnyInstance) -> Self {
 		todo!()
 	}
+
+	fn instance(&self) -> &AnyInstance {
+		&self._this
+	}
 }
 
 macro_rules! test_const {
 	($TEST_NAME:ident $EXPECTED:literal) => {
 		#[test]
 		fn $TEST_NAME() {
-			let mut runtime = launch(128);
+			let mut runtime = launch(1024);
 
 			assert_eq!(ConstantTests::$TEST_NAME(&mut runtime).unwrap(), $EXPECTED);
 		}
@@ -3,7 +3,7 @@ use crate::launch;
 
 #[test]
 fn test() -> Result<(), std::io::Error> {
-	let mut runtime = launch(128);
+	let mut runtime = launch(1024);
 
 	for i in 0..8i32 {
 		for j in 0..8i32 {
@@ -3,6 +3,6 @@ use crate::launch;
 
 #[test]
 fn basic() {
-	let mut runtime = launch(128);
+	let mut runtime = launch(1024);
 	let basic1 = Java::basic(&mut runtime, true);
 }
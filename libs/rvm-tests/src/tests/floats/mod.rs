@@ -5,7 +5,7 @@ use crate::{compile, launch};
 
 #[test]
 fn test() -> Result<(), std::io::Error> {
-	let mut runtime = launch(128);
+	let mut runtime = launch(1024);
 
 	let rust = {
 		let mut i = 3.14159265358979323846f64;
@@ -0,0 +1,7 @@
+package tests.generics;
+
+public interface Container<T> {
+	T get();
+
+	void set(T value);
+}
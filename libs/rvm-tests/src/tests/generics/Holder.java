@@ -0,0 +1,17 @@
+package tests.generics;
+
+public class Holder<T> implements Container<T> {
+	public T value;
+
+	public Holder(T value) {
+		this.value = value;
+	}
+
+	public T get() {
+		return value;
+	}
+
+	public void set(T value) {
+		this.value = value;
+	}
+}
@@ -0,0 +1,17 @@
+package tests.generics;
+
+public class Holders {
+	public static <T> Holder<T> of(T value) {
+		return new Holder<>(value);
+	}
+
+	public static boolean isEmpty(Container<? extends Label> container) {
+		return container.get() == null;
+	}
+
+	public static <T> T swap(Container<T> container, T value) {
+		T old = container.get();
+		container.set(value);
+		return old;
+	}
+}
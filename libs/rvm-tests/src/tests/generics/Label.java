@@ -0,0 +1,9 @@
+package tests.generics;
+
+public class Label {
+	public int id;
+
+	public Label(int id) {
+		this.id = id;
+	}
+}
@@ -0,0 +1,7 @@
+package tests.generics;
+
+public class LabelHolder extends Holder<Label> {
+	public LabelHolder(Label label) {
+		super(label);
+	}
+}
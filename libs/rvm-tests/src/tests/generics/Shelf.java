@@ -0,0 +1,9 @@
+package tests.generics;
+
+public class Shelf {
+	public Holder<Label> top;
+
+	public Shelf(Label label) {
+		this.top = new Holder<>(label);
+	}
+}
@@ -0,0 +1,72 @@
+use crate::bindings::tests::generics::{
+	Container, ContainerInterface, Holder, Holders, Label, LabelHolder, Shelf,
+};
+use crate::launch;
+use rvm_runtime::{Instance, Runtime};
+
+fn runtime() -> Runtime<'static> {
+	// The instances held by the tests are not roots, so they may not move while the tests run.
+	launch(1024 * 1024)
+}
+
+#[test]
+fn type_argument() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	let label = Label::new(&mut runtime, 3)?;
+	let holder: Instance<Holder<Instance<Label>>> = Holder::new(&mut runtime, label)?;
+	let label: Instance<Label> = holder.get(&mut runtime)?;
+	assert_eq!(*label.id, 3);
+
+	let other = Label::new(&mut runtime, 4)?;
+	holder.set(&mut runtime, other)?;
+	assert_eq!(*holder.get(&mut runtime)?.id, 4);
+	Ok(())
+}
+
+#[test]
+fn generic_method() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	let label = Label::new(&mut runtime, 5)?;
+	let holder = Holders::of(&mut runtime, label)?;
+	assert_eq!(*holder.get(&mut runtime)?.id, 5);
+
+	let container = holder.cast_to::<Container<Instance<Label>>>();
+	let other = Label::new(&mut runtime, 6)?;
+	let old = Holders::swap(&mut runtime, container, other)?;
+	assert_eq!(*old.id, 5);
+	assert_eq!(*holder.get(&mut runtime)?.id, 6);
+	Ok(())
+}
+
+#[test]
+fn wildcard() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	let label = Label::new(&mut runtime, 7)?;
+	let holder = Holders::of(&mut runtime, label)?;
+	let container = holder.cast_to::<Container<Instance<Label>>>();
+	assert!(!Holders::isEmpty(&mut runtime, container)?);
+	Ok(())
+}
+
+#[test]
+fn generic_superclass() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	let label = Label::new(&mut runtime, 8)?;
+	let holder = LabelHolder::new(&mut runtime, label)?;
+	assert_eq!(*holder.get(&mut runtime)?.id, 8);
+
+	// The interface gets the type argument the super class gave it.
+	let label: Instance<Label> = ContainerInterface::get(&*holder, &mut runtime)?;
+	assert_eq!(*label.id, 8);
+	Ok(())
+}
+
+#[test]
+fn generic_field() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	let label = Label::new(&mut runtime, 9)?;
+	let shelf = Shelf::new(&mut runtime, label)?;
+	let top: &Instance<Holder<Instance<Label>>> = &shelf.top;
+	assert_eq!(*top.get(&mut runtime)?.id, 9);
+	Ok(())
+}
@@ -0,0 +1,129 @@
+use std::path::PathBuf;
+
+use rvm_core::{FieldAccessFlags, MethodAccessFlags, PrimitiveType};
+use rvm_reader::{ArrayInst, ClassBuilder, Inst};
+use rvm_runtime::{MethodBinding, Runtime};
+
+use crate::{config, launch_with, STATIC};
+
+const TAG_STRING: u8 = 0x01;
+const TAG_LOAD_CLASS: u8 = 0x02;
+const TAG_HEAP_DUMP_SEGMENT: u8 = 0x1C;
+const TAG_HEAP_DUMP_END: u8 = 0x2C;
+const CLASS_DUMP: u8 = 0x20;
+const PRIM_ARRAY_DUMP: u8 = 0x23;
+const INT: u8 = 10;
+
+/// A class which keeps an `int[3]` in a static field and dumps the heap from a native method.
+fn runtime(name: &str, path: PathBuf) -> eyre::Result<Runtime<'static>> {
+	let mut class = ClassBuilder::new(name);
+	class
+		.field(
+			FieldAccessFlags::PUBLIC | FieldAccessFlags::STATIC,
+			"values",
+			"[I",
+		)
+		.declare_method(STATIC | MethodAccessFlags::NATIVE, "dump", "()V");
+	class.method(STATIC, "run", "()V", |code| {
+		code.int(3)
+			.inst(Inst::Array(ArrayInst::NewPrim(PrimitiveType::Int)))
+			.put_static(name, "values", "[I")
+			.invoke_static(name, "dump", "()V")
+			.return_void();
+		Ok(())
+	})?;
+
+	let runtime = launch_with(vec![(name, class.write()?)], config(true))?;
+	runtime.vm.bindings.bind(
+		name,
+		"dump",
+		MethodBinding::new(move |runtime, _, _: ()| runtime.dump_heap(&path).unwrap()),
+	);
+	Ok(runtime)
+}
+
+fn path(name: &str) -> PathBuf {
+	std::env::temp_dir().join(format!("rvm-{}-{}.hprof", name, std::process::id()))
+}
+
+/// The tag and body of every record after the header.
+fn records(bytes: &[u8]) -> Vec<(u8, &[u8])> {
+	let header = b"JAVA PROFILE 1.0.2\0";
+	assert_eq!(&bytes[..header.len()], header);
+	let id_size = u32::from_be_bytes(bytes[header.len()..header.len() + 4].try_into().unwrap());
+	assert_eq!(id_size, 8);
+
+	// The size of the identifiers and the timestamp.
+	let mut rest = &bytes[header.len() + 12..];
+	let mut records = vec![];
+	while !rest.is_empty() {
+		let length = u32::from_be_bytes(rest[5..9].try_into().unwrap()) as usize;
+		records.push((rest[0], &rest[9..9 + length]));
+		rest = &rest[9 + length..];
+	}
+	records
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> u64 {
+	u64::from_be_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+fn check_dump(bytes: &[u8], name: &str) {
+	let records = records(bytes);
+	assert_eq!(records.last().unwrap().0, TAG_HEAP_DUMP_END);
+
+	let string = records
+		.iter()
+		.find(|(tag, body)| *tag == TAG_STRING && &body[8..] == name.as_bytes())
+		.map(|(_, body)| u64_at(body, 0))
+		.expect("The name of the class should be a string");
+	// The serial, the class, the stack trace serial and the name.
+	let class = records
+		.iter()
+		.find(|(tag, body)| *tag == TAG_LOAD_CLASS && u64_at(body, 16) == string)
+		.map(|(_, body)| u64_at(body, 4))
+		.expect("The class should be loaded");
+
+	let segments: Vec<&[u8]> = records
+		.iter()
+		.filter(|(tag, _)| *tag == TAG_HEAP_DUMP_SEGMENT)
+		.map(|(_, body)| *body)
+		.collect();
+	let class_dump = [&[CLASS_DUMP][..], &class.to_be_bytes()].concat();
+	assert!(segments
+		.iter()
+		.any(|segment| segment.windows(9).any(|window| window == class_dump)));
+
+	// The array with its id, the stack trace serial, the length, the type and three zeros.
+	let array = segments.iter().any(|segment| {
+		segment.windows(30).any(|window| {
+			window[0] == PRIM_ARRAY_DUMP
+				&& window[13..17] == 3u32.to_be_bytes()
+				&& window[17] == INT
+				&& window[18..].iter().all(|byte| *byte == 0)
+		})
+	});
+	assert!(array, "The int[3] should be dumped");
+}
+
+#[test]
+fn dump_heap() -> eyre::Result<()> {
+	let name = "tests/hprof/Dump";
+	// The native method dumps with the runtime of the running java thread.
+	let from_thread = path("thread");
+	let mut runtime = runtime(name, from_thread.clone())?;
+	runtime
+		.class(name)?
+		.static_method::<(), ()>("run")?
+		.call(())?;
+	let bytes = std::fs::read(&from_thread)?;
+	std::fs::remove_file(&from_thread)?;
+	check_dump(&bytes, name);
+
+	let from_vm = path("vm");
+	runtime.vm.dump_heap(&from_vm)?;
+	let bytes = std::fs::read(&from_vm)?;
+	std::fs::remove_file(&from_vm)?;
+	check_dump(&bytes, name);
+	Ok(())
+}
@@ -3,7 +3,7 @@ use crate::launch;
 
 #[test]
 fn test() -> Result<(), std::io::Error> {
-	let mut runtime = launch(128);
+	let mut runtime = launch(1024);
 
 	// v == 0
 	let mut func = |v| Java::testZeroEq(&mut runtime, v).unwrap();
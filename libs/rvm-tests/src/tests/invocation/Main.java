@@ -0,0 +1,7 @@
+package tests.invocation;
+
+public class Main {
+	public static int square(int value) {
+		return value * value;
+	}
+}
@@ -0,0 +1,206 @@
+use crate::load_sdk;
+use rvm_engine_ben::JNI_CreateJavaVM;
+use rvm_runtime::native::jni_sys::{self, *};
+use rvm_runtime::native::JavaVM;
+use std::ffi::{c_void, CString};
+use std::ptr::null_mut;
+
+/// Creates a vm the way a C host would, the natives of the sdk still get bound from rust.
+unsafe fn create_vm(
+	options: &[&str],
+	ignore_unrecognized: bool,
+) -> Result<(*mut jni_sys::JavaVM, *mut JNIEnv), jint> {
+	let strings: Vec<CString> = options
+		.iter()
+		.map(|option| CString::new(*option).unwrap())
+		.collect();
+	let mut options: Vec<JavaVMOption> = strings
+		.iter()
+		.map(|string| JavaVMOption {
+			optionString: string.as_ptr() as *mut _,
+			extraInfo: null_mut(),
+		})
+		.collect();
+	let mut args = JavaVMInitArgs {
+		version: JNI_VERSION_1_8,
+		nOptions: options.len() as jint,
+		options: options.as_mut_ptr(),
+		ignoreUnrecognized: ignore_unrecognized,
+	};
+
+	let mut vm = null_mut();
+	let mut env = null_mut();
+	match JNI_CreateJavaVM(&mut vm, &mut env, &mut args as *mut _ as *mut c_void) {
+		JNI_OK => {
+			load_sdk(&JavaVM::from_raw(vm).vm().unwrap());
+			Ok((vm, env as *mut JNIEnv))
+		}
+		error => Err(error),
+	}
+}
+
+#[test]
+fn create_and_call() {
+	unsafe {
+		let (vm, env) = create_vm(&["-Xmx1m", "-Djava.class.path=bytecode"], false).unwrap();
+		let functions = &(**env).v1_2;
+
+		let class = (functions.FindClass)(env, c"tests/invocation/Main".as_ptr());
+		assert!(!class.is_null());
+		let method =
+			(functions.GetStaticMethodID)(env, class, c"square".as_ptr(), c"(I)I".as_ptr());
+		assert!(!method.is_null());
+		let args = [jvalue { i: 7 }];
+		let value = (functions.CallStaticIntMethodA)(env, class, method, args.as_ptr());
+		assert_eq!(value, 49);
+		assert!(!(functions.ExceptionCheck)(env));
+
+		assert_eq!(((**vm).v1_4.DestroyJavaVM)(vm), JNI_OK);
+	}
+}
+
+#[test]
+fn get_env() {
+	unsafe {
+		let (vm, env) = create_vm(&[], false).unwrap();
+		let invoke = &(**vm).v1_4;
+
+		let mut current = null_mut();
+		assert_eq!((invoke.GetEnv)(vm, &mut current, JNI_VERSION_1_8), JNI_OK);
+		assert_eq!(current as *mut JNIEnv, env);
+
+		let mut java_vm = null_mut();
+		assert_eq!(((**env).v1_2.GetJavaVM)(env, &mut java_vm), JNI_OK);
+		assert_eq!(java_vm, vm);
+
+		assert_eq!((invoke.DetachCurrentThread)(vm), JNI_OK);
+		assert_eq!(
+			(invoke.GetEnv)(vm, &mut current, JNI_VERSION_1_8),
+			JNI_EDETACHED
+		);
+		assert_eq!(
+			(invoke.AttachCurrentThread)(vm, &mut current, null_mut()),
+			JNI_OK
+		);
+		assert!(!current.is_null());
+
+		assert_eq!((invoke.DestroyJavaVM)(vm), JNI_OK);
+	}
+}
+
+#[test]
+fn attach_other_thread() {
+	unsafe {
+		let (vm, _) = create_vm(&["-Djava.class.path=bytecode"], false).unwrap();
+		let address = vm as usize;
+
+		let value = std::thread::spawn(move || {
+			let vm = address as *mut jni_sys::JavaVM;
+			let invoke = &(**vm).v1_4;
+			let mut env = null_mut();
+			assert_eq!(
+				(invoke.GetEnv)(vm, &mut env, JNI_VERSION_1_8),
+				JNI_EDETACHED
+			);
+			assert_eq!(
+				(invoke.AttachCurrentThread)(vm, &mut env, null_mut()),
+				JNI_OK
+			);
+
+			let env = env as *mut JNIEnv;
+			let functions = &(**env).v1_2;
+			let class = (functions.FindClass)(env, c"tests/invocation/Main".as_ptr());
+			let method =
+				(functions.GetStaticMethodID)(env, class, c"square".as_ptr(), c"(I)I".as_ptr());
+			let value =
+				(functions.CallStaticIntMethodA)(env, class, method, [jvalue { i: 5 }].as_ptr());
+
+			assert_eq!((invoke.DetachCurrentThread)(vm), JNI_OK);
+			value
+		})
+		.join()
+		.unwrap();
+		assert_eq!(value, 25);
+
+		assert_eq!(((**vm).v1_4.DestroyJavaVM)(vm), JNI_OK);
+	}
+}
+
+#[test]
+fn options() {
+	unsafe {
+		assert_eq!(create_vm(&["-Xfoo"], false).err(), Some(JNI_EINVAL));
+		assert_eq!(create_vm(&["-Xmx12q"], false).err(), Some(JNI_EINVAL));
+
+		let (vm, _) = create_vm(&["-Xfoo", "-Dkey=value", "-verbose:class"], true).unwrap();
+		let java_vm = JavaVM::from_raw(vm).vm().unwrap();
+		assert_eq!(
+			java_vm.properties.read().get("key").map(String::as_str),
+			Some("value")
+		);
+		assert_eq!(((**vm).v1_4.DestroyJavaVM)(vm), JNI_OK);
+	}
+}
+
+#[test]
+fn collect_while_attached() {
+	unsafe {
+		let (vm, env) = create_vm(&["-Xmx1m"], false).unwrap();
+		let functions = &(**env).v1_2;
+		let string = (functions.NewStringUTF)(env, c"kept".as_ptr());
+		let java_vm = JavaVM::from_raw(vm).vm().unwrap();
+
+		// This thread stays attached in native code, the collection does not wait for it.
+		let collector = java_vm.clone();
+		std::thread::spawn(move || collector.gc.gc())
+			.join()
+			.unwrap();
+
+		// The local reference has been moved along with the string.
+		assert_eq!((functions.GetStringUTFLength)(env, string), 4);
+		assert_eq!(((**vm).v1_4.DestroyJavaVM)(vm), JNI_OK);
+	}
+}
+
+#[test]
+fn exit_without_detaching() {
+	unsafe {
+		let (vm, _) = create_vm(&[], false).unwrap();
+		let address = vm as usize;
+		std::thread::spawn(move || {
+			let vm = address as *mut jni_sys::JavaVM;
+			let mut env = null_mut();
+			assert_eq!(
+				((**vm).v1_4.AttachCurrentThread)(vm, &mut env, null_mut()),
+				JNI_OK
+			);
+		})
+		.join()
+		.unwrap();
+
+		// The thread got detached when it exited, so collections do not wait for it.
+		JavaVM::from_raw(vm).vm().unwrap().gc.gc();
+		assert_eq!(((**vm).v1_4.DestroyJavaVM)(vm), JNI_OK);
+	}
+}
+
+#[test]
+fn attach_to_other_vm() {
+	unsafe {
+		let (vm, _) = create_vm(&[], false).unwrap();
+		let (other, _) = std::thread::spawn(|| {
+			let (other, env) = create_vm(&[], false).unwrap();
+			(other as usize, env as usize)
+		})
+		.join()
+		.unwrap();
+		let other = other as *mut jni_sys::JavaVM;
+
+		let mut env = null_mut();
+		assert_eq!(
+			((**other).v1_4.AttachCurrentThread)(other, &mut env, null_mut()),
+			JNI_ERR
+		);
+		assert_eq!(((**vm).v1_4.DestroyJavaVM)(vm), JNI_OK);
+	}
+}
@@ -0,0 +1,123 @@
+use rvm_javap::ClassListing;
+use rvm_reader::ClassInfo;
+use serde_json::{json, Value};
+use std::fs::read;
+
+/// The text and the JSON listing of a checked in class.
+fn listing(path: &str) -> eyre::Result<(String, Value)> {
+	let data = read(format!("bytecode/{path}.class"))?;
+	let listing = ClassListing::new(&ClassInfo::parse_complete(&data)?)?;
+	Ok((listing.to_string(), serde_json::to_value(&listing)?))
+}
+
+fn method<'a>(listing: &'a Value, name: &str) -> &'a Value {
+	listing["methods"]
+		.as_array()
+		.unwrap()
+		.iter()
+		.find(|method| method["name"] == name)
+		.unwrap_or_else(|| panic!("No method {name}"))
+}
+
+fn assert_lines(text: &str, lines: &[&str]) {
+	for line in lines {
+		assert!(
+			text.lines().any(|text| text == *line),
+			"Missing {line:?} in\n{text}"
+		);
+	}
+}
+
+#[test]
+fn instructions() -> eyre::Result<()> {
+	let (text, json) = listing("tests/writer/Loop")?;
+	assert_lines(
+		&text,
+		&[
+			"class tests/writer/Loop",
+			"    #1 = Methodref          #2.#3 // java/lang/Object.\"<init>\":()V",
+			"  sum(I)I",
+			"    flags: PUBLIC, STATIC",
+			"         1: invokespecial   #1 // java/lang/Object.\"<init>\":()V",
+			// The offsets skip the operands and the jumps point at offsets.
+			"         6: if_icmpge       19",
+			"         9: iload_1",
+			"        13: iinc            2, 1",
+			"        16: goto            4",
+			"        20: ireturn",
+		],
+	);
+	assert!(!text.contains("sum(I)I;"), "{text}");
+
+	let constructor = &method(&json, "<init>")["code"]["instructions"][1];
+	assert_eq!(constructor["opcode"], "invokespecial");
+	assert_eq!(constructor["operands"], json!(["#1"]));
+	assert_eq!(constructor["reference"], "java/lang/Object.\"<init>\":()V");
+
+	let code = &method(&json, "sum")["code"];
+	let offsets: Vec<u64> = code["instructions"]
+		.as_array()
+		.unwrap()
+		.iter()
+		.map(|inst| inst["offset"].as_u64().unwrap())
+		.collect();
+	assert_eq!(
+		offsets,
+		[0, 1, 2, 3, 4, 5, 6, 9, 10, 11, 12, 13, 16, 19, 20]
+	);
+	assert_eq!(code["instructions"][6]["operands"], json!(["19"]));
+	assert_eq!(code["instructions"][12]["operands"], json!(["4"]));
+	Ok(())
+}
+
+#[test]
+fn attributes() -> eyre::Result<()> {
+	let (text, json) = listing("tests/attributes/Shape$Circle")?;
+	assert_lines(
+		&text,
+		&[
+			"  scaled(I)I",
+			"         1: getfield        #7 // tests/attributes/Shape$Circle.radius:I",
+			"      Exception table:",
+			"            0    10    11   java/lang/ArithmeticException",
+			"      LineNumberTable:",
+			"        line 20: 0",
+			"        line 21: 11",
+			"        line 22: 12",
+			"      LocalVariableTable:",
+			"           12       2     2  e      Ljava/lang/ArithmeticException;",
+			"            0      14     1  factor I",
+		],
+	);
+
+	let code = &method(&json, "scaled")["code"];
+	assert_eq!(
+		code["exception_table"],
+		json!([{
+			"start": 0,
+			"end": 10,
+			"handler": 11,
+			"catch_type": "java/lang/ArithmeticException",
+		}])
+	);
+	assert_eq!(
+		code["line_numbers"],
+		json!([
+			{ "offset": 0, "line": 20 },
+			{ "offset": 11, "line": 21 },
+			{ "offset": 12, "line": 22 },
+		])
+	);
+	assert_eq!(
+		code["local_variables"][0],
+		json!({
+			"start": 12,
+			"length": 2,
+			"index": 2,
+			"name": "e",
+			"descriptor": "Ljava/lang/ArithmeticException;",
+		})
+	);
+	assert_eq!(code["local_variables"].as_array().unwrap().len(), 3);
+	Ok(())
+}
@@ -1,13 +1,101 @@
 package tests.jni;
 
 public class Main {
-	static {
-		System.loadLibrary("native");
+	private int value;
+
+	public Main(int value) {
+		this.value = value;
+	}
+
+	public static int add(int a, int b) {
+		return addNative(a, b);
+	}
+
+	public static double mixed(int a, float b, long c, double d, int e, int f, int g, int h, float i, double j) {
+		return mixedNative(a, b, c, d, e, f, g, h, i, j);
+	}
+
+	public static int callback(int value) {
+		return new Main(value).callbackNative();
+	}
+
+	public static int field(int value) {
+		return fieldNative(value);
+	}
+
+	public static int arraySum(int length) {
+		return arraySumNative(length);
+	}
+
+	public static int stringLength() {
+		return stringLengthNative();
+	}
+
+	public static int globalRef(int value) {
+		keepNative(new Main(value));
+		garbage();
+		return releaseNative();
+	}
+
+	public static boolean weakRef() {
+		weakNative(new Main(0));
+		garbage();
+		return weakClearedNative();
+	}
+
+	public static int registered(int value) {
+		return registeredNative(value);
+	}
+
+	public static void throwNew() {
+		throwNewNative("Thrown by native code");
+	}
+
+	public static void rethrow() {
+		rethrowNative();
+	}
+
+	public static boolean cleared() {
+		return clearedNative();
 	}
 
-	public static void main() {
-		new Main().sayHello();
+	// Enough to fill the heap a few times over, so it gets collected and compacted.
+	private static void garbage() {
+		for (int i = 0; i < 10000; i++) {
+			new Main(i);
+		}
 	}
 
-	private native void sayHello();
-}
\ No newline at end of file
+	public int twice() {
+		return value * 2;
+	}
+
+	private static native int addNative(int a, int b);
+
+	private static native double mixedNative(int a, float b, long c, double d, int e, int f, int g, int h, float i, double j);
+
+	private native int callbackNative();
+
+	private static native int fieldNative(int value);
+
+	private static native int arraySumNative(int length);
+
+	private static native int stringLengthNative();
+
+	private static native void keepNative(Main main);
+
+	private static native int releaseNative();
+
+	private static native void weakNative(Main main);
+
+	private static native boolean weakClearedNative();
+
+	private static native void throwNewNative(String message);
+
+	private static native void rethrowNative();
+
+	private static native boolean clearedNative();
+
+	// Registered by JNI_OnLoad, the library does not export a symbol for it.
+	private static native int registeredNative(int value);
+}
@@ -1,16 +1,139 @@
-use rvm_runtime::java_bind_method;
-
-use crate::{compile, launch};
-
-//#[test]
-//fn main() {
-//	let runtime = launch(1024, vec![]);
-//	compile(&runtime, &[("Main.java", include_str!("Main.java"))]).unwrap();
-//
-//	runtime.linker.lock().link_library(
-//		"/home/alphasucks/CLionProjects/rvm/libs/rvm-tests/src/tests/jni/libnative.so",
-//	);
-//
-//	let java = java_bind_method!(runtime fn Main:main());
-//	let _ = java();
-//}
+use crate::bindings::tests::jni::Main;
+use crate::launch;
+use rvm_core::ObjectType;
+use rvm_runtime::{AnyValue, FromJava, Runtime, Throwable};
+
+fn runtime() -> Runtime<'static> {
+	let mut runtime = launch(1024);
+	// Built from native.c by the build script
+	runtime
+		.properties
+		.write()
+		.insert("java.library.path".to_string(), env!("OUT_DIR").to_string());
+	runtime.load_library("native").unwrap();
+	runtime
+}
+
+#[test]
+fn add() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	assert_eq!(Main::add(&mut runtime, 3, 4)?, 7);
+	Ok(())
+}
+
+#[test]
+fn mixed_arguments() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	let value = Main::mixed(&mut runtime, 1, 2.0, 3, 4.0, 5, 6, 7, 8, 9.0, 10.0)?;
+	assert_eq!(value, 55.0);
+	Ok(())
+}
+
+#[test]
+fn callback() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	assert_eq!(Main::callback(&mut runtime, 21)?, 42);
+	Ok(())
+}
+
+#[test]
+fn field() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	assert_eq!(Main::field(&mut runtime, 42)?, 42);
+	Ok(())
+}
+
+#[test]
+fn array() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	assert_eq!(Main::arraySum(&mut runtime, 10)?, 45);
+	Ok(())
+}
+
+#[test]
+fn string() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	// 7 characters, the é takes two bytes in modified UTF-8
+	assert_eq!(Main::stringLength(&mut runtime)?, 7 * 100 + 8);
+	Ok(())
+}
+
+#[test]
+fn global_reference() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	assert_eq!(Main::globalRef(&mut runtime, 42)?, 42);
+	Ok(())
+}
+
+#[test]
+fn weak_reference() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	assert!(Main::weakRef(&mut runtime)?);
+	Ok(())
+}
+
+#[test]
+fn registered_natives() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	assert_eq!(Main::registered(&mut runtime, 21)?, 42);
+	Ok(())
+}
+
+/// The class and message of the exception which a native method threw.
+fn thrown(
+	runtime: &mut Runtime,
+	error: eyre::Report,
+) -> eyre::Result<(ObjectType, Option<String>)> {
+	let throwable = error
+		.downcast_ref::<Throwable>()
+		.unwrap_or_else(|| panic!("Expected a Throwable: {error:?}"));
+	// Only the throwable keeps the exception alive, the collection moves it.
+	runtime.gc();
+	let instance = throwable
+		.reference()
+		.to_instance()?
+		.resolve(runtime.vm.clone());
+	let message = match instance
+		.fields()
+		.by_name("detailMessage")
+		.map(|field| field.get())
+	{
+		Some(AnyValue::Reference(message)) if message.is_null() => None,
+		Some(message) => Some(String::from_java(message, &runtime.vm)?),
+		None => None,
+	};
+	Ok((throwable.class.clone(), message))
+}
+
+#[test]
+fn throw_new() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	let error = Main::throwNew(&mut runtime).unwrap_err();
+	let (class, message) = thrown(&mut runtime, error)?;
+	assert_eq!(class, ObjectType::new("java/lang/IllegalStateException"));
+	assert_eq!(message.as_deref(), Some("Thrown by native code"));
+	Ok(())
+}
+
+#[test]
+fn rethrow() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	let error = Main::rethrow(&mut runtime).unwrap_err();
+	let (class, message) = thrown(&mut runtime, error)?;
+	assert_eq!(class, ObjectType::new("java/lang/RuntimeException"));
+	assert_eq!(message.as_deref(), Some("Rethrown"));
+	Ok(())
+}
+
+#[test]
+fn exception_cleared() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	assert!(Main::cleared(&mut runtime)?);
+	Ok(())
+}
+
+#[test]
+fn missing_library() {
+	let mut runtime = runtime();
+	assert!(runtime.load_library("missing").is_err());
+}
@@ -0,0 +1,126 @@
+#include <jni.h>
+
+JNIEXPORT jint JNICALL Java_tests_jni_Main_addNative(JNIEnv *env, jclass clazz, jint a, jint b) {
+	return a + b;
+}
+
+JNIEXPORT jdouble JNICALL Java_tests_jni_Main_mixedNative(
+	JNIEnv *env, jclass clazz, jint a, jfloat b, jlong c, jdouble d, jint e, jint f, jint g, jint h,
+	jfloat i, jdouble j
+) {
+	return a + b + c + d + e + f + g + h + i + j;
+}
+
+JNIEXPORT jint JNICALL Java_tests_jni_Main_callbackNative(JNIEnv *env, jobject this) {
+	jclass clazz = (*env)->GetObjectClass(env, this);
+	jmethodID twice = (*env)->GetMethodID(env, clazz, "twice", "()I");
+	return (*env)->CallIntMethod(env, this, twice);
+}
+
+JNIEXPORT jint JNICALL Java_tests_jni_Main_fieldNative(JNIEnv *env, jclass clazz, jint value) {
+	jmethodID init = (*env)->GetMethodID(env, clazz, "<init>", "(I)V");
+	jobject main = (*env)->NewObject(env, clazz, init, 0);
+	jfieldID field = (*env)->GetFieldID(env, clazz, "value", "I");
+	(*env)->SetIntField(env, main, field, value);
+	return (*env)->GetIntField(env, main, field);
+}
+
+JNIEXPORT jint JNICALL Java_tests_jni_Main_arraySumNative(JNIEnv *env, jclass clazz, jint length) {
+	jintArray array = (*env)->NewIntArray(env, length);
+	jint *elements = (*env)->GetIntArrayElements(env, array, NULL);
+	for (jint i = 0; i < length; i++) {
+		elements[i] = i;
+	}
+	(*env)->ReleaseIntArrayElements(env, array, elements, 0);
+
+	jint sum = 0;
+	jint region[1];
+	for (jint i = 0; i < (*env)->GetArrayLength(env, array); i++) {
+		(*env)->GetIntArrayRegion(env, array, i, 1, region);
+		sum += region[0];
+	}
+	return sum;
+}
+
+JNIEXPORT jint JNICALL Java_tests_jni_Main_stringLengthNative(JNIEnv *env, jclass clazz) {
+	jstring string = (*env)->NewStringUTF(env, "Hello \xc3\xa9");
+	const char *chars = (*env)->GetStringUTFChars(env, string, NULL);
+	(*env)->ReleaseStringUTFChars(env, string, chars);
+	return (*env)->GetStringLength(env, string) * 100 + (*env)->GetStringUTFLength(env, string);
+}
+
+static jobject kept;
+static jweak weak;
+
+JNIEXPORT void JNICALL Java_tests_jni_Main_keepNative(JNIEnv *env, jclass clazz, jobject main) {
+	kept = (*env)->NewGlobalRef(env, main);
+}
+
+JNIEXPORT jint JNICALL Java_tests_jni_Main_releaseNative(JNIEnv *env, jclass clazz) {
+	if ((*env)->GetObjectRefType(env, kept) != JNIGlobalRefType) {
+		return -1;
+	}
+
+	jfieldID field = (*env)->GetFieldID(env, clazz, "value", "I");
+	jint value = (*env)->GetIntField(env, kept, field);
+	(*env)->DeleteGlobalRef(env, kept);
+	return value;
+}
+
+JNIEXPORT void JNICALL Java_tests_jni_Main_weakNative(JNIEnv *env, jclass clazz, jobject main) {
+	weak = (*env)->NewWeakGlobalRef(env, main);
+}
+
+JNIEXPORT jboolean JNICALL Java_tests_jni_Main_weakClearedNative(JNIEnv *env, jclass clazz) {
+	jboolean cleared = (*env)->IsSameObject(env, weak, NULL);
+	(*env)->DeleteWeakGlobalRef(env, weak);
+	return cleared;
+}
+
+JNIEXPORT void JNICALL Java_tests_jni_Main_throwNewNative(JNIEnv *env, jclass clazz, jstring message) {
+	const char *chars = (*env)->GetStringUTFChars(env, message, NULL);
+	jclass exception = (*env)->FindClass(env, "java/lang/IllegalStateException");
+	(*env)->ThrowNew(env, exception, chars);
+	(*env)->ReleaseStringUTFChars(env, message, chars);
+}
+
+// Catches an exception and throws it again as it is.
+JNIEXPORT void JNICALL Java_tests_jni_Main_rethrowNative(JNIEnv *env, jclass clazz) {
+	jclass exception = (*env)->FindClass(env, "java/lang/RuntimeException");
+	(*env)->ThrowNew(env, exception, "Rethrown");
+	jthrowable thrown = (*env)->ExceptionOccurred(env);
+	(*env)->ExceptionClear(env);
+	if (thrown == NULL || (*env)->ExceptionOccurred(env) != NULL) {
+		return;
+	}
+	(*env)->Throw(env, thrown);
+}
+
+// Returns normally once the exception is cleared.
+JNIEXPORT jboolean JNICALL Java_tests_jni_Main_clearedNative(JNIEnv *env, jclass clazz) {
+	jclass exception = (*env)->FindClass(env, "java/lang/RuntimeException");
+	(*env)->ThrowNew(env, exception, NULL);
+	jboolean thrown = (*env)->ExceptionCheck(env);
+	(*env)->ExceptionClear(env);
+	return thrown && !(*env)->ExceptionCheck(env);
+}
+
+static jint registered(JNIEnv *env, jclass clazz, jint value) {
+	return value * 2;
+}
+
+JNIEXPORT jint JNICALL JNI_OnLoad(JavaVM *vm, void *reserved) {
+	JNIEnv *env;
+	if ((*vm)->GetEnv(vm, (void **) &env, JNI_VERSION_1_8) != JNI_OK) {
+		return JNI_ERR;
+	}
+
+	jclass clazz = (*env)->FindClass(env, "tests/jni/Main");
+	JNINativeMethod methods[] = {
+		{"registeredNative", "(I)I", (void *) registered},
+	};
+	if ((*env)->RegisterNatives(env, clazz, methods, 1) != JNI_OK) {
+		return JNI_ERR;
+	}
+	return JNI_VERSION_1_8;
+}
@@ -1,17 +1,49 @@
+#[cfg(rt)]
 mod ackermann;
+#[cfg(rt)]
 mod array;
+mod assembler;
+mod attributes;
+#[cfg(rt)]
+mod bind;
+mod builder;
+#[cfg(rt)]
+mod call;
+mod compact_string;
+#[cfg(rt)]
 mod control_flow;
+#[cfg(rt)]
 mod floats;
+#[cfg(rt)]
+mod generics;
+mod hprof;
+#[cfg(rt)]
 mod integers;
+#[cfg(rt)]
+mod invocation;
+mod javap;
+#[cfg(all(jdk, rt))]
 mod jni;
+#[cfg(rt)]
 mod math;
+#[cfg(rt)]
 mod object;
+#[cfg(rt)]
 mod rni;
+#[cfg(rt)]
 mod switch_statement;
+mod verifier;
+mod writer;
 
+#[cfg(rt)]
 mod argument_order;
+#[cfg(rt)]
 mod constants;
+#[cfg(rt)]
 mod consts;
+#[cfg(rt)]
 mod exception;
+#[cfg(rt)]
 mod statics;
+#[cfg(rt)]
 mod string;
@@ -147,7 +147,7 @@ pub fn gc() {
 	let mut frozen = 0;
 	let mut ran_gc = false;
 	for i in 0..16 {
-		match runtime.gc.alloc_instance(class) {
+		match runtime.gc.alloc_instance(None, class) {
 			Err(AllocationError::OutOfHeap) => {
 				runtime.gc();
 				ran_gc = true;
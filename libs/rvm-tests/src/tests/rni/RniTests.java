@@ -1,11 +1,35 @@
 package tests.rni;
 
 public class RniTests {
+	public int value;
 
 	public static long test(int number1, long number2, int number3) {
 		return testNative(number1, number2, number3);
 	}
 
+	public static int instance(int value) {
+		RniTests tests = new RniTests();
+		tests.value = value;
+		return tests.valueNative();
+	}
+
+	public static int callback(int value) {
+		return callbackNative(value);
+	}
+
+	public static int square(int value) {
+		return value * value;
+	}
+
+	public static void throwing() {
+		throwNative();
+	}
 
 	public static native long testNative(int number1, long number2, int number3);
+
+	public native int valueNative();
+
+	public static native int callbackNative(int value);
+
+	public static native void throwNative();
 }
@@ -1,21 +1,49 @@
-use rvm_runtime::MethodBinding;
+use rvm_core::ObjectType;
+use rvm_macro::{jni_binding, jni_method};
+use rvm_runtime::{AnyValue, Reference, Runtime, Throwable};
 
 use crate::bindings::tests::rni::RniTests;
 use crate::launch;
 
+pub struct RniTestsBindings;
+
+#[jni_binding(tests/rni/RniTests)]
+impl RniTestsBindings {
+	#[jni_method]
+	fn test_native(_: &mut Runtime, number_1: i32, number_2: i64, number_3: i32) -> i64 {
+		(number_1 as i64) + number_2 * (number_3 as i64)
+	}
+
+	#[jni_method]
+	fn value_native(runtime: &mut Runtime, this: Reference) -> eyre::Result<i32> {
+		let instance = this.to_instance()?.resolve(runtime.vm.clone());
+		match instance.fields().by_name("value").map(|field| field.get()) {
+			Some(AnyValue::Int(value)) => Ok(value),
+			_ => eyre::bail!("No value field"),
+		}
+	}
+
+	#[jni_method]
+	fn callback_native(runtime: &mut Runtime, value: i32) -> eyre::Result<i32> {
+		RniTests::square(runtime, value)
+	}
+
+	#[jni_method]
+	fn throw_native(runtime: &mut Runtime) -> Result<(), Throwable> {
+		let class = ObjectType::new("java/lang/IllegalStateException".to_string());
+		Err(Throwable::new(runtime, &class).unwrap())
+	}
+}
+
+fn runtime() -> Runtime<'static> {
+	let runtime = launch(1024);
+	RniTestsBindings::register(&runtime);
+	runtime
+}
+
 #[test]
 pub fn basic() -> eyre::Result<()> {
-	let mut runtime = launch(1024);
-
-	runtime.bindings.bind(
-		"tests/rni/RniTests",
-		"testNative",
-		MethodBinding::new(
-			|_, (number_1, number_2, number_3): (i32, i64, i32)| -> i64 {
-				(number_1 as i64) + number_2 * (number_3 as i64)
-			},
-		),
-	);
+	let mut runtime = runtime();
 
 	let i = RniTests::test(&mut runtime, 69, 50, 12)?;
 
@@ -23,41 +51,45 @@ pub fn basic() -> eyre::Result<()> {
 	Ok(())
 }
 
-//pub struct RniTestsLinking;
-//
-// #[jni_binding(testing/rni/RniTests)]
-// impl RniTestsLinking {
-// 	#[jni_method]
-// 	fn test_native(_: &Arc<Runtime>, number_1: i32, number_2: i64, number_3: i32) -> i64 {
-// 		(number_1 as i64) + number_2 * (number_3 as i64)
-// 	}
-//
-// 	fn hello() {}
-//
-// 	//fn link(runtime: &Runtime) {
-// 	//	unsafe extern "C" fn test(
-// 	//		runtime: *const Runtime,
-// 	//		number_1: i32,
-// 	//		number_2: i64,
-// 	//		number_3: i32,
-// 	//	) -> i64 {
-// 	//		let runtime = Arc::from_raw(runtime);
-// 	//		let returns = RniTestsLinking::test(&runtime, number_1, number_2, number_3);
-// 	//		let _ = Arc::into_raw(runtime);
-// 	//		returns
-// 	//	}
-// 	//
-// 	//	unsafe {
-// 	//		runtime.linker.lock().link(
-// 	//			"Java_testing_rni_RniTests_testNative".to_string(),
-// 	//			MethodBinding::new(
-// 	//				transmute::<*const (), extern "C" fn()>(test as *const ()),
-// 	//				MethodDescriptor {
-// 	//					parameters: vec![i32::ty(), i64::ty(), i32::ty()],
-// 	//					returns: Some(i64::ty()),
-// 	//				},
-// 	//			),
-// 	//		);
-// 	//	}
-// 	//}
-// }
+#[test]
+pub fn instance() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	assert_eq!(RniTests::instance(&mut runtime, 42)?, 42);
+	Ok(())
+}
+
+pub struct MismatchedBindings;
+
+#[jni_binding(tests/rni/RniTests)]
+impl MismatchedBindings {
+	/// The java method takes an int.
+	#[jni_method]
+	fn callback_native(_: &mut Runtime, value: i64) -> i32 {
+		value as i32
+	}
+}
+
+#[test]
+pub fn mismatched_descriptor() {
+	let mut runtime = launch(1024);
+	MismatchedBindings::register(&runtime);
+	let error = RniTests::callback(&mut runtime, 12).unwrap_err();
+	assert!(
+		format!("{error:?}").contains("RniTests.callbackNative is (J)I but the method is (I)I"),
+		"{error:?}"
+	);
+}
+
+#[test]
+pub fn callback() -> eyre::Result<()> {
+	let mut runtime = runtime();
+	assert_eq!(RniTests::callback(&mut runtime, 12)?, 144);
+	Ok(())
+}
+
+#[test]
+pub fn throwing() {
+	let mut runtime = runtime();
+	let error = RniTests::throwing(&mut runtime).unwrap_err();
+	assert!(format!("{error:?}").contains("java/lang/IllegalStateException; was thrown"));
+}
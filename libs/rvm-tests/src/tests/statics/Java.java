@@ -1,6 +1,14 @@
 package tests.statics;
 
 public class Java {
+	public static final int LIMIT = 42;
+	public static final long BIG = 1L << 40;
+	public static final double HALF = 0.5;
+	public static final float NOT_A_NUMBER = 0.0f / 0.0f;
+	public static final boolean ENABLED = true;
+	public static final char LETTER = 'c';
+	public static final String NAME = "Cake";
+
 	public static int number;
 
 	static {
@@ -53,3 +53,25 @@ fn static_class_init() {
 	// This is set by the class initialiation
 	assert_eq!(*field, 3);
 }
+
+#[test]
+fn accessors() -> eyre::Result<()> {
+	let mut runtime = launch(1024);
+	assert_eq!(Java::get_number(&mut runtime)?, 3);
+
+	Java::set_number(&mut runtime, 7)?;
+	assert_eq!(Java::get_number(&mut runtime)?, 7);
+	assert_eq!(Java::getStatic(&mut runtime)?, 7);
+	Ok(())
+}
+
+#[test]
+fn constants() {
+	assert_eq!(Java::LIMIT, 42);
+	assert_eq!(Java::BIG, 1 << 40);
+	assert_eq!(Java::HALF, 0.5);
+	assert!(Java::NOT_A_NUMBER.is_nan());
+	assert!(Java::ENABLED);
+	assert_eq!(Java::LETTER, b'c' as u16);
+	assert_eq!(Java::NAME, "Cake");
+}
@@ -5,5 +5,19 @@ public class Java {
 	public static String ldc() {
 		return "Cake";
 	}
+
+	public static String supplementary() {
+		return "\uD83C\uDF70 Cake";
+	}
+
+	public static String echo(String value) {
+		return value;
+	}
+
+	public static String reverse(String value) {
+		return reverseNative(value);
+	}
+
+	public static native String reverseNative(String value);
 	
 }
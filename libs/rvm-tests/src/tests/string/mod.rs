@@ -1,8 +1,51 @@
 use crate::bindings::tests::string::Java;
 use crate::launch;
+use rvm_runtime::MethodBinding;
 
 #[test]
 fn ldc() {
 	let mut runtime = launch(1024);
 	Java::ldc(&mut runtime).unwrap();
 }
+
+#[test]
+fn ldc_to_rust() -> eyre::Result<()> {
+	let mut runtime = launch(1024);
+	let class = runtime.class("tests/string/Java")?;
+	assert_eq!(class.static_method::<(), String>("ldc")?.call(())?, "Cake");
+
+	let class = runtime.class("tests/string/Java")?;
+	let value = class
+		.static_method::<(), String>("supplementary")?
+		.call(())?;
+	assert_eq!(value, "🍰 Cake");
+	Ok(())
+}
+
+#[test]
+fn round_trip() -> eyre::Result<()> {
+	let mut runtime = launch(1024);
+	for value in ["", "Cake", "Küche", "ケーキ", "🍰\0🎂"] {
+		let class = runtime.class("tests/string/Java")?;
+		let returned = class.static_method::<&str, String>("echo")?.call(value)?;
+		assert_eq!(returned, value);
+	}
+	Ok(())
+}
+
+#[test]
+fn binding() -> eyre::Result<()> {
+	let mut runtime = launch(1024);
+	runtime.bindings.bind(
+		"tests/string/Java",
+		"reverseNative",
+		MethodBinding::new(|_, _, value: String| -> String { value.chars().rev().collect() }),
+	);
+
+	let class = runtime.class("tests/string/Java")?;
+	let returned = class
+		.static_method::<String, String>("reverse")?
+		.call("🍰 Cake".to_string())?;
+	assert_eq!(returned, "ekaC 🍰");
+	Ok(())
+}
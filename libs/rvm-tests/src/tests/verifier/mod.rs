@@ -0,0 +1,193 @@
+use crate::{call, config, launch_with, MemoryClassSource, STATIC};
+use rvm_core::{ObjectType, PrimitiveType, StackKind};
+use rvm_reader::{
+	ClassBuilder, ClassInfo, ClassWriter, CodeBuilder, ConstPtr, ConversionInst, Inst, JumpKind,
+	MathInst, VerifyError,
+};
+use rvm_runtime::VmConfig;
+
+/// Adds two floats with an int instruction.
+fn add_floats(code: &mut CodeBuilder) -> eyre::Result<()> {
+	code.load(StackKind::Float, 0)
+		.load(StackKind::Float, 1)
+		.inst(Inst::Math(MathInst::Add(PrimitiveType::Int)))
+		.return_value(StackKind::Int);
+	Ok(())
+}
+
+fn class(
+	name: &str,
+	version: u16,
+	build: fn(&mut CodeBuilder) -> eyre::Result<()>,
+) -> eyre::Result<ClassBuilder> {
+	let mut class = ClassBuilder::new(name);
+	class.version(version, 0);
+	class.method(STATIC, "run", "(FF)I", build)?;
+	Ok(class)
+}
+
+fn verify_error(name: &str, class: ClassBuilder) -> eyre::Result<VerifyError> {
+	let mut runtime = launch_with(vec![(name, class.write()?)], config(true))?;
+	let error = runtime
+		.class(name)
+		.err()
+		.expect("The class should not verify");
+	Ok(error
+		.chain()
+		.find_map(|error| error.downcast_ref::<VerifyError>())
+		.expect("Expected a VerifyError")
+		.clone())
+}
+
+#[test]
+fn inferred() -> eyre::Result<()> {
+	let name = "tests/verifier/Inferred";
+	let error = verify_error(name, class(name, 49, add_floats)?)?;
+	assert_eq!(error.class, name);
+	assert_eq!(error.method, "run(FF)I");
+	assert_eq!(error.offset, Some(2));
+	Ok(())
+}
+
+#[test]
+fn type_checked() -> eyre::Result<()> {
+	let name = "tests/verifier/TypeChecked";
+	let error = verify_error(name, class(name, 52, add_floats)?)?;
+	assert_eq!(error.method, "run(FF)I");
+	assert_eq!(error.offset, Some(2));
+	Ok(())
+}
+
+#[test]
+fn missing_stack_map() -> eyre::Result<()> {
+	let name = "tests/verifier/MissingStackMap";
+	// The assembler does not write a StackMapTable, which classes from version 50 on need for
+	// every branch target.
+	let class = class(name, 52, |code| {
+		let end = code.label();
+		code.int(0).jump(JumpKind::IFEQ, end);
+		code.place(end).int(0).return_value(StackKind::Int);
+		Ok(())
+	})?;
+	let error = verify_error(name, class)?;
+	assert!(error.message.contains("stack map"), "{error}");
+	Ok(())
+}
+
+#[test]
+fn subroutines() -> eyre::Result<()> {
+	// The subroutine overwrites local 2, the caller still sees its float parameters.
+	let name = "tests/verifier/Subroutine";
+	let build = |version| {
+		class(name, version, |code| {
+			let subroutine = code.label();
+			code.jsr(subroutine)
+				.load(StackKind::Float, 0)
+				.load(StackKind::Float, 1)
+				.inst(Inst::Math(MathInst::Add(PrimitiveType::Float)))
+				.inst(Inst::Conversion(ConversionInst::F2I))
+				.return_value(StackKind::Int);
+			code.place(subroutine)
+				.store(StackKind::Reference, 3)
+				.int(0)
+				.store(StackKind::Int, 2)
+				.ret(3);
+			Ok(())
+		})
+	};
+	let mut runtime = launch_with(vec![(name, build(49)?.write()?)], config(true))?;
+	runtime.class(name)?;
+
+	// Class files with a stack map can not have subroutines.
+	let error = verify_error(name, build(52)?)?;
+	assert!(error.message.contains("Subroutines"), "{error}");
+
+	// Here the float which the caller loads after the jsr got overwritten by an int.
+	let name = "tests/verifier/Overwritten";
+	let error = verify_error(
+		name,
+		class(name, 49, |code| {
+			let subroutine = code.label();
+			code.jsr(subroutine)
+				.load(StackKind::Float, 0)
+				.inst(Inst::Conversion(ConversionInst::F2I))
+				.return_value(StackKind::Int);
+			code.place(subroutine)
+				.store(StackKind::Reference, 2)
+				.int(0)
+				.store(StackKind::Int, 0)
+				.ret(2);
+			Ok(())
+		})?,
+	)?;
+	assert_eq!(error.offset, Some(3));
+	Ok(())
+}
+
+#[test]
+fn disabled() -> eyre::Result<()> {
+	let name = "tests/verifier/Disabled";
+	let class = class(name, 52, add_floats)?;
+	let mut runtime = launch_with(vec![(name, class.write()?)], config(false))?;
+	runtime.class(name)?;
+	Ok(())
+}
+
+#[test]
+fn unverified() -> eyre::Result<()> {
+	// Without the verifier invalid code fails when it runs instead of panicking.
+	let name = "tests/verifier/Unverified";
+	let class = class(name, 49, |code| {
+		code.int(0).store(StackKind::Int, 2).ret(2);
+		Ok(())
+	})?;
+	let mut runtime = launch_with(vec![(name, class.write()?)], config(false))?;
+	let error = call::<(f32, f32), i32>(&mut runtime, name, "run", (1.0, 2.0))
+		.expect_err("The method should fail");
+	assert!(
+		format!("{error:?}").contains("not a return address"),
+		"{error:?}"
+	);
+	Ok(())
+}
+
+#[test]
+fn boot_class_path() -> eyre::Result<()> {
+	// Only -Xverify:all verifies the classes of the boot class path.
+	let name = "tests/verifier/Boot";
+	for verify_boot in [false, true] {
+		let config = VmConfig {
+			verify_boot,
+			..config(true)
+		};
+		let mut runtime = launch_with(vec![], config)?;
+		runtime
+			.vm
+			.classes
+			.add_boot_source(Box::new(MemoryClassSource::new(vec![(
+				ObjectType::new(name),
+				class(name, 52, add_floats)?.write()?,
+			)])));
+		assert_eq!(runtime.class(name).is_err(), verify_boot);
+	}
+	Ok(())
+}
+
+#[test]
+fn invalid_super_class() -> eyre::Result<()> {
+	let name = "tests/verifier/InvalidSuper";
+	let bytes = class(name, 52, add_floats)?.write()?;
+	let mut info = ClassInfo::parse_complete(&bytes)?;
+	info.super_class = ConstPtr::new(999);
+
+	let mut runtime = launch_with(vec![(name, ClassWriter::new(&info).write()?)], config(true))?;
+	let error = runtime
+		.class(name)
+		.err()
+		.expect("The class should not load");
+	assert!(
+		format!("{error:?}").contains("ClassFormatError"),
+		"{error:?}"
+	);
+	Ok(())
+}
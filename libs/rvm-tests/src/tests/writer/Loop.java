@@ -0,0 +1,11 @@
+package tests.writer;
+
+public class Loop {
+	public static int sum(int n) {
+		int sum = 0;
+		for (int i = 0; i < n; i++) {
+			sum += i;
+		}
+		return sum;
+	}
+}
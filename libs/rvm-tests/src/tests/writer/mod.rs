@@ -0,0 +1,90 @@
+use crate::{call, config, launch_with, method_code_mut};
+use rvm_core::ObjectType;
+use rvm_reader::{ClassInfo, ClassWriter, Code, Inst, JumpKind};
+use rvm_runtime::{ClassSource, JarClassSource};
+use std::fs::read;
+use walkdir::WalkDir;
+
+fn round_trip(bytes: &[u8]) -> Vec<u8> {
+	let info = ClassInfo::parse_complete(bytes).unwrap();
+	ClassWriter::new(&info).write().unwrap()
+}
+
+#[test]
+fn unnamed_jar() {
+	let jar = JarClassSource::new(read("../../unnamed.jar").unwrap()).unwrap();
+	for name in ["Main", "Child"] {
+		let bytes = jar.try_load(&ObjectType::new(name)).unwrap().unwrap();
+		assert_eq!(round_trip(&bytes), bytes, "{name} changed");
+	}
+}
+
+#[test]
+fn test_classes() {
+	for entry in WalkDir::new("bytecode") {
+		let entry = entry.unwrap();
+		if entry
+			.path()
+			.extension()
+			.is_none_or(|extension| extension != "class")
+		{
+			continue;
+		}
+
+		let bytes = read(entry.path()).unwrap();
+		assert_eq!(round_trip(&bytes), bytes, "{:?} changed", entry.path());
+	}
+}
+
+/// Moves every jump which goes over `at`, as the instructions after it moved by `count`.
+fn insert_nops(code: &mut Code, at: usize, count: usize) -> eyre::Result<()> {
+	let shift = |index: usize| if index >= at { index + count } else { index };
+	for (index, inst) in code.instructions.iter_mut().enumerate() {
+		if let Inst::Jump(jump) = inst {
+			let target = (index as i32 + jump.offset) as usize;
+			jump.offset = shift(target) as i32 - shift(index) as i32;
+		}
+	}
+	code.instructions
+		.splice(at..at, std::iter::repeat_n(Inst::Nop, count));
+	code.pc_map.insert(at, count)
+}
+
+#[test]
+fn wide_jumps() -> eyre::Result<()> {
+	let bytes = read("bytecode/tests/writer/Loop.class")?;
+	let mut info = ClassInfo::parse_complete(&bytes)?;
+
+	// Both the loop condition and the jump back have to go over all of these.
+	let code = method_code_mut(&mut info, "sum");
+	let condition = code
+		.instructions
+		.iter()
+		.position(|inst| matches!(inst, Inst::Jump(jump) if jump.kind.is_conditional()))
+		.unwrap();
+	let length = code.instructions.len();
+	// Past the last instruction is the furthest to insert at.
+	assert!(code.pc_map.clone().insert(length, 1).is_ok());
+	assert!(code.pc_map.clone().insert(length + 1, 1).is_err());
+	insert_nops(code, condition + 1, 40000)?;
+
+	let bytes = ClassWriter::new(&info).write()?;
+	let mut written = ClassInfo::parse_complete(&bytes)?;
+	let code = method_code_mut(&mut written, "sum");
+	// The condition is inverted to jump over a goto_w.
+	assert_eq!(code.instructions.len(), length + 40000 + 1);
+	let Inst::Jump(jump) = &code.instructions[condition] else {
+		panic!("Expected the condition");
+	};
+	assert!(matches!(jump.kind, JumpKind::IF_ICMPLT));
+	assert_eq!(jump.offset, 2);
+	assert!(matches!(
+		&code.instructions[condition + 1],
+		Inst::Jump(jump) if matches!(jump.kind, JumpKind::GOTO)
+	));
+
+	let name = "tests/writer/Loop";
+	let mut runtime = launch_with(vec![(name, bytes)], config(true))?;
+	assert_eq!(call::<i32, i32>(&mut runtime, name, "sum", 10)?, 45);
+	Ok(())
+}